@@ -0,0 +1,31 @@
+-- Full-text search over products (name, brand, category path, review text).
+-- unicode61 with remove_diacritics folds case and accents ("creme" matches "Crème").
+-- Rows are kept in sync by the application (see db::product_search).
+
+CREATE VIRTUAL TABLE IF NOT EXISTS products_fts USING fts5(
+    product_id UNINDEXED,
+    name,
+    brand,
+    category,
+    reviews,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Backfill: category is the product's category name followed by its ancestors' names.
+WITH RECURSIVE chain(product_id, parent_id, names) AS (
+    SELECT p.id, c.parent_id, c.name
+    FROM products p JOIN categories c ON c.id = p.category_id
+    UNION ALL
+    SELECT chain.product_id, c.parent_id, chain.names || ' ' || c.name
+    FROM chain JOIN categories c ON c.id = chain.parent_id
+)
+INSERT INTO products_fts (product_id, name, brand, category, reviews)
+SELECT
+    p.id,
+    p.name,
+    p.brand,
+    chain.names,
+    (SELECT group_concat(r.text, ' ') FROM reviews r
+     WHERE r.product_id = p.id AND r.deleted_at IS NULL)
+FROM products p
+JOIN chain ON chain.product_id = p.id AND chain.parent_id IS NULL;
@@ -0,0 +1,8 @@
+-- Optional GTIN barcode per product variation (a barcode identifies one size).
+-- Unique among active variations; soft-deleted variations keep their barcode.
+
+ALTER TABLE product_variations ADD COLUMN barcode TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_variations_barcode_active
+    ON product_variations (barcode)
+    WHERE barcode IS NOT NULL AND deleted_at IS NULL;
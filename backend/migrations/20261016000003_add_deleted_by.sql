@@ -0,0 +1,9 @@
+-- Who soft-deleted a row, shown in the trash listing. NULL for rows deleted before this
+-- migration, from the CLI, or whose deleting user was removed.
+
+ALTER TABLE categories ADD COLUMN deleted_by TEXT REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE products ADD COLUMN deleted_by TEXT REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE product_variations ADD COLUMN deleted_by TEXT REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE locations ADD COLUMN deleted_by TEXT REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE reviews ADD COLUMN deleted_by TEXT REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE purchases ADD COLUMN deleted_by TEXT REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,16 @@
+-- Audit log: one row per insert, update, soft-delete, restore or hard-delete.
+-- actor is the acting user's id, or 'cli' for changes made from the command line.
+-- No foreign keys: entries outlive the rows and users they mention. id orders the entries.
+-- changes is a JSON object mapping each changed column to {"before": ..., "after": ...}.
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor       TEXT    NOT NULL,
+    entity_type TEXT    NOT NULL,
+    entity_id   TEXT    NOT NULL,
+    action      TEXT    NOT NULL,
+    changes     TEXT    NOT NULL,
+    created_at  INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log (entity_id, id);
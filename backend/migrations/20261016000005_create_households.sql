@@ -0,0 +1,60 @@
+-- Households: separate data spaces on one server. Every category, product, variation, location,
+-- review, purchase and audit entry belongs to one household; users join households through
+-- household_members. Existing data and users move into the default household.
+
+CREATE TABLE IF NOT EXISTS households (
+    id         TEXT    NOT NULL PRIMARY KEY,
+    name       TEXT    NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS household_members (
+    household_id TEXT    NOT NULL REFERENCES households(id),
+    user_id      TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at   INTEGER NOT NULL,
+    PRIMARY KEY (household_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_household_members_user ON household_members (user_id, created_at);
+
+INSERT INTO households (id, name, created_at)
+VALUES ('00000000-0000-4000-8000-000000000001', 'Default', CAST(strftime('%s', 'now') AS INTEGER));
+
+INSERT INTO household_members (household_id, user_id, created_at)
+SELECT '00000000-0000-4000-8000-000000000001', id, CAST(strftime('%s', 'now') AS INTEGER)
+FROM users;
+
+-- SQLite cannot add a REFERENCES column with a non-NULL default while foreign keys are on, so
+-- household_id is a plain column; the db modules only ever write existing household ids.
+ALTER TABLE categories ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+ALTER TABLE products ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+ALTER TABLE product_variations ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+ALTER TABLE locations ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+ALTER TABLE reviews ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+ALTER TABLE purchases ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+ALTER TABLE audit_log ADD COLUMN household_id TEXT NOT NULL DEFAULT '00000000-0000-4000-8000-000000000001';
+
+CREATE INDEX IF NOT EXISTS idx_categories_household ON categories (household_id);
+CREATE INDEX IF NOT EXISTS idx_products_household ON products (household_id);
+CREATE INDEX IF NOT EXISTS idx_product_variations_household ON product_variations (household_id);
+CREATE INDEX IF NOT EXISTS idx_locations_household ON locations (household_id);
+CREATE INDEX IF NOT EXISTS idx_reviews_household ON reviews (household_id);
+CREATE INDEX IF NOT EXISTS idx_purchases_household ON purchases (household_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_household ON audit_log (household_id, id);
+
+-- Names and barcodes are unique per household.
+DROP INDEX IF EXISTS idx_categories_name_root;
+DROP INDEX IF EXISTS idx_categories_name_parent;
+DROP INDEX IF EXISTS idx_product_variations_barcode_active;
+
+CREATE UNIQUE INDEX idx_categories_name_root
+    ON categories (household_id, name)
+    WHERE parent_id IS NULL AND deleted_at IS NULL;
+
+CREATE UNIQUE INDEX idx_categories_name_parent
+    ON categories (parent_id, name)
+    WHERE parent_id IS NOT NULL AND deleted_at IS NULL;
+
+CREATE UNIQUE INDEX idx_product_variations_barcode_active
+    ON product_variations (household_id, barcode)
+    WHERE barcode IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,16 @@
+-- Login sessions: one per login, referenced by the `jti` of its access tokens. The refresh token
+-- is stored as a SHA-256 hash and rotated on every refresh; revoking a session (logout) makes its
+-- access tokens and refresh token unusable.
+
+CREATE TABLE IF NOT EXISTS sessions (
+    id                 TEXT    NOT NULL PRIMARY KEY,
+    user_id            TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    household_id       TEXT    NOT NULL REFERENCES households(id),
+    refresh_token_hash TEXT    NOT NULL UNIQUE,
+    created_at         INTEGER NOT NULL,
+    last_used_at       INTEGER NOT NULL,
+    expires_at         INTEGER NOT NULL,
+    revoked_at         INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions (user_id, created_at);
@@ -0,0 +1,18 @@
+-- Personal access tokens for scripts and integrations: named, read or write scoped, optionally
+-- expiring, revocable. Only a SHA-256 hash of the token is stored. Each token works in the
+-- household it was created for.
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id           TEXT    NOT NULL PRIMARY KEY,
+    user_id      TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    household_id TEXT    NOT NULL REFERENCES households(id),
+    name         TEXT    NOT NULL,
+    scope        TEXT    NOT NULL CHECK (scope IN ('read', 'write')),
+    token_hash   TEXT    NOT NULL UNIQUE,
+    created_at   INTEGER NOT NULL,
+    expires_at   INTEGER,
+    last_used_at INTEGER,
+    revoked_at   INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_tokens_user ON api_tokens (user_id, created_at);
@@ -0,0 +1,13 @@
+-- One-time password reset tokens issued by an administrator (CLI). Only a SHA-256 hash of the
+-- token is stored; a token is used up on redemption and superseded by newer ones.
+
+CREATE TABLE IF NOT EXISTS password_resets (
+    id         TEXT    NOT NULL PRIMARY KEY,
+    user_id    TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT    NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    used_at    INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_resets_user ON password_resets (user_id);
@@ -0,0 +1,9 @@
+-- Failed login attempts per email and per client IP, for brute-force lockout. Keys are
+-- `email:<address>` or `ip:<address>`. Persisted so lockouts survive restarts.
+
+CREATE TABLE IF NOT EXISTS login_attempts (
+    key             TEXT    NOT NULL PRIMARY KEY,
+    failures        INTEGER NOT NULL,
+    last_failure_at INTEGER NOT NULL,
+    locked_until    INTEGER
+);
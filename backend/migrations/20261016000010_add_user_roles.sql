@@ -0,0 +1,8 @@
+-- User roles: `admin` (everything, incl. permanent deletes and category management), `member`
+-- (read and write data) and `guest` (read-only). Existing users keep full access as admins; new
+-- users default to member.
+
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'member'
+    CHECK (role IN ('admin', 'member', 'guest'));
+
+UPDATE users SET role = 'admin';
@@ -0,0 +1,16 @@
+-- Single-use, expiring invites for self-registration, issued by an administrator (CLI). Only a
+-- SHA-256 hash of the token is stored. The invite fixes the email, role and household of the new
+-- user; a newer invite for the same email supersedes older unused ones.
+
+CREATE TABLE IF NOT EXISTS invites (
+    id           TEXT    NOT NULL PRIMARY KEY,
+    email        TEXT    NOT NULL,
+    role         TEXT    NOT NULL CHECK (role IN ('admin', 'member', 'guest')),
+    household_id TEXT    NOT NULL REFERENCES households(id),
+    token_hash   TEXT    NOT NULL UNIQUE,
+    created_at   INTEGER NOT NULL,
+    expires_at   INTEGER NOT NULL,
+    used_at      INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_invites_email ON invites (email);
@@ -0,0 +1,30 @@
+-- Optional TOTP two-factor authentication (RFC 6238). A user has 2FA enabled while a row exists
+-- in user_totp; `last_used_step` makes each code single-use. Recovery codes and login challenges
+-- (issued after a correct password, redeemed with a code) store only a SHA-256 hash.
+
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id        TEXT    NOT NULL PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret         TEXT    NOT NULL,
+    created_at     INTEGER NOT NULL,
+    last_used_step INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    id        TEXT    NOT NULL PRIMARY KEY,
+    user_id   TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash TEXT    NOT NULL,
+    used_at   INTEGER,
+    UNIQUE (user_id, code_hash)
+);
+
+CREATE TABLE IF NOT EXISTS login_challenges (
+    id           TEXT    NOT NULL PRIMARY KEY,
+    user_id      TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    household_id TEXT    NOT NULL REFERENCES households(id),
+    token_hash   TEXT    NOT NULL UNIQUE,
+    created_at   INTEGER NOT NULL,
+    expires_at   INTEGER NOT NULL,
+    used_at      INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_challenges_user ON login_challenges (user_id);
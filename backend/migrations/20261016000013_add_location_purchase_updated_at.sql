@@ -0,0 +1,20 @@
+-- Last-change time of locations and purchases, as the other synced entities already have.
+-- Existing rows take the time of their latest audit entry, else their deletion time, else the
+-- purchase time (purchases) or the migration time (locations).
+
+ALTER TABLE locations ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE purchases ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+
+UPDATE locations SET updated_at = COALESCE(
+    (SELECT MAX(a.created_at) FROM audit_log a
+     WHERE a.entity_type = 'location' AND a.entity_id = locations.id),
+    deleted_at,
+    CAST(strftime('%s', 'now') AS INTEGER)
+);
+
+UPDATE purchases SET updated_at = COALESCE(
+    (SELECT MAX(a.created_at) FROM audit_log a
+     WHERE a.entity_type = 'purchase' AND a.entity_id = purchases.id),
+    deleted_at,
+    purchased_at
+);
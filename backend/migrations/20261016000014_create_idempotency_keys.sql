@@ -0,0 +1,16 @@
+-- Responses to POST requests sent with an Idempotency-Key header, so that a retry gets the
+-- original response instead of creating a second row. Keys are scoped to the user; `status` is
+-- null while the first request is still running. Rows expire after the configured window.
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id      TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key          TEXT    NOT NULL,
+    request_hash TEXT    NOT NULL,
+    status       INTEGER,
+    content_type TEXT,
+    body         BLOB,
+    created_at   INTEGER NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys (created_at);
@@ -0,0 +1,37 @@
+-- Rebuild the product search index with a household column, so searches are narrowed to one
+-- household by the index itself. The household is stored as its id without hyphens, which
+-- unicode61 keeps as a single token.
+-- products_fts_vocab lists the indexed terms, for typo-tolerant search (see db::product_search).
+
+DROP TABLE IF EXISTS products_fts;
+
+CREATE VIRTUAL TABLE products_fts USING fts5(
+    product_id UNINDEXED,
+    household,
+    name,
+    brand,
+    category,
+    reviews,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS products_fts_vocab USING fts5vocab(products_fts, 'col');
+
+WITH RECURSIVE chain(product_id, parent_id, names) AS (
+    SELECT p.id, c.parent_id, c.name
+    FROM products p JOIN categories c ON c.id = p.category_id
+    UNION ALL
+    SELECT chain.product_id, c.parent_id, chain.names || ' ' || c.name
+    FROM chain JOIN categories c ON c.id = chain.parent_id
+)
+INSERT INTO products_fts (product_id, household, name, brand, category, reviews)
+SELECT
+    p.id,
+    replace(p.household_id, '-', ''),
+    p.name,
+    p.brand,
+    chain.names,
+    (SELECT group_concat(r.text, ' ') FROM reviews r
+     WHERE r.product_id = p.id AND r.deleted_at IS NULL)
+FROM products p
+JOIN chain ON chain.product_id = p.id AND chain.parent_id IS NULL;
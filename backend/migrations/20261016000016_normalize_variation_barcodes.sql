@@ -0,0 +1,29 @@
+-- Store barcodes in one canonical form, so equal GTINs with different leading zeros (UPC-A
+-- 036000291452 and EAN-13 0036000291452) are one barcode: zero-padded to 13 digits, or 14 digits
+-- when a GTIN-14 starts with a packaging indicator other than 0 (see
+-- domain::product_variation::normalize_gtin).
+-- Active variations whose barcodes become equal keep the barcode on the oldest one only.
+
+DROP INDEX IF EXISTS idx_product_variations_barcode_active;
+
+UPDATE product_variations
+SET barcode = CASE
+    WHEN length(barcode) < 13 THEN substr('0000000000000', 1, 13 - length(barcode)) || barcode
+    WHEN length(barcode) = 14 AND substr(barcode, 1, 1) = '0' THEN substr(barcode, 2)
+    ELSE barcode
+END
+WHERE barcode IS NOT NULL;
+
+UPDATE product_variations
+SET barcode = NULL
+WHERE barcode IS NOT NULL AND deleted_at IS NULL AND EXISTS (
+    SELECT 1 FROM product_variations older
+    WHERE older.household_id = product_variations.household_id
+      AND older.barcode = product_variations.barcode
+      AND older.deleted_at IS NULL
+      AND (older.created_at, older.id) < (product_variations.created_at, product_variations.id)
+);
+
+CREATE UNIQUE INDEX idx_product_variations_barcode_active
+    ON product_variations (household_id, barcode)
+    WHERE barcode IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,19 @@
+-- Scope idempotency keys to the household as well as the user, so the same key sent to two
+-- households (one user, two logins) runs in each. Stored responses only live for the
+-- idempotency window, so existing ones are dropped instead of being assigned a household.
+
+DROP TABLE IF EXISTS idempotency_keys;
+
+CREATE TABLE idempotency_keys (
+    household_id TEXT    NOT NULL REFERENCES households(id) ON DELETE CASCADE,
+    user_id      TEXT    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key          TEXT    NOT NULL,
+    request_hash TEXT    NOT NULL,
+    status       INTEGER,
+    content_type TEXT,
+    body         BLOB,
+    created_at   INTEGER NOT NULL,
+    PRIMARY KEY (household_id, user_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys (created_at);
@@ -0,0 +1,167 @@
+//! Personal access tokens REST API: list, create and revoke the current user's tokens.
+//!
+//! Managing tokens needs a login session (JWT); requests made with a personal access token are
+//! rejected so a leaked token cannot mint more.
+
+use axum::routing::{delete, get};
+use axum::{
+    Json, Router,
+    extract::{Extension, Path, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::auth::{CurrentSessionId, CurrentUserId};
+use crate::api::{error::ApiError, state::AppState};
+use crate::auth::token;
+use crate::db;
+use crate::db::household::current_household;
+use crate::domain::api_token::{API_TOKEN_PREFIX, ApiToken, TokenScope};
+
+/// Request body for creating a token.
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    /// `read` (default) or `write`.
+    pub scope: Option<String>,
+    /// Days until the token expires; omitted = never.
+    pub expires_in_days: Option<u32>,
+}
+
+/// Response body: a token without its secret.
+#[derive(Debug, serde::Serialize)]
+pub struct ApiTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub scope: String,
+    pub household_id: Uuid,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+/// Response body for create: the token and its secret, which is only shown this once.
+#[derive(Debug, serde::Serialize)]
+pub struct CreatedApiTokenResponse {
+    #[serde(flatten)]
+    pub api_token: ApiTokenResponse,
+    pub token: String,
+}
+
+fn api_token_to_response(t: &ApiToken) -> ApiTokenResponse {
+    ApiTokenResponse {
+        id: t.id(),
+        name: t.name().to_string(),
+        scope: t.scope().to_string(),
+        household_id: t.household_id(),
+        created_at: t.created_at(),
+        expires_at: t.expires_at(),
+        last_used_at: t.last_used_at(),
+        revoked_at: t.revoked_at(),
+    }
+}
+
+/// Reject requests not made with a login session.
+fn require_session(session: Option<&Extension<CurrentSessionId>>) -> Result<(), ApiError> {
+    if session.is_none() {
+        return Err(ApiError::Forbidden(
+            "API tokens cannot manage API tokens.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// GET /api/v1/me/tokens — list the current user's tokens, newest first.
+pub async fn list_api_tokens(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(user_id)): Extension<CurrentUserId>,
+    session: Option<Extension<CurrentSessionId>>,
+) -> Result<Json<Vec<ApiTokenResponse>>, ApiError> {
+    require_session(session.as_ref())?;
+    let tokens = db::api_token::list_for_user(&state.pool, user_id)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(Json(tokens.iter().map(api_token_to_response).collect()))
+}
+
+/// POST /api/v1/me/tokens — create a token for the current user and household.
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(user_id)): Extension<CurrentUserId>,
+    session: Option<Extension<CurrentSessionId>>,
+    Json(body): Json<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedApiTokenResponse>), ApiError> {
+    require_session(session.as_ref())?;
+    if body.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Name is required.".to_string()));
+    }
+    let scope: TokenScope = body
+        .scope
+        .as_deref()
+        .unwrap_or("read")
+        .parse()
+        .map_err(|_| ApiError::BadRequest("Scope must be read or write.".to_string()))?;
+    if body.expires_in_days == Some(0) {
+        return Err(ApiError::BadRequest(
+            "expires_in_days must be at least 1.".to_string(),
+        ));
+    }
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = body
+        .expires_in_days
+        .map(|days| now + i64::from(days) * 86_400);
+    let api_token = ApiToken::new(
+        Uuid::new_v4(),
+        user_id,
+        current_household(),
+        body.name.trim().to_string(),
+        scope,
+        now,
+        expires_at,
+        None,
+        None,
+    )
+    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let secret = format!("{API_TOKEN_PREFIX}{}", token::generate());
+    db::api_token::insert(&state.pool, &api_token, &token::hash(&secret))
+        .await
+        .map_err(ApiError::internal)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiTokenResponse {
+            api_token: api_token_to_response(&api_token),
+            token: secret,
+        }),
+    ))
+}
+
+/// DELETE /api/v1/me/tokens/:id — revoke one of the current user's tokens.
+pub async fn revoke_api_token(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(user_id)): Extension<CurrentUserId>,
+    session: Option<Extension<CurrentSessionId>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    require_session(session.as_ref())?;
+    let api_token = db::api_token::get_by_id(&state.pool, id)
+        .await
+        .map_err(ApiError::internal)?
+        .filter(|t| t.user_id() == user_id)
+        .ok_or_else(|| ApiError::NotFound("API token not found.".to_string()))?;
+    db::api_token::revoke(&state.pool, api_token.id())
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for /api/v1/me/tokens.
+pub fn route() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/v1/me/tokens",
+            get(list_api_tokens).post(create_api_token),
+        )
+        .route("/api/v1/me/tokens/{id}", delete(revoke_api_token))
+}
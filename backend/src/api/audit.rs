@@ -0,0 +1,202 @@
+//! Audit REST API: list recorded changes.
+
+use axum::routing::get;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::pagination::{self, ListResponse};
+use crate::api::{error::ApiError, state::AppState};
+use crate::db;
+use crate::db::audit::{AuditEntity, AuditEntry, AuditFilter};
+
+/// Query params for GET /api/v1/audit.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListAuditQuery {
+    /// Only changes of this row.
+    pub entity_id: Option<Uuid>,
+    /// Only changes of this entity type (`category`, `product`, `variation`, `location`,
+    /// `review`, `purchase`, `user`).
+    pub entity_type: Option<String>,
+    /// Page size; when set the response is a page envelope (see [`ListResponse`]).
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+/// One audit log entry.
+#[derive(Debug, Serialize)]
+pub struct AuditEntryResponse {
+    pub id: i64,
+    /// Acting user id, or `cli`.
+    pub actor: String,
+    /// Name of the acting user; `null` for `cli` or a removed user.
+    pub actor_name: Option<String>,
+    pub entity_type: &'static str,
+    pub entity_id: Uuid,
+    pub action: &'static str,
+    /// Changed columns as `{"column": {"before": ..., "after": ...}}`.
+    pub changes: serde_json::Value,
+    pub created_at: i64,
+}
+
+fn entry_to_response(entry: AuditEntry) -> AuditEntryResponse {
+    AuditEntryResponse {
+        id: entry.id,
+        actor: entry.actor.as_stored(),
+        actor_name: entry.actor_name,
+        entity_type: entry.entity.name(),
+        entity_id: entry.entity_id,
+        action: entry.action.name(),
+        changes: entry.changes,
+        created_at: entry.created_at,
+    }
+}
+
+fn map_db_error(e: &db::DbError) -> ApiError {
+    match e {
+        db::DbError::InvalidData(msg) => ApiError::BadRequest(msg.clone()),
+        db::DbError::Sqlx(_) | db::DbError::Migrate(_) => ApiError::internal(e),
+    }
+}
+
+/// GET /api/v1/audit — recorded changes, newest first. `entity_id` and `entity_type` filter;
+/// `limit` and `cursor` select the page (see [`crate::api::pagination`]).
+pub async fn list_audit(
+    State(state): State<AppState>,
+    Query(q): Query<ListAuditQuery>,
+) -> Result<Json<ListResponse<AuditEntryResponse>>, ApiError> {
+    let entity = q
+        .entity_type
+        .as_deref()
+        .map(|s| {
+            AuditEntity::from_param(s)
+                .ok_or_else(|| ApiError::BadRequest("Invalid entity_type.".to_string()))
+        })
+        .transpose()?;
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), db::audit::AUDIT_SORT_NAME)?;
+    let filter = AuditFilter {
+        entity_id: q.entity_id,
+        entity,
+    };
+    let result = db::audit::list_page(&state.pool, &filter, &page)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(Json(ListResponse::from_page(
+        result,
+        q.limit.is_some(),
+        entry_to_response,
+    )))
+}
+
+/// Router for the audit endpoint.
+pub fn route() -> Router<AppState> {
+    Router::new().route("/api/v1/audit", get(list_audit))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+    use crate::db::audit::{Actor, with_actor};
+    use crate::domain::location::Location;
+    use crate::test_helpers::{insert_location, insert_user};
+
+    async fn test_pool() -> (AppState, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("audit_test.db");
+        let path_str = db_path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path_str).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        let state = AppState {
+            config: Config {
+                database_path: path_str,
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: std::env::temp_dir()
+                    .join("pocketratings-audit-test.pid")
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            pool,
+        };
+        (state, dir)
+    }
+
+    async fn get_json(state: &AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = route()
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn list_audit_returns_changes_of_entity_with_actor() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Alice", "alice@example.com").await;
+        let location_id = insert_location(&state.pool, "Corner Shop").await;
+        let renamed = Location::new(location_id, "Corner Store".to_string(), None).expect("valid");
+        with_actor(
+            Actor::User(user_id),
+            db::location::update(&state.pool, &renamed),
+        )
+        .await
+        .expect("update");
+
+        let (status, json) =
+            get_json(&state, &format!("/api/v1/audit?entity_id={location_id}")).await;
+        assert_eq!(status, StatusCode::OK);
+        let items = json.as_array().expect("array");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["action"], "update");
+        assert_eq!(items[0]["actor"], user_id.to_string());
+        assert_eq!(items[0]["actor_name"], "Alice");
+        assert_eq!(
+            items[0]["changes"],
+            serde_json::json!({ "name": { "before": "Corner Shop", "after": "Corner Store" } })
+        );
+        assert_eq!(items[1]["action"], "insert");
+        assert_eq!(items[1]["actor"], "cli");
+        assert!(items[1]["actor_name"].is_null());
+
+        let (status, json) = get_json(&state, "/api/v1/audit?entity_type=location&limit=1").await;
+        assert_eq!(status, StatusCode::OK);
+        let items = json["items"].as_array().expect("items");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["action"], "update");
+        assert!(json["next_cursor"].is_string());
+
+        let (status, _) = get_json(&state, "/api/v1/audit?entity_type=nope").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,16 +1,22 @@
-//! JWT issue and verify for API auth.
+//! JWT issue and verify for API auth. Tokens are signed with the current key of a
+//! [`JwtKeys`] set and name it in their `kid` header.
 
-use jsonwebtoken::{
-    DecodingKey, EncodingKey, Header, Validation, decode, encode, get_current_timestamp,
-};
+use jsonwebtoken::{Header, Validation, decode, decode_header, encode, get_current_timestamp};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-/// JWT claims: subject (user id) and expiration.
+use crate::auth::jwt_keys::JwtKeys;
+
+/// JWT claims: subject (user id), session id, active household, and expiration.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Claims {
     /// Subject — user id (UUID string).
     pub sub: String,
+    /// Token id — the login session (UUID string), checked against revoked sessions.
+    pub jti: String,
+    /// Active household id (UUID string). Absent in tokens issued before households existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub household: Option<String>,
     /// Expiration time (Unix timestamp, seconds).
     pub exp: u64,
 }
@@ -25,55 +31,85 @@ pub enum JwtError {
     Invalid,
 }
 
-/// Issue a new JWT for the given user with the given expiration.
+/// Issue a new JWT for the given user, session and active household with the given expiration,
+/// signed with the signing key of `keys`.
 ///
 /// # Errors
 ///
 /// Returns [`JwtError::Encode`] if encoding fails.
-pub fn issue_token(secret: &str, user_id: Uuid, expiration_secs: u64) -> Result<String, JwtError> {
+pub fn issue_token(
+    keys: &JwtKeys,
+    user_id: Uuid,
+    session_id: Uuid,
+    household_id: Uuid,
+    expiration_secs: u64,
+) -> Result<String, JwtError> {
     let now = get_current_timestamp();
     let claims = Claims {
         sub: user_id.to_string(),
+        jti: session_id.to_string(),
+        household: Some(household_id.to_string()),
         exp: now + expiration_secs,
     };
-    let key = EncodingKey::from_secret(secret.as_ref());
-    encode(&Header::default(), &claims, &key).map_err(|_| JwtError::Encode)
+    let key = keys.signing_key();
+    let mut header = Header::new(key.algorithm());
+    header.kid = Some(key.kid().to_string());
+    let encoding = key.encoding().ok_or(JwtError::Encode)?;
+    encode(&header, &claims, encoding).map_err(|_| JwtError::Encode)
 }
 
-/// Verify a JWT and return its claims.
+/// Verify a JWT with the key named by its `kid` header and return its claims. The token's
+/// algorithm must match the key's.
 ///
 /// # Errors
 ///
-/// Returns [`JwtError::Invalid`] if the token is missing, malformed, expired, or signature is wrong.
-pub fn verify_token(secret: &str, token: &str) -> Result<Claims, JwtError> {
-    let key = DecodingKey::from_secret(secret.as_ref());
-    let mut validation = Validation::default();
+/// Returns [`JwtError::Invalid`] if the token is missing, malformed, expired, signed with an
+/// unknown or retired key, or signature is wrong.
+pub fn verify_token(keys: &JwtKeys, token: &str) -> Result<Claims, JwtError> {
+    let header = decode_header(token).map_err(|_| JwtError::Invalid)?;
+    let key = keys
+        .verification_key(header.kid.as_deref())
+        .ok_or(JwtError::Invalid)?;
+    let mut validation = Validation::new(key.algorithm());
     validation.leeway = 0;
-    let token_data = decode::<Claims>(token, &key, &validation).map_err(|_| JwtError::Invalid)?;
+    let token_data =
+        decode::<Claims>(token, key.decoding(), &validation).map_err(|_| JwtError::Invalid)?;
     Ok(token_data.claims)
 }
 
 #[cfg(test)]
 mod tests {
+    use jsonwebtoken::{Algorithm, EncodingKey};
     use uuid::Uuid;
 
     use super::*;
+    use crate::auth::jwt_keys::JwtKey;
+    use crate::auth::jwt_keys::tests::{ED25519_PRIVATE_PEM, ED25519_PUBLIC_PEM};
+
+    fn issue(keys: &JwtKeys) -> String {
+        issue_token(keys, Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), 3600).expect("issue")
+    }
 
     #[test]
     fn issue_then_verify_succeeds() {
-        let secret = "test-secret";
+        let keys = JwtKeys::from_secret("test-secret");
         let user_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        let household_id = Uuid::new_v4();
         let exp_secs = 3600u64;
-        let token = issue_token(secret, user_id, exp_secs).expect("issue should succeed");
-        let claims = verify_token(secret, &token).expect("verify should succeed");
+        let token = issue_token(&keys, user_id, session_id, household_id, exp_secs)
+            .expect("issue should succeed");
+        let claims = verify_token(&keys, &token).expect("verify should succeed");
         assert_eq!(claims.sub, user_id.to_string());
+        assert_eq!(claims.jti, session_id.to_string());
+        assert_eq!(claims.household, Some(household_id.to_string()));
         assert!(claims.exp > get_current_timestamp());
     }
 
     #[test]
     fn verify_with_wrong_secret_fails() {
-        let token = issue_token("secret-a", Uuid::new_v4(), 3600).expect("issue");
-        let err = verify_token("secret-b", &token).unwrap_err();
+        let token = issue(&JwtKeys::from_secret("secret-a"));
+        let err = verify_token(&JwtKeys::from_secret("secret-b"), &token).unwrap_err();
         assert!(matches!(err, JwtError::Invalid));
     }
 
@@ -84,17 +120,111 @@ mod tests {
         let now = get_current_timestamp();
         let claims = Claims {
             sub: user_id.to_string(),
+            jti: Uuid::new_v4().to_string(),
+            household: None,
             exp: now.saturating_sub(1),
         };
         let key = EncodingKey::from_secret(secret.as_ref());
         let token = encode(&Header::default(), &claims, &key).expect("encode");
-        let err = verify_token(secret, &token).unwrap_err();
+        let err = verify_token(&JwtKeys::from_secret(secret), &token).unwrap_err();
         assert!(matches!(err, JwtError::Invalid));
     }
 
     #[test]
     fn verify_garbage_fails() {
-        let err = verify_token("secret", "not.a.valid.jwt").unwrap_err();
+        let err = verify_token(&JwtKeys::from_secret("secret"), "not.a.valid.jwt").unwrap_err();
         assert!(matches!(err, JwtError::Invalid));
     }
+
+    #[test]
+    fn tokens_of_previous_keys_verify_until_retired() {
+        let old = JwtKeys::from_secret("old-secret");
+        let old_token = issue(&old);
+        assert_eq!(
+            decode_header(&old_token).expect("header").kid.as_deref(),
+            Some("default")
+        );
+
+        let rotated = JwtKeys::new(
+            vec![
+                JwtKey::secret("2026-10", "new-secret"),
+                JwtKey::secret("default", "old-secret"),
+            ],
+            None,
+            Vec::new(),
+        )
+        .expect("keys");
+        let new_token = issue(&rotated);
+        assert_eq!(
+            decode_header(&new_token).expect("header").kid.as_deref(),
+            Some("2026-10")
+        );
+        assert!(verify_token(&rotated, &old_token).is_ok());
+        assert!(verify_token(&rotated, &new_token).is_ok());
+        assert!(
+            verify_token(&old, &new_token).is_err(),
+            "unknown kid is rejected"
+        );
+
+        let retired = JwtKeys::new(
+            vec![
+                JwtKey::secret("2026-10", "new-secret"),
+                JwtKey::secret("default", "old-secret"),
+            ],
+            None,
+            vec!["default".to_string()],
+        )
+        .expect("keys");
+        assert!(matches!(
+            verify_token(&retired, &old_token),
+            Err(JwtError::Invalid)
+        ));
+        assert!(verify_token(&retired, &new_token).is_ok());
+    }
+
+    #[test]
+    fn eddsa_keys_sign_and_verify() {
+        let public = JwtKey::from_public_pem("ed", Algorithm::EdDSA, ED25519_PUBLIC_PEM.as_bytes())
+            .expect("public key");
+        let signing = JwtKeys::new(
+            vec![
+                public
+                    .clone()
+                    .with_private_pem(ED25519_PRIVATE_PEM.as_bytes())
+                    .expect("private key"),
+            ],
+            None,
+            Vec::new(),
+        )
+        .expect("keys");
+        let token = issue(&signing);
+        assert_eq!(decode_header(&token).expect("header").alg, Algorithm::EdDSA);
+
+        // A server that only holds the public key can verify it.
+        let verifying = JwtKeys::new(
+            vec![JwtKey::secret("hs", "secret"), public],
+            None,
+            Vec::new(),
+        )
+        .expect("keys");
+        assert!(verify_token(&verifying, &token).is_ok());
+    }
+
+    #[test]
+    fn token_with_algorithm_other_than_its_key_is_rejected() {
+        let keys = JwtKeys::from_secret("secret");
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            jti: Uuid::new_v4().to_string(),
+            household: None,
+            exp: get_current_timestamp() + 3600,
+        };
+        let mut header = Header::new(Algorithm::HS512);
+        header.kid = Some("default".to_string());
+        let token = encode(&header, &claims, &EncodingKey::from_secret(b"secret")).expect("encode");
+        assert!(matches!(
+            verify_token(&keys, &token),
+            Err(JwtError::Invalid)
+        ));
+    }
 }
@@ -1,10 +1,20 @@
-//! `POST /api/v1/auth/login` — authenticate and receive a JWT.
+//! `POST /api/v1/auth/login` — authenticate and receive a JWT for one of the user's households.
+//!
+//! Users with two-factor authentication get a short-lived challenge instead, which they redeem
+//! with a code at `POST /api/v1/auth/login/totp` (see [`super::two_factor`]).
 
-use axum::{Json, extract::State};
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+};
 use serde::Deserialize;
 
 use crate::api::{error::ApiError, state::AppState};
-use crate::auth::password;
+use crate::auth::{lockout, password, token};
+use crate::config::IpNet;
 use crate::db;
 
 /// Request body for login.
@@ -12,41 +22,196 @@ use crate::db;
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// Household to work in; defaults to the first household the user joined.
+    #[serde(default)]
+    pub household_id: Option<uuid::Uuid>,
 }
 
-/// Response body: token only.
+/// Response body: short-lived access token and the session's refresh token.
 #[derive(Debug, serde::Serialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
+}
+
+/// Response body when the user has two-factor authentication enabled.
+#[derive(Debug, serde::Serialize)]
+pub struct TwoFactorChallengeResponse {
+    /// Always `true`.
+    pub two_factor_required: bool,
+    /// Pass to `POST /api/v1/auth/login/totp` together with a code.
+    pub challenge: String,
+    pub expires_at: i64,
+}
+
+/// Response body for login: tokens, or a two-factor challenge.
+#[derive(Debug, serde::Serialize)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Tokens(LoginResponse),
+    TwoFactorRequired(TwoFactorChallengeResponse),
+}
+
+/// How long a two-factor challenge can be redeemed.
+const CHALLENGE_LIFETIME_SECS: i64 = 300;
+
+/// Client IP for per-IP lockout: the peer address, or, when the peer is a trusted reverse proxy
+/// (see [`crate::config::Config::trusted_proxies`]) or unknown, the last `X-Forwarded-For` entry
+/// that is not itself a trusted proxy.
+fn client_ip(peer: Option<SocketAddr>, headers: &HeaderMap, trusted: &[IpNet]) -> Option<IpAddr> {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|net| net.contains(ip));
+    let peer = peer.map(|addr| addr.ip());
+    if peer.is_some_and(|ip| !is_trusted(ip)) {
+        return peer;
+    }
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let mut hops = forwarded
+        .rsplit(',')
+        .map_while(|ip| ip.trim().parse::<IpAddr>().ok())
+        .peekable();
+    while let Some(ip) = hops.next() {
+        if !is_trusted(ip) || hops.peek().is_none() {
+            return Some(ip);
+        }
+    }
+    peer
+}
+
+/// Lockout keys for a login attempt: the email key first, then the client IP key if known.
+pub(super) fn lockout_keys(
+    state: &AppState,
+    email: &str,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: &HeaderMap,
+) -> Vec<String> {
+    let mut keys = vec![lockout::email_key(email)];
+    if let Some(ip) = client_ip(
+        connect_info.map(|Extension(ConnectInfo(addr))| addr),
+        headers,
+        &state.config.trusted_proxies,
+    ) {
+        keys.push(lockout::ip_key(ip));
+    }
+    keys
+}
+
+/// Reject the attempt with `429 Too Many Requests` if any of `keys` is locked at `now`.
+pub(super) async fn ensure_not_locked(
+    state: &AppState,
+    keys: &[String],
+    now: i64,
+) -> Result<(), ApiError> {
+    if let Some(until) = db::login_attempt::locked_until(&state.pool, keys, now)
+        .await
+        .map_err(ApiError::internal)?
+    {
+        return Err(ApiError::TooManyRequests {
+            message: "Too many failed login attempts. Try again later.".to_string(),
+            retry_after_secs: u64::try_from(until - now).unwrap_or(1),
+        });
+    }
+    Ok(())
+}
+
+/// Count a failed attempt against each of `keys`.
+pub(super) async fn record_failures(
+    state: &AppState,
+    keys: &[String],
+    now: i64,
+) -> Result<(), ApiError> {
+    for key in keys {
+        db::login_attempt::record_failure(&state.pool, key, now)
+            .await
+            .map_err(ApiError::internal)?;
+    }
+    Ok(())
+}
+
+/// Forget the failed attempts of each of `keys` after a successful login.
+pub(super) async fn clear_failures(state: &AppState, keys: &[String]) -> Result<(), ApiError> {
+    for key in keys {
+        db::login_attempt::clear(&state.pool, key)
+            .await
+            .map_err(ApiError::internal)?;
+    }
+    Ok(())
 }
 
 /// Handler for `POST /api/v1/auth/login`.
+///
+/// Failed attempts are counted per email and per client IP; after too many, further attempts are
+/// rejected with `429 Too Many Requests` and `Retry-After` (see [`lockout`]).
 pub async fn login(
     State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
     Json(body): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, ApiError> {
+) -> Result<Json<LoginOutcome>, ApiError> {
     if body.email.is_empty() {
         return Err(ApiError::BadRequest("Email is required.".to_string()));
     }
+    let keys = lockout_keys(&state, &body.email, connect_info, &headers);
+    let now = chrono::Utc::now().timestamp();
+    ensure_not_locked(&state, &keys, now).await?;
+
     let user = db::user::get_by_email(&state.pool, &body.email)
         .await
-        .map_err(|_| ApiError::Internal)?;
-    let user =
-        user.ok_or_else(|| ApiError::Unauthorized("Invalid email or password.".to_string()))?;
-    let ok = password::verify_password(&body.password, user.password())
-        .map_err(|_| ApiError::Internal)?;
-    if !ok {
+        .map_err(ApiError::internal)?;
+    let verified = match &user {
+        Some(user) => password::verify_password(&body.password, user.password())
+            .map_err(ApiError::internal)?,
+        None => false,
+    };
+    let Some(user) = user.filter(|_| verified) else {
+        record_failures(&state, &keys, now).await?;
         return Err(ApiError::Unauthorized(
             "Invalid email or password.".to_string(),
         ));
+    };
+
+    let households = db::household::list_for_user(&state.pool, user.id())
+        .await
+        .map_err(ApiError::internal)?;
+    let household = body.household_id.map_or_else(
+        || households.first(),
+        |id| households.iter().find(|h| h.id() == id),
+    );
+    let household = household.ok_or_else(|| {
+        ApiError::Forbidden("Not a member of the requested household.".to_string())
+    })?;
+
+    let two_factor = db::totp::get_secret(&state.pool, user.id())
+        .await
+        .map_err(ApiError::internal)?;
+    if two_factor.is_some() {
+        // The email stays counted until the second step succeeds.
+        let challenge = token::generate();
+        let expires_at = now + CHALLENGE_LIFETIME_SECS;
+        db::login_challenge::insert(
+            &state.pool,
+            user.id(),
+            household.id(),
+            &token::hash(&challenge),
+            now,
+            expires_at,
+        )
+        .await
+        .map_err(ApiError::internal)?;
+        return Ok(Json(LoginOutcome::TwoFactorRequired(
+            TwoFactorChallengeResponse {
+                two_factor_required: true,
+                challenge,
+                expires_at,
+            },
+        )));
     }
-    let token = crate::api::auth::jwt::issue_token(
-        &state.config.jwt_secret,
-        user.id(),
-        state.config.jwt_expiration_seconds,
-    )
-    .map_err(|_| ApiError::Internal)?;
-    Ok(Json(LoginResponse { token }))
+    clear_failures(&state, &keys).await?;
+    crate::api::auth::session::start_session(&state, user.id(), household.id())
+        .await
+        .map(|tokens| Json(LoginOutcome::Tokens(tokens)))
 }
 
 /// Route for this endpoint (public, no auth).
@@ -61,10 +226,12 @@ mod tests {
     use http_body_util::BodyExt;
     use tower::ServiceExt;
 
-    use crate::api::auth::login::route;
+    use crate::api::auth::login::{client_ip, route};
     use crate::api::state::AppState;
-    use crate::auth::password;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::auth::{lockout, password};
+    use crate::config::IpNet;
+    use crate::config::{Config, LogFormat};
     use crate::db;
     use sqlx::SqlitePool;
     use uuid::Uuid;
@@ -72,10 +239,15 @@ mod tests {
     fn test_config(db_path: &str) -> Config {
         Config {
             database_path: db_path.to_string(),
-            jwt_secret: "test-secret".to_string(),
+            jwt_keys: JwtKeys::from_secret("test-secret"),
             jwt_expiration_seconds: 3600,
             jwt_refresh_threshold_seconds: 600,
+            refresh_token_expiration_seconds: 86400,
+            idempotency_window_seconds: 86400,
+            trusted_proxies: Vec::new(),
             bind: "127.0.0.1:3099".to_string(),
+            metrics_bind: None,
+            log_format: LogFormat::Text,
             pid_file: std::env::temp_dir()
                 .join("pocketratings-login-test.pid")
                 .to_string_lossy()
@@ -111,6 +283,9 @@ mod tests {
         .execute(pool)
         .await
         .expect("insert user");
+        db::household::add_member(pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+            .await
+            .expect("add member");
         id
     }
 
@@ -146,6 +321,7 @@ mod tests {
             .to_bytes();
         let json: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
         assert!(json.get("token").and_then(|t| t.as_str()).is_some());
+        assert!(json.get("refresh_token").and_then(|t| t.as_str()).is_some());
     }
 
     #[tokio::test]
@@ -198,4 +374,168 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
+
+    #[tokio::test]
+    async fn login_returns_403_when_not_member_of_requested_household() {
+        let (pool, path_str, _dir) = test_pool().await;
+        let _ = setup_db_with_user(&pool, "u@example.com", "secret123").await;
+        let state = AppState {
+            config: test_config(&path_str),
+            pool,
+        };
+        let app = route().with_state(state);
+
+        let body = serde_json::json!({
+            "email": "u@example.com",
+            "password": "secret123",
+            "household_id": Uuid::new_v4(),
+        });
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/auth/login")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).expect("json")))
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    /// POST a login for `email`/`password`, optionally from a forwarded client IP.
+    async fn post_login(
+        app: &axum::Router,
+        email: &str,
+        password: &str,
+        forwarded_for: Option<&str>,
+    ) -> axum::response::Response {
+        let body = serde_json::json!({ "email": email, "password": password });
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/api/v1/auth/login")
+            .header("content-type", "application/json");
+        if let Some(ip) = forwarded_for {
+            builder = builder.header("x-forwarded-for", ip);
+        }
+        app.clone()
+            .oneshot(
+                builder
+                    .body(Body::from(serde_json::to_vec(&body).expect("json")))
+                    .expect("request"),
+            )
+            .await
+            .expect("service")
+    }
+
+    #[tokio::test]
+    async fn login_locks_email_after_repeated_failures_until_cleared() {
+        let (pool, path_str, _dir) = test_pool().await;
+        let _ = setup_db_with_user(&pool, "u@example.com", "secret123").await;
+        let state = AppState {
+            config: test_config(&path_str),
+            pool: pool.clone(),
+        };
+        let app = route().with_state(state);
+
+        for _ in 0..=lockout::FREE_ATTEMPTS {
+            let response = post_login(&app, "u@example.com", "wrong", None).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = post_login(&app, "U@example.com", "secret123", None).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: i64 = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .expect("Retry-After header");
+        assert!(retry_after > 0 && retry_after <= lockout::BASE_LOCKOUT_SECS);
+
+        db::login_attempt::clear(&pool, &lockout::email_key("u@example.com"))
+            .await
+            .expect("clear");
+        let response = post_login(&app, "u@example.com", "secret123", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn login_locks_client_ip_across_emails() {
+        let (pool, path_str, _dir) = test_pool().await;
+        let _ = setup_db_with_user(&pool, "u@example.com", "secret123").await;
+        let state = AppState {
+            config: test_config(&path_str),
+            pool,
+        };
+        let app = route().with_state(state);
+
+        for n in 0..=lockout::FREE_ATTEMPTS {
+            let email = format!("guess{n}@example.com");
+            let response = post_login(&app, &email, "wrong", Some("203.0.113.7")).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = post_login(&app, "u@example.com", "secret123", Some("203.0.113.7")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let response = post_login(&app, "u@example.com", "secret123", Some("198.51.100.1")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn successful_login_clears_client_ip_failures() {
+        let (pool, path_str, _dir) = test_pool().await;
+        let _ = setup_db_with_user(&pool, "u@example.com", "secret123").await;
+        let state = AppState {
+            config: test_config(&path_str),
+            pool,
+        };
+        let app = route().with_state(state);
+
+        for _ in 0..2 {
+            for n in 0..lockout::FREE_ATTEMPTS {
+                let email = format!("guess{n}@example.com");
+                let response = post_login(&app, &email, "wrong", Some("203.0.113.7")).await;
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            }
+            let response =
+                post_login(&app, "u@example.com", "secret123", Some("203.0.113.7")).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    #[test]
+    fn client_ip_trusts_forwarded_for_only_from_trusted_proxies() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "10.0.0.1, 203.0.113.7, 172.18.0.5".parse().expect("header"),
+        );
+        let trusted: Vec<IpNet> = ["127.0.0.0/8", "172.16.0.0/12"]
+            .iter()
+            .map(|net| net.parse().expect("net"))
+            .collect();
+        let loopback: std::net::SocketAddr = "127.0.0.1:40000".parse().expect("addr");
+        let container: std::net::SocketAddr = "172.18.0.2:40000".parse().expect("addr");
+        let remote: std::net::SocketAddr = "198.51.100.1:40000".parse().expect("addr");
+
+        for proxy in [loopback, container] {
+            assert_eq!(
+                client_ip(Some(proxy), &headers, &trusted),
+                Some("203.0.113.7".parse().expect("ip"))
+            );
+        }
+        assert_eq!(
+            client_ip(Some(container), &headers, &trusted[..1]),
+            Some(container.ip())
+        );
+        assert_eq!(
+            client_ip(Some(remote), &headers, &trusted),
+            Some(remote.ip())
+        );
+        assert_eq!(
+            client_ip(Some(loopback), &axum::http::HeaderMap::new(), &trusted),
+            Some(loopback.ip())
+        );
+    }
 }
@@ -1,16 +1,20 @@
-//! Auth middleware: require valid JWT and attach current user id to request.
+//! Auth middleware: require a valid JWT or personal access token and attach current user id to request.
 
 use axum::{
     extract::Request,
-    http::{HeaderValue, header},
+    http::{HeaderValue, Method, header},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use uuid::Uuid;
 
 use crate::api::auth::jwt;
+use crate::api::auth::role::CurrentRole;
 use crate::api::error::ApiError;
 use crate::api::state::AppState;
+use crate::db::audit::{Actor, with_actor};
+use crate::db::household::{DEFAULT_HOUSEHOLD_ID, current_household, with_household};
+use crate::domain::api_token::{API_TOKEN_PREFIX, TokenScope};
 use axum::extract::State;
 
 /// Response header with a new JWT when sliding refresh is applied.
@@ -20,8 +24,90 @@ pub const X_NEW_TOKEN: &str = "x-new-token";
 #[derive(Debug, Clone)]
 pub struct CurrentUserId(pub Uuid);
 
-/// Auth middleware: require `Authorization: Bearer <token>`, verify JWT, set `CurrentUserId` in extensions.
-/// If the token expires within `jwt_refresh_threshold_seconds`, issues a new token and adds `X-New-Token` header.
+/// Extension value: the login session of the current token (set by auth middleware).
+#[derive(Debug, Clone)]
+pub struct CurrentSessionId(pub Uuid);
+
+/// Who a request is authenticated as, from a JWT or a personal access token.
+struct Authenticated {
+    user_id: Uuid,
+    household_id: Uuid,
+    /// Session id and expiry of a JWT; `None` for personal access tokens.
+    session: Option<(Uuid, u64)>,
+    /// `false` for read-only personal access tokens.
+    can_write: bool,
+}
+
+/// Verify a JWT and that its session (`jti`) belongs to its subject and is not revoked or expired.
+/// Tokens without a household claim use the default household.
+async fn authenticate_jwt(state: &AppState, token: &str) -> Result<Authenticated, ApiError> {
+    let Ok(claims) = jwt::verify_token(&state.config.jwt_keys, token) else {
+        return Err(ApiError::Unauthorized(
+            "invalid or expired token".to_string(),
+        ));
+    };
+    let Ok(user_id) = Uuid::parse_str(&claims.sub) else {
+        return Err(ApiError::Unauthorized("Invalid token subject.".to_string()));
+    };
+    let Ok(session_id) = Uuid::parse_str(&claims.jti) else {
+        return Err(ApiError::Unauthorized("Invalid token id.".to_string()));
+    };
+    let session = crate::db::session::get_by_id(&state.pool, session_id)
+        .await
+        .map_err(ApiError::internal)?;
+    if !session
+        .is_some_and(|s| s.user_id() == user_id && s.is_active(chrono::Utc::now().timestamp()))
+    {
+        return Err(ApiError::Unauthorized(
+            "Session expired or revoked.".to_string(),
+        ));
+    }
+    let household_id = match claims.household.as_deref().map(Uuid::parse_str) {
+        None => DEFAULT_HOUSEHOLD_ID,
+        Some(Ok(id)) => id,
+        Some(Err(_)) => {
+            return Err(ApiError::Unauthorized(
+                "Invalid token household.".to_string(),
+            ));
+        }
+    };
+    Ok(Authenticated {
+        user_id,
+        household_id,
+        session: Some((session_id, claims.exp)),
+        can_write: true,
+    })
+}
+
+/// Look up an active personal access token and record its use.
+async fn authenticate_api_token(state: &AppState, token: &str) -> Result<Authenticated, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+    let api_token = crate::db::api_token::get_active_by_hash(
+        &state.pool,
+        &crate::auth::token::hash(token),
+        now,
+    )
+    .await
+    .map_err(ApiError::internal)?
+    .ok_or_else(|| ApiError::Unauthorized("Invalid or expired API token.".to_string()))?;
+    crate::db::api_token::touch(&state.pool, api_token.id(), now)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(Authenticated {
+        user_id: api_token.user_id(),
+        household_id: api_token.household_id(),
+        session: None,
+        can_write: api_token.scope() == TokenScope::Write,
+    })
+}
+
+/// Auth middleware: require `Authorization: Bearer <token>` with a JWT (see [`authenticate_jwt`])
+/// or a personal access token (prefixed with [`API_TOKEN_PREFIX`]) of an active user, set
+/// `CurrentUserId`, `CurrentRole` (and `CurrentSessionId` for JWTs) in extensions. Read-only
+/// tokens may only make `GET` requests.
+/// The rest of the request runs with the user as audit actor (see [`crate::db::audit`]) and scoped
+/// to the token's household (see [`crate::db::household`]), which the user must be a member of.
+/// If a JWT expires within `jwt_refresh_threshold_seconds`, issues a new token and adds `X-New-Token` header.
 pub async fn auth_middleware(
     State(state): State<AppState>,
     mut request: Request,
@@ -38,23 +124,59 @@ pub async fn auth_middleware(
         return ApiError::Unauthorized("Missing or invalid bearer token.".to_string())
             .into_response();
     }
-    let Ok(claims) = jwt::verify_token(&state.config.jwt_secret, token) else {
-        return ApiError::Unauthorized("invalid or expired token".to_string()).into_response();
+    let authenticated = if token.starts_with(API_TOKEN_PREFIX) {
+        authenticate_api_token(&state, token).await
+    } else {
+        authenticate_jwt(&state, token).await
     };
-    let Ok(user_id) = Uuid::parse_str(&claims.sub) else {
-        return ApiError::Unauthorized("Invalid token subject.".to_string()).into_response();
+    let auth = match authenticated {
+        Ok(auth) => auth,
+        Err(e) => return e.into_response(),
     };
-    request.extensions_mut().insert(CurrentUserId(user_id));
+    if !auth.can_write && !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return ApiError::Forbidden("This API token is read-only.".to_string()).into_response();
+    }
+    match crate::db::household::is_member(&state.pool, auth.household_id, auth.user_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return ApiError::Forbidden("Not a member of this household.".to_string())
+                .into_response();
+        }
+        Err(e) => return ApiError::internal(e).into_response(),
+    }
+    let user = match crate::db::user::get_by_id(&state.pool, auth.user_id, false).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return ApiError::Unauthorized("User not found or deleted.".to_string())
+                .into_response();
+        }
+        Err(e) => return ApiError::internal(e).into_response(),
+    };
+    tracing::Span::current().record("user_id", tracing::field::display(auth.user_id));
+    request.extensions_mut().insert(CurrentUserId(auth.user_id));
+    request.extensions_mut().insert(CurrentRole(user.role()));
+    if let Some((session_id, _)) = auth.session {
+        request
+            .extensions_mut()
+            .insert(CurrentSessionId(session_id));
+    }
 
-    let mut response = next.run(request).await;
+    let mut response = with_household(
+        auth.household_id,
+        with_actor(Actor::User(auth.user_id), next.run(request)),
+    )
+    .await;
 
-    // Sliding expiration: if token expires within threshold, issue new token and set X-New-Token header.
+    // Sliding expiration: if a JWT expires within threshold, issue new token and set X-New-Token header.
     let now = jsonwebtoken::get_current_timestamp();
     let refresh_at = now + state.config.jwt_refresh_threshold_seconds;
-    if claims.exp <= refresh_at
+    if let Some((session_id, exp)) = auth.session
+        && exp <= refresh_at
         && let Ok(new_token) = jwt::issue_token(
-            &state.config.jwt_secret,
-            user_id,
+            &state.config.jwt_keys,
+            auth.user_id,
+            session_id,
+            auth.household_id,
             state.config.jwt_expiration_seconds,
         )
         && let Ok(hv) = HeaderValue::from_str(&new_token)
@@ -70,26 +192,84 @@ pub async fn auth_middleware(
 pub struct MeResponse {
     pub user_id: String,
     pub name: String,
+    pub email: String,
+    /// `admin`, `member` or `guest`.
+    pub role: String,
+    pub household: HouseholdRef,
+}
+
+/// The active household in [`MeResponse`].
+#[derive(Debug, serde::Serialize)]
+pub struct HouseholdRef {
+    pub id: String,
+    pub name: String,
+}
+
+/// Request body for `PATCH /api/v1/me`.
+#[derive(Debug, serde::Deserialize)]
+pub struct UpdateMeRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
 }
 
-/// `GET /api/v1/me` — return current user id and name. Protected.
+/// `GET /api/v1/me` — return current user id, name, email and role, and the active household.
+/// Protected.
 pub async fn me(
     axum::extract::Extension(CurrentUserId(user_id)): axum::extract::Extension<CurrentUserId>,
     axum::extract::State(state): axum::extract::State<AppState>,
 ) -> Result<axum::Json<MeResponse>, ApiError> {
     let user = crate::db::user::get_by_id(&state.pool, user_id, false)
         .await
-        .map_err(|_| ApiError::Internal)?;
+        .map_err(ApiError::internal)?;
+    let user = user.ok_or_else(|| ApiError::NotFound("User not found.".to_string()))?;
+    me_response(&state, &user).await.map(axum::Json)
+}
+
+/// `PATCH /api/v1/me` — change the current user's display name and/or email (not the role).
+/// Protected; open to every role.
+pub async fn update_me(
+    axum::extract::Extension(CurrentUserId(user_id)): axum::extract::Extension<CurrentUserId>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Json(body): axum::Json<UpdateMeRequest>,
+) -> Result<axum::Json<MeResponse>, ApiError> {
+    let user = crate::db::user::get_by_id(&state.pool, user_id, false)
+        .await
+        .map_err(ApiError::internal)?;
     let user = user.ok_or_else(|| ApiError::NotFound("User not found.".to_string()))?;
-    Ok(axum::Json(MeResponse {
-        user_id: user_id.to_string(),
+    let updated = crate::api::user::apply_update(
+        &state,
+        &user,
+        body.name.as_deref(),
+        body.email.as_deref(),
+        user.role(),
+    )
+    .await?;
+    me_response(&state, &updated).await.map(axum::Json)
+}
+
+async fn me_response(
+    state: &AppState,
+    user: &crate::domain::user::User,
+) -> Result<MeResponse, ApiError> {
+    let household = crate::db::household::get_by_id(&state.pool, current_household())
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::NotFound("Household not found.".to_string()))?;
+    Ok(MeResponse {
+        user_id: user.id().to_string(),
         name: user.name().to_string(),
-    }))
+        email: user.email().to_string(),
+        role: user.role().to_string(),
+        household: HouseholdRef {
+            id: household.id().to_string(),
+            name: household.name().to_string(),
+        },
+    })
 }
 
-/// Router for the /me endpoint (no layer; layer is applied in the main router).
+/// Router for the /me endpoints (no layer; layer is applied in the main router).
 pub fn me_route() -> axum::Router<AppState> {
-    axum::Router::new().route("/api/v1/me", axum::routing::get(me))
+    axum::Router::new().route("/api/v1/me", axum::routing::get(me).patch(update_me))
 }
 
 #[cfg(test)]
@@ -100,20 +280,29 @@ mod tests {
     use tower::ServiceExt;
 
     use crate::api::auth::jwt;
+    use crate::api::auth::session::start_session;
     use crate::api::router;
     use crate::api::state::AppState;
-    use crate::auth::password;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::auth::{password, token};
+    use crate::config::{Config, LogFormat};
     use crate::db;
+    use crate::domain::api_token::{API_TOKEN_PREFIX, ApiToken, TokenScope};
+    use crate::domain::household::Household;
     use uuid::Uuid;
 
     fn test_config(db_path: &str) -> Config {
         Config {
             database_path: db_path.to_string(),
-            jwt_secret: "test-secret".to_string(),
+            jwt_keys: JwtKeys::from_secret("test-secret"),
             jwt_expiration_seconds: 3600,
             jwt_refresh_threshold_seconds: 600,
+            refresh_token_expiration_seconds: 86400,
+            idempotency_window_seconds: 86400,
+            trusted_proxies: Vec::new(),
             bind: "127.0.0.1:3099".to_string(),
+            metrics_bind: None,
+            log_format: LogFormat::Text,
             pid_file: std::env::temp_dir()
                 .join("pocketratings-me-test.pid")
                 .to_string_lossy()
@@ -145,6 +334,9 @@ mod tests {
         .execute(&pool)
         .await
         .expect("insert user");
+        db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+            .await
+            .expect("add member");
         let state = AppState {
             config: test_config(&path_str),
             pool,
@@ -173,12 +365,10 @@ mod tests {
     #[tokio::test]
     async fn get_me_returns_200_and_user_id_and_name_with_valid_token() {
         let (state, user_id, _dir) = test_pool_with_user("u@ex.co", "pass").await;
-        let token = jwt::issue_token(
-            &state.config.jwt_secret,
-            user_id,
-            state.config.jwt_expiration_seconds,
-        )
-        .expect("issue token");
+        let token = start_session(&state, user_id, db::household::DEFAULT_HOUSEHOLD_ID)
+            .await
+            .expect("start session")
+            .token;
         let app = router::router(state);
 
         let response = app
@@ -205,5 +395,114 @@ mod tests {
             Some(user_id.to_string().as_str())
         );
         assert_eq!(json.get("name").and_then(|v| v.as_str()), Some("Test User"));
+        assert_eq!(
+            json.pointer("/household/name").and_then(|v| v.as_str()),
+            Some("Default")
+        );
+    }
+
+    #[tokio::test]
+    async fn get_me_returns_403_when_not_member_of_token_household() {
+        let (state, user_id, _dir) = test_pool_with_user("u@ex.co", "pass").await;
+        let other = Household::new(Uuid::new_v4(), "Other".to_string(), 1_700_000_000)
+            .expect("valid household");
+        db::household::insert(&state.pool, &other)
+            .await
+            .expect("insert household");
+        let token = start_session(&state, user_id, other.id())
+            .await
+            .expect("start session")
+            .token;
+        let app = router::router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/me")
+                    .header("authorization", format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn requests_with_revoked_session_return_401() {
+        let (state, user_id, _dir) = test_pool_with_user("u@ex.co", "pass").await;
+        let pair = start_session(&state, user_id, db::household::DEFAULT_HOUSEHOLD_ID)
+            .await
+            .expect("start session");
+        let claims = jwt::verify_token(&state.config.jwt_keys, &pair.token).expect("claims");
+        let session_id = Uuid::parse_str(&claims.jti).expect("jti");
+        db::session::revoke(&state.pool, session_id)
+            .await
+            .expect("revoke");
+        let app = router::router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/me")
+                    .header("authorization", format!("Bearer {}", pair.token))
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn api_tokens_do_not_get_sliding_refresh() {
+        let (mut state, user_id, _dir) = test_pool_with_user("u@ex.co", "pass").await;
+        // Every JWT is within the refresh threshold, so JWT requests always get a new token.
+        state.config.jwt_refresh_threshold_seconds = 7200;
+        let pair = start_session(&state, user_id, db::household::DEFAULT_HOUSEHOLD_ID)
+            .await
+            .expect("start session");
+        let now = chrono::Utc::now().timestamp();
+        let api_token = ApiToken::new(
+            Uuid::new_v4(),
+            user_id,
+            db::household::DEFAULT_HOUSEHOLD_ID,
+            "script".to_string(),
+            TokenScope::Read,
+            now,
+            None,
+            None,
+            None,
+        )
+        .expect("valid token");
+        let secret = format!("{API_TOKEN_PREFIX}secret");
+        db::api_token::insert(&state.pool, &api_token, &token::hash(&secret))
+            .await
+            .expect("insert token");
+
+        for (bearer, expect_refresh) in [(pair.token.as_str(), true), (secret.as_str(), false)] {
+            let response = router::router(state.clone())
+                .oneshot(
+                    Request::builder()
+                        .uri("/api/v1/me")
+                        .header("authorization", format!("Bearer {bearer}"))
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("service");
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers().contains_key(super::X_NEW_TOKEN),
+                expect_refresh
+            );
+        }
+        let stored = db::api_token::get_by_id(&state.pool, api_token.id())
+            .await
+            .expect("get_by_id")
+            .expect("token");
+        assert!(stored.last_used_at().is_some());
     }
 }
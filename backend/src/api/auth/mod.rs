@@ -1,8 +1,19 @@
-//! API auth: JWT issue/verify, login endpoint, and auth middleware.
+//! API auth: JWT issue/verify, login (with optional two-factor step), register, refresh, logout
+//! and password endpoints, auth middleware and role layers.
 
 mod jwt;
 mod login;
 mod middleware;
+mod password;
+mod register;
+mod role;
+mod session;
+mod two_factor;
 
 pub use login::route as login_route;
-pub use middleware::{CurrentUserId, auth_middleware, me_route};
+pub use middleware::{CurrentSessionId, CurrentUserId, auth_middleware, me_route};
+pub use password::{change_password_route, reset_password_route};
+pub use register::register_route;
+pub use role::{admin_guard, admin_write_guard, data_write_guard};
+pub use session::{logout_route, refresh_route};
+pub use two_factor::route as login_totp_route;
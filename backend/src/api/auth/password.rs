@@ -0,0 +1,138 @@
+//! `POST /api/v1/me/password` (change own password) and `POST /api/v1/auth/password-reset`
+//! (redeem a one-time reset token issued with `pocketratings user reset-password`).
+
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::auth::login::{clear_failures, ensure_not_locked, record_failures};
+use crate::api::auth::middleware::{CurrentSessionId, CurrentUserId};
+use crate::api::{error::ApiError, state::AppState};
+use crate::auth::{lockout, password, token};
+use crate::db;
+use crate::db::audit::{Actor, with_actor};
+
+/// Request body for changing the current user's password.
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Request body for redeeming a reset token.
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+/// Hash and store a new password for `user_id`, then revoke its sessions (except `keep`), API
+/// tokens and outstanding reset tokens. API tokens go too, since a leaked one would otherwise
+/// outlive the password rotation meant to shut the leak.
+async fn replace_password(
+    state: &AppState,
+    user_id: Uuid,
+    new_password: &str,
+    keep: Option<Uuid>,
+) -> Result<(), ApiError> {
+    if new_password.is_empty() {
+        return Err(ApiError::BadRequest(
+            "New password is required.".to_string(),
+        ));
+    }
+    let hash = password::hash_password(new_password).map_err(ApiError::internal)?;
+    db::user::set_password(&state.pool, user_id, &hash)
+        .await
+        .map_err(ApiError::internal)?;
+    db::session::revoke_all_for_user(&state.pool, user_id, keep)
+        .await
+        .map_err(ApiError::internal)?;
+    db::api_token::revoke_all_for_user(&state.pool, user_id)
+        .await
+        .map_err(ApiError::internal)?;
+    db::password_reset::invalidate_for_user(&state.pool, user_id)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(())
+}
+
+/// Handler for `POST /api/v1/me/password`. Protected; requires a login session. Other sessions of
+/// the user are logged out.
+///
+/// Wrong current passwords count against the user's email like failed logins, so a hijacked
+/// session cannot be used to guess the password (see [`lockout`]).
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(user_id)): Extension<CurrentUserId>,
+    session: Option<Extension<CurrentSessionId>>,
+    Json(body): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    let Some(Extension(CurrentSessionId(session_id))) = session else {
+        return Err(ApiError::Forbidden(
+            "API tokens cannot change passwords.".to_string(),
+        ));
+    };
+    let user = db::user::get_by_id(&state.pool, user_id, false)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::NotFound("User not found.".to_string()))?;
+    let keys = [lockout::email_key(user.email())];
+    let now = chrono::Utc::now().timestamp();
+    ensure_not_locked(&state, &keys, now).await?;
+    let ok = password::verify_password(&body.current_password, user.password())
+        .map_err(ApiError::internal)?;
+    if !ok {
+        record_failures(&state, &keys, now).await?;
+        return Err(ApiError::BadRequest(
+            "Current password is incorrect.".to_string(),
+        ));
+    }
+    clear_failures(&state, &keys).await?;
+    replace_password(&state, user_id, &body.new_password, Some(session_id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for `POST /api/v1/auth/password-reset`. Public; the reset token works once and all
+/// sessions and API tokens of the user are revoked.
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(body): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if body.new_password.is_empty() {
+        return Err(ApiError::BadRequest(
+            "New password is required.".to_string(),
+        ));
+    }
+    let now = chrono::Utc::now().timestamp();
+    let user_id = db::password_reset::consume(&state.pool, &token::hash(&body.token), now)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid or expired reset token.".to_string()))?;
+    let user = db::user::get_by_id(&state.pool, user_id, false)
+        .await
+        .map_err(ApiError::internal)?;
+    if user.is_none() {
+        return Err(ApiError::Unauthorized(
+            "Invalid or expired reset token.".to_string(),
+        ));
+    }
+    with_actor(
+        Actor::User(user_id),
+        replace_password(&state, user_id, &body.new_password, None),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Route for the change-password endpoint (no layer; layer is applied in the main router).
+pub fn change_password_route() -> axum::Router<AppState> {
+    axum::Router::new().route("/api/v1/me/password", axum::routing::post(change_password))
+}
+
+/// Route for the reset endpoint (public, no auth).
+pub fn reset_password_route() -> axum::Router<AppState> {
+    axum::Router::new().route(
+        "/api/v1/auth/password-reset",
+        axum::routing::post(reset_password),
+    )
+}
@@ -0,0 +1,84 @@
+//! `POST /api/v1/auth/register` — create an account with an invite issued by an administrator
+//! (`pocketratings user invite`), then log in.
+
+use axum::{Json, extract::State, http::StatusCode};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::auth::login::LoginResponse;
+use crate::api::auth::session::start_session;
+use crate::api::user::map_validation_error;
+use crate::api::{error::ApiError, state::AppState};
+use crate::auth::{password, token};
+use crate::db;
+use crate::db::audit::{Actor, with_actor};
+use crate::domain::user::User;
+
+/// Request body for register. Email, role and household come from the invite.
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub token: String,
+    pub name: String,
+    pub password: String,
+}
+
+/// Handler for `POST /api/v1/auth/register`. Public; the invite works once. Returns a token pair
+/// for the new user in the invite's household.
+pub async fn register(
+    State(state): State<AppState>,
+    Json(body): Json<RegisterRequest>,
+) -> Result<(StatusCode, Json<LoginResponse>), ApiError> {
+    let invalid = || ApiError::Unauthorized("Invalid or expired invite.".to_string());
+    let now = chrono::Utc::now().timestamp();
+    let invite = db::invite::get_active_by_hash(&state.pool, &token::hash(&body.token), now)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(invalid)?;
+    if body.password.is_empty() {
+        return Err(ApiError::BadRequest("Password is required.".to_string()));
+    }
+    let taken = db::user::get_by_email(&state.pool, invite.email())
+        .await
+        .map_err(ApiError::internal)?;
+    if taken.is_some() {
+        return Err(ApiError::Conflict(
+            "Email is already registered.".to_string(),
+        ));
+    }
+    let hash = password::hash_password(&body.password).map_err(ApiError::internal)?;
+    let user = User::new(
+        Uuid::new_v4(),
+        body.name.trim().to_string(),
+        invite.email().to_string(),
+        hash,
+        now,
+        now,
+        None,
+    )
+    .map_err(|e| map_validation_error(&e))?
+    .with_role(invite.role());
+
+    // The invite is used up together with creating the user, so a concurrent request with it
+    // fails and a failed insert leaves it usable.
+    let redeemed = with_actor(
+        Actor::User(user.id()),
+        db::invite::redeem(&state.pool, invite.id(), &user, now),
+    )
+    .await
+    .map_err(|e| match &e {
+        db::DbError::Sqlx(sqlx::Error::Database(db)) if db.is_unique_violation() => {
+            ApiError::Conflict("Email is already registered.".to_string())
+        }
+        _ => ApiError::internal(e),
+    })?;
+    if !redeemed {
+        return Err(invalid());
+    }
+    let pair = start_session(&state, user.id(), invite.household_id()).await?;
+    Ok((StatusCode::CREATED, Json(pair)))
+}
+
+/// Route for the register endpoint (public, no auth).
+pub fn register_route() -> axum::Router<AppState> {
+    axum::Router::new().route("/api/v1/auth/register", axum::routing::post(register))
+}
@@ -0,0 +1,87 @@
+//! Role-based access control layers (see [`Role`]), applied in the main router after
+//! [`super::auth_middleware`], which sets [`CurrentRole`].
+//!
+//! - [`data_write_guard`]: guests may only read; permanent deletes (`?force=true`) are admin-only.
+//! - [`admin_write_guard`]: only admins may write (e.g. category management).
+//! - [`admin_guard`]: only admins may read or write (e.g. user management).
+
+use axum::{
+    extract::{Query, Request},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use serde::Deserialize;
+
+use crate::api::error::ApiError;
+use crate::domain::user::Role;
+
+/// Extension value: the current authenticated user's role (set by auth middleware).
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentRole(pub Role);
+
+/// Role of the request, or `None` if the auth middleware did not run.
+fn current_role(request: &Request) -> Option<Role> {
+    request
+        .extensions()
+        .get::<CurrentRole>()
+        .map(|CurrentRole(role)| *role)
+}
+
+fn is_read(request: &Request) -> bool {
+    matches!(*request.method(), Method::GET | Method::HEAD)
+}
+
+/// The `force` query parameter, decoded the way the delete handlers decode it.
+#[derive(Debug, Deserialize)]
+struct ForceQuery {
+    #[serde(default, deserialize_with = "crate::api::query::parse_flag")]
+    force: bool,
+}
+
+/// Whether the request asks for a permanent delete (`DELETE ...?force=true`). The query is
+/// percent-decoded first, so encoded keys or values cannot slip past the guard; a query that
+/// does not decode counts as a permanent delete.
+fn is_force_delete(request: &Request) -> bool {
+    request.method() == Method::DELETE
+        && Query::<ForceQuery>::try_from_uri(request.uri()).map_or(true, |Query(query)| query.force)
+}
+
+/// Layer for data routes: reject writes by guests, and permanent deletes by non-admins.
+pub async fn data_write_guard(request: Request, next: Next) -> Response {
+    let Some(role) = current_role(&request) else {
+        return ApiError::internal("role guard used without auth_middleware").into_response();
+    };
+    if role == Role::Guest && !is_read(&request) {
+        return ApiError::Forbidden("Guests have read-only access.".to_string()).into_response();
+    }
+    if role != Role::Admin && is_force_delete(&request) {
+        return ApiError::Forbidden("Only admins can delete permanently.".to_string())
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Layer for admin-managed routes: reject writes by non-admins.
+pub async fn admin_write_guard(request: Request, next: Next) -> Response {
+    let Some(role) = current_role(&request) else {
+        return ApiError::internal("role guard used without auth_middleware").into_response();
+    };
+    if role != Role::Admin && !is_read(&request) {
+        return ApiError::Forbidden("Only admins can make this change.".to_string())
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Layer for admin-only routes: reject non-admins, for reads as well as writes.
+pub async fn admin_guard(request: Request, next: Next) -> Response {
+    let Some(role) = current_role(&request) else {
+        return ApiError::internal("role guard used without auth_middleware").into_response();
+    };
+    if role != Role::Admin {
+        return ApiError::Forbidden("Only admins can access this.".to_string()).into_response();
+    }
+    next.run(request).await
+}
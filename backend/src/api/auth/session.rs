@@ -0,0 +1,129 @@
+//! Login sessions: `POST /api/v1/auth/refresh` and `POST /api/v1/auth/logout`.
+//!
+//! Login opens a session and returns a short-lived access token (JWT whose `jti` is the session
+//! id) plus a refresh token. Refreshing exchanges the refresh token for a new pair (the old refresh
+//! token stops working); logging out revokes the session, which the auth middleware then rejects.
+
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::auth::jwt;
+use crate::api::auth::login::LoginResponse;
+use crate::api::auth::middleware::CurrentSessionId;
+use crate::api::{error::ApiError, state::AppState};
+use crate::auth::token;
+use crate::db;
+use crate::domain::session::Session;
+
+/// Request body for refresh.
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// Open a session for `user_id` in `household_id` and issue its first token pair.
+pub(super) async fn start_session(
+    state: &AppState,
+    user_id: Uuid,
+    household_id: Uuid,
+) -> Result<LoginResponse, ApiError> {
+    let now = chrono::Utc::now().timestamp();
+    let lifetime =
+        i64::try_from(state.config.refresh_token_expiration_seconds).map_err(ApiError::internal)?;
+    let session = Session::new(
+        Uuid::new_v4(),
+        user_id,
+        household_id,
+        now,
+        now,
+        now.saturating_add(lifetime),
+        None,
+    )
+    .map_err(ApiError::internal)?;
+    let refresh_token = token::generate();
+    db::session::insert(&state.pool, &session, &token::hash(&refresh_token))
+        .await
+        .map_err(ApiError::internal)?;
+    token_pair(state, &session, refresh_token)
+}
+
+/// Sign an access token for `session` and pair it with `refresh_token`.
+fn token_pair(
+    state: &AppState,
+    session: &Session,
+    refresh_token: String,
+) -> Result<LoginResponse, ApiError> {
+    let token = jwt::issue_token(
+        &state.config.jwt_keys,
+        session.user_id(),
+        session.id(),
+        session.household_id(),
+        state.config.jwt_expiration_seconds,
+    )
+    .map_err(ApiError::internal)?;
+    Ok(LoginResponse {
+        token,
+        refresh_token,
+    })
+}
+
+/// Handler for `POST /api/v1/auth/refresh`.
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(body): Json<RefreshRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let invalid = || ApiError::Unauthorized("Invalid or expired refresh token.".to_string());
+    let now = chrono::Utc::now().timestamp();
+    let old_hash = token::hash(&body.refresh_token);
+    let session = db::session::get_active_by_refresh_hash(&state.pool, &old_hash, now)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(invalid)?;
+    let user = db::user::get_by_id(&state.pool, session.user_id(), false)
+        .await
+        .map_err(ApiError::internal)?;
+    if user.is_none() {
+        return Err(invalid());
+    }
+    let refresh_token = token::generate();
+    let rotated = db::session::rotate(
+        &state.pool,
+        session.id(),
+        &old_hash,
+        &token::hash(&refresh_token),
+        now,
+    )
+    .await
+    .map_err(ApiError::internal)?;
+    if !rotated {
+        return Err(invalid());
+    }
+    token_pair(&state, &session, refresh_token).map(Json)
+}
+
+/// Handler for `POST /api/v1/auth/logout` — revoke the current session. Protected.
+pub async fn logout(
+    State(state): State<AppState>,
+    session: Option<Extension<CurrentSessionId>>,
+) -> Result<StatusCode, ApiError> {
+    let Some(Extension(CurrentSessionId(session_id))) = session else {
+        return Err(ApiError::BadRequest(
+            "API tokens have no session; revoke the token instead.".to_string(),
+        ));
+    };
+    db::session::revoke(&state.pool, session_id)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Route for the refresh endpoint (public, no auth).
+pub fn refresh_route() -> axum::Router<AppState> {
+    axum::Router::new().route("/api/v1/auth/refresh", axum::routing::post(refresh))
+}
+
+/// Route for the logout endpoint (no layer; layer is applied in the main router).
+pub fn logout_route() -> axum::Router<AppState> {
+    axum::Router::new().route("/api/v1/auth/logout", axum::routing::post(logout))
+}
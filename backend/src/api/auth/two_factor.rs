@@ -0,0 +1,104 @@
+//! `POST /api/v1/auth/login/totp` — second login step for users with two-factor authentication:
+//! redeem the challenge from `POST /api/v1/auth/login` with a TOTP code or a recovery code.
+//!
+//! Wrong codes count as failed logins for the user's email and the client IP (see
+//! [`crate::auth::lockout`]).
+
+use std::net::SocketAddr;
+
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+};
+use serde::Deserialize;
+
+use crate::api::auth::login::{
+    LoginResponse, clear_failures, ensure_not_locked, lockout_keys, record_failures,
+};
+use crate::api::auth::session::start_session;
+use crate::api::{error::ApiError, state::AppState};
+use crate::auth::{token, totp};
+use crate::db;
+
+/// Request body for the second login step.
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    pub challenge: String,
+    /// Six-digit code from the authenticator app, or an unused recovery code.
+    pub code: String,
+}
+
+/// Whether `code` is accepted for `user_id`: a TOTP code not used before, or an unused recovery
+/// code (which is used up).
+async fn accept_code(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    code: &str,
+    now: i64,
+) -> Result<bool, ApiError> {
+    let Some(secret) = db::totp::get_secret(&state.pool, user_id)
+        .await
+        .map_err(ApiError::internal)?
+    else {
+        return Ok(false);
+    };
+    let code = code.trim();
+    if code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit()) {
+        let Some(step) = totp::verify(&secret.secret, code, now).map_err(ApiError::internal)?
+        else {
+            return Ok(false);
+        };
+        return db::totp::mark_step_used(&state.pool, user_id, step)
+            .await
+            .map_err(ApiError::internal);
+    }
+    let code_hash = token::hash(&totp::normalize_recovery_code(code));
+    db::totp::consume_recovery_code(&state.pool, user_id, &code_hash, now)
+        .await
+        .map_err(ApiError::internal)
+}
+
+/// Handler for `POST /api/v1/auth/login/totp`. Public; the challenge completes one login.
+pub async fn login_totp(
+    State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(body): Json<TwoFactorLoginRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let invalid = || ApiError::Unauthorized("Invalid or expired login challenge.".to_string());
+    let now = chrono::Utc::now().timestamp();
+    let challenge =
+        db::login_challenge::get_active_by_hash(&state.pool, &token::hash(&body.challenge), now)
+            .await
+            .map_err(ApiError::internal)?
+            .ok_or_else(invalid)?;
+    let user = db::user::get_by_id(&state.pool, challenge.user_id, false)
+        .await
+        .map_err(ApiError::internal)?
+        .ok_or_else(invalid)?;
+    let keys = lockout_keys(&state, user.email(), connect_info, &headers);
+    ensure_not_locked(&state, &keys, now).await?;
+
+    if !accept_code(&state, user.id(), &body.code, now).await? {
+        record_failures(&state, &keys, now).await?;
+        return Err(ApiError::Unauthorized(
+            "Invalid two-factor code.".to_string(),
+        ));
+    }
+    let consumed = db::login_challenge::consume(&state.pool, challenge.id, now)
+        .await
+        .map_err(ApiError::internal)?;
+    if !consumed {
+        return Err(invalid());
+    }
+    clear_failures(&state, &keys).await?;
+    start_session(&state, user.id(), challenge.household_id)
+        .await
+        .map(Json)
+}
+
+/// Route for this endpoint (public, no auth).
+pub fn route() -> axum::Router<AppState> {
+    axum::Router::new().route("/api/v1/auth/login/totp", axum::routing::post(login_totp))
+}
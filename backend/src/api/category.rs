@@ -1,14 +1,17 @@
-//! Categories REST API: list, get, create, update, delete.
+//! Categories REST API: list, get, create, update, delete, restore.
 
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
 };
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::api::auth::CurrentUserId;
+use crate::api::pagination::{self, ListResponse};
+use crate::api::query::parse_flag;
 use crate::api::{error::ApiError, state::AppState};
 use crate::db;
 use crate::domain::category::Category;
@@ -34,6 +37,12 @@ pub struct ListCategoriesQuery {
     /// When `Some(1)`, return only one level (roots when no `parent_id`, or direct children of `parent_id`). Omit for full tree.
     #[serde(default)]
     pub depth: Option<u8>,
+    /// `name` (default), `created_at` or `updated_at`. Orders the top-level nodes.
+    pub sort: Option<String>,
+    /// Page size for the top-level nodes; when set the response is a page envelope.
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
 }
 
 /// Query params for GET /api/v1/categories/:id (optional depth for nested children).
@@ -47,7 +56,7 @@ pub struct GetCategoryQuery {
 /// Query params for delete (optional force).
 #[derive(Debug, Default, Deserialize)]
 pub struct DeleteCategoryQuery {
-    #[serde(default, deserialize_with = "parse_force")]
+    #[serde(default, deserialize_with = "parse_flag")]
     pub force: bool,
 }
 
@@ -123,7 +132,7 @@ async fn categories_to_response_list(
 fn map_db_error(e: &db::DbError) -> ApiError {
     match e {
         db::DbError::InvalidData(msg) => {
-            if msg.contains("cannot delete") {
+            if msg.contains("cannot delete") || msg.contains("cannot restore") {
                 ApiError::Conflict(msg.clone())
             } else {
                 ApiError::BadRequest(msg.clone())
@@ -135,18 +144,32 @@ fn map_db_error(e: &db::DbError) -> ApiError {
             {
                 return ApiError::BadRequest(e.to_string());
             }
-            ApiError::Internal
+            ApiError::internal(e)
         }
-        db::DbError::Migrate(_) => ApiError::Internal,
+        db::DbError::Migrate(_) => ApiError::internal(e),
     }
 }
 
 /// GET /api/v1/categories — list categories as a nested tree; optional `parent_id` and `depth`.
+/// `sort`, `limit` and `cursor` order and page the top-level nodes (see
+/// [`crate::api::pagination`]); each node keeps its nested children up to `depth`.
 pub async fn list_categories(
     State(state): State<AppState>,
     Query(q): Query<ListCategoriesQuery>,
-) -> Result<Json<Vec<CategoryResponse>>, ApiError> {
+) -> Result<Json<ListResponse<CategoryResponse>>, ApiError> {
     let depth = q.depth.filter(|&d| d > 0);
+    let sort = pagination::parse_sort(
+        q.sort.as_deref(),
+        db::category::CategorySort::Name,
+        db::category::CategorySort::from_param,
+    )?;
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), sort.name())?;
+
+    if q.sort.is_some() || q.limit.is_some() {
+        return list_categories_page(&state, q.parent_id, depth, sort, &page, q.limit.is_some())
+            .await
+            .map(Json);
+    }
 
     let response = if depth == Some(1) {
         let list = db::category::get_children(&state.pool, q.parent_id)
@@ -176,7 +199,51 @@ pub async fn list_categories(
         categories_to_response_list(&state.pool, &tree.children).await?
     };
 
-    Ok(Json(response))
+    Ok(Json(ListResponse::All(response)))
+}
+
+/// Sorted (and optionally paged) top-level nodes for [`list_categories`], each with its subtree
+/// down to `depth` levels in total.
+async fn list_categories_page(
+    state: &AppState,
+    parent_id: Option<Uuid>,
+    depth: Option<u8>,
+    sort: db::category::CategorySort,
+    page: &db::page::PageRequest,
+    paged: bool,
+) -> Result<ListResponse<CategoryResponse>, ApiError> {
+    if let Some(pid) = parent_id {
+        db::category::get_by_id(&state.pool, pid, false)
+            .await
+            .map_err(|e| map_db_error(&e))?
+            .ok_or_else(|| ApiError::NotFound("Parent category not found.".to_string()))?;
+    }
+    let result = db::category::list_page(&state.pool, parent_id, sort, false, page)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let all = if depth == Some(1) {
+        Vec::new()
+    } else {
+        db::category::get_all(&state.pool, false)
+            .await
+            .map_err(|e| map_db_error(&e))?
+    };
+    let mut out = Vec::with_capacity(result.items.len());
+    for c in &result.items {
+        let children = if depth == Some(1) {
+            Vec::new()
+        } else {
+            let tree = db::category::Categories::from_list(
+                all.clone(),
+                Some(c.clone()),
+                depth.map(|d| d - 1),
+                false,
+            );
+            categories_to_response_list(&state.pool, &tree.children).await?
+        };
+        out.push(category_to_response(&state.pool, c, children).await?);
+    }
+    Ok(ListResponse::new(out, result.next_cursor, paged))
 }
 
 /// GET /api/v1/categories/:id — get one category. Optional `?depth=N`: omitted = full subtree, 0 = no children, 1+ = N levels.
@@ -216,7 +283,7 @@ pub async fn create_category(
     if let Some(pid) = body.parent_id {
         let parent = db::category::get_by_id(&state.pool, pid, false)
             .await
-            .map_err(|_| ApiError::Internal)?;
+            .map_err(ApiError::internal)?;
         if parent.is_none() {
             return Err(ApiError::NotFound("Parent category not found.".to_string()));
         }
@@ -270,7 +337,7 @@ pub async fn update_category(
         }
         let parent = db::category::get_by_id(&state.pool, pid, false)
             .await
-            .map_err(|_| ApiError::Internal)?;
+            .map_err(ApiError::internal)?;
         if parent.is_none() {
             return Err(ApiError::NotFound("Parent category not found.".to_string()));
         }
@@ -304,6 +371,7 @@ pub async fn update_category(
 /// DELETE /api/v1/categories/:id — soft delete, or hard with ?force=true.
 pub async fn delete_category(
     State(state): State<AppState>,
+    current_user: Option<Extension<CurrentUserId>>,
     Path(id): Path<Uuid>,
     Query(q): Query<DeleteCategoryQuery>,
 ) -> Result<StatusCode, ApiError> {
@@ -317,23 +385,43 @@ pub async fn delete_category(
             .await
             .map_err(|e| map_db_error(&e))?;
     } else {
-        db::category::soft_delete(&state.pool, id)
-            .await
-            .map_err(|e| map_db_error(&e))?;
+        db::category::soft_delete(
+            &state.pool,
+            id,
+            current_user.map(|Extension(CurrentUserId(u))| u),
+        )
+        .await
+        .map_err(|e| map_db_error(&e))?;
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Deserialize "true" / "false" for force query param.
-pub fn parse_force<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
-    Ok(s.to_lowercase() == "true" || s == "1")
+/// POST /api/v1/categories/:id/restore — restore a soft-deleted category; 409 when its parent is
+/// deleted.
+pub async fn restore_category(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CategoryResponse>, ApiError> {
+    let existing = db::category::get_by_id(&state.pool, id, true)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .ok_or_else(|| ApiError::NotFound("Category not found.".to_string()))?;
+    if existing.is_active() {
+        return Err(ApiError::Conflict("Category is not deleted.".to_string()));
+    }
+
+    db::category::restore(&state.pool, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let restored = db::category::get_by_id(&state.pool, id, false)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .expect("category just restored");
+    let resp = category_to_response(&state.pool, &restored, Vec::new()).await?;
+    Ok(Json(resp))
 }
 
-/// Router for /api/v1/categories (all five handlers).
+/// Router for /api/v1/categories (all six handlers).
 pub fn route() -> Router<AppState> {
     Router::new()
         .route(
@@ -346,6 +434,7 @@ pub fn route() -> Router<AppState> {
                 .patch(update_category)
                 .delete(delete_category),
         )
+        .route("/api/v1/categories/{id}/restore", post(restore_category))
 }
 
 #[cfg(test)]
@@ -357,7 +446,8 @@ mod tests {
     use uuid::Uuid;
 
     use super::*;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
     use crate::db;
 
     async fn test_pool() -> (AppState, tempfile::TempDir) {
@@ -369,10 +459,15 @@ mod tests {
         let state = AppState {
             config: Config {
                 database_path: path_str,
-                jwt_secret: "test".to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
                 jwt_expiration_seconds: 3600,
                 jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
                 bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
                 pid_file: std::env::temp_dir()
                     .join("pocketratings-category-test.pid")
                     .to_string_lossy()
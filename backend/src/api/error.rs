@@ -1,8 +1,11 @@
 //! API error type and JSON response format for 4xx/5xx responses.
+//!
+//! The cause of an internal error is logged (within the request's span, see
+//! [`super::request_log`]) but never sent to the client.
 
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
@@ -32,12 +35,28 @@ pub enum ApiError {
 
     #[error("conflict: {0}")]
     Conflict(String),
+    /// An `If-Match` precondition failed (the record changed since the client read it).
+    #[error("precondition failed: {0}")]
+    PreconditionFailed(String),
 
-    #[error("internal server error")]
-    Internal,
+    /// Rate limited; the response carries a `Retry-After` header.
+    #[error("too many requests: {message}")]
+    TooManyRequests {
+        message: String,
+        retry_after_secs: u64,
+    },
+
+    /// Unexpected failure; the cause is logged, the response only says `internal_server_error`.
+    #[error("internal server error: {0}")]
+    Internal(String),
 }
 
 impl ApiError {
+    /// An [`ApiError::Internal`] caused by `cause` (e.g. a database error), for `map_err`.
+    pub fn internal(cause: impl std::fmt::Display) -> Self {
+        Self::Internal(cause.to_string())
+    }
+
     const fn status(&self) -> StatusCode {
         match self {
             Self::BadRequest(_) => StatusCode::BAD_REQUEST,
@@ -45,7 +64,9 @@ impl ApiError {
             Self::Forbidden(_) => StatusCode::FORBIDDEN,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
             Self::Conflict(_) => StatusCode::CONFLICT,
-            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
@@ -56,7 +77,9 @@ impl ApiError {
             Self::Forbidden(_) => "forbidden",
             Self::NotFound(_) => "not_found",
             Self::Conflict(_) => "conflict",
-            Self::Internal => "internal_server_error",
+            Self::PreconditionFailed(_) => "precondition_failed",
+            Self::TooManyRequests { .. } => "too_many_requests",
+            Self::Internal(_) => "internal_server_error",
         }
     }
 
@@ -66,19 +89,33 @@ impl ApiError {
             | Self::Unauthorized(msg)
             | Self::Forbidden(msg)
             | Self::NotFound(msg)
-            | Self::Conflict(msg) => Some(msg.clone()),
-            Self::Internal => None,
+            | Self::Conflict(msg)
+            | Self::PreconditionFailed(msg)
+            | Self::TooManyRequests { message: msg, .. } => Some(msg.clone()),
+            Self::Internal(_) => None,
         }
     }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        if let Self::Internal(cause) = &self {
+            tracing::error!(error = %cause, "internal server error");
+        }
         let body = ErrorBody {
             error: self.error_code().to_string(),
             message: self.message(),
         };
-        (self.status(), Json(body)).into_response()
+        let mut response = (self.status(), Json(body)).into_response();
+        if let Self::TooManyRequests {
+            retry_after_secs, ..
+        } = self
+        {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
     }
 }
 
@@ -99,15 +136,34 @@ mod tests {
         assert_eq!(json["message"], "not allowed");
     }
 
+    #[tokio::test]
+    async fn too_many_requests_into_response_returns_429_with_retry_after() {
+        let err = ApiError::TooManyRequests {
+            message: "slow down".to_string(),
+            retry_after_secs: 30,
+        };
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            res.headers().get(header::RETRY_AFTER),
+            Some(&HeaderValue::from(30u64))
+        );
+        let (_, body) = res.into_parts();
+        let bytes = body.collect().await.expect("body collect").to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["error"], "too_many_requests");
+        assert_eq!(json["message"], "slow down");
+    }
+
     #[tokio::test]
     async fn internal_into_response_returns_500_and_spec_body_no_message() {
-        let err = ApiError::Internal;
+        let err = ApiError::internal("database is locked");
         let res = err.into_response();
         assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
         let (_, body) = res.into_parts();
         let bytes = body.collect().await.expect("body collect").to_bytes();
         let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
         assert_eq!(json["error"], "internal_server_error");
-        assert!(json.get("message").is_none());
+        assert!(json.get("message").is_none(), "the cause is not exposed");
     }
 }
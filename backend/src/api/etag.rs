@@ -0,0 +1,233 @@
+//! Conditional requests on data routes: `ETag` on reads, `If-None-Match` (304) for cheap polling,
+//! and `If-Match` (412) against lost updates.
+//!
+//! Versions come from the audit log (see [`crate::db::audit::latest_id`] and
+//! [`crate::db::audit::entity_version`]), which records every change in order, so no row needs a
+//! version column. A response's `ETag` carries the household's latest change, which covers the
+//! related records embedded in responses; on single records (`/api/v1/<type>/{id}`) it also
+//! carries the record's own version, and `If-Match` is compared with that part only, so edits of
+//! other records do not make it stale.
+//!
+//! Writes to a single record hold its [`lock_record`] lock from the `If-Match` check until the
+//! handler is done, so no other write of the server can slip in between check and write.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+use super::{error::ApiError, state::AppState};
+use crate::db;
+use crate::db::audit::AuditEntity;
+
+/// Routes of single records, whose tags include the record's version.
+const RECORD_ROUTES: [(&str, AuditEntity); 6] = [
+    ("/api/v1/categories/{id}", AuditEntity::Category),
+    ("/api/v1/locations/{id}", AuditEntity::Location),
+    ("/api/v1/products/{id}", AuditEntity::Product),
+    ("/api/v1/purchases/{id}", AuditEntity::Purchase),
+    ("/api/v1/reviews/{id}", AuditEntity::Review),
+    ("/api/v1/variations/{id}", AuditEntity::Variation),
+];
+
+/// Routes without tags: sync has its own change tokens.
+const UNTAGGED_ROUTES: [&str; 1] = ["/api/v1/sync"];
+
+/// Number of locks the records are spread over.
+const RECORD_LOCK_COUNT: usize = 64;
+
+/// Locks serializing writes to single records; a record uses the lock its type and id hash to.
+static RECORD_LOCKS: [Mutex<()>; RECORD_LOCK_COUNT] =
+    [const { Mutex::const_new(()) }; RECORD_LOCK_COUNT];
+
+/// Wait for and take the write lock of a record. Hold it across checking the record's version
+/// and writing it (REST `If-Match`, sync `base_version`), so the check still holds at the write.
+pub(super) async fn lock_record(entity: AuditEntity, id: Uuid) -> MutexGuard<'static, ()> {
+    let mut hasher = DefaultHasher::new();
+    entity.name().hash(&mut hasher);
+    id.hash(&mut hasher);
+    let slot = usize::try_from(hasher.finish() % RECORD_LOCK_COUNT as u64).unwrap_or_default();
+    RECORD_LOCKS[slot].lock().await
+}
+
+/// Entity type and id of the single record a request is about, if any.
+fn target(request: &Request) -> Option<(AuditEntity, Uuid)> {
+    let matched = request.extensions().get::<MatchedPath>()?.as_str();
+    let (_, entity) = RECORD_ROUTES.iter().find(|(route, _)| *route == matched)?;
+    let id = request.uri().path().rsplit('/').next()?.parse().ok()?;
+    Some((*entity, id))
+}
+
+/// Quoted tag for the household's `token` and, on single records, the record's `version`. The
+/// server version is included so that an upgrade that changes responses invalidates old tags.
+fn format_tag(token: i64, version: Option<i64>) -> String {
+    let server = env!("CARGO_PKG_VERSION");
+    version.map_or_else(
+        || format!("\"{server}/{token}\""),
+        |version| format!("\"{server}/{token}/{version}\""),
+    )
+}
+
+/// Current tag of the request's response.
+async fn current_tag(
+    state: &AppState,
+    target: Option<(AuditEntity, Uuid)>,
+) -> Result<String, db::DbError> {
+    let token = db::audit::latest_id(&state.pool).await?;
+    let version = match target {
+        Some((entity, id)) => Some(db::audit::entity_version(&state.pool, entity, id).await?),
+        None => None,
+    };
+    Ok(format_tag(token, version))
+}
+
+/// Tags listed in an `If-Match` or `If-None-Match` header value.
+fn listed_tags(value: &HeaderValue) -> Vec<&str> {
+    value
+        .to_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// `If-None-Match` (weak comparison): true when `value` is `*` or lists `tag`.
+fn none_match_hits(value: &HeaderValue, tag: &str) -> bool {
+    listed_tags(value)
+        .into_iter()
+        .any(|listed| listed == "*" || listed.trim_start_matches("W/") == tag)
+}
+
+/// `If-Match`: true when `value` is `*` or lists a strong tag of the record at `version`.
+fn if_match_hits(value: &HeaderValue, version: i64) -> bool {
+    listed_tags(value).into_iter().any(|listed| {
+        listed == "*"
+            || listed
+                .strip_prefix('"')
+                .and_then(|tag| tag.strip_suffix('"'))
+                .and_then(|tag| tag.rsplit_once('/'))
+                .is_some_and(|(rest, listed_version)| {
+                    rest.contains('/') && listed_version.parse() == Ok(version)
+                })
+    })
+}
+
+fn set_tag(response: &mut Response, tag: &str) {
+    if let Ok(value) = HeaderValue::from_str(tag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+}
+
+/// Layer for data routes (inside [`super::auth::auth_middleware`], which selects the household).
+///
+/// - `GET`: successful responses get an `ETag`; when `If-None-Match` lists the current tag the
+///   handler is skipped and the response is `304 Not Modified`.
+/// - `PATCH` and `DELETE` of a single record: `412 Precondition Failed` when `If-Match` is given
+///   and does not list the record's current version; a successful `PATCH` gets the new `ETag`.
+///   The record's [`lock_record`] lock is held until the handler is done.
+pub async fn conditional(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let untagged = request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|matched| UNTAGGED_ROUTES.contains(&matched.as_str()));
+    if untagged {
+        return next.run(request).await;
+    }
+    let target = target(&request);
+
+    if matches!(*request.method(), Method::GET | Method::HEAD) {
+        let tag = match current_tag(&state, target).await {
+            Ok(tag) => tag,
+            Err(e) => return ApiError::internal(e).into_response(),
+        };
+        if let Some(value) = request.headers().get(header::IF_NONE_MATCH)
+            && none_match_hits(value, &tag)
+        {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NOT_MODIFIED;
+            set_tag(&mut response, &tag);
+            return response;
+        }
+        let mut response = next.run(request).await;
+        if response.status().is_success() {
+            set_tag(&mut response, &tag);
+        }
+        return response;
+    }
+
+    let Some((entity, id)) = target else {
+        return next.run(request).await;
+    };
+    let _lock = lock_record(entity, id).await;
+    if let Some(value) = request.headers().get(header::IF_MATCH) {
+        let version = match db::audit::entity_version(&state.pool, entity, id).await {
+            Ok(version) => version,
+            Err(e) => return ApiError::internal(e).into_response(),
+        };
+        if !if_match_hits(value, version) {
+            return ApiError::PreconditionFailed(format!(
+                "The {} was changed since it was read; fetch it again.",
+                entity.name()
+            ))
+            .into_response();
+        }
+    }
+    let is_patch = request.method() == Method::PATCH;
+    let mut response = next.run(request).await;
+    if is_patch && response.status().is_success() {
+        match current_tag(&state, Some((entity, id))).await {
+            Ok(tag) => set_tag(&mut response, &tag),
+            Err(e) => tracing::warn!(error = %e, "reading the new version failed"),
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_match_compares_record_version_only() {
+        let tag = format_tag(40, Some(7));
+        let value = HeaderValue::from_str(&tag).expect("header");
+        assert!(if_match_hits(&value, 7));
+        assert!(!if_match_hits(&value, 8));
+        let newer_household = HeaderValue::from_str(&format_tag(41, Some(7))).expect("header");
+        assert!(if_match_hits(&newer_household, 7));
+
+        let list_tag = HeaderValue::from_str(&format_tag(7, None)).expect("header");
+        assert!(!if_match_hits(&list_tag, 7));
+        let weak = HeaderValue::from_str(&format!("W/{tag}")).expect("header");
+        assert!(!if_match_hits(&weak, 7));
+        assert!(if_match_hits(&HeaderValue::from_static("*"), 7));
+        let several = HeaderValue::from_str(&format!("\"x\", {tag}")).expect("header");
+        assert!(if_match_hits(&several, 7));
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let tag = format_tag(40, None);
+        assert!(none_match_hits(
+            &HeaderValue::from_str(&tag).expect("header"),
+            &tag
+        ));
+        assert!(none_match_hits(
+            &HeaderValue::from_str(&format!("W/{tag}")).expect("header"),
+            &tag
+        ));
+        assert!(!none_match_hits(
+            &HeaderValue::from_str(&format_tag(39, None)).expect("header"),
+            &tag
+        ));
+        assert!(none_match_hits(&HeaderValue::from_static("*"), &tag));
+    }
+}
@@ -0,0 +1,244 @@
+//! `GET /healthz` (liveness) and `GET /readyz` (readiness) for container healthchecks and proxy
+//! upstream checks.
+//!
+//! Liveness only shows the process answers requests. Readiness runs a trivial query, checks that
+//! every embedded migration is applied (see [`crate::db::health`]), and checks the free disk
+//! space next to the database; it returns `503 Service Unavailable` with the failed checks
+//! otherwise.
+
+use axum::{Json, Router, extract::State, http::StatusCode, routing::get};
+use serde::Serialize;
+
+use super::state::AppState;
+use crate::db;
+
+/// Least free space, in bytes, on the database's filesystem for the server to count as ready.
+pub const MIN_FREE_DISK_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Response body for `GET /healthz`.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+}
+
+/// Handler for `GET /healthz`. Does not touch the database.
+pub async fn healthz() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+/// Result of the database connectivity check.
+#[derive(Debug, Serialize)]
+pub struct DatabaseCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of the migrations check.
+#[derive(Debug, Serialize)]
+pub struct MigrationsCheck {
+    pub ok: bool,
+    pub applied: usize,
+    pub pending: Vec<i64>,
+    pub dirty: Option<i64>,
+    pub modified: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of the free disk space check. `free_bytes` is `null` when unknown, which counts as ok.
+#[derive(Debug, Serialize)]
+pub struct DiskCheck {
+    pub ok: bool,
+    pub free_bytes: Option<u64>,
+    pub min_free_bytes: u64,
+}
+
+/// All readiness checks.
+#[derive(Debug, Serialize)]
+pub struct ReadinessChecks {
+    pub database: DatabaseCheck,
+    pub migrations: MigrationsCheck,
+    pub disk: DiskCheck,
+}
+
+/// Response body for `GET /readyz`.
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// `ready` or `not_ready`.
+    pub status: &'static str,
+    pub checks: ReadinessChecks,
+}
+
+/// Handler for `GET /readyz`. Returns `200 OK` when every check passes, `503 Service Unavailable`
+/// otherwise; the body lists the result of each check either way.
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    // Causes are logged, not returned: the endpoint is public.
+    let database = match db::health::ping(&state.pool).await {
+        Ok(()) => DatabaseCheck {
+            ok: true,
+            error: None,
+        },
+        Err(e) => {
+            tracing::warn!(error = %e, "readiness: database query failed");
+            DatabaseCheck {
+                ok: false,
+                error: Some("database query failed".to_string()),
+            }
+        }
+    };
+    let migrations = match db::health::migration_status(&state.pool).await {
+        Ok(status) => MigrationsCheck {
+            ok: status.is_complete(),
+            applied: status.applied,
+            pending: status.pending,
+            dirty: status.dirty,
+            modified: status.modified,
+            error: None,
+        },
+        Err(e) => {
+            tracing::warn!(error = %e, "readiness: reading migrations failed");
+            MigrationsCheck {
+                ok: false,
+                applied: 0,
+                pending: Vec::new(),
+                dirty: None,
+                modified: Vec::new(),
+                error: Some("reading migrations failed".to_string()),
+            }
+        }
+    };
+    let free_bytes = db::health::free_disk_space(&state.config.database_path);
+    let disk = DiskCheck {
+        ok: free_bytes.is_none_or(|free| free >= MIN_FREE_DISK_BYTES),
+        free_bytes,
+        min_free_bytes: MIN_FREE_DISK_BYTES,
+    };
+
+    let ready = database.ok && migrations.ok && disk.ok;
+    let (status, label) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            status: label,
+            checks: ReadinessChecks {
+                database,
+                migrations,
+                disk,
+            },
+        }),
+    )
+}
+
+/// Routes for these endpoints (public, no auth).
+pub fn route() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+
+    fn test_state(path: &str, pool: sqlx::SqlitePool) -> AppState {
+        AppState {
+            config: Config {
+                database_path: path.to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:3099".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: "pocketratings-test.pid".to_string(),
+            },
+            pool,
+        }
+    }
+
+    async fn get_json(state: AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = route()
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        (status, serde_json::from_slice(&body).expect("json"))
+    }
+
+    #[tokio::test]
+    async fn readyz_is_not_ready_until_migrations_are_applied() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("ready_test.db");
+        let path = path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path).await.expect("pool");
+
+        let (status, json) = get_json(test_state(&path, pool.clone()), "/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "ok");
+
+        let (status, json) = get_json(test_state(&path, pool.clone()), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["status"], "not_ready");
+        assert_eq!(json["checks"]["database"]["ok"], true);
+        assert_eq!(json["checks"]["migrations"]["ok"], false);
+        assert_eq!(json["checks"]["migrations"]["applied"], 0);
+        assert!(
+            !json["checks"]["migrations"]["pending"]
+                .as_array()
+                .expect("pending")
+                .is_empty()
+        );
+
+        db::run_migrations(&pool).await.expect("migrate");
+        let (status, json) = get_json(test_state(&path, pool), "/readyz").await;
+        assert_eq!(status, StatusCode::OK, "{json}");
+        assert_eq!(json["status"], "ready");
+        assert_eq!(
+            json["checks"]["migrations"]["pending"],
+            serde_json::json!([])
+        );
+        assert!(json["checks"]["disk"]["free_bytes"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn readyz_reports_unreachable_database() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("closed_test.db");
+        let path = path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        pool.close().await;
+
+        let (status, json) = get_json(test_state(&path, pool), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["checks"]["database"]["ok"], false);
+        assert_eq!(json["checks"]["database"]["error"], "database query failed");
+    }
+}
@@ -0,0 +1,176 @@
+//! Idempotency layer: a `POST` sent with an `Idempotency-Key` header runs once per user, household
+//! and key; retries with the same key get the original response back instead of creating a second
+//! row.
+//!
+//! Only successful responses are stored (see [`crate::db::idempotency`]), for
+//! `idempotency_window_seconds`; after an error the request can be retried with the same key.
+//! Reusing a key for a different request (method, path or body) is rejected, and so is a retry
+//! while the first request is still running.
+
+use std::fmt::Write;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use super::auth::CurrentUserId;
+use super::{error::ApiError, state::AppState};
+use crate::db;
+use crate::db::idempotency::StoredResponse;
+
+/// Header carrying the client's idempotency key.
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Header set (to `true`) on replayed responses.
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest accepted idempotency key.
+const MAX_KEY_LEN: usize = 255;
+
+/// Largest request body read to compare retries; matches axum's default body limit.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Seconds after which a request that claimed a key but never finished no longer blocks it.
+const ABANDONED_AFTER_SECS: i64 = 60;
+
+/// The request's idempotency key: `None` without the header, 400 when it is empty, too long or
+/// not printable ASCII.
+fn idempotency_key(request: &Request) -> Result<Option<String>, ApiError> {
+    let Some(value) = request.headers().get(&IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .filter(|key| {
+            !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(|key| Some(key.to_string()))
+        .ok_or_else(|| ApiError::BadRequest("Invalid Idempotency-Key header.".to_string()))
+}
+
+/// Hash of what makes two requests the same: method, path with query, and body (hex SHA-256).
+fn request_hash(method: &Method, path: &str, body: &Bytes) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(path);
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher
+        .finalize()
+        .iter()
+        .fold(String::with_capacity(64), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
+/// Rebuild a stored response, marked as replayed.
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    if let Some(value) = stored
+        .content_type
+        .and_then(|ct| HeaderValue::from_str(&ct).ok())
+    {
+        response.headers_mut().insert(header::CONTENT_TYPE, value);
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+/// Layer for protected routes (inside [`super::auth::auth_middleware`]). Requests other than
+/// `POST`, and those without the header, pass through untouched.
+pub async fn idempotency(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let key = match idempotency_key(&request) {
+        Ok(Some(key)) => key,
+        Ok(None) => return next.run(request).await,
+        Err(e) => return e.into_response(),
+    };
+    let Some(CurrentUserId(user_id)) = request.extensions().get::<CurrentUserId>().cloned() else {
+        return ApiError::internal("idempotency layer used without auth_middleware")
+            .into_response();
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, MAX_BODY_BYTES).await else {
+        return ApiError::BadRequest("Request body is too large.".to_string()).into_response();
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map_or_else(|| parts.uri.path(), |pq| pq.as_str());
+    let hash = request_hash(&parts.method, path, &body);
+    let now = chrono::Utc::now().timestamp();
+    let window = i64::try_from(state.config.idempotency_window_seconds).unwrap_or(i64::MAX);
+    let claimed = db::idempotency::claim(
+        &state.pool,
+        user_id,
+        &key,
+        &hash,
+        now,
+        now.saturating_sub(window),
+        now - ABANDONED_AFTER_SECS,
+    )
+    .await;
+    match claimed {
+        Err(e) => return ApiError::internal(e).into_response(),
+        Ok(Some(existing)) if existing.request_hash != hash => {
+            return ApiError::BadRequest(
+                "Idempotency-Key was already used for a different request.".to_string(),
+            )
+            .into_response();
+        }
+        Ok(Some(existing)) => {
+            return existing.response.map_or_else(
+                || {
+                    ApiError::Conflict(
+                        "A request with this Idempotency-Key is still in progress.".to_string(),
+                    )
+                    .into_response()
+                },
+                replay,
+            );
+        }
+        Ok(None) => {}
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !response.status().is_success() {
+        if let Err(e) = db::idempotency::release(&state.pool, user_id, &key).await {
+            tracing::warn!(error = %e, "releasing idempotency key failed");
+        }
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => return ApiError::internal(e).into_response(),
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    };
+    // The request took effect either way; if storing fails, retries get 409 until the claim is
+    // abandoned.
+    if let Err(e) = db::idempotency::complete(&state.pool, user_id, &key, &stored).await {
+        tracing::warn!(error = %e, "storing idempotent response failed");
+    }
+    Response::from_parts(parts, Body::from(body))
+}
@@ -1,16 +1,21 @@
-//! Locations REST API: list, get, create, update, delete.
+//! Locations REST API: list, get, create, update, delete, restore.
 
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
 };
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::api::auth::CurrentUserId;
+use crate::api::pagination::{self, ListResponse};
+use crate::api::query::parse_flag;
+use crate::api::sync::new_id;
 use crate::api::{error::ApiError, state::AppState};
 use crate::db;
+use crate::db::audit::AuditEntity;
 use crate::domain::location::Location;
 
 /// Minimal location info for embedding in purchase (and future) responses.
@@ -23,6 +28,8 @@ pub struct LocationRef {
 /// Request body for creating a location.
 #[derive(Debug, Deserialize)]
 pub struct CreateLocationRequest {
+    /// Client-chosen id (offline clients create records before syncing); generated when absent.
+    pub id: Option<Uuid>,
     pub name: String,
 }
 
@@ -32,10 +39,21 @@ pub struct UpdateLocationRequest {
     pub name: Option<String>,
 }
 
+/// Query params for list locations.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListLocationsQuery {
+    /// `name` (default).
+    pub sort: Option<String>,
+    /// Page size; when set the response is a page envelope (see [`ListResponse`]).
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
 /// Query params for delete (optional force).
 #[derive(Debug, Default, Deserialize)]
 pub struct DeleteLocationQuery {
-    #[serde(default, deserialize_with = "parse_force")]
+    #[serde(default, deserialize_with = "parse_flag")]
     pub force: bool,
 }
 
@@ -60,7 +78,7 @@ fn location_to_response(l: &Location) -> LocationResponse {
 fn map_db_error(e: &db::DbError) -> ApiError {
     match e {
         db::DbError::InvalidData(msg) => {
-            if msg.contains("cannot delete") {
+            if msg.contains("cannot delete") || msg.contains("cannot restore") {
                 ApiError::Conflict(msg.clone())
             } else {
                 ApiError::BadRequest(msg.clone())
@@ -72,29 +90,40 @@ fn map_db_error(e: &db::DbError) -> ApiError {
             {
                 return ApiError::BadRequest(e.to_string());
             }
-            ApiError::Internal
+            ApiError::internal(e)
         }
-        db::DbError::Migrate(_) => ApiError::Internal,
+        db::DbError::Migrate(_) => ApiError::internal(e),
     }
 }
 
-/// Deserialize "true" / "false" for force query param.
-pub fn parse_force<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
-    Ok(s.to_lowercase() == "true" || s == "1")
-}
-
 /// GET /api/v1/locations — list all active locations.
+/// `sort`, `limit` and `cursor` select the order and page (see [`crate::api::pagination`]).
 pub async fn list_locations(
     State(state): State<AppState>,
-) -> Result<Json<Vec<LocationResponse>>, ApiError> {
-    let list = db::location::get_all(&state.pool, false)
+    Query(q): Query<ListLocationsQuery>,
+) -> Result<Json<ListResponse<LocationResponse>>, ApiError> {
+    let sort = pagination::parse_sort(
+        q.sort.as_deref(),
+        db::location::LocationSort::Name,
+        db::location::LocationSort::from_param,
+    )?;
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), sort.name())?;
+    if q.sort.is_none() && q.limit.is_none() {
+        let list = db::location::get_all(&state.pool, false)
+            .await
+            .map_err(|e| map_db_error(&e))?;
+        return Ok(Json(ListResponse::All(
+            list.iter().map(location_to_response).collect(),
+        )));
+    }
+    let result = db::location::list_page(&state.pool, sort, false, &page)
         .await
         .map_err(|e| map_db_error(&e))?;
-    Ok(Json(list.iter().map(location_to_response).collect()))
+    Ok(Json(ListResponse::from_page(
+        result,
+        q.limit.is_some(),
+        |l| location_to_response(&l),
+    )))
 }
 
 /// GET /api/v1/locations/:id — get one location.
@@ -117,7 +146,7 @@ pub async fn create_location(
     if body.name.trim().is_empty() {
         return Err(ApiError::BadRequest("Name is required.".to_string()));
     }
-    let id = Uuid::new_v4();
+    let id = new_id(&state.pool, AuditEntity::Location, body.id).await?;
     let location = Location::new(id, body.name.trim().to_string(), None)
         .map_err(|e| ApiError::BadRequest(e.to_string()))?;
     db::location::insert(&state.pool, &location)
@@ -163,6 +192,7 @@ pub async fn update_location(
 /// DELETE /api/v1/locations/:id — soft delete, or hard with ?force=true.
 pub async fn delete_location(
     State(state): State<AppState>,
+    current_user: Option<Extension<CurrentUserId>>,
     Path(id): Path<Uuid>,
     Query(q): Query<DeleteLocationQuery>,
 ) -> Result<StatusCode, ApiError> {
@@ -176,14 +206,39 @@ pub async fn delete_location(
             .await
             .map_err(|e| map_db_error(&e))?;
     } else {
-        db::location::soft_delete(&state.pool, id)
-            .await
-            .map_err(|e| map_db_error(&e))?;
+        db::location::soft_delete(
+            &state.pool,
+            id,
+            current_user.map(|Extension(CurrentUserId(u))| u),
+        )
+        .await
+        .map_err(|e| map_db_error(&e))?;
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Router for /api/v1/locations (all five handlers).
+/// POST /api/v1/locations/:id/restore — restore a soft-deleted location.
+pub async fn restore_location(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LocationResponse>, ApiError> {
+    let existing = db::location::get_by_id(&state.pool, id, true)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .ok_or_else(|| ApiError::NotFound("location not found".to_string()))?;
+    if existing.is_active() {
+        return Err(ApiError::Conflict("location is not deleted".to_string()));
+    }
+
+    db::location::restore(&state.pool, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let restored = Location::new(existing.id(), existing.name().to_string(), None)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(location_to_response(&restored)))
+}
+
+/// Router for /api/v1/locations (all six handlers).
 pub fn route() -> Router<AppState> {
     Router::new()
         .route(
@@ -196,6 +251,7 @@ pub fn route() -> Router<AppState> {
                 .patch(update_location)
                 .delete(delete_location),
         )
+        .route("/api/v1/locations/{id}/restore", post(restore_location))
 }
 
 #[cfg(test)]
@@ -206,7 +262,8 @@ mod tests {
     use tower::ServiceExt;
 
     use super::*;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
 
     async fn test_pool() -> (AppState, tempfile::TempDir) {
         let dir = tempfile::tempdir().expect("temp dir");
@@ -217,10 +274,15 @@ mod tests {
         let state = AppState {
             config: Config {
                 database_path: path_str,
-                jwt_secret: "test".to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
                 jwt_expiration_seconds: 3600,
                 jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
                 bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
                 pid_file: std::env::temp_dir()
                     .join("pocketratings-location-test.pid")
                     .to_string_lossy()
@@ -675,6 +737,59 @@ mod tests {
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn restore_location_returns_200_and_404_when_not_found() {
+        let (state, _dir) = test_pool().await;
+        let location = Location::new(Uuid::new_v4(), "Market".to_string(), None).expect("valid");
+        db::location::insert(&state.pool, &location)
+            .await
+            .expect("insert");
+        db::location::soft_delete(&state.pool, location.id(), None)
+            .await
+            .expect("soft delete");
+        let app = route().with_state(state.clone());
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/locations/{}/restore", location.id()))
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        assert_eq!(json.get("name").and_then(|v| v.as_str()), Some("Market"));
+        assert!(
+            json.get("deleted_at")
+                .is_none_or(serde_json::Value::is_null)
+        );
+        let active = db::location::get_by_id(&state.pool, location.id(), false)
+            .await
+            .expect("db");
+        assert!(active.is_some(), "location must be active after restore");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/locations/{}/restore", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn list_locations_returns_populated_list() {
         let (state, _dir) = test_pool().await;
@@ -0,0 +1,162 @@
+//! `GET /metrics` — Prometheus metrics: request counts and latencies, cache hits and misses (see
+//! [`crate::metrics`]), plus connection pool usage and entity counts read at scrape time.
+//!
+//! Public, like the version endpoint; set `METRICS_BIND` to serve it on a separate address
+//! instead of next to the API.
+
+use axum::{
+    Router,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use super::{error::ApiError, state::AppState};
+use crate::db;
+use crate::metrics::{self, write_header, write_sample};
+
+/// Content type of the Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Handler for `GET /metrics`.
+pub async fn get_metrics(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let counts = db::metrics::entity_counts(&state.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let mut out = String::new();
+    metrics::render(&mut out);
+
+    let pool = &state.pool;
+    write_header(
+        &mut out,
+        "db_pool_connections",
+        "gauge",
+        "SQLite pool connections by state.",
+    );
+    let idle = pool.num_idle();
+    let size = pool.size() as usize;
+    write_sample(&mut out, "db_pool_connections", &[("state", "idle")], &idle);
+    write_sample(
+        &mut out,
+        "db_pool_connections",
+        &[("state", "in_use")],
+        &size.saturating_sub(idle),
+    );
+    write_header(
+        &mut out,
+        "db_pool_max_connections",
+        "gauge",
+        "Maximum number of SQLite pool connections.",
+    );
+    write_sample(
+        &mut out,
+        "db_pool_max_connections",
+        &[],
+        &pool.options().get_max_connections(),
+    );
+
+    write_header(
+        &mut out,
+        "entities",
+        "gauge",
+        "Stored rows by entity type and state (active or soft-deleted), across all households.",
+    );
+    for count in counts {
+        write_sample(
+            &mut out,
+            "entities",
+            &[("entity", count.entity), ("state", "active")],
+            &count.active,
+        );
+        if count.entity != "households" {
+            write_sample(
+                &mut out,
+                "entities",
+                &[("entity", count.entity), ("state", "deleted")],
+                &count.deleted,
+            );
+        }
+    }
+
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], out).into_response())
+}
+
+/// Route for this endpoint (public, no auth).
+pub fn route() -> Router<AppState> {
+    Router::new().route("/metrics", get(get_metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::api::router;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+
+    async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        (status, String::from_utf8(body.to_vec()).expect("utf-8"))
+    }
+
+    #[tokio::test]
+    async fn metrics_include_requests_pool_and_entity_counts() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("metrics_test.db");
+        let path = path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        let state = AppState {
+            config: Config {
+                database_path: path,
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:3099".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: "pocketratings-test.pid".to_string(),
+            },
+            pool,
+        };
+        let app = router(state);
+
+        let (status, _) = get(app.clone(), "/api/v1/version").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = get(app, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+
+        assert!(body.lines().any(|l| l.starts_with(
+            r#"pocketratings_http_requests_total{method="GET",route="/api/v1/version",status="200"}"#
+        )));
+        assert!(body.contains("pocketratings_db_pool_max_connections "));
+        assert!(body.contains(r#"pocketratings_db_pool_connections{state="in_use"}"#));
+        assert!(body.contains(r#"pocketratings_entities{entity="products",state="active"} 0"#));
+        assert!(body.contains(r#"pocketratings_entities{entity="households",state="active"} 1"#));
+        assert!(body.contains(r#"pocketratings_cache_misses_total{cache="product_list"}"#));
+    }
+}
@@ -1,21 +1,35 @@
 //! REST routes and handlers.
 
+mod api_token;
+mod audit;
 mod auth;
 mod category;
 mod error;
+mod etag;
+mod health;
+mod idempotency;
 mod location;
+mod metrics;
+mod pagination;
 mod product;
 mod product_variations;
 mod purchase;
+mod query;
+mod request_log;
 mod review;
 mod router;
 mod server;
 mod state;
+mod stats;
+mod sync;
+mod trash;
 mod user;
 mod version;
 
 pub use error::{ApiError, ErrorBody};
-pub use router::router;
+pub use idempotency::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED};
+pub use request_log::{RequestId, X_REQUEST_ID};
+pub use router::{metrics_router, router};
 pub use server::{ServerError, start as server_start};
 pub use state::AppState;
 pub use version::{VersionResponse, version};
@@ -0,0 +1,139 @@
+//! Shared pagination types for list endpoints.
+//!
+//! List endpoints accept `limit`, `cursor` and `sort` query parameters. Without `limit` they
+//! keep returning a bare JSON array; with `limit` they return a [`PageResponse`] envelope whose
+//! `next_cursor` is passed back as `cursor` to fetch the following page.
+
+use serde::Serialize;
+
+use crate::api::error::ApiError;
+use crate::db::page::{Cursor, MAX_LIMIT, Page, PageRequest};
+
+/// One page of a list: `items` plus the cursor of the next page (`null` on the last page).
+#[derive(Debug, Serialize)]
+pub struct PageResponse<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// List endpoint body: a bare array when no `limit` was given, otherwise a page envelope.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ListResponse<T> {
+    All(Vec<T>),
+    Page(PageResponse<T>),
+}
+
+impl<T> ListResponse<T> {
+    /// Wrap `items` in the shape requested by the client (`paged` when `limit` was given).
+    pub fn new(items: Vec<T>, next_cursor: Option<String>, paged: bool) -> Self {
+        if paged {
+            Self::Page(PageResponse { items, next_cursor })
+        } else {
+            Self::All(items)
+        }
+    }
+
+    /// Map a db page to a list response, converting each item.
+    pub fn from_page<U>(page: Page<U>, paged: bool, f: impl FnMut(U) -> T) -> Self {
+        let items = page.items.into_iter().map(f).collect();
+        Self::new(items, page.next_cursor, paged)
+    }
+}
+
+/// Build a db page request from the `limit` and `cursor` query parameters. `sort` is the name of
+/// the sort in effect; a cursor issued for another sort is rejected.
+///
+/// # Errors
+///
+/// Returns [`ApiError::BadRequest`] if `limit` is out of range, the cursor is malformed or was
+/// issued for another sort, or a cursor is given without a limit.
+pub fn page_request(
+    limit: Option<u32>,
+    cursor: Option<&str>,
+    sort: &str,
+) -> Result<PageRequest, ApiError> {
+    if let Some(limit) = limit
+        && !(1..=MAX_LIMIT).contains(&limit)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Limit must be between 1 and {MAX_LIMIT}."
+        )));
+    }
+    let after = match cursor {
+        None => None,
+        Some(_) if limit.is_none() => {
+            return Err(ApiError::BadRequest("Cursor requires limit.".to_string()));
+        }
+        Some(c) => {
+            let cursor = Cursor::decode(c)
+                .filter(|c| c.sort == sort)
+                .ok_or_else(|| ApiError::BadRequest("Invalid cursor.".to_string()))?;
+            Some(cursor)
+        }
+    };
+    Ok(PageRequest { limit, after })
+}
+
+/// Parse the `sort` query parameter with `parse`, falling back to `default` when absent.
+///
+/// # Errors
+///
+/// Returns [`ApiError::BadRequest`] if the value is not a known sort.
+pub fn parse_sort<S>(
+    sort: Option<&str>,
+    default: S,
+    parse: impl Fn(&str) -> Option<S>,
+) -> Result<S, ApiError> {
+    sort.map_or(Ok(default), |s| {
+        parse(s).ok_or_else(|| ApiError::BadRequest("Invalid sort.".to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::page::Value;
+
+    #[test]
+    fn page_request_without_params_is_unlimited() {
+        let page = page_request(None, None, "name").expect("ok");
+        assert_eq!(page, PageRequest::default());
+    }
+
+    #[test]
+    fn page_request_rejects_out_of_range_limit() {
+        assert!(page_request(Some(0), None, "name").is_err());
+        assert!(page_request(Some(MAX_LIMIT + 1), None, "name").is_err());
+        assert!(page_request(Some(MAX_LIMIT), None, "name").is_ok());
+    }
+
+    #[test]
+    fn page_request_rejects_cursor_without_limit_or_for_other_sort() {
+        let cursor = Cursor {
+            sort: "name".to_string(),
+            key: Value::Text("a".to_string()),
+            id: "1".to_string(),
+        }
+        .encode();
+        assert!(page_request(None, Some(&cursor), "name").is_err());
+        assert!(page_request(Some(10), Some(&cursor), "price").is_err());
+        assert!(page_request(Some(10), Some("garbage"), "name").is_err());
+        let page = page_request(Some(10), Some(&cursor), "name").expect("ok");
+        assert_eq!(page.after.map(|c| c.id), Some("1".to_string()));
+    }
+
+    #[test]
+    fn list_response_serializes_bare_array_or_envelope() {
+        let all = ListResponse::new(vec![1, 2], None, false);
+        assert_eq!(
+            serde_json::to_value(&all).unwrap(),
+            serde_json::json!([1, 2])
+        );
+        let page = ListResponse::new(vec![1], Some("c".to_string()), true);
+        assert_eq!(
+            serde_json::to_value(&page).unwrap(),
+            serde_json::json!({ "items": [1], "next_cursor": "c" })
+        );
+    }
+}
@@ -1,4 +1,4 @@
-//! Products REST API: list, get, create, update, delete.
+//! Products REST API: list, get, create, update, delete, restore.
 
 /// Routing method constructors used in [`route()`]: `get`, `post`, `patch`, `delete` are passed to
 /// `.route("/api/v1/products", get(...).post(...))` and
@@ -9,16 +9,21 @@
 use axum::routing::{delete, get, patch, post};
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
 };
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::api::auth::CurrentUserId;
 use crate::api::category::CategoryRef;
+use crate::api::pagination::{self, ListResponse};
 use crate::api::product_variations;
+use crate::api::query::parse_flag;
+use crate::api::sync::new_id;
 use crate::api::{error::ApiError, state::AppState};
 use crate::db;
+use crate::db::audit::AuditEntity;
 use crate::domain::product::Product;
 use crate::domain::product_variation::ProductVariation;
 
@@ -41,6 +46,8 @@ pub struct FirstVariationRequest {
 /// Request body for creating a product.
 #[derive(Debug, Deserialize)]
 pub struct CreateProductRequest {
+    /// Client-chosen id (offline clients create records before syncing); generated when absent.
+    pub id: Option<Uuid>,
     pub category_id: Uuid,
     pub brand: String,
     pub name: String,
@@ -59,13 +66,21 @@ pub struct UpdateProductRequest {
 #[derive(Debug, Default, Deserialize)]
 pub struct ListProductsQuery {
     pub category_id: Option<Uuid>,
+    /// Full-text search; best match first unless `sort` is given.
     pub q: Option<String>,
+    /// `updated_at` (default), `name`, `review_score`, `price`, `unit_price` or `relevance`
+    /// (default when `q` is set).
+    pub sort: Option<String>,
+    /// Page size; when set the response is a page envelope (see [`ListResponse`]).
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
 }
 
 /// Query params for delete (optional force).
 #[derive(Debug, Default, Deserialize)]
 pub struct DeleteProductQuery {
-    #[serde(default, deserialize_with = "parse_force")]
+    #[serde(default, deserialize_with = "parse_flag")]
     pub force: bool,
 }
 
@@ -86,9 +101,15 @@ pub struct ProductResponse {
     /// Lowest purchase price for this product. Omitted if no purchases.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub price: Option<String>,
+    /// Lowest price per kg or l. Omitted if no purchase of a variation with weight or volume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<product_variations::UnitPriceRef>,
 }
 
-fn product_with_relations_to_response(p: &db::product::ProductWithRelations) -> ProductResponse {
+/// Build the list response of a product (also embedded in the barcode lookup).
+pub fn product_with_relations_to_response(
+    p: &db::product::ProductWithRelations,
+) -> ProductResponse {
     let category = CategoryRef {
         id: p.category_id,
         name: p.category_name.clone(),
@@ -115,6 +136,10 @@ fn product_with_relations_to_response(p: &db::product::ProductWithRelations) ->
         deleted_at: p.deleted_at,
         review_score,
         price: p.lowest_price.clone(),
+        unit_price: p
+            .lowest_unit_price
+            .as_ref()
+            .map(product_variations::unit_price_to_ref),
     }
 }
 
@@ -122,7 +147,7 @@ fn product_with_relations_to_response(p: &db::product::ProductWithRelations) ->
 fn map_db_error(e: &db::DbError) -> ApiError {
     match e {
         db::DbError::InvalidData(msg) => {
-            if msg.contains("cannot delete") {
+            if msg.contains("cannot delete") || msg.contains("cannot restore") {
                 ApiError::Conflict(msg.clone())
             } else {
                 ApiError::BadRequest(msg.clone())
@@ -134,27 +159,37 @@ fn map_db_error(e: &db::DbError) -> ApiError {
             {
                 return ApiError::BadRequest(e.to_string());
             }
-            ApiError::Internal
+            ApiError::internal(e)
         }
-        db::DbError::Migrate(_) => ApiError::Internal,
+        db::DbError::Migrate(_) => ApiError::internal(e),
     }
 }
 
-/// Deserialize "true" / "false" for force query param.
-pub fn parse_force<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
-    Ok(s.to_lowercase() == "true" || s == "1")
-}
-
 /// GET /api/v1/products — list products, optionally filtered by `category_id` (subtree) and/or `q` (search).
 /// When `category_id` is set, returns products in that category or any descendant; 404 if category not found or deleted.
+/// `sort`, `limit` and `cursor` select the order and page (see [`crate::api::pagination`]); without
+/// them the cached list is returned in the default order.
 pub async fn list_products(
     State(state): State<AppState>,
     Query(q): Query<ListProductsQuery>,
-) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+) -> Result<Json<ListResponse<ProductResponse>>, ApiError> {
+    let search = q.q.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let default_sort = if search.is_some() {
+        db::product::ProductSort::Relevance
+    } else {
+        db::product::ProductSort::UpdatedAt
+    };
+    let sort = pagination::parse_sort(
+        q.sort.as_deref(),
+        default_sort,
+        db::product::ProductSort::from_param,
+    )?;
+    if sort == db::product::ProductSort::Relevance && search.is_none() {
+        return Err(ApiError::BadRequest(
+            "Sort relevance requires q.".to_string(),
+        ));
+    }
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), sort.name())?;
     let category_ids = if let Some(cat_id) = q.category_id {
         let ids = db::category::get_category_and_descendant_ids(
             &state.pool,
@@ -171,14 +206,31 @@ pub async fn list_products(
     } else {
         None
     };
-    let list = db::product::list_with_relations(&state.pool, category_ids, q.q.as_deref(), false)
-        .await
-        .map_err(|e| map_db_error(&e))?;
-    let mut out = Vec::with_capacity(list.len());
-    for p in &list {
-        out.push(product_with_relations_to_response(p));
+    if q.sort.is_none() && q.limit.is_none() {
+        let list = db::product::list_with_relations(&state.pool, category_ids, search, false)
+            .await
+            .map_err(|e| map_db_error(&e))?;
+        let out = list
+            .iter()
+            .map(product_with_relations_to_response)
+            .collect();
+        return Ok(Json(ListResponse::All(out)));
     }
-    Ok(Json(out))
+    let result = db::product::list_page(
+        &state.pool,
+        category_ids.as_deref(),
+        search,
+        sort,
+        false,
+        &page,
+    )
+    .await
+    .map_err(|e| map_db_error(&e))?;
+    Ok(Json(ListResponse::from_page(
+        result,
+        q.limit.is_some(),
+        |p| product_with_relations_to_response(&p),
+    )))
 }
 
 /// Response body for GET /api/v1/products/:id (product with nested variations).
@@ -238,7 +290,7 @@ pub async fn create_product(
     }
 
     let now = chrono::Utc::now().timestamp();
-    let id = Uuid::new_v4();
+    let id = new_id(&state.pool, AuditEntity::Product, body.id).await?;
     let product = Product::new(
         id,
         body.category_id,
@@ -354,6 +406,7 @@ pub async fn update_product(
 /// DELETE /api/v1/products/:id — soft delete, or hard with ?force=true.
 pub async fn delete_product(
     State(state): State<AppState>,
+    current_user: Option<Extension<CurrentUserId>>,
     Path(id): Path<Uuid>,
     Query(q): Query<DeleteProductQuery>,
 ) -> Result<StatusCode, ApiError> {
@@ -367,14 +420,42 @@ pub async fn delete_product(
             .await
             .map_err(|e| map_db_error(&e))?;
     } else {
-        db::product::soft_delete(&state.pool, id)
-            .await
-            .map_err(|e| map_db_error(&e))?;
+        db::product::soft_delete(
+            &state.pool,
+            id,
+            current_user.map(|Extension(CurrentUserId(u))| u),
+        )
+        .await
+        .map_err(|e| map_db_error(&e))?;
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Router for /api/v1/products (list, get, create, update, delete) and variation
+/// POST /api/v1/products/:id/restore — restore a soft-deleted product; 409 when its category is
+/// deleted.
+pub async fn restore_product(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProductResponse>, ApiError> {
+    let existing = db::product::get_by_id(&state.pool, id, true)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .ok_or_else(|| ApiError::NotFound("Product not found.".to_string()))?;
+    if existing.is_active() {
+        return Err(ApiError::Conflict("Product is not deleted.".to_string()));
+    }
+
+    db::product::restore(&state.pool, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let current = db::product::get_by_id_with_relations(&state.pool, id, false)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .expect("product just restored");
+    Ok(Json(product_with_relations_to_response(&current)))
+}
+
+/// Router for /api/v1/products (list, get, create, update, delete, restore) and variation
 /// sub-routes (merged from `product_variations`).
 pub fn route() -> Router<AppState> {
     Router::new()
@@ -385,6 +466,7 @@ pub fn route() -> Router<AppState> {
                 .patch(update_product)
                 .delete(delete_product),
         )
+        .route("/api/v1/products/{id}/restore", post(restore_product))
         .merge(product_variations::route())
 }
 
@@ -400,7 +482,8 @@ mod tests {
     use rust_decimal::Decimal;
 
     use super::*;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
     use crate::db;
     use crate::domain::category::Category;
     use crate::domain::product_variation::{ProductVariation, Unit};
@@ -419,10 +502,15 @@ mod tests {
         let state = AppState {
             config: Config {
                 database_path: path_str,
-                jwt_secret: "test".to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
                 jwt_expiration_seconds: 3600,
                 jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
                 bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
                 pid_file: std::env::temp_dir()
                     .join("pocketratings-product-test.pid")
                     .to_string_lossy()
@@ -547,6 +635,168 @@ mod tests {
         assert_eq!(product.get("price").and_then(|v| v.as_str()), Some("2.99"));
     }
 
+    #[tokio::test]
+    async fn list_products_sort_unit_price_orders_cheapest_per_kg_first() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Coffee").await;
+        let user_id = insert_user(&state.pool, "User", "u@example.com").await;
+        let loc_id = insert_location(&state.pool, "Store").await;
+        let now = chrono::Utc::now().timestamp();
+        let pricey_id = insert_product(&state.pool, cat_id, "B", "Pricey").await;
+        let cheap_id = insert_product(&state.pool, cat_id, "B", "Cheap").await;
+        let unrated_id = insert_product(&state.pool, cat_id, "B", "Loose").await;
+        ensure_product_variation(&state.pool, unrated_id).await;
+        for (product_id, grams, price) in [(pricey_id, 250, "4.00"), (cheap_id, 1000, "9.00")] {
+            let var_id = Uuid::new_v4();
+            let var = ProductVariation::new(
+                var_id,
+                product_id,
+                "",
+                &Unit::Grams.to_string(),
+                Some(grams),
+                now,
+                now,
+                None,
+            )
+            .expect("valid");
+            db::product_variation::insert(&state.pool, &var)
+                .await
+                .expect("insert");
+            let purchase = Purchase::new(
+                Uuid::new_v4(),
+                user_id,
+                product_id,
+                var_id,
+                loc_id,
+                1,
+                price.parse().expect("decimal"),
+                now,
+                None,
+            )
+            .expect("valid");
+            db::purchase::insert(&state.pool, &purchase)
+                .await
+                .expect("insert");
+        }
+
+        let app = route().with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/products?sort=unit_price")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        let arr = json.as_array().expect("array");
+        let names: Vec<&str> = arr
+            .iter()
+            .map(|p| p.get("name").and_then(|v| v.as_str()).expect("name"))
+            .collect();
+        assert_eq!(names, vec!["Cheap", "Pricey", "Loose"]);
+        assert_eq!(
+            arr[0].get("unit_price"),
+            Some(&serde_json::json!({ "price": "9.00", "per": "kg" }))
+        );
+        assert_eq!(
+            arr[1].get("unit_price"),
+            Some(&serde_json::json!({ "price": "16.00", "per": "kg" }))
+        );
+        assert!(arr[2].get("unit_price").is_none());
+    }
+
+    #[tokio::test]
+    async fn list_products_returns_400_when_sort_invalid() {
+        let (state, _dir) = test_pool().await;
+        let app = route().with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/products?sort=bogus")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn list_products_with_limit_returns_page_envelope_and_cursor() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Snacks").await;
+        for name in ["Chips", "Apples", "Biscuits"] {
+            insert_product(&state.pool, cat_id, "B", name).await;
+        }
+
+        let mut names = Vec::new();
+        let mut uri = "/api/v1/products?sort=name&limit=2".to_string();
+        loop {
+            let app = route().with_state(state.clone());
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri(&uri)
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("service");
+            assert_eq!(response.status(), StatusCode::OK);
+            let bytes = response
+                .into_body()
+                .collect()
+                .await
+                .expect("body")
+                .to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+            let items = json.get("items").and_then(|v| v.as_array()).expect("items");
+            names.extend(
+                items
+                    .iter()
+                    .filter_map(|p| p.get("name").and_then(|v| v.as_str()))
+                    .map(str::to_string),
+            );
+            match json.get("next_cursor").and_then(|v| v.as_str()) {
+                Some(cursor) => uri = format!("/api/v1/products?sort=name&limit=2&cursor={cursor}"),
+                None => break,
+            }
+        }
+        assert_eq!(names, vec!["Apples", "Biscuits", "Chips"]);
+    }
+
+    #[tokio::test]
+    async fn list_products_returns_400_when_limit_or_cursor_invalid() {
+        let (state, _dir) = test_pool().await;
+        for uri in [
+            "/api/v1/products?limit=0",
+            "/api/v1/products?limit=501",
+            "/api/v1/products?limit=10&cursor=bogus",
+            "/api/v1/products?cursor=bogus",
+        ] {
+            let app = route().with_state(state.clone());
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri(uri)
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("service");
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
     #[tokio::test]
     async fn create_product_returns_201_and_body() {
         let (state, _dir) = test_pool().await;
@@ -928,6 +1178,264 @@ mod tests {
         assert_eq!(response.status(), StatusCode::CONFLICT);
     }
 
+    async fn send_json(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let builder = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(b) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&b).expect("json"))),
+            None => builder.body(Body::empty()),
+        }
+        .expect("request");
+        let response = route()
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .expect("service");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    #[tokio::test]
+    async fn variation_by_barcode_returns_product_variation_and_price_history() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, cat_id, "B", "Milk").await;
+        let (status, created) = send_json(
+            &state,
+            "POST",
+            &format!("/api/v1/products/{product_id}/variations"),
+            Some(serde_json::json!({
+                "label": "1 l",
+                "unit": "milliliters",
+                "quantity": 1000,
+                "barcode": " 4006381333931 "
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["barcode"], "4006381333931");
+        let var_id: Uuid = created["id"].as_str().expect("id").parse().expect("uuid");
+        let user_id = insert_user(&state.pool, "User", "u@example.com").await;
+        let loc_id = insert_location(&state.pool, "Store").await;
+        for (price, at) in [("1.20", 1_000), ("1.50", 2_000)] {
+            let purchase = Purchase::new(
+                Uuid::new_v4(),
+                user_id,
+                product_id,
+                var_id,
+                loc_id,
+                1,
+                price.parse().expect("decimal"),
+                at,
+                None,
+            )
+            .expect("valid");
+            db::purchase::insert(&state.pool, &purchase)
+                .await
+                .expect("insert");
+        }
+
+        let (status, json) = send_json(
+            &state,
+            "GET",
+            "/api/v1/variations/by-barcode/4006381333931",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["product"]["name"], "Milk");
+        assert_eq!(json["product"]["price"], "1.20");
+        assert_eq!(json["variation"]["id"], var_id.to_string());
+        assert_eq!(json["variation"]["purchase_count"], 2);
+        let history = json["price_history"].as_array().expect("array");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["price"], "1.50");
+        assert_eq!(history[0]["unit_price"]["per"], "l");
+        assert_eq!(history[0]["location"]["name"], "Store");
+        assert_eq!(history[1]["purchased_at"], 1_000);
+    }
+
+    #[tokio::test]
+    async fn variation_by_barcode_returns_400_for_invalid_and_404_for_unknown() {
+        let (state, _dir) = test_pool().await;
+        let (status, _) = send_json(
+            &state,
+            "GET",
+            "/api/v1/variations/by-barcode/4006381333932",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send_json(
+            &state,
+            "GET",
+            "/api/v1/variations/by-barcode/96385074",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn variation_barcode_must_be_unique_and_can_be_cleared() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, cat_id, "B", "N").await;
+        let first_id = ensure_product_variation(&state.pool, product_id).await;
+        let uri = format!("/api/v1/variations/{first_id}");
+        let (status, json) = send_json(
+            &state,
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "barcode": "96385074" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            json["barcode"], "0000096385074",
+            "stored zero-padded to EAN-13"
+        );
+
+        let (status, _) = send_json(
+            &state,
+            "POST",
+            &format!("/api/v1/products/{product_id}/variations"),
+            Some(serde_json::json!({ "unit": "other", "barcode": "96385074" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, json) = send_json(
+            &state,
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "label": "x" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["barcode"], "0000096385074");
+
+        let (status, json) = send_json(
+            &state,
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "barcode": null })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json.get("barcode").is_none());
+    }
+
+    #[tokio::test]
+    async fn variation_price_history_summarizes_prices_and_trend() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, cat_id, "B", "Olive oil").await;
+        let (_, created) = send_json(
+            &state,
+            "POST",
+            &format!("/api/v1/products/{product_id}/variations"),
+            Some(serde_json::json!({ "unit": "milliliters", "quantity": 500 })),
+        )
+        .await;
+        let var_id: Uuid = created["id"].as_str().expect("id").parse().expect("uuid");
+        let user_id = insert_user(&state.pool, "User", "u@example.com").await;
+        let market = insert_location(&state.pool, "Market").await;
+        let corner = insert_location(&state.pool, "Corner").await;
+        let day = 86_400;
+        let now = chrono::Utc::now().timestamp();
+        for (location, price, at) in [
+            (market, "4.00", now - 730 * day),
+            (corner, "5.00", now - 180 * day),
+            (market, "5.75", now - day),
+        ] {
+            let purchase = Purchase::new(
+                Uuid::new_v4(),
+                user_id,
+                product_id,
+                var_id,
+                location,
+                1,
+                price.parse().expect("decimal"),
+                at,
+                None,
+            )
+            .expect("valid");
+            db::purchase::insert(&state.pool, &purchase)
+                .await
+                .expect("insert");
+        }
+
+        let uri = format!("/api/v1/variations/{var_id}/price-history");
+        let (status, json) = send_json(&state, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let points = json["points"].as_array().expect("points");
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0]["price"], "4.00");
+        assert_eq!(points[2]["unit_price"]["price"], "11.50");
+        assert_eq!(json["summary"]["purchase_count"], 3);
+        assert_eq!(json["summary"]["min"]["price"], "4.00");
+        assert_eq!(json["summary"]["median"]["unit_price"]["price"], "10.00");
+        assert_eq!(json["summary"]["max"]["price"], "5.75");
+        assert_eq!(json["summary"]["last"]["price"], "5.75");
+        assert_eq!(json["months"], 12);
+        assert_eq!(json["change_percent"], "43.8");
+        let locations = json["locations"].as_array().expect("locations");
+        assert_eq!(locations[0]["location"]["name"], "Corner");
+        assert_eq!(locations[1]["purchase_count"], 2);
+        assert_eq!(locations[1]["min"]["price"], "4.00");
+
+        let (_, json) = send_json(&state, "GET", &format!("{uri}?months=3"), None).await;
+        assert_eq!(json["change_percent"], "15.0");
+    }
+
+    #[tokio::test]
+    async fn variation_price_history_rejects_bad_months_and_unknown_variation() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, cat_id, "B", "N").await;
+        let var_id = ensure_product_variation(&state.pool, product_id).await;
+        let (status, json) = send_json(
+            &state,
+            "GET",
+            &format!("/api/v1/variations/{var_id}/price-history"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json["summary"].is_null());
+        assert!(json["change_percent"].is_null());
+        let (status, _) = send_json(
+            &state,
+            "GET",
+            &format!("/api/v1/variations/{var_id}/price-history?months=0"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send_json(
+            &state,
+            "GET",
+            &format!("/api/v1/variations/{}/price-history", Uuid::new_v4()),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn create_product_returns_404_when_category_not_found() {
         let (state, _dir) = test_pool().await;
@@ -1244,6 +1752,51 @@ mod tests {
         assert_eq!(arr[0].get("name").and_then(|v| v.as_str()), Some("Milk"));
     }
 
+    #[tokio::test]
+    async fn list_products_with_q_folds_accents_and_pages_by_relevance() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Dairy").await;
+        let creme_id = insert_product(&state.pool, cat_id, "Elvea", "Crème fraîche").await;
+        insert_product(&state.pool, cat_id, "Elvea", "Yoghurt").await;
+
+        let app = route().with_state(state.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/products?q=creme%20fra&limit=10")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        let items = json.get("items").and_then(|v| v.as_array()).expect("items");
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].get("id").and_then(|v| v.as_str()),
+            Some(creme_id.to_string().as_str())
+        );
+
+        let app = route().with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/products?sort=relevance")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn list_products_with_q_search_by_category_name() {
         let (state, _dir) = test_pool().await;
@@ -1579,6 +2132,80 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn restore_product_returns_409_while_category_deleted_then_200() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Dairy").await;
+        let product_id = insert_product(&state.pool, cat_id, "Brand", "Yoghurt").await;
+        db::product::soft_delete(&state.pool, product_id, None)
+            .await
+            .expect("soft delete product");
+        db::category::soft_delete(&state.pool, cat_id, None)
+            .await
+            .expect("soft delete category");
+        let uri = format!("/api/v1/products/{product_id}/restore");
+
+        let (status, json) = send_json(&state, "POST", &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(json["error"], "conflict");
+
+        db::category::restore(&state.pool, cat_id)
+            .await
+            .expect("restore category");
+        let (status, json) = send_json(&state, "POST", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["name"], "Yoghurt");
+        assert!(
+            json.get("deleted_at")
+                .is_none_or(serde_json::Value::is_null)
+        );
+
+        let (status, _) = send_json(&state, "POST", &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT, "product is no longer deleted");
+    }
+
+    #[tokio::test]
+    async fn restore_variation_returns_200_and_lists_it_again() {
+        let (state, _dir) = test_pool().await;
+        let cat_id = insert_category(&state.pool, "Dairy").await;
+        let product_id = insert_product(&state.pool, cat_id, "Brand", "Milk").await;
+        ensure_product_variation(&state.pool, product_id).await;
+        let (_, created) = send_json(
+            &state,
+            "POST",
+            &format!("/api/v1/products/{product_id}/variations"),
+            Some(serde_json::json!({ "label": "1 L", "unit": "milliliters", "quantity": 1000 })),
+        )
+        .await;
+        let var_id = created["id"].as_str().expect("id").to_string();
+        let (status, _) = send_json(
+            &state,
+            "DELETE",
+            &format!("/api/v1/variations/{var_id}"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, json) = send_json(
+            &state,
+            "POST",
+            &format!("/api/v1/variations/{var_id}/restore"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["label"], "1 L");
+        let (_, list) = send_json(
+            &state,
+            "GET",
+            &format!("/api/v1/products/{product_id}/variations"),
+            None,
+        )
+        .await;
+        assert_eq!(list.as_array().expect("array").len(), 2);
+    }
+
     #[tokio::test]
     async fn delete_product_returns_404_when_not_found() {
         let (state, _dir) = test_pool().await;
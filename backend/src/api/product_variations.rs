@@ -1,22 +1,31 @@
-//! Product variations REST API: list, create, update, delete.
+//! Product variations REST API: list, create, update, delete, restore.
 //!
-//! Handlers and types for GET/POST /api/v1/products/:id/variations and
-//! PATCH/DELETE /api/v1/variations/:id. Used by product detail (variations
-//! included in GET product) and by the variation list endpoint.
+//! Handlers and types for GET/POST /api/v1/products/:id/variations,
+//! PATCH/DELETE /api/v1/variations/:id, POST /api/v1/variations/:id/restore,
+//! GET /api/v1/variations/:id/price-history and GET /api/v1/variations/by-barcode/:code.
+//! Used by product detail (variations included in GET product) and by the
+//! variation list endpoint.
 
 #[allow(unused_imports)]
 use axum::routing::{delete, get, patch, post};
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
 };
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::api::auth::CurrentUserId;
+use crate::api::location::LocationRef;
+use crate::api::product::{ProductResponse, product_with_relations_to_response};
 use crate::api::{error::ApiError, state::AppState};
 use crate::db;
-use crate::domain::product_variation::ProductVariation;
+use crate::db::page::PageRequest;
+use crate::db::purchase::PurchaseWithRelations;
+use crate::db::stats::PriceStats;
+use crate::domain::product_variation::{ProductVariation, UnitPrice, is_valid_gtin};
 
 /// One variation in list response (GET /api/v1/products/:id/variations) and in
 /// product detail variations array.
@@ -27,33 +36,184 @@ pub struct VariationListItem {
     pub unit: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quantity: Option<u32>,
+    /// GTIN (EAN/UPC) barcode. Omitted when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub barcode: Option<String>,
     /// Number of non-deleted purchases referencing this variation (for edit-product UI).
     pub purchase_count: u64,
 }
 
+fn variation_to_list_item(v: &ProductVariation, purchase_count: i64) -> VariationListItem {
+    VariationListItem {
+        id: v.id(),
+        label: v.label().to_string(),
+        unit: v.unit().to_string(),
+        quantity: v.quantity(),
+        barcode: v.barcode().map(str::to_string),
+        purchase_count: u64::try_from(purchase_count).unwrap_or(0),
+    }
+}
+
+/// Price normalized to one kilogram or one litre, embedded in purchase and product responses.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UnitPriceRef {
+    pub price: String,
+    /// Base unit: "kg" or "l".
+    pub per: String,
+}
+
+/// Build the response representation of a normalized unit price.
+pub fn unit_price_to_ref(u: &UnitPrice) -> UnitPriceRef {
+    UnitPriceRef {
+        price: u.price.to_string(),
+        per: u.per.to_string(),
+    }
+}
+
 /// Request body for creating a variation (POST /api/v1/products/:id/variations).
 #[derive(Debug, Deserialize)]
 pub struct CreateVariationRequest {
     pub label: Option<String>,
     pub unit: String,
     pub quantity: Option<u32>,
+    pub barcode: Option<String>,
 }
 
 /// Request body for updating a variation (PATCH /api/v1/variations/:id).
-/// `quantity`: omit = keep existing, null = clear, number = set.
+/// `quantity` and `barcode`: omit = keep existing, null = clear, value = set.
 #[derive(Debug, Deserialize)]
 #[allow(clippy::option_option)]
 pub struct UpdateVariationRequest {
     pub label: Option<String>,
     pub unit: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
     pub quantity: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub barcode: Option<Option<String>>,
+}
+
+/// Deserialize a present field (value or null) as `Some`, so that null can be told apart from
+/// an omitted field (which `#[serde(default)]` leaves as `None`).
+#[allow(clippy::option_option)]
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// One purchase in the price history of a variation.
+#[derive(Debug, serde::Serialize)]
+pub struct PricePoint {
+    pub purchased_at: i64,
+    pub price: String,
+    /// Price per kg or l. Omitted when the variation has no weight or volume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<UnitPriceRef>,
+    pub location: LocationRef,
+}
+
+fn purchase_to_price_point(p: &PurchaseWithRelations) -> PricePoint {
+    PricePoint {
+        purchased_at: p.purchased_at,
+        price: p.price.clone(),
+        unit_price: p.unit_price().as_ref().map(unit_price_to_ref),
+        location: LocationRef {
+            id: p.location_id,
+            name: p.location_name.clone(),
+        },
+    }
+}
+
+/// Query params for GET /api/v1/variations/:id/price-history.
+#[derive(Debug, Default, Deserialize)]
+pub struct PriceHistoryQuery {
+    /// Trailing window for `change_percent`, in months (default 12).
+    pub months: Option<u32>,
+}
+
+/// An item price with its price per kg or l.
+#[derive(Debug, serde::Serialize)]
+pub struct PriceValue {
+    pub price: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<UnitPriceRef>,
+}
+
+/// Lowest, median, highest and last price of a set of purchases.
+#[derive(Debug, serde::Serialize)]
+pub struct PriceSummary {
+    pub purchase_count: u64,
+    pub min: PriceValue,
+    pub median: PriceValue,
+    pub max: PriceValue,
+    pub last: PriceValue,
+}
+
+/// Price summary of the purchases at one location.
+#[derive(Debug, serde::Serialize)]
+pub struct LocationPriceSummary {
+    pub location: LocationRef,
+    #[serde(flatten)]
+    pub summary: PriceSummary,
+}
+
+/// Response body for GET /api/v1/variations/:id/price-history.
+#[derive(Debug, serde::Serialize)]
+pub struct PriceHistoryResponse {
+    pub variation_id: Uuid,
+    /// Purchases oldest first.
+    pub points: Vec<PricePoint>,
+    /// `null` when the variation has no purchases.
+    pub summary: Option<PriceSummary>,
+    /// Ordered by location name.
+    pub locations: Vec<LocationPriceSummary>,
+    pub months: u32,
+    /// Start of the trend window (UNIX time).
+    pub change_since: i64,
+    /// Percent change of the last price over the window, one decimal.
+    pub change_percent: Option<String>,
+}
+
+/// Default and maximum `months` for the price history trend.
+const DEFAULT_TREND_MONTHS: u32 = 12;
+const MAX_TREND_MONTHS: u32 = 120;
+
+fn price_value(price: Decimal, variation: &ProductVariation) -> PriceValue {
+    PriceValue {
+        price: price.to_string(),
+        unit_price: UnitPrice::from_item_price(price, variation.unit(), variation.quantity())
+            .as_ref()
+            .map(unit_price_to_ref),
+    }
+}
+
+fn price_summary(stats: &PriceStats, variation: &ProductVariation) -> PriceSummary {
+    PriceSummary {
+        purchase_count: stats.purchase_count,
+        min: price_value(stats.min, variation),
+        median: price_value(stats.median, variation),
+        max: price_value(stats.max, variation),
+        last: price_value(stats.last, variation),
+    }
+}
+
+/// Response body for GET /api/v1/variations/by-barcode/:code.
+#[derive(Debug, serde::Serialize)]
+pub struct BarcodeLookupResponse {
+    /// Product with category, review score and prices (same shape as in the product list).
+    pub product: ProductResponse,
+    pub variation: VariationListItem,
+    /// Purchases of this variation, newest first.
+    pub price_history: Vec<PricePoint>,
 }
 
 #[allow(clippy::needless_pass_by_value)]
 fn map_db_error(e: db::DbError) -> ApiError {
     match &e {
         db::DbError::InvalidData(msg) => {
-            if msg.contains("cannot delete") {
+            if msg.contains("cannot delete") || msg.contains("cannot restore") {
                 ApiError::Conflict(msg.clone())
             } else {
                 ApiError::BadRequest(msg.clone())
@@ -65,9 +225,9 @@ fn map_db_error(e: db::DbError) -> ApiError {
             {
                 return ApiError::BadRequest(e.to_string());
             }
-            ApiError::Internal
+            ApiError::internal(e)
         }
-        db::DbError::Migrate(_) => ApiError::Internal,
+        db::DbError::Migrate(_) => ApiError::internal(e),
     }
 }
 
@@ -86,20 +246,30 @@ pub async fn list_variations_for_product(
         .map_err(map_db_error)?;
     let list: Vec<VariationListItem> = variations
         .iter()
-        .map(|v| {
-            let purchase_count = counts.get(&v.id()).copied().unwrap_or(0);
-            VariationListItem {
-                id: v.id(),
-                label: v.label().to_string(),
-                unit: v.unit().to_string(),
-                quantity: v.quantity(),
-                purchase_count: u64::try_from(purchase_count).unwrap_or(0),
-            }
-        })
+        .map(|v| variation_to_list_item(v, counts.get(&v.id()).copied().unwrap_or(0)))
         .collect();
     Ok(list)
 }
 
+/// 409 if another active variation already has the barcode of `variation`.
+async fn ensure_barcode_available(
+    pool: &sqlx::SqlitePool,
+    variation: &ProductVariation,
+) -> Result<(), ApiError> {
+    let Some(barcode) = variation.barcode() else {
+        return Ok(());
+    };
+    let other = db::product_variation::get_by_barcode(pool, barcode)
+        .await
+        .map_err(map_db_error)?;
+    if other.is_some_and(|o| o.id() != variation.id()) {
+        return Err(ApiError::Conflict(
+            "Barcode is already used by another variation.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// GET /api/v1/products/:id/variations — list active variations for a product.
 pub async fn list_product_variations(
     State(state): State<AppState>,
@@ -134,18 +304,16 @@ pub async fn create_variation(
         chrono::Utc::now().timestamp(),
         None,
     )
+    .and_then(|v| v.with_barcode(body.barcode.as_deref()))
     .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    ensure_barcode_available(&state.pool, &variation).await?;
     db::product_variation::insert(&state.pool, &variation)
         .await
         .map_err(map_db_error)?;
-    let item = VariationListItem {
-        id: variation.id(),
-        label: variation.label().to_string(),
-        unit: variation.unit().to_string(),
-        quantity: variation.quantity(),
-        purchase_count: 0,
-    };
-    Ok((StatusCode::CREATED, Json(item)))
+    Ok((
+        StatusCode::CREATED,
+        Json(variation_to_list_item(&variation, 0)),
+    ))
 }
 
 /// PATCH /api/v1/variations/:id — update a variation.
@@ -168,6 +336,9 @@ pub async fn update_variation(
         .as_deref()
         .map_or_else(|| existing.unit().to_string(), |s| s.trim().to_string());
     let quantity = body.quantity.unwrap_or_else(|| existing.quantity());
+    let barcode = body
+        .barcode
+        .unwrap_or_else(|| existing.barcode().map(str::to_string));
     let updated = ProductVariation::new(
         existing.id(),
         existing.product_id(),
@@ -178,7 +349,9 @@ pub async fn update_variation(
         chrono::Utc::now().timestamp(),
         existing.deleted_at(),
     )
+    .and_then(|v| v.with_barcode(barcode.as_deref()))
     .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    ensure_barcode_available(&state.pool, &updated).await?;
     db::product_variation::update(&state.pool, &updated)
         .await
         .map_err(map_db_error)?;
@@ -186,19 +359,13 @@ pub async fn update_variation(
         .await
         .map_err(map_db_error)?;
     let purchase_count = count.get(&id).copied().unwrap_or(0);
-    let item = VariationListItem {
-        id: updated.id(),
-        label: updated.label().to_string(),
-        unit: updated.unit().to_string(),
-        quantity: updated.quantity(),
-        purchase_count: u64::try_from(purchase_count).unwrap_or(0),
-    };
-    Ok(Json(item))
+    Ok(Json(variation_to_list_item(&updated, purchase_count)))
 }
 
 /// DELETE /api/v1/variations/:id — soft-delete a variation.
 pub async fn delete_variation(
     State(state): State<AppState>,
+    current_user: Option<Extension<CurrentUserId>>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, ApiError> {
     let existing = db::product_variation::get_by_id(&state.pool, id, false)
@@ -218,10 +385,130 @@ pub async fn delete_variation(
             "Cannot delete the last variation.".to_string(),
         ));
     }
-    db::product_variation::soft_delete(&state.pool, id)
+    db::product_variation::soft_delete(
+        &state.pool,
+        id,
+        current_user.map(|Extension(CurrentUserId(u))| u),
+    )
+    .await
+    .map_err(map_db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/variations/:id/restore — restore a soft-deleted variation; 409 when its product
+/// is deleted or an active variation took its barcode.
+pub async fn restore_variation(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<VariationListItem>, ApiError> {
+    let existing = db::product_variation::get_by_id(&state.pool, id, true)
+        .await
+        .map_err(map_db_error)?
+        .ok_or_else(|| ApiError::NotFound("Variation not found.".to_string()))?;
+    if existing.is_active() {
+        return Err(ApiError::Conflict("Variation is not deleted.".to_string()));
+    }
+    ensure_barcode_available(&state.pool, &existing).await?;
+    db::product_variation::restore(&state.pool, id)
         .await
         .map_err(map_db_error)?;
-    Ok(StatusCode::NO_CONTENT)
+    let restored = db::product_variation::get_by_id(&state.pool, id, false)
+        .await
+        .map_err(map_db_error)?
+        .expect("variation just restored");
+    let count = db::purchase::count_by_variation_ids(&state.pool, &[id])
+        .await
+        .map_err(map_db_error)?;
+    let purchase_count = count.get(&id).copied().unwrap_or(0);
+    Ok(Json(variation_to_list_item(&restored, purchase_count)))
+}
+
+/// GET /api/v1/variations/by-barcode/:code — find the variation with a scanned barcode, with
+/// its product (review score, prices) and price history.
+pub async fn get_variation_by_barcode(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<BarcodeLookupResponse>, ApiError> {
+    let code = code.trim();
+    if !is_valid_gtin(code) {
+        return Err(ApiError::BadRequest("Invalid barcode.".to_string()));
+    }
+    let variation = db::product_variation::get_by_barcode(&state.pool, code)
+        .await
+        .map_err(map_db_error)?
+        .ok_or_else(|| ApiError::NotFound("Variation not found.".to_string()))?;
+    let product = db::product::get_by_id_with_aggregates(&state.pool, variation.product_id())
+        .await
+        .map_err(map_db_error)?
+        .ok_or_else(|| ApiError::NotFound("Product not found.".to_string()))?;
+    let filter = db::purchase::PurchaseFilter {
+        variation_id: Some(variation.id()),
+        ..Default::default()
+    };
+    let purchases = db::purchase::list_page(
+        &state.pool,
+        &filter,
+        db::purchase::PurchaseSort::PurchasedAt,
+        &PageRequest::default(),
+    )
+    .await
+    .map_err(map_db_error)?
+    .items;
+    let price_history = purchases.iter().map(purchase_to_price_point).collect();
+    let purchase_count = i64::try_from(purchases.len()).unwrap_or(i64::MAX);
+    Ok(Json(BarcodeLookupResponse {
+        product: product_with_relations_to_response(&product),
+        variation: variation_to_list_item(&variation, purchase_count),
+        price_history,
+    }))
+}
+
+/// GET /api/v1/variations/:id/price-history — prices paid for a variation over time, with
+/// min/median/max overall and per location, and the percent change over the last `months`.
+pub async fn get_price_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<PriceHistoryQuery>,
+) -> Result<Json<PriceHistoryResponse>, ApiError> {
+    let months = q.months.unwrap_or(DEFAULT_TREND_MONTHS);
+    if !(1..=MAX_TREND_MONTHS).contains(&months) {
+        return Err(ApiError::BadRequest(format!(
+            "Months must be between 1 and {MAX_TREND_MONTHS}."
+        )));
+    }
+    let variation = db::product_variation::get_by_id(&state.pool, id, false)
+        .await
+        .map_err(map_db_error)?
+        .ok_or_else(|| ApiError::NotFound("Variation not found.".to_string()))?;
+    let history = db::stats::price_history(&state.pool, id, months)
+        .await
+        .map_err(map_db_error)?;
+    Ok(Json(PriceHistoryResponse {
+        variation_id: id,
+        points: history
+            .purchases
+            .iter()
+            .map(purchase_to_price_point)
+            .collect(),
+        summary: history
+            .overall
+            .as_ref()
+            .map(|s| price_summary(s, &variation)),
+        locations: history
+            .by_location
+            .iter()
+            .map(|l| LocationPriceSummary {
+                location: LocationRef {
+                    id: l.location_id,
+                    name: l.location_name.clone(),
+                },
+                summary: price_summary(&l.stats, &variation),
+            })
+            .collect(),
+        months,
+        change_since: history.window_start,
+        change_percent: history.change_percent.map(|c| c.to_string()),
+    }))
 }
 
 /// Router for product variation endpoints (merge into product router).
@@ -235,4 +522,13 @@ pub fn route() -> Router<AppState> {
             "/api/v1/variations/{id}",
             patch(update_variation).delete(delete_variation),
         )
+        .route("/api/v1/variations/{id}/restore", post(restore_variation))
+        .route(
+            "/api/v1/variations/{id}/price-history",
+            get(get_price_history),
+        )
+        .route(
+            "/api/v1/variations/by-barcode/{code}",
+            get(get_variation_by_barcode),
+        )
 }
@@ -1,10 +1,10 @@
-//! Purchases REST API: list, get, create, update, delete.
+//! Purchases REST API: list, get, create, update, delete, restore.
 //!
 //! List endpoints return `200 OK` with an empty array when there are no matching
 //! records (e.g. product exists but has no purchases). They do not return 404.
 
 use axum::extract::Extension;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
@@ -16,10 +16,15 @@ use uuid::Uuid;
 
 use crate::api::auth::CurrentUserId;
 use crate::api::location::LocationRef;
+use crate::api::pagination::{self, ListResponse};
 use crate::api::product::ProductRef;
+use crate::api::product_variations::{UnitPriceRef, unit_price_to_ref};
+use crate::api::query::parse_flag;
+use crate::api::sync::new_id;
 use crate::api::user::UserRef;
 use crate::api::{error::ApiError, state::AppState};
 use crate::db;
+use crate::db::audit::AuditEntity;
 use crate::db::purchase::PurchaseWithRelations;
 use crate::domain::purchase::{Purchase, ValidationError};
 
@@ -31,6 +36,12 @@ pub struct ListPurchasesQuery {
     pub location_id: Option<Uuid>,
     pub from: Option<String>,
     pub to: Option<String>,
+    /// `purchased_at` (default) or `price`.
+    pub sort: Option<String>,
+    /// Page size; when set the response is a page envelope (see [`ListResponse`]).
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
 }
 
 /// Variation reference for purchase response.
@@ -46,6 +57,8 @@ pub struct VariationRef {
 /// Request body for creating a purchase.
 #[derive(Debug, Deserialize)]
 pub struct CreatePurchaseRequest {
+    /// Client-chosen id (offline clients create records before syncing); generated when absent.
+    pub id: Option<Uuid>,
     pub product_id: Uuid,
     /// When absent (backward compat), the product's first variation is used.
     pub variation_id: Option<Uuid>,
@@ -70,18 +83,10 @@ pub struct UpdatePurchaseRequest {
 /// Query params for delete (optional force).
 #[derive(Debug, Default, Deserialize)]
 pub struct DeletePurchaseQuery {
-    #[serde(default, deserialize_with = "parse_force")]
+    #[serde(default, deserialize_with = "parse_flag")]
     pub force: bool,
 }
 
-fn parse_force<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
-    Ok(s.to_lowercase() == "true" || s == "1")
-}
-
 /// Response body: purchase with nested user, product, variation, location; price as string, timestamps as i64.
 #[derive(Debug, serde::Serialize)]
 pub struct PurchaseResponse {
@@ -92,6 +97,9 @@ pub struct PurchaseResponse {
     pub location: LocationRef,
     pub quantity: i32,
     pub price: String,
+    /// Price per kg or l. Omitted when the variation has no weight or volume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<UnitPriceRef>,
     pub purchased_at: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deleted_at: Option<i64>,
@@ -121,6 +129,7 @@ fn purchase_with_relations_to_response(p: &PurchaseWithRelations) -> PurchaseRes
         },
         quantity: p.quantity,
         price: p.price.clone(),
+        unit_price: p.unit_price().as_ref().map(unit_price_to_ref),
         purchased_at: p.purchased_at,
         deleted_at: p.deleted_at,
     }
@@ -129,16 +138,22 @@ fn purchase_with_relations_to_response(p: &PurchaseWithRelations) -> PurchaseRes
 /// Map `DbError` to `ApiError` for purchase operations.
 fn map_db_error(e: &db::DbError) -> ApiError {
     match e {
-        db::DbError::InvalidData(msg) => ApiError::BadRequest(msg.clone()),
+        db::DbError::InvalidData(msg) => {
+            if msg.contains("cannot restore") {
+                ApiError::Conflict(msg.clone())
+            } else {
+                ApiError::BadRequest(msg.clone())
+            }
+        }
         db::DbError::Sqlx(sqlx_err) => {
             if let sqlx::Error::Database(db) = sqlx_err
                 && (db.is_unique_violation() || db.is_foreign_key_violation())
             {
                 return ApiError::BadRequest(e.to_string());
             }
-            ApiError::Internal
+            ApiError::internal(e)
         }
-        db::DbError::Migrate(_) => ApiError::Internal,
+        db::DbError::Migrate(_) => ApiError::internal(e),
     }
 }
 
@@ -151,29 +166,55 @@ fn parse_iso_date_to_ts(s: &str) -> Option<i64> {
 
 /// GET /api/v1/purchases — list purchases; optional `user_id` filter (when set, filter by that user).
 /// Returns 200 with an empty array when there are no matching purchases (e.g. product has none).
+/// `sort`, `limit` and `cursor` select the order and page (see [`crate::api::pagination`]).
 pub async fn list_purchases(
     State(state): State<AppState>,
     Extension(CurrentUserId(_current_user_id)): Extension<CurrentUserId>,
     Query(q): Query<ListPurchasesQuery>,
-) -> Result<Json<Vec<PurchaseResponse>>, ApiError> {
+) -> Result<Json<ListResponse<PurchaseResponse>>, ApiError> {
+    let sort = pagination::parse_sort(
+        q.sort.as_deref(),
+        db::purchase::PurchaseSort::PurchasedAt,
+        db::purchase::PurchaseSort::from_param,
+    )?;
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), sort.name())?;
     let from_ts = q.from.as_deref().and_then(parse_iso_date_to_ts);
     let to_ts = q.to.as_deref().and_then(parse_iso_date_to_ts);
-    let list = db::purchase::list_with_relations(
-        &state.pool,
-        q.user_id,
-        q.product_id,
-        q.location_id,
+    if q.sort.is_none() && q.limit.is_none() {
+        let list = db::purchase::list_with_relations(
+            &state.pool,
+            q.user_id,
+            q.product_id,
+            q.location_id,
+            from_ts,
+            to_ts,
+            false,
+        )
+        .await
+        .map_err(|e| map_db_error(&e))?;
+        let out = list
+            .iter()
+            .map(purchase_with_relations_to_response)
+            .collect();
+        return Ok(Json(ListResponse::All(out)));
+    }
+    let filter = db::purchase::PurchaseFilter {
+        user_id: q.user_id,
+        product_id: q.product_id,
+        variation_id: None,
+        location_id: q.location_id,
         from_ts,
         to_ts,
-        false,
-    )
-    .await
-    .map_err(|e| map_db_error(&e))?;
-    Ok(Json(
-        list.iter()
-            .map(purchase_with_relations_to_response)
-            .collect(),
-    ))
+        include_deleted: false,
+    };
+    let result = db::purchase::list_page(&state.pool, &filter, sort, &page)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(Json(ListResponse::from_page(
+        result,
+        q.limit.is_some(),
+        |p| purchase_with_relations_to_response(&p),
+    )))
 }
 
 /// GET /api/v1/purchases/:id — get one purchase.
@@ -250,7 +291,7 @@ pub async fn create_purchase(
         .and_then(parse_iso_date_to_ts)
         .unwrap_or_else(|| chrono::Utc::now().timestamp());
 
-    let id = Uuid::new_v4();
+    let id = new_id(&state.pool, AuditEntity::Purchase, body.id).await?;
     let purchase = Purchase::new(
         id,
         user_id,
@@ -429,13 +470,44 @@ pub async fn delete_purchase(
             .await
             .map_err(|e| map_db_error(&e))?;
     } else {
-        db::purchase::soft_delete(&state.pool, id)
+        db::purchase::soft_delete(&state.pool, id, Some(current_user_id))
             .await
             .map_err(|e| map_db_error(&e))?;
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// POST /api/v1/purchases/:id/restore — restore a soft-deleted purchase; only owner. 409 when its
+/// product, variation or location is deleted.
+pub async fn restore_purchase(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(current_user_id)): Extension<CurrentUserId>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseResponse>, ApiError> {
+    let purchase = db::purchase::get_by_id(&state.pool, id, true)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let purchase = purchase.ok_or_else(|| ApiError::NotFound("Purchase not found.".to_string()))?;
+
+    if purchase.user_id() != current_user_id {
+        return Err(ApiError::Forbidden(
+            "not allowed to restore another user's purchase".to_string(),
+        ));
+    }
+    if purchase.is_active() {
+        return Err(ApiError::Conflict("Purchase is not deleted.".to_string()));
+    }
+
+    db::purchase::restore(&state.pool, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let with_relations = db::purchase::get_by_id_with_relations(&state.pool, id, false)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .expect("purchase just restored");
+    Ok(Json(purchase_with_relations_to_response(&with_relations)))
+}
+
 /// Router for /api/v1/purchases.
 pub fn route() -> Router<AppState> {
     Router::new()
@@ -449,6 +521,7 @@ pub fn route() -> Router<AppState> {
                 .patch(update_purchase)
                 .delete(delete_purchase),
         )
+        .route("/api/v1/purchases/{id}/restore", post(restore_purchase))
 }
 
 #[cfg(test)]
@@ -462,7 +535,8 @@ mod tests {
 
     use super::*;
     use crate::api::auth::CurrentUserId;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
     use crate::db;
     use crate::test_helpers::{
         ensure_product_variation, insert_category, insert_location, insert_product, insert_user,
@@ -484,10 +558,15 @@ mod tests {
         let state = AppState {
             config: Config {
                 database_path: path_str,
-                jwt_secret: "test".to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
                 jwt_expiration_seconds: 3600,
                 jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
                 bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
                 pid_file: std::env::temp_dir()
                     .join("pocketratings-purchase-api-test.pid")
                     .to_string_lossy()
@@ -692,6 +771,104 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn create_purchase_response_includes_unit_price_for_weighed_variation() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Bob", "b@example.com").await;
+        let category_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, category_id, "Brand", "Coffee").await;
+        let now = chrono::Utc::now().timestamp();
+        let variation_id = Uuid::new_v4();
+        let variation = crate::domain::product_variation::ProductVariation::new(
+            variation_id,
+            product_id,
+            "",
+            "grams",
+            Some(500),
+            now,
+            now,
+            None,
+        )
+        .expect("valid");
+        db::product_variation::insert(&state.pool, &variation)
+            .await
+            .expect("insert variation");
+        let location_id = insert_location(&state.pool, "Store").await;
+        let app = app_with_user(state, user_id);
+
+        let body = serde_json::json!({
+            "product_id": product_id,
+            "variation_id": variation_id,
+            "location_id": location_id,
+            "price": "2.99",
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/purchases")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let created: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        assert_eq!(
+            created.get("unit_price"),
+            Some(&serde_json::json!({ "price": "5.98", "per": "kg" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn create_purchase_response_omits_unit_price_without_weight_or_volume() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Bob", "b@example.com").await;
+        let category_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, category_id, "Brand", "Name").await;
+        let variation_id = ensure_product_variation(&state.pool, product_id).await;
+        let location_id = insert_location(&state.pool, "Store").await;
+        let app = app_with_user(state, user_id);
+
+        let body = serde_json::json!({
+            "product_id": product_id,
+            "variation_id": variation_id,
+            "location_id": location_id,
+            "price": "2.00",
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/purchases")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let created: serde_json::Value = serde_json::from_slice(&bytes).expect("json");
+        assert!(created.get("unit_price").is_none());
+    }
+
     #[tokio::test]
     async fn create_purchase_returns_400_when_variation_id_belongs_to_different_product() {
         let (state, _dir) = test_pool().await;
@@ -1106,6 +1283,80 @@ mod tests {
         assert_eq!(get_resp.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn restore_purchase_returns_200_for_owner_and_403_for_other_user() {
+        let (state, _dir) = test_pool().await;
+        let owner = insert_user(&state.pool, "Owner", "o@example.com").await;
+        let other = insert_user(&state.pool, "Other", "x@example.com").await;
+        let category_id = insert_category(&state.pool, "Cat").await;
+        let product_id = insert_product(&state.pool, category_id, "Brand", "Name").await;
+        let variation_id = ensure_product_variation(&state.pool, product_id).await;
+        let location_id = insert_location(&state.pool, "Store").await;
+        let now = chrono::Utc::now().timestamp();
+        let purchase = Purchase::new(
+            Uuid::new_v4(),
+            owner,
+            product_id,
+            variation_id,
+            location_id,
+            1,
+            Decimal::from(2),
+            now,
+            None,
+        )
+        .expect("valid purchase");
+        db::purchase::insert(&state.pool, &purchase)
+            .await
+            .expect("insert purchase");
+        db::purchase::soft_delete(&state.pool, purchase.id(), None)
+            .await
+            .expect("soft delete purchase");
+        let uri = format!("/api/v1/purchases/{}/restore", purchase.id());
+
+        let response = app_with_user(state.clone(), other)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let app = app_with_user(state.clone(), owner);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::OK);
+        let restored = db::purchase::get_by_id(&state.pool, purchase.id(), false)
+            .await
+            .expect("db");
+        assert!(restored.is_some(), "purchase must be active after restore");
+
+        // Restoring an active purchase is a conflict.
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
     #[tokio::test]
     async fn delete_purchase_returns_404_when_not_found() {
         let (state, _dir) = test_pool().await;
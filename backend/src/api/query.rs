@@ -0,0 +1,13 @@
+//! Shared query parameter parsing for list and delete endpoints.
+
+use serde::Deserialize;
+
+/// Deserialize a boolean flag such as `force` or `include_deleted`: `true` (any case) or `1` are
+/// `true`, anything else (or no value) is `false`.
+pub fn parse_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    Ok(s.eq_ignore_ascii_case("true") || s == "1")
+}
@@ -0,0 +1,205 @@
+//! Request logging layer: assigns each request an `X-Request-Id` (or keeps the client's), runs it
+//! in a `tracing` span with method, matched route and user id, and logs status and latency when
+//! it completes.
+//!
+//! Server errors are logged at `error`, client errors at `warn`, everything else at `info`.
+//! Headers and bodies are never logged. Each request is also counted in [`crate::metrics`].
+
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header carrying the request id, on requests (optional) and on every response.
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request id that is kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Request id of the current request, inserted into request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// The client's `X-Request-Id`, if it is short and printable enough to log and echo back.
+fn client_request_id(request: &Request) -> Option<String> {
+    let value = request.headers().get(&X_REQUEST_ID)?.to_str().ok()?;
+    let valid = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.bytes().all(|b| b.is_ascii_graphic());
+    valid.then(|| value.to_string())
+}
+
+/// Middleware for the whole router. The span's `user_id` field is filled in by
+/// [`super::auth::auth_middleware`] once the request is authenticated.
+pub async fn request_log(mut request: Request, next: Next) -> Response {
+    let request_id = client_request_id(&request).unwrap_or_else(|| Uuid::new_v4().to_string());
+    let matched = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let route = matched
+        .clone()
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let method = request.method().clone();
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %method,
+        route = %route,
+        user_id = tracing::field::Empty,
+    );
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+
+    let start = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let latency = start.elapsed().as_secs_f64();
+    let latency_ms = latency * 1000.0;
+    let status = response.status();
+    // Raw paths of unmatched requests would make one series per URL scanned.
+    crate::metrics::record_request(
+        method.as_str(),
+        matched.as_deref().unwrap_or("unmatched"),
+        status.as_u16(),
+        latency,
+    );
+    span.in_scope(|| {
+        if status.is_server_error() {
+            tracing::error!(status = status.as_u16(), latency_ms, "request failed");
+        } else if status.is_client_error() {
+            tracing::warn!(status = status.as_u16(), latency_ms, "request rejected");
+        } else {
+            tracing::info!(status = status.as_u16(), latency_ms, "request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        Extension, Router,
+        body::Body,
+        http::{Request, StatusCode},
+        middleware,
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::api::error::ApiError;
+
+    /// Log output captured by a test subscriber.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("lock").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/items/{id}",
+                get(|Extension(id): Extension<RequestId>| async move { id.0 }),
+            )
+            .route(
+                "/broken",
+                get(|| async { Err::<(), _>(ApiError::internal("disk I/O error")) }),
+            )
+            .layer(middleware::from_fn(request_log))
+    }
+
+    async fn get_with_id(uri: &str, request_id: Option<&str>) -> Response {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(id) = request_id {
+            builder = builder.header(X_REQUEST_ID, id);
+        }
+        app()
+            .oneshot(builder.body(Body::empty()).expect("request"))
+            .await
+            .expect("service")
+    }
+
+    #[tokio::test]
+    async fn request_id_is_generated_or_propagated() {
+        let response = get_with_id("/items/1", None).await;
+        let generated = response.headers().get(&X_REQUEST_ID).expect("header");
+        assert!(Uuid::parse_str(generated.to_str().expect("ascii")).is_ok());
+
+        let response = get_with_id("/items/1", Some("abc-123")).await;
+        assert_eq!(
+            response.headers().get(&X_REQUEST_ID).expect("header"),
+            "abc-123"
+        );
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .expect("body");
+        assert_eq!(&body[..], b"abc-123", "handlers see the same id");
+
+        let response = get_with_id("/items/1", Some("has space")).await;
+        assert_ne!(
+            response.headers().get(&X_REQUEST_ID).expect("header"),
+            "has space"
+        );
+    }
+
+    #[tokio::test]
+    async fn logs_route_status_and_internal_error_cause_in_request_span() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let response = get_with_id("/broken", Some("req-42")).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        get_with_id("/items/7", Some("req-43")).await;
+
+        let output = String::from_utf8(captured.0.lock().expect("lock").clone()).expect("utf-8");
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        let cause = lines
+            .iter()
+            .find(|l| l["fields"]["error"] == "disk I/O error")
+            .expect("cause logged");
+        assert_eq!(cause["span"]["request_id"], "req-42");
+        let failed = lines
+            .iter()
+            .find(|l| l["fields"]["message"] == "request failed")
+            .expect("request logged");
+        assert_eq!(failed["fields"]["status"], 500);
+        assert_eq!(failed["span"]["route"], "/broken");
+        let completed = lines
+            .iter()
+            .find(|l| l["fields"]["message"] == "request completed")
+            .expect("request logged");
+        assert_eq!(completed["span"]["route"], "/items/{id}");
+        assert_eq!(completed["span"]["method"], "GET");
+        assert!(completed["fields"]["latency_ms"].is_number());
+    }
+}
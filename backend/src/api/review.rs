@@ -1,9 +1,9 @@
-//! Reviews REST API: list, get, create, update, delete.
+//! Reviews REST API: list, get, create, update, delete, restore.
 
 use std::str::FromStr;
 
 use axum::extract::Extension;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
@@ -14,16 +14,22 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::api::auth::CurrentUserId;
+use crate::api::pagination::{self, ListResponse};
 use crate::api::product::ProductRef;
+use crate::api::query::parse_flag;
+use crate::api::sync::new_id;
 use crate::api::user::UserRef;
 use crate::api::{error::ApiError, state::AppState};
 use crate::db;
+use crate::db::audit::AuditEntity;
 use crate::db::review::ReviewWithRelations;
 use crate::domain::review::{Review, ValidationError};
 
 /// Request body for creating a review. Ignores `id`, `user_id`, `created_at`, `updated_at`, `deleted_at`.
 #[derive(Debug, Deserialize)]
 pub struct CreateReviewRequest {
+    /// Client-chosen id (offline clients create records before syncing); generated when absent.
+    pub id: Option<Uuid>,
     pub product_id: Uuid,
     /// Rating 1–5 (JSON number); converted to Decimal.
     pub rating: f64,
@@ -43,12 +49,18 @@ pub struct UpdateReviewRequest {
 pub struct ListReviewsQuery {
     pub product_id: Option<Uuid>,
     pub user_id: Option<Uuid>,
+    /// `updated_at` (default), `created_at` or `rating`.
+    pub sort: Option<String>,
+    /// Page size; when set the response is a page envelope (see [`ListResponse`]).
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
 }
 
 /// Query params for delete (optional force).
 #[derive(Debug, Default, Deserialize)]
 pub struct DeleteReviewQuery {
-    #[serde(default, deserialize_with = "parse_force")]
+    #[serde(default, deserialize_with = "parse_flag")]
     pub force: bool,
 }
 
@@ -77,7 +89,7 @@ where
 }
 
 fn review_with_relations_to_response(r: &ReviewWithRelations) -> Result<ReviewResponse, ApiError> {
-    let rating: Decimal = r.rating.parse().map_err(|_| ApiError::Internal)?;
+    let rating: Decimal = r.rating.parse().map_err(ApiError::internal)?;
     Ok(ReviewResponse {
         id: r.id,
         user: UserRef {
@@ -100,7 +112,13 @@ fn review_with_relations_to_response(r: &ReviewWithRelations) -> Result<ReviewRe
 /// Map `DbError` to `ApiError` for review operations.
 fn map_db_error(e: &db::DbError) -> ApiError {
     match e {
-        db::DbError::InvalidData(msg) => ApiError::BadRequest(msg.clone()),
+        db::DbError::InvalidData(msg) => {
+            if msg.contains("cannot restore") {
+                ApiError::Conflict(msg.clone())
+            } else {
+                ApiError::BadRequest(msg.clone())
+            }
+        }
         db::DbError::Sqlx(sqlx_err) => {
             if let sqlx::Error::Database(db) = sqlx_err {
                 if db.is_foreign_key_violation() {
@@ -112,35 +130,47 @@ fn map_db_error(e: &db::DbError) -> ApiError {
                     return ApiError::BadRequest(e.to_string());
                 }
             }
-            ApiError::Internal
+            ApiError::internal(e)
         }
-        db::DbError::Migrate(_) => ApiError::Internal,
+        db::DbError::Migrate(_) => ApiError::internal(e),
     }
 }
 
-/// Deserialize "true" / "false" for force query param.
-fn parse_force<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
-    Ok(s.to_lowercase() == "true" || s == "1")
-}
-
 /// GET /api/v1/reviews — list reviews; optional `user_id` filter (when set, filter by that user).
+/// `sort`, `limit` and `cursor` select the order and page (see [`crate::api::pagination`]); without
+/// them the cached list is returned in the default order.
 pub async fn list_reviews(
     State(state): State<AppState>,
     Extension(CurrentUserId(_current_user_id)): Extension<CurrentUserId>,
     Query(q): Query<ListReviewsQuery>,
-) -> Result<Json<Vec<ReviewResponse>>, ApiError> {
-    let list = db::review::list_with_relations(&state.pool, q.product_id, q.user_id, false)
-        .await
-        .map_err(|e| map_db_error(&e))?;
+) -> Result<Json<ListResponse<ReviewResponse>>, ApiError> {
+    let sort = pagination::parse_sort(
+        q.sort.as_deref(),
+        db::review::ReviewSort::UpdatedAt,
+        db::review::ReviewSort::from_param,
+    )?;
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), sort.name())?;
+    let (list, next_cursor) = if q.sort.is_none() && q.limit.is_none() {
+        let list = db::review::list_with_relations(&state.pool, q.product_id, q.user_id, false)
+            .await
+            .map_err(|e| map_db_error(&e))?;
+        (list, None)
+    } else {
+        let result =
+            db::review::list_page(&state.pool, q.product_id, q.user_id, sort, false, &page)
+                .await
+                .map_err(|e| map_db_error(&e))?;
+        (result.items, result.next_cursor)
+    };
     let responses: Vec<ReviewResponse> = list
         .iter()
         .map(review_with_relations_to_response)
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(Json(responses))
+    Ok(Json(ListResponse::new(
+        responses,
+        next_cursor,
+        q.limit.is_some(),
+    )))
 }
 
 /// GET /api/v1/reviews/:id — get one review.
@@ -180,7 +210,7 @@ pub async fn create_review(
         return Err(ApiError::BadRequest("Invalid rating.".to_string()));
     };
     let now = chrono::Utc::now().timestamp();
-    let id = Uuid::new_v4();
+    let id = new_id(&state.pool, AuditEntity::Review, body.id).await?;
     let review = Review::new(
         id,
         body.product_id,
@@ -297,14 +327,45 @@ pub async fn delete_review(
             .await
             .map_err(|e| map_db_error(&e))?;
     } else {
-        db::review::soft_delete(&state.pool, id)
+        db::review::soft_delete(&state.pool, id, Some(current_user_id))
             .await
             .map_err(|e| map_db_error(&e))?;
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Router for /api/v1/reviews (all five handlers).
+/// POST /api/v1/reviews/:id/restore — restore a soft-deleted review; only owner. 409 when the
+/// product is deleted.
+pub async fn restore_review(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(current_user_id)): Extension<CurrentUserId>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReviewResponse>, ApiError> {
+    let review = db::review::get_by_id(&state.pool, id, true)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let review = review.ok_or_else(|| ApiError::NotFound("Review not found.".to_string()))?;
+
+    if review.user_id() != current_user_id {
+        return Err(ApiError::Forbidden(
+            "not allowed to restore another user's review".to_string(),
+        ));
+    }
+    if review.is_active() {
+        return Err(ApiError::Conflict("Review is not deleted.".to_string()));
+    }
+
+    db::review::restore(&state.pool, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    let with_relations = db::review::get_by_id_with_relations(&state.pool, id, false)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .expect("review just restored");
+    Ok(Json(review_with_relations_to_response(&with_relations)?))
+}
+
+/// Router for /api/v1/reviews (all six handlers).
 pub fn route() -> Router<AppState> {
     Router::new()
         .route("/api/v1/reviews", get(list_reviews).post(create_review))
@@ -312,6 +373,7 @@ pub fn route() -> Router<AppState> {
             "/api/v1/reviews/{id}",
             get(get_review).patch(update_review).delete(delete_review),
         )
+        .route("/api/v1/reviews/{id}/restore", post(restore_review))
 }
 
 #[cfg(test)]
@@ -325,7 +387,8 @@ mod tests {
 
     use super::*;
     use crate::api::auth::CurrentUserId;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
     use crate::db;
     use crate::test_helpers::{insert_category, insert_product, insert_user};
 
@@ -345,10 +408,15 @@ mod tests {
         let state = AppState {
             config: Config {
                 database_path: path_str,
-                jwt_secret: "test".to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
                 jwt_expiration_seconds: 3600,
                 jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
                 bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
                 pid_file: std::env::temp_dir()
                     .join("pocketratings-review-test.pid")
                     .to_string_lossy()
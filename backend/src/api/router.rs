@@ -2,27 +2,70 @@
 
 use axum::{Router, middleware};
 
-use super::auth::{auth_middleware, login_route, me_route};
+use super::api_token;
+use super::audit;
+use super::auth::{
+    admin_guard, admin_write_guard, auth_middleware, change_password_route, data_write_guard,
+    login_route, login_totp_route, logout_route, me_route, refresh_route, register_route,
+    reset_password_route,
+};
 use super::category;
+use super::etag::conditional;
+use super::health;
+use super::idempotency::idempotency;
 use super::location;
+use super::metrics;
 use super::product;
 use super::purchase;
+use super::request_log::request_log;
 use super::review;
 use super::state::AppState;
+use super::stats;
+use super::sync;
+use super::trash;
+use super::user;
 
-/// Build the API router with all v1 routes.
+/// Build the API router with all v1 routes. Every request is logged with its request id (see
+/// [`super::request_log`]); authenticated `POST`s honour `Idempotency-Key` (see
+/// [`super::idempotency`]).
+///
+/// `GET /metrics` is included unless `metrics_bind` is configured (see [`metrics_router`]).
 pub fn router(state: AppState) -> Router {
-    let public = Router::new()
+    let mut public = Router::new()
         .merge(super::version::route())
-        .merge(login_route());
+        .merge(health::route())
+        .merge(login_route())
+        .merge(login_totp_route())
+        .merge(refresh_route())
+        .merge(register_route())
+        .merge(reset_password_route());
+    if state.config.metrics_bind.is_none() {
+        public = public.merge(metrics::route());
+    }
 
-    let protected = Router::new()
-        .merge(me_route())
-        .merge(category::route())
+    // Data routes: guests read only, permanent deletes and category changes are admin-only.
+    // Reads carry an ETag and single-record writes honour If-Match (see `super::etag`).
+    let data = Router::new()
+        .merge(audit::route())
+        .merge(category::route().route_layer(middleware::from_fn(admin_write_guard)))
         .merge(location::route())
         .merge(product::route())
         .merge(purchase::route())
         .merge(review::route())
+        .merge(stats::route())
+        .merge(sync::route())
+        .merge(trash::route())
+        .route_layer(middleware::from_fn_with_state(state.clone(), conditional))
+        .route_layer(middleware::from_fn(data_write_guard));
+
+    let protected = Router::new()
+        .merge(me_route())
+        .merge(logout_route())
+        .merge(change_password_route())
+        .merge(api_token::route())
+        .merge(user::route().route_layer(middleware::from_fn(admin_guard)))
+        .merge(data)
+        .route_layer(middleware::from_fn_with_state(state.clone(), idempotency))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -31,5 +74,11 @@ pub fn router(state: AppState) -> Router {
     Router::new()
         .merge(public)
         .merge(protected)
+        .layer(middleware::from_fn(request_log))
         .with_state(state)
 }
+
+/// Router with only `GET /metrics`, for the separate `metrics_bind` listener.
+pub fn metrics_router(state: AppState) -> Router {
+    metrics::route().with_state(state)
+}
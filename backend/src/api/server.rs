@@ -1,6 +1,7 @@
 //! HTTP server startup and graceful shutdown.
 
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 
 use sqlx::SqlitePool;
@@ -34,10 +35,13 @@ fn wait_for_sigterm() -> Pin<Box<dyn Future<Output = ()> + Send>> {
 ///
 /// Binds to `bind_addr`, serves the router with graceful shutdown.
 /// The database pool is available for future routes (e.g. auth); the version endpoint does not use it.
+/// When `config.metrics_bind` is set, `GET /metrics` is served there instead, until the API
+/// server stops.
 ///
 /// # Errors
 ///
-/// Returns an error if binding to `bind_addr` fails or if the server exits with an error.
+/// Returns an error if binding to `bind_addr` or the metrics address fails or if the server exits
+/// with an error.
 pub async fn start(config: &Config, pool: &SqlitePool, bind_addr: &str) -> Result<(), ServerError> {
     let listener = TcpListener::bind(bind_addr)
         .await
@@ -50,6 +54,23 @@ pub async fn start(config: &Config, pool: &SqlitePool, bind_addr: &str) -> Resul
         pool: pool.clone(),
     };
 
+    let metrics_server = match &config.metrics_bind {
+        Some(metrics_addr) => {
+            let metrics_listener = TcpListener::bind(metrics_addr)
+                .await
+                .map_err(ServerError::Bind)?;
+            let addr = metrics_listener.local_addr().map_err(ServerError::Bind)?;
+            tracing::info!("serving metrics on {}", addr);
+            let app = router::metrics_router(state.clone());
+            Some(tokio::spawn(async move {
+                if let Err(e) = axum::serve(metrics_listener, app).await {
+                    tracing::error!("metrics server error: {}", e);
+                }
+            }))
+        }
+        None => None,
+    };
+
     let shutdown = async {
         tokio::select! {
             _ = signal::ctrl_c() => {}
@@ -57,12 +78,18 @@ pub async fn start(config: &Config, pool: &SqlitePool, bind_addr: &str) -> Resul
         }
     };
 
-    axum::serve(listener, router::router(state))
-        .with_graceful_shutdown(shutdown)
-        .await
-        .map_err(ServerError::Serve)?;
+    let served = axum::serve(
+        listener,
+        router::router(state).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await
+    .map_err(ServerError::Serve);
 
-    Ok(())
+    if let Some(handle) = metrics_server {
+        handle.abort();
+    }
+    served
 }
 
 /// Errors that can occur when starting or running the server.
@@ -0,0 +1,285 @@
+//! Statistics REST API: spending totals over purchases.
+
+use axum::routing::get;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::{error::ApiError, state::AppState};
+use crate::db;
+use crate::db::stats::{SpendingFilter, SpendingGroup, SpendingReport, SpendingRow};
+
+/// Query params for GET /api/v1/stats/spending.
+#[derive(Debug, Default, Deserialize)]
+pub struct SpendingQuery {
+    /// Start of the range (RFC 3339, inclusive).
+    pub from: Option<String>,
+    /// End of the range (RFC 3339, inclusive).
+    pub to: Option<String>,
+    /// `month` (default), `week`, `category`, `location` or `user`.
+    pub group_by: Option<String>,
+    pub category_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+}
+
+/// One group of the spending response.
+#[derive(Debug, Serialize)]
+pub struct SpendingGroupResponse {
+    pub key: String,
+    pub label: String,
+    pub total: String,
+    pub purchase_count: u64,
+}
+
+/// Response body for GET /api/v1/stats/spending.
+#[derive(Debug, Serialize)]
+pub struct SpendingResponse {
+    pub group_by: &'static str,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub total: String,
+    pub purchase_count: u64,
+    pub groups: Vec<SpendingGroupResponse>,
+}
+
+fn row_to_response(row: SpendingRow) -> SpendingGroupResponse {
+    SpendingGroupResponse {
+        key: row.key,
+        label: row.label,
+        total: row.total.to_string(),
+        purchase_count: row.purchase_count,
+    }
+}
+
+fn report_to_response(
+    report: SpendingReport,
+    group: SpendingGroup,
+    filter: &SpendingFilter,
+) -> SpendingResponse {
+    SpendingResponse {
+        group_by: group.name(),
+        from: filter.from_ts,
+        to: filter.to_ts,
+        total: report.total.to_string(),
+        purchase_count: report.purchase_count,
+        groups: report.groups.into_iter().map(row_to_response).collect(),
+    }
+}
+
+fn map_db_error(e: &db::DbError) -> ApiError {
+    match e {
+        db::DbError::InvalidData(msg) => ApiError::BadRequest(msg.clone()),
+        db::DbError::Sqlx(_) | db::DbError::Migrate(_) => ApiError::internal(e),
+    }
+}
+
+/// Parse an RFC 3339 query date to a UNIX timestamp; 400 naming the parameter when invalid.
+fn parse_date_param(value: Option<&str>, name: &str) -> Result<Option<i64>, ApiError> {
+    value
+        .map(|s| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .map(|dt| dt.timestamp())
+                .map_err(|_| ApiError::BadRequest(format!("Invalid {name} date.")))
+        })
+        .transpose()
+}
+
+/// GET /api/v1/stats/spending — total spent (`price` x `quantity`) on non-deleted purchases in
+/// a date range, grouped by month, week, category, location or user.
+pub async fn get_spending(
+    State(state): State<AppState>,
+    Query(q): Query<SpendingQuery>,
+) -> Result<Json<SpendingResponse>, ApiError> {
+    let group = match q.group_by.as_deref() {
+        None => SpendingGroup::Month,
+        Some(s) => SpendingGroup::from_param(s)
+            .ok_or_else(|| ApiError::BadRequest("Invalid group_by.".to_string()))?,
+    };
+    let filter = SpendingFilter {
+        user_id: q.user_id,
+        location_id: q.location_id,
+        category_id: q.category_id,
+        from_ts: parse_date_param(q.from.as_deref(), "from")?,
+        to_ts: parse_date_param(q.to.as_deref(), "to")?,
+    };
+    if let Some(cat_id) = q.category_id {
+        let category = db::category::get_by_id(&state.pool, cat_id, false)
+            .await
+            .map_err(|e| map_db_error(&e))?;
+        if category.is_none() {
+            return Err(ApiError::NotFound("Category not found.".to_string()));
+        }
+    }
+    let report = db::stats::spending(&state.pool, &filter, group)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(Json(report_to_response(report, group, &filter)))
+}
+
+/// Router for statistics endpoints.
+pub fn route() -> Router<AppState> {
+    Router::new().route("/api/v1/stats/spending", get(get_spending))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+    use crate::domain::category::Category;
+    use crate::domain::purchase::Purchase;
+    use crate::test_helpers::{
+        ensure_product_variation, insert_location, insert_product, insert_user,
+    };
+
+    async fn test_pool() -> (AppState, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("stats_test.db");
+        let path_str = db_path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path_str).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        let state = AppState {
+            config: Config {
+                database_path: path_str,
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: std::env::temp_dir()
+                    .join("pocketratings-stats-test.pid")
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            pool,
+        };
+        (state, dir)
+    }
+
+    async fn insert_category(state: &AppState, name: &str, parent_id: Option<Uuid>) -> Uuid {
+        let id = Uuid::new_v4();
+        let cat = Category::new(id, parent_id, name.to_string(), 1_000, 1_000, None)
+            .expect("valid category");
+        db::category::insert(&state.pool, &cat)
+            .await
+            .expect("insert category");
+        id
+    }
+
+    async fn get(state: AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = route()
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    #[tokio::test]
+    async fn spending_groups_by_month_and_category_subtree() {
+        let (state, _dir) = test_pool().await;
+        let food = insert_category(&state, "Food", None).await;
+        let dairy = insert_category(&state, "Dairy", Some(food)).await;
+        let cheese = insert_category(&state, "Cheese", Some(dairy)).await;
+        let bakery = insert_category(&state, "Bakery", Some(food)).await;
+        let user_id = insert_user(&state.pool, "User", "u@example.com").await;
+        let loc_id = insert_location(&state.pool, "Store").await;
+        // 2026-01-15, 2026-01-20 and 2026-02-03 (UTC).
+        for (category, price, quantity, at) in [
+            (cheese, "2.50", 2, 1_768_435_200),
+            (bakery, "1.10", 1, 1_768_867_200),
+            (dairy, "0.99", 3, 1_770_076_800),
+        ] {
+            let product_id = insert_product(&state.pool, category, "B", "P").await;
+            let var_id = ensure_product_variation(&state.pool, product_id).await;
+            let purchase = Purchase::new(
+                Uuid::new_v4(),
+                user_id,
+                product_id,
+                var_id,
+                loc_id,
+                quantity,
+                price.parse().expect("decimal"),
+                at,
+                None,
+            )
+            .expect("valid");
+            db::purchase::insert(&state.pool, &purchase)
+                .await
+                .expect("insert");
+        }
+
+        let (status, json) = get(state.clone(), "/api/v1/stats/spending").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["group_by"], "month");
+        assert_eq!(json["total"], "9.07");
+        assert_eq!(json["purchase_count"], 3);
+        let groups = json["groups"].as_array().expect("groups");
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["key"], "2026-01");
+        assert_eq!(groups[0]["total"], "6.10");
+        assert_eq!(groups[1]["key"], "2026-02");
+
+        let (status, json) = get(
+            state.clone(),
+            &format!("/api/v1/stats/spending?group_by=category&category_id={food}"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let groups = json["groups"].as_array().expect("groups");
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["label"], "Dairy");
+        assert_eq!(groups[0]["total"], "7.97");
+        assert_eq!(groups[0]["purchase_count"], 2);
+        assert_eq!(groups[1]["label"], "Bakery");
+
+        let (_, json) = get(
+            state,
+            "/api/v1/stats/spending?group_by=week&from=2026-01-16T00:00:00Z&to=2026-01-31T00:00:00Z",
+        )
+        .await;
+        assert_eq!(json["total"], "1.10");
+        assert_eq!(json["groups"][0]["key"], "2026-01-19");
+    }
+
+    #[tokio::test]
+    async fn spending_rejects_invalid_params() {
+        let (state, _dir) = test_pool().await;
+        let (status, _) = get(state.clone(), "/api/v1/stats/spending?group_by=year").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get(state.clone(), "/api/v1/stats/spending?from=yesterday").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get(
+            state,
+            &format!("/api/v1/stats/spending?category_id={}", Uuid::new_v4()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,623 @@
+//! Offline sync: `GET /api/v1/sync` (changes since a token) and `POST /api/v1/sync` (apply
+//! queued client mutations).
+//!
+//! The feed is read from [`crate::db::sync`]: a full sync (no `since`) returns every record, a
+//! delta sync returns the records created, updated, soft-deleted or restored after the token plus
+//! the ids removed for good. Mutations go through the same handlers as the REST endpoints, so
+//! validation and permissions are identical; each one is applied on its own and gets a result.
+
+use axum::{
+    Json, Router,
+    extract::{Extension, Path, Query, State},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::auth::CurrentUserId;
+use super::{error::ApiError, location, product, purchase, review, state::AppState};
+use crate::db;
+use crate::db::audit::AuditEntity;
+use crate::db::sync::Record;
+
+/// Most mutations accepted in one `POST /api/v1/sync`.
+pub const MAX_MUTATIONS: usize = 500;
+
+/// Query params for `GET /api/v1/sync`.
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncQuery {
+    /// Token from a previous response; omit for a full sync.
+    pub since: Option<String>,
+}
+
+/// An entity removed for good (hard-deleted) since the token.
+#[derive(Debug, Serialize)]
+pub struct RemovedRef {
+    #[serde(rename = "type")]
+    pub entity_type: &'static str,
+    pub id: Uuid,
+}
+
+/// Response body for `GET /api/v1/sync`.
+#[derive(Debug, Default, Serialize)]
+pub struct SyncResponse {
+    /// Opaque token to pass as `since` next time.
+    pub token: String,
+    pub categories: Vec<Record>,
+    pub products: Vec<Record>,
+    pub variations: Vec<Record>,
+    pub locations: Vec<Record>,
+    pub reviews: Vec<Record>,
+    pub purchases: Vec<Record>,
+    pub removed: Vec<RemovedRef>,
+}
+
+/// Entity types clients may change through `POST /api/v1/sync`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationType {
+    Location,
+    Product,
+    Review,
+    Purchase,
+}
+
+impl MutationType {
+    const fn entity(self) -> AuditEntity {
+        match self {
+            Self::Location => AuditEntity::Location,
+            Self::Product => AuditEntity::Product,
+            Self::Review => AuditEntity::Review,
+            Self::Purchase => AuditEntity::Purchase,
+        }
+    }
+}
+
+/// What a mutation does; `delete` is a soft delete.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationOp {
+    Create,
+    Update,
+    Delete,
+}
+
+/// One queued client change.
+#[derive(Debug, Deserialize)]
+pub struct Mutation {
+    #[serde(rename = "type")]
+    pub entity_type: MutationType,
+    pub op: MutationOp,
+    /// Id of the entity; for `create`, the id the client chose for it.
+    pub id: Uuid,
+    /// `version` of the record the client last saw; when it no longer matches, the mutation is
+    /// not applied and reported as a conflict. Omit to overwrite regardless.
+    pub base_version: Option<i64>,
+    /// Body of the matching REST request (create or update); ignored for `delete`.
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// Request body for `POST /api/v1/sync`.
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    pub mutations: Vec<Mutation>,
+}
+
+/// Outcome of one mutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationStatus {
+    Applied,
+    /// Changed on the server since `base_version`, or (create) the id is taken.
+    Conflict,
+    /// No such entity, or it is in the trash.
+    NotFound,
+    /// Invalid data or not allowed; see `error`.
+    Rejected,
+}
+
+/// Result of one mutation, in request order.
+#[derive(Debug, Serialize)]
+pub struct MutationResult {
+    #[serde(rename = "type")]
+    pub entity_type: &'static str,
+    pub id: Uuid,
+    pub status: MutationStatus,
+    /// The server's record after the mutation; absent when it does not exist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<Record>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Response body for `POST /api/v1/sync`. There is no token: the client keeps its own and gets
+/// the effects of its mutations, with everyone else's, from the next `GET /api/v1/sync`.
+#[derive(Debug, Serialize)]
+pub struct ApplyResponse {
+    pub results: Vec<MutationResult>,
+}
+
+fn map_db_error(e: &db::DbError) -> ApiError {
+    match e {
+        db::DbError::InvalidData(msg) => ApiError::BadRequest(msg.clone()),
+        db::DbError::Sqlx(_) | db::DbError::Migrate(_) => ApiError::internal(e),
+    }
+}
+
+/// Id for a new entity: the client's own when given (409 if already taken in the household, 400
+/// if used in another one), else a new one.
+pub(super) async fn new_id(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    requested: Option<Uuid>,
+) -> Result<Uuid, ApiError> {
+    let Some(id) = requested else {
+        return Ok(Uuid::new_v4());
+    };
+    if db::sync::id_taken(pool, entity, id)
+        .await
+        .map_err(|e| map_db_error(&e))?
+    {
+        return Err(ApiError::Conflict(format!(
+            "A {} with this id already exists.",
+            entity.name()
+        )));
+    }
+    // Says nothing about the other household's row, only that the id is unusable.
+    if db::sync::id_used_elsewhere(pool, entity, id)
+        .await
+        .map_err(|e| map_db_error(&e))?
+    {
+        return Err(ApiError::BadRequest(
+            "This id cannot be used; choose another one.".to_string(),
+        ));
+    }
+    Ok(id)
+}
+
+/// Parse a `since` token; 400 when it is not one the server issued.
+fn parse_token(s: &str) -> Result<i64, ApiError> {
+    s.parse::<i64>()
+        .ok()
+        .filter(|t| *t >= 0)
+        .ok_or_else(|| ApiError::BadRequest("Invalid sync token.".to_string()))
+}
+
+/// GET /api/v1/sync — every record (no `since`) or the changes after `since`. 409 when `since`
+/// is newer than the server's data (e.g. restored from a backup); the client must sync again
+/// without it.
+pub async fn get_changes(
+    State(state): State<AppState>,
+    Query(q): Query<SyncQuery>,
+) -> Result<Json<SyncResponse>, ApiError> {
+    let since = q.since.as_deref().map(parse_token).transpose()?;
+    let changes = db::sync::changes(&state.pool, since)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .ok_or_else(|| {
+            ApiError::Conflict(
+                "Sync token is ahead of the server; sync again without since.".to_string(),
+            )
+        })?;
+
+    let mut response = SyncResponse {
+        token: changes.token.to_string(),
+        ..SyncResponse::default()
+    };
+    for (entity, records) in changes.records {
+        let list = match entity {
+            AuditEntity::Category => &mut response.categories,
+            AuditEntity::Product => &mut response.products,
+            AuditEntity::Variation => &mut response.variations,
+            AuditEntity::Location => &mut response.locations,
+            AuditEntity::Review => &mut response.reviews,
+            AuditEntity::Purchase => &mut response.purchases,
+            AuditEntity::User => continue,
+        };
+        *list = records;
+    }
+    response.removed = changes
+        .removed
+        .into_iter()
+        .map(|(entity, id)| RemovedRef {
+            entity_type: entity.name(),
+            id,
+        })
+        .collect();
+    Ok(Json(response))
+}
+
+/// Deserialize the `data` of a mutation into a REST request body.
+fn parse_data<T: serde::de::DeserializeOwned>(data: Value) -> Result<T, ApiError> {
+    serde_json::from_value(data).map_err(|e| ApiError::BadRequest(format!("Invalid data: {e}")))
+}
+
+/// Run one mutation through the REST handler of its entity type and operation.
+async fn run_handler(state: &AppState, user_id: Uuid, m: Mutation) -> Result<(), ApiError> {
+    let state = State(state.clone());
+    let user = CurrentUserId(user_id);
+    let id = m.id;
+    match (m.entity_type, m.op) {
+        (MutationType::Location, MutationOp::Create) => {
+            let mut body: location::CreateLocationRequest = parse_data(m.data)?;
+            body.id = Some(id);
+            let _ = location::create_location(state, Json(body)).await?;
+        }
+        (MutationType::Location, MutationOp::Update) => {
+            let _ = location::update_location(state, Path(id), Json(parse_data(m.data)?)).await?;
+        }
+        (MutationType::Location, MutationOp::Delete) => {
+            let q = location::DeleteLocationQuery { force: false };
+            location::delete_location(state, Some(Extension(user)), Path(id), Query(q)).await?;
+        }
+        (MutationType::Product, MutationOp::Create) => {
+            let mut body: product::CreateProductRequest = parse_data(m.data)?;
+            body.id = Some(id);
+            let _ = product::create_product(state, Json(body)).await?;
+        }
+        (MutationType::Product, MutationOp::Update) => {
+            let _ = product::update_product(state, Path(id), Json(parse_data(m.data)?)).await?;
+        }
+        (MutationType::Product, MutationOp::Delete) => {
+            let q = product::DeleteProductQuery { force: false };
+            product::delete_product(state, Some(Extension(user)), Path(id), Query(q)).await?;
+        }
+        (MutationType::Review, MutationOp::Create) => {
+            let mut body: review::CreateReviewRequest = parse_data(m.data)?;
+            body.id = Some(id);
+            let _ = review::create_review(state, Extension(user), Json(body)).await?;
+        }
+        (MutationType::Review, MutationOp::Update) => {
+            let _ =
+                review::update_review(state, Extension(user), Path(id), Json(parse_data(m.data)?))
+                    .await?;
+        }
+        (MutationType::Review, MutationOp::Delete) => {
+            let q = review::DeleteReviewQuery { force: false };
+            review::delete_review(state, Extension(user), Path(id), Query(q)).await?;
+        }
+        (MutationType::Purchase, MutationOp::Create) => {
+            let mut body: purchase::CreatePurchaseRequest = parse_data(m.data)?;
+            body.id = Some(id);
+            let _ = purchase::create_purchase(state, Extension(user), Json(body)).await?;
+        }
+        (MutationType::Purchase, MutationOp::Update) => {
+            let _ = purchase::update_purchase(
+                state,
+                Extension(user),
+                Path(id),
+                Json(parse_data(m.data)?),
+            )
+            .await?;
+        }
+        (MutationType::Purchase, MutationOp::Delete) => {
+            let q = purchase::DeletePurchaseQuery { force: false };
+            purchase::delete_purchase(state, Extension(user), Path(id), Query(q)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply one mutation, holding the record's write lock from the version check to the write. Only
+/// internal errors are returned; everything else is a result.
+async fn apply(state: &AppState, user_id: Uuid, m: Mutation) -> Result<MutationResult, ApiError> {
+    let entity = m.entity_type.entity();
+    let id = m.id;
+    let _lock = super::etag::lock_record(entity, id).await;
+    let current = db::sync::record(&state.pool, entity, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+
+    let outdated = match (m.op, &current) {
+        (MutationOp::Create, Some(_)) => true,
+        (MutationOp::Create, None) => false,
+        (_, None) => {
+            return Ok(MutationResult {
+                entity_type: entity.name(),
+                id,
+                status: MutationStatus::NotFound,
+                record: None,
+                error: None,
+            });
+        }
+        (_, Some(record)) => m
+            .base_version
+            .is_some_and(|base| record.get("version") != Some(&Value::from(base))),
+    };
+    if outdated {
+        return Ok(MutationResult {
+            entity_type: entity.name(),
+            id,
+            status: MutationStatus::Conflict,
+            record: current,
+            error: None,
+        });
+    }
+
+    let (status, error) = match run_handler(state, user_id, m).await {
+        Ok(()) => (MutationStatus::Applied, None),
+        Err(ApiError::Internal(cause)) => return Err(ApiError::Internal(cause)),
+        Err(ApiError::NotFound(msg)) => (MutationStatus::NotFound, Some(msg)),
+        Err(ApiError::Conflict(msg) | ApiError::PreconditionFailed(msg)) => {
+            (MutationStatus::Conflict, Some(msg))
+        }
+        Err(
+            ApiError::BadRequest(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::TooManyRequests { message: msg, .. },
+        ) => (MutationStatus::Rejected, Some(msg)),
+    };
+    let record = db::sync::record(&state.pool, entity, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(MutationResult {
+        entity_type: entity.name(),
+        id,
+        status,
+        record,
+        error,
+    })
+}
+
+/// POST /api/v1/sync — apply queued mutations in order. Each is applied on its own: a conflict
+/// or rejection does not stop the others, and earlier ones stay applied if a later one fails
+/// with an internal error.
+pub async fn apply_mutations(
+    State(state): State<AppState>,
+    Extension(CurrentUserId(user_id)): Extension<CurrentUserId>,
+    Json(body): Json<SyncRequest>,
+) -> Result<Json<ApplyResponse>, ApiError> {
+    if body.mutations.len() > MAX_MUTATIONS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_MUTATIONS} mutations per request."
+        )));
+    }
+    let mut results = Vec::with_capacity(body.mutations.len());
+    for m in body.mutations {
+        results.push(apply(&state, user_id, m).await?);
+    }
+    Ok(Json(ApplyResponse { results }))
+}
+
+/// Route for this endpoint.
+pub fn route() -> Router<AppState> {
+    Router::new().route("/api/v1/sync", get(get_changes).post(apply_mutations))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+    use crate::test_helpers::{insert_category, insert_location, insert_product, insert_user};
+
+    async fn test_pool() -> (AppState, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("sync_test.db");
+        let path_str = db_path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path_str).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        let state = AppState {
+            config: Config {
+                database_path: path_str,
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: std::env::temp_dir()
+                    .join("pocketratings-sync-test.pid")
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            pool,
+        };
+        (state, dir)
+    }
+
+    async fn send(
+        state: &AppState,
+        user_id: Uuid,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let app = route()
+            .layer(Extension(CurrentUserId(user_id)))
+            .with_state(state.clone());
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        let body = body.map_or_else(Body::empty, |b| {
+            Body::from(serde_json::to_vec(&b).expect("json"))
+        });
+        let response = app
+            .oneshot(request.body(body).expect("request"))
+            .await
+            .expect("service");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        (status, serde_json::from_slice(&bytes).expect("json"))
+    }
+
+    #[tokio::test]
+    async fn get_sync_returns_full_then_delta_then_removed() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Alice", "a@example.com").await;
+        let cat_id = insert_category(&state.pool, "Dairy").await;
+        insert_product(&state.pool, cat_id, "Brand", "Milk").await;
+
+        let (status, full) = send(&state, user_id, "GET", "/api/v1/sync", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(full["categories"][0]["name"], "Dairy");
+        assert_eq!(full["products"][0]["name"], "Milk");
+        assert_eq!(full["products"][0]["category_id"], cat_id.to_string());
+        assert_eq!(full["locations"], json!([]));
+        let token = full["token"].as_str().expect("token").to_string();
+
+        let location_id = insert_location(&state.pool, "Corner store").await;
+        let (status, delta) = send(
+            &state,
+            user_id,
+            "GET",
+            &format!("/api/v1/sync?since={token}"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(delta["products"], json!([]));
+        assert_eq!(delta["locations"][0]["id"], location_id.to_string());
+        assert!(
+            delta["locations"][0]["updated_at"]
+                .as_i64()
+                .expect("updated_at")
+                > 0
+        );
+        assert_eq!(delta["removed"], json!([]));
+        let token = delta["token"].as_str().expect("token").to_string();
+
+        db::location::hard_delete(&state.pool, location_id)
+            .await
+            .expect("hard delete");
+        let (_, delta) = send(
+            &state,
+            user_id,
+            "GET",
+            &format!("/api/v1/sync?since={token}"),
+            None,
+        )
+        .await;
+        assert_eq!(delta["locations"], json!([]));
+        assert_eq!(
+            delta["removed"],
+            json!([{ "type": "location", "id": location_id.to_string() }])
+        );
+
+        let (status, _) = send(&state, user_id, "GET", "/api/v1/sync?since=abc", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(&state, user_id, "GET", "/api/v1/sync?since=99999", None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn post_sync_applies_mutations_and_reports_conflicts() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Alice", "a@example.com").await;
+        let cat_id = insert_category(&state.pool, "Dairy").await;
+        let product_id = insert_product(&state.pool, cat_id, "Brand", "Milk").await;
+        let location_id = Uuid::new_v4();
+
+        let (status, json) = send(
+            &state,
+            user_id,
+            "POST",
+            "/api/v1/sync",
+            Some(json!({ "mutations": [
+                { "type": "location", "op": "create", "id": location_id,
+                  "data": { "name": "Market" } },
+                { "type": "review", "op": "create", "id": Uuid::new_v4(),
+                  "data": { "product_id": product_id, "rating": 9 } },
+                { "type": "product", "op": "update", "id": Uuid::new_v4(),
+                  "data": { "name": "Gone" } },
+                { "type": "location", "op": "create", "id": location_id,
+                  "data": { "name": "Again" } },
+            ]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{json}");
+        let results = json["results"].as_array().expect("results");
+        assert_eq!(results[0]["status"], "applied");
+        assert_eq!(results[0]["record"]["name"], "Market");
+        assert_eq!(results[1]["status"], "rejected");
+        assert_eq!(results[1]["error"], "Rating must be between 1 and 5.");
+        assert_eq!(results[2]["status"], "not_found");
+        assert_eq!(results[3]["status"], "conflict");
+        assert_eq!(results[3]["record"]["name"], "Market");
+        let base = results[0]["record"]["version"].as_i64().expect("version");
+
+        let (_, json) = send(
+            &state,
+            user_id,
+            "POST",
+            "/api/v1/sync",
+            Some(json!({ "mutations": [
+                { "type": "location", "op": "update", "id": location_id,
+                  "base_version": base - 1, "data": { "name": "Stale" } },
+                { "type": "location", "op": "update", "id": location_id,
+                  "base_version": base, "data": { "name": "Fresh" } },
+                { "type": "location", "op": "update", "id": location_id,
+                  "base_version": base, "data": { "name": "Lost" } },
+            ]})),
+        )
+        .await;
+        assert_eq!(json["results"][0]["status"], "conflict");
+        assert_eq!(json["results"][0]["record"]["name"], "Market");
+        assert_eq!(json["results"][1]["status"], "applied");
+        assert!(
+            json["results"][1]["record"]["version"]
+                .as_i64()
+                .expect("version")
+                > base
+        );
+        assert_eq!(
+            json["results"][2]["status"], "conflict",
+            "a change within the same second is still detected"
+        );
+        let location = db::location::get_by_id(&state.pool, location_id, false)
+            .await
+            .expect("db")
+            .expect("location");
+        assert_eq!(location.name(), "Fresh");
+    }
+
+    #[tokio::test]
+    async fn post_sync_rejects_ids_of_other_households() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Alice", "a@example.com").await;
+        let other = crate::domain::household::Household::new(
+            Uuid::new_v4(),
+            "Other".to_string(),
+            1_700_000_000,
+        )
+        .expect("household");
+        db::household::insert(&state.pool, &other)
+            .await
+            .expect("insert household");
+        let foreign_id =
+            db::household::with_household(other.id(), insert_location(&state.pool, "Theirs")).await;
+
+        let (_, json) = send(
+            &state,
+            user_id,
+            "POST",
+            "/api/v1/sync",
+            Some(json!({ "mutations": [
+                { "type": "location", "op": "create", "id": foreign_id,
+                  "data": { "name": "Mine" } },
+            ]})),
+        )
+        .await;
+        assert_eq!(json["results"][0]["status"], "rejected");
+        assert!(json["results"][0].get("record").is_none());
+    }
+}
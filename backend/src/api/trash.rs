@@ -0,0 +1,222 @@
+//! Trash REST API: list soft-deleted rows of all entity types.
+
+use axum::routing::get;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::pagination::{self, ListResponse};
+use crate::api::user::UserRef;
+use crate::api::{error::ApiError, state::AppState};
+use crate::db;
+use crate::db::trash::{TrashItem, TrashKind};
+
+/// Query params for GET /api/v1/trash.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListTrashQuery {
+    /// Only rows of this type (`category`, `product`, `variation`, `location`, `review`,
+    /// `purchase`).
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// Page size; when set the response is a page envelope (see [`ListResponse`]).
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+/// One soft-deleted row.
+#[derive(Debug, Serialize)]
+pub struct TrashItemResponse {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: Uuid,
+    pub label: String,
+    pub deleted_at: i64,
+    /// `null` when deleted from the CLI or before deleting users were recorded.
+    pub deleted_by: Option<UserRef>,
+}
+
+fn item_to_response(item: TrashItem) -> TrashItemResponse {
+    let deleted_by = item.deleted_by.map(|id| UserRef {
+        id,
+        name: item.deleted_by_name.unwrap_or_default(),
+    });
+    TrashItemResponse {
+        kind: item.kind.name(),
+        id: item.id,
+        label: item.label,
+        deleted_at: item.deleted_at,
+        deleted_by,
+    }
+}
+
+fn map_db_error(e: &db::DbError) -> ApiError {
+    match e {
+        db::DbError::InvalidData(msg) => ApiError::BadRequest(msg.clone()),
+        db::DbError::Sqlx(_) | db::DbError::Migrate(_) => ApiError::internal(e),
+    }
+}
+
+/// GET /api/v1/trash — soft-deleted categories, products, variations, locations, reviews and
+/// purchases, most recently deleted first. `type` filters by entity type; `limit` and `cursor`
+/// select the page (see [`crate::api::pagination`]).
+pub async fn list_trash(
+    State(state): State<AppState>,
+    Query(q): Query<ListTrashQuery>,
+) -> Result<Json<ListResponse<TrashItemResponse>>, ApiError> {
+    let kind = q
+        .kind
+        .as_deref()
+        .map(|s| {
+            TrashKind::from_param(s)
+                .ok_or_else(|| ApiError::BadRequest("Invalid type.".to_string()))
+        })
+        .transpose()?;
+    let page = pagination::page_request(q.limit, q.cursor.as_deref(), db::trash::TRASH_SORT_NAME)?;
+    let result = db::trash::list_page(&state.pool, kind, &page)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(Json(ListResponse::from_page(
+        result,
+        q.limit.is_some(),
+        item_to_response,
+    )))
+}
+
+/// Router for the trash endpoint.
+pub fn route() -> Router<AppState> {
+    Router::new().route("/api/v1/trash", get(list_trash))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::api::auth::CurrentUserId;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+    use crate::test_helpers::{insert_category, insert_location, insert_product, insert_user};
+
+    async fn test_pool() -> (AppState, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("trash_test.db");
+        let path_str = db_path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path_str).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        let state = AppState {
+            config: Config {
+                database_path: path_str,
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: std::env::temp_dir()
+                    .join("pocketratings-trash-test.pid")
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            pool,
+        };
+        (state, dir)
+    }
+
+    async fn get_json(state: &AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = route()
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn list_trash_returns_deleted_rows_with_deleting_user() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Alice", "alice@example.com").await;
+        let cat_id = insert_category(&state.pool, "Dairy").await;
+        let product_id = insert_product(&state.pool, cat_id, "Brand", "Milk").await;
+        let location_id = insert_location(&state.pool, "Corner Shop").await;
+        db::product::soft_delete(&state.pool, product_id, Some(user_id))
+            .await
+            .expect("soft delete product");
+        db::location::soft_delete(&state.pool, location_id, None)
+            .await
+            .expect("soft delete location");
+
+        let (status, json) = get_json(&state, "/api/v1/trash").await;
+        assert_eq!(status, StatusCode::OK);
+        let items = json.as_array().expect("array");
+        assert_eq!(items.len(), 2);
+        let product = items
+            .iter()
+            .find(|i| i["type"] == "product")
+            .expect("product in trash");
+        assert_eq!(product["label"], "Milk");
+        assert_eq!(product["deleted_by"]["name"], "Alice");
+        let location = items
+            .iter()
+            .find(|i| i["type"] == "location")
+            .expect("location in trash");
+        assert!(location["deleted_by"].is_null());
+
+        let (status, json) = get_json(&state, "/api/v1/trash?type=location&limit=10").await;
+        assert_eq!(status, StatusCode::OK);
+        let items = json["items"].as_array().expect("items");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["label"], "Corner Shop");
+        assert!(json["next_cursor"].is_null());
+
+        let (status, _) = get_json(&state, "/api/v1/trash?type=user").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn delete_records_current_user_in_trash() {
+        let (state, _dir) = test_pool().await;
+        let user_id = insert_user(&state.pool, "Bob", "bob@example.com").await;
+        let location_id = insert_location(&state.pool, "Market").await;
+        let app = crate::api::location::route()
+            .layer(axum::Extension(CurrentUserId(user_id)))
+            .with_state(state.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/v1/locations/{location_id}"))
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("service");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let (_, json) = get_json(&state, "/api/v1/trash").await;
+        let items = json.as_array().expect("array");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["deleted_by"]["id"], user_id.to_string());
+    }
+}
@@ -1,11 +1,520 @@
-//! User-related API types (e.g. refs for embedding in other responses).
+//! Users REST API (admin only): list, get, create, update, delete. Also user refs for embedding
+//! in other responses.
+//!
+//! Admins only see and manage the users of their current household; other users are not found.
+//! Accounts and roles are shared by all households of a user, so admins can only change or delete
+//! users whose households they all belong to.
 
-use serde::Serialize;
+use axum::routing::get;
+use axum::{
+    Json, Router,
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::api::auth::CurrentUserId;
+use crate::api::query::parse_flag;
+use crate::api::{error::ApiError, state::AppState};
+use crate::auth::password;
+use crate::db;
+use crate::db::household::current_household;
+use crate::domain::user::{Role, User, ValidationError};
+
 /// Minimal user info for embedding in purchase (and future) responses.
 #[derive(Debug, Clone, Serialize)]
 pub struct UserRef {
     pub id: Uuid,
     pub name: String,
 }
+
+/// Request body for creating a user.
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    /// `admin`, `member` (default) or `guest`.
+    pub role: Option<String>,
+}
+
+/// Request body for partial update.
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub role: Option<String>,
+}
+
+/// Query params for list users.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListUsersQuery {
+    #[serde(default, deserialize_with = "parse_flag")]
+    pub include_deleted: bool,
+}
+
+/// Query params for delete (optional force).
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteUserQuery {
+    #[serde(default, deserialize_with = "parse_flag")]
+    pub force: bool,
+}
+
+/// Response body: a user without the password hash.
+#[derive(Debug, Serialize)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub role: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
+}
+
+fn user_to_response(u: &User) -> UserResponse {
+    UserResponse {
+        id: u.id(),
+        name: u.name().to_string(),
+        email: u.email().to_string(),
+        role: u.role().to_string(),
+        created_at: u.created_at(),
+        updated_at: u.updated_at(),
+        deleted_at: u.deleted_at(),
+    }
+}
+
+/// Map `DbError` to `ApiError` for user operations.
+fn map_db_error(e: &db::DbError) -> ApiError {
+    match e {
+        db::DbError::InvalidData(msg) => {
+            if msg.contains("cannot delete") {
+                ApiError::Conflict(msg.clone())
+            } else {
+                ApiError::BadRequest(msg.clone())
+            }
+        }
+        db::DbError::Sqlx(sqlx_err) => {
+            if let sqlx::Error::Database(db) = sqlx_err
+                && db.is_unique_violation()
+            {
+                return ApiError::Conflict("Email is already registered.".to_string());
+            }
+            ApiError::internal(e)
+        }
+        db::DbError::Migrate(_) => ApiError::internal(e),
+    }
+}
+
+/// Map a user [`ValidationError`] to `400 Bad Request`. Shared with `POST /api/v1/auth/register`.
+pub fn map_validation_error(e: &ValidationError) -> ApiError {
+    match e {
+        ValidationError::NameEmpty => ApiError::BadRequest("Name is required.".to_string()),
+        ValidationError::EmailInvalid(s) => {
+            ApiError::BadRequest(format!("Invalid email address: {s}"))
+        }
+        ValidationError::PasswordEmpty => ApiError::BadRequest("Password is required.".to_string()),
+        ValidationError::RoleInvalid(_) => {
+            ApiError::BadRequest("Role must be admin, member or guest.".to_string())
+        }
+        ValidationError::CreatedAfterUpdated { .. }
+        | ValidationError::CreatedAfterDeleted { .. } => ApiError::BadRequest(e.to_string()),
+    }
+}
+
+fn parse_role(role: &str) -> Result<Role, ApiError> {
+    role.parse().map_err(|e| map_validation_error(&e))
+}
+
+/// Reject `email` if an active user already has it. (Emails of deleted users are caught by the
+/// unique constraint, see [`map_db_error`].)
+async fn ensure_email_available(state: &AppState, email: &str) -> Result<(), ApiError> {
+    let taken = db::user::get_by_email(&state.pool, email)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    if taken.is_some() {
+        return Err(ApiError::Conflict(
+            "Email is already registered.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Validate and store a new name, email and role for `existing`; omitted fields are kept.
+/// Shared with `PATCH /api/v1/me`.
+pub async fn apply_update(
+    state: &AppState,
+    existing: &User,
+    name: Option<&str>,
+    email: Option<&str>,
+    role: Role,
+) -> Result<User, ApiError> {
+    let name = name.map_or_else(|| existing.name(), str::trim);
+    let email = email.map_or_else(|| existing.email(), str::trim);
+    if email != existing.email() {
+        ensure_email_available(state, email).await?;
+    }
+    let updated = User::new(
+        existing.id(),
+        name.to_string(),
+        email.to_string(),
+        existing.password().to_string(),
+        existing.created_at(),
+        chrono::Utc::now().timestamp(),
+        None,
+    )
+    .map_err(|e| map_validation_error(&e))?
+    .with_role(role);
+    db::user::update(&state.pool, &updated)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(updated)
+}
+
+/// Active user `id`, if they are a member of the current household; other users are not found.
+async fn get_active(state: &AppState, id: Uuid) -> Result<User, ApiError> {
+    let not_found = || ApiError::NotFound("User not found.".to_string());
+    let member = db::household::is_member(&state.pool, current_household(), id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    if !member {
+        return Err(not_found());
+    }
+    db::user::get_by_id(&state.pool, id, false)
+        .await
+        .map_err(|e| map_db_error(&e))?
+        .ok_or_else(not_found)
+}
+
+/// Reject changes to user `id` unless the acting admin is a member of every household the user
+/// belongs to (the current household only when there is no acting user).
+async fn ensure_administered(
+    state: &AppState,
+    current_user: Option<&Extension<CurrentUserId>>,
+    id: Uuid,
+) -> Result<(), ApiError> {
+    let admin_households: Vec<Uuid> = match current_user {
+        Some(Extension(CurrentUserId(admin))) => db::household::list_for_user(&state.pool, *admin)
+            .await
+            .map_err(|e| map_db_error(&e))?
+            .iter()
+            .map(crate::domain::household::Household::id)
+            .collect(),
+        None => vec![current_household()],
+    };
+    let user_households = db::household::list_for_user(&state.pool, id)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    if user_households
+        .iter()
+        .any(|h| !admin_households.contains(&h.id()))
+    {
+        return Err(ApiError::Forbidden(
+            "This user also belongs to a household you do not administer.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// GET /api/v1/users — list the current household's users ordered by email; `include_deleted=true`
+/// adds deleted ones.
+pub async fn list_users(
+    State(state): State<AppState>,
+    Query(q): Query<ListUsersQuery>,
+) -> Result<Json<Vec<UserResponse>>, ApiError> {
+    let users = db::user::list_members(&state.pool, q.include_deleted)
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok(Json(users.iter().map(user_to_response).collect()))
+}
+
+/// GET /api/v1/users/:id — get one active user.
+pub async fn get_user(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UserResponse>, ApiError> {
+    let user = get_active(&state, id).await?;
+    Ok(Json(user_to_response(&user)))
+}
+
+/// POST /api/v1/users — register a user and add them to the current household.
+pub async fn create_user(
+    State(state): State<AppState>,
+    Json(body): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<UserResponse>), ApiError> {
+    let role = body
+        .role
+        .as_deref()
+        .map_or_else(|| Ok(Role::default()), parse_role)?;
+    if body.password.is_empty() {
+        return Err(ApiError::BadRequest("Password is required.".to_string()));
+    }
+    let email = body.email.trim();
+    ensure_email_available(&state, email).await?;
+    let hash = password::hash_password(&body.password).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp();
+    let user = User::new(
+        Uuid::new_v4(),
+        body.name.trim().to_string(),
+        email.to_string(),
+        hash,
+        now,
+        now,
+        None,
+    )
+    .map_err(|e| map_validation_error(&e))?
+    .with_role(role);
+    db::user::insert_member(&state.pool, &user, current_household())
+        .await
+        .map_err(|e| map_db_error(&e))?;
+    Ok((StatusCode::CREATED, Json(user_to_response(&user))))
+}
+
+/// PATCH /api/v1/users/:id — change a user's name, email and/or role. Admins cannot remove their
+/// own admin role.
+pub async fn update_user(
+    State(state): State<AppState>,
+    current_user: Option<Extension<CurrentUserId>>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateUserRequest>,
+) -> Result<Json<UserResponse>, ApiError> {
+    let existing = get_active(&state, id).await?;
+    ensure_administered(&state, current_user.as_ref(), id).await?;
+    let role = body
+        .role
+        .as_deref()
+        .map_or_else(|| Ok(existing.role()), parse_role)?;
+    if role != Role::Admin && is_self(current_user.as_ref(), id) {
+        return Err(ApiError::Conflict(
+            "You cannot remove your own admin role.".to_string(),
+        ));
+    }
+    let updated = apply_update(
+        &state,
+        &existing,
+        body.name.as_deref(),
+        body.email.as_deref(),
+        role,
+    )
+    .await?;
+    Ok(Json(user_to_response(&updated)))
+}
+
+/// DELETE /api/v1/users/:id — soft-delete (or with `force=true` remove) a user. Fails if the user
+/// has purchases or reviews; admins cannot delete themselves.
+pub async fn delete_user(
+    State(state): State<AppState>,
+    current_user: Option<Extension<CurrentUserId>>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<DeleteUserQuery>,
+) -> Result<StatusCode, ApiError> {
+    let _ = get_active(&state, id).await?;
+    if is_self(current_user.as_ref(), id) {
+        return Err(ApiError::Conflict(
+            "You cannot delete your own account.".to_string(),
+        ));
+    }
+    ensure_administered(&state, current_user.as_ref(), id).await?;
+    if q.force {
+        db::user::hard_delete(&state.pool, id)
+            .await
+            .map_err(|e| map_db_error(&e))?;
+    } else {
+        db::user::soft_delete(&state.pool, id)
+            .await
+            .map_err(|e| map_db_error(&e))?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn is_self(current_user: Option<&Extension<CurrentUserId>>, id: Uuid) -> bool {
+    current_user.is_some_and(|Extension(CurrentUserId(u))| *u == id)
+}
+
+/// Router for /api/v1/users (no layer; the admin layer is applied in the main router).
+pub fn route() -> Router<AppState> {
+    Router::new()
+        .route("/api/v1/users", get(list_users).post(create_user))
+        .route(
+            "/api/v1/users/{id}",
+            get(get_user).patch(update_user).delete(delete_user),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
+
+    async fn test_pool() -> (AppState, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("user_test.db");
+        let path_str = db_path.to_str().expect("path utf-8").to_string();
+        let pool = db::create_pool(&path_str).await.expect("pool");
+        db::run_migrations(&pool).await.expect("migrate");
+        let state = AppState {
+            config: Config {
+                database_path: path_str,
+                jwt_keys: JwtKeys::from_secret("test"),
+                jwt_expiration_seconds: 3600,
+                jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
+                bind: "127.0.0.1:0".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
+                pid_file: std::env::temp_dir()
+                    .join("pocketratings-user-test.pid")
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            pool,
+        };
+        (state, dir)
+    }
+
+    async fn send(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let builder = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(json) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&json).expect("json")))
+                .expect("request"),
+            None => builder.body(Body::empty()).expect("request"),
+        };
+        let response = route()
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .expect("service");
+        let status = response.status();
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("body")
+            .to_bytes();
+        let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    async fn create(state: &AppState, email: &str) -> serde_json::Value {
+        let (status, json) = send(
+            state,
+            "POST",
+            "/api/v1/users",
+            Some(serde_json::json!({
+                "name": "Alice",
+                "email": email,
+                "password": "secret",
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        json
+    }
+
+    #[tokio::test]
+    async fn create_user_returns_201_without_password() {
+        let (state, _dir) = test_pool().await;
+        let json = create(&state, "alice@example.com").await;
+        assert_eq!(json["email"], "alice@example.com");
+        assert_eq!(json["role"], "member");
+        assert!(json.get("password").is_none());
+
+        let id = Uuid::parse_str(json["id"].as_str().expect("id")).expect("uuid");
+        let is_member =
+            db::household::is_member(&state.pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+                .await
+                .expect("is_member");
+        assert!(is_member, "new users join the current household");
+    }
+
+    #[tokio::test]
+    async fn create_user_rejects_taken_email_and_invalid_input() {
+        let (state, _dir) = test_pool().await;
+        create(&state, "alice@example.com").await;
+
+        let cases = [
+            (
+                serde_json::json!({ "name": "A", "email": "alice@example.com", "password": "x" }),
+                StatusCode::CONFLICT,
+            ),
+            (
+                serde_json::json!({ "name": "A", "email": "not-an-email", "password": "x" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({ "name": " ", "email": "b@example.com", "password": "x" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({ "name": "B", "email": "b@example.com", "password": "" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({ "name": "B", "email": "b@example.com", "password": "x", "role": "owner" }),
+                StatusCode::BAD_REQUEST,
+            ),
+        ];
+        for (body, expected) in cases {
+            let (status, _) = send(&state, "POST", "/api/v1/users", Some(body.clone())).await;
+            assert_eq!(status, expected, "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn update_user_changes_fields_and_keeps_omitted_ones() {
+        let (state, _dir) = test_pool().await;
+        let created = create(&state, "alice@example.com").await;
+        let uri = format!("/api/v1/users/{}", created["id"].as_str().expect("id"));
+
+        let (status, json) = send(
+            &state,
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({ "role": "guest", "name": "Alicia" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["name"], "Alicia");
+        assert_eq!(json["email"], "alice@example.com");
+        assert_eq!(json["role"], "guest");
+
+        let (status, json) = send(&state, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["role"], "guest");
+    }
+
+    #[tokio::test]
+    async fn delete_user_then_list_with_include_deleted() {
+        let (state, _dir) = test_pool().await;
+        let created = create(&state, "alice@example.com").await;
+        let uri = format!("/api/v1/users/{}", created["id"].as_str().expect("id"));
+
+        let (status, _) = send(&state, "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&state, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, active) = send(&state, "GET", "/api/v1/users", None).await;
+        assert!(active.as_array().expect("array").is_empty());
+        let (_, all) = send(&state, "GET", "/api/v1/users?include_deleted=true", None).await;
+        assert_eq!(all.as_array().expect("array").len(), 1);
+        assert!(all[0]["deleted_at"].is_i64());
+    }
+}
@@ -28,7 +28,8 @@ mod tests {
     use sqlx::SqlitePool;
 
     use crate::api::state::AppState;
-    use crate::config::Config;
+    use crate::auth::jwt_keys::JwtKeys;
+    use crate::config::{Config, LogFormat};
 
     use super::route;
 
@@ -36,10 +37,15 @@ mod tests {
         AppState {
             config: Config {
                 database_path: ":memory:".to_string(),
-                jwt_secret: "test".to_string(),
+                jwt_keys: JwtKeys::from_secret("test"),
                 jwt_expiration_seconds: 3600,
                 jwt_refresh_threshold_seconds: 600,
+                refresh_token_expiration_seconds: 86400,
+                idempotency_window_seconds: 86400,
+                trusted_proxies: Vec::new(),
                 bind: "127.0.0.1:3099".to_string(),
+                metrics_bind: None,
+                log_format: LogFormat::Text,
                 pid_file: std::env::temp_dir()
                     .join("pocketratings-test.pid")
                     .to_string_lossy()
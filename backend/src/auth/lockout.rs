@@ -0,0 +1,75 @@
+//! Login brute-force protection policy: how long to lock out after repeated failed logins.
+//!
+//! Failures are counted per email and per client IP (see [`crate::db::login_attempt`]). The first
+//! [`FREE_ATTEMPTS`] failures are free; every further failure locks the key for
+//! [`BASE_LOCKOUT_SECS`], doubling each time up to [`MAX_LOCKOUT_SECS`]. A key's count starts over
+//! once it has had no failure for [`FAILURE_WINDOW_SECS`], or after a successful login.
+
+/// Failed attempts allowed before the first lockout.
+pub const FREE_ATTEMPTS: i64 = 5;
+
+/// Lockout after the first failure beyond [`FREE_ATTEMPTS`].
+pub const BASE_LOCKOUT_SECS: i64 = 30;
+
+/// Upper bound for a single lockout.
+pub const MAX_LOCKOUT_SECS: i64 = 60 * 60;
+
+/// Failures older than this no longer count.
+pub const FAILURE_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// Storage key for failed logins of an email address (case-insensitive).
+#[must_use]
+pub fn email_key(email: &str) -> String {
+    format!("email:{}", email.trim().to_lowercase())
+}
+
+/// Storage key for failed logins from a client IP address.
+#[must_use]
+pub fn ip_key(ip: std::net::IpAddr) -> String {
+    format!("ip:{ip}")
+}
+
+/// Lockout duration in seconds after `failures` consecutive failed attempts, or `None` while
+/// attempts are still free.
+#[must_use]
+pub fn lockout_secs(failures: i64) -> Option<i64> {
+    let excess = failures - FREE_ATTEMPTS;
+    if excess <= 0 {
+        return None;
+    }
+    let doublings = u32::try_from(excess - 1).unwrap_or(u32::MAX).min(31);
+    Some(
+        BASE_LOCKOUT_SECS
+            .saturating_mul(1_i64 << doublings)
+            .min(MAX_LOCKOUT_SECS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_attempts_do_not_lock() {
+        for failures in 0..=FREE_ATTEMPTS {
+            assert_eq!(lockout_secs(failures), None);
+        }
+    }
+
+    #[test]
+    fn lockout_doubles_and_is_capped() {
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 1), Some(BASE_LOCKOUT_SECS));
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 2), Some(2 * BASE_LOCKOUT_SECS));
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 3), Some(4 * BASE_LOCKOUT_SECS));
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 100), Some(MAX_LOCKOUT_SECS));
+    }
+
+    #[test]
+    fn email_keys_ignore_case_and_whitespace() {
+        assert_eq!(email_key(" Alice@Example.com "), "email:alice@example.com");
+        assert_eq!(
+            ip_key("127.0.0.1".parse().expect("ip")),
+            "ip:127.0.0.1".to_string()
+        );
+    }
+}
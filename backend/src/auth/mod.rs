@@ -1,3 +1,8 @@
-//! Authentication: password hashing and verification.
+//! Authentication: password hashing and verification, opaque tokens, JWT keys, login lockout
+//! policy, TOTP two-factor codes.
 
+pub mod jwt_keys;
+pub mod lockout;
 pub mod password;
+pub mod token;
+pub mod totp;
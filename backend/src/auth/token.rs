@@ -0,0 +1,54 @@
+//! Opaque random tokens (e.g. refresh tokens) and their storage hashes.
+//!
+//! Tokens are high-entropy random strings, so a plain SHA-256 digest is enough for storage; the
+//! plaintext is only ever shown to the client once.
+
+use std::fmt::Write;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Number of random bytes in a generated token.
+const TOKEN_BYTES: usize = 32;
+
+/// Generate a new random token (URL-safe base64, no padding).
+#[must_use]
+pub fn generate() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hash a token for storage and lookup (lowercase hex SHA-256).
+#[must_use]
+pub fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_returns_distinct_tokens() {
+        let a = generate();
+        let b = generate();
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 43);
+    }
+
+    #[test]
+    fn hash_is_stable_and_differs_per_token() {
+        let token = generate();
+        assert_eq!(hash(&token), hash(&token));
+        assert_ne!(hash(&token), hash(&generate()));
+        assert_eq!(hash(&token).len(), 64);
+    }
+}
@@ -0,0 +1,90 @@
+//! Audit subcommands (list).
+
+use std::io::Write;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::cli::CliError;
+use crate::db;
+use crate::db::audit::{Actor, AuditEntity, AuditFilter};
+use crate::db::page::{MAX_LIMIT, PageRequest};
+
+/// List the newest audit log entries, optionally for one row or entity type.
+pub async fn list(
+    pool: &SqlitePool,
+    entity_id: Option<&str>,
+    entity_type: Option<&str>,
+    limit: u32,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let entity_id = entity_id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| CliError::Validation("invalid entity_id".to_string()))?;
+    let entity = entity_type
+        .map(|t| {
+            AuditEntity::from_param(t)
+                .ok_or_else(|| CliError::Validation(format!("invalid entity-type: {t}")))
+        })
+        .transpose()?;
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(CliError::Validation(format!(
+            "limit must be between 1 and {MAX_LIMIT}"
+        )));
+    }
+
+    let filter = AuditFilter { entity_id, entity };
+    let page = PageRequest {
+        limit: Some(limit),
+        after: None,
+    };
+    let entries = db::audit::list_page(pool, &filter, &page).await?.items;
+
+    if output_json {
+        let items: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "id": e.id,
+                    "actor": e.actor.as_stored(),
+                    "actor_name": e.actor_name,
+                    "entity_type": e.entity.name(),
+                    "entity_id": e.entity_id.to_string(),
+                    "action": e.action.name(),
+                    "changes": e.changes,
+                    "created_at": e.created_at,
+                })
+            })
+            .collect();
+        writeln!(
+            stdout,
+            "{}",
+            serde_json::to_string(&items).map_err(|e| CliError::Other(e.into()))?
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        for e in &entries {
+            let when = chrono::DateTime::from_timestamp(e.created_at, 0)
+                .map_or_else(|| e.created_at.to_string(), |dt| dt.to_rfc3339());
+            let actor = match (e.actor, &e.actor_name) {
+                (Actor::Cli, _) => "cli".to_string(),
+                (Actor::User(_), Some(name)) => name.clone(),
+                (Actor::User(id), None) => id.to_string(),
+            };
+            writeln!(
+                stdout,
+                "{when} {actor} {} {} {} {}",
+                e.action.name(),
+                e.entity.name(),
+                e.entity_id,
+                e.changes
+            )
+            .map_err(|e| CliError::Other(e.into()))?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-//! Category subcommands (create, list, show, update, delete).
+//! Category subcommands (create, list, show, update, delete, restore).
 
 use std::io::Write;
 
@@ -236,8 +236,22 @@ pub async fn delete(
         db::category::hard_delete(pool, id).await?;
         writeln!(stdout, "Category removed: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     } else {
-        db::category::soft_delete(pool, id).await?;
+        db::category::soft_delete(pool, id, None).await?;
         writeln!(stdout, "Category deleted: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     }
     Ok(())
 }
+
+/// Restore a soft-deleted category by id.
+pub async fn restore(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid category id: {id_str}")))?;
+    db::category::restore(pool, id).await?;
+    writeln!(stdout, "Category restored: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
@@ -1,4 +1,4 @@
-//! Database subcommands (backup).
+//! Database subcommands (backup, purge).
 
 use std::io::Write;
 
@@ -40,6 +40,62 @@ pub async fn backup(
     Ok(())
 }
 
+/// Permanently delete rows soft-deleted longer ago than `older_than` (e.g. `90d`).
+///
+/// Prints the number of rows per table; with `dry_run` nothing is deleted and the counts are
+/// what a real run would remove.
+pub async fn purge(
+    pool: &SqlitePool,
+    older_than: &str,
+    dry_run: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let age = parse_age(older_than).ok_or_else(|| {
+        CliError::Validation(format!(
+            "invalid --older-than (expected e.g. 12h, 90d or 4w): {older_than}"
+        ))
+    })?;
+    let before = chrono::Utc::now().timestamp() - age;
+
+    let report = db::trash::purge(pool, before, dry_run).await?;
+
+    let heading = if dry_run {
+        "Dry run, nothing deleted. Would purge:"
+    } else {
+        "Purged:"
+    };
+    writeln!(stdout, "{heading}").map_err(|e| CliError::Other(e.into()))?;
+    for (name, count) in [
+        ("reviews", report.reviews),
+        ("purchases", report.purchases),
+        ("variations", report.variations),
+        ("products", report.products),
+        ("categories", report.categories),
+        ("locations", report.locations),
+    ] {
+        writeln!(stdout, "  {name}: {count}").map_err(|e| CliError::Other(e.into()))?;
+    }
+    writeln!(stdout, "  total: {}", report.total()).map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Parse an age like `12h`, `90d` or `4w` into seconds.
+pub(super) fn parse_age(s: &str) -> Option<i64> {
+    let unit = s.chars().last()?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    let secs = match unit {
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        _ => return None,
+    };
+    if n < 0 {
+        return None;
+    }
+    n.checked_mul(secs)
+}
+
 /// Reject paths that could escape the intended directory or inject SQL.
 fn is_safe_backup_path(path: &str) -> bool {
     !path.contains("..") && !path.contains(|c: char| c.is_control() || c == '\0')
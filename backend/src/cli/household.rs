@@ -0,0 +1,122 @@
+//! Household subcommands (create, list, add-user) and `--household` resolution.
+
+use std::io::Write;
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::cli::CliError;
+use crate::db;
+use crate::domain::household::{Household, ValidationError};
+
+/// Resolve a household given by UUID or by name.
+pub async fn resolve(pool: &SqlitePool, id_or_name: &str) -> Result<Household, CliError> {
+    let found = match Uuid::parse_str(id_or_name) {
+        Ok(id) => db::household::get_by_id(pool, id).await?,
+        Err(_) => db::household::get_by_name(pool, id_or_name).await?,
+    };
+    found.ok_or_else(|| CliError::Validation(format!("household not found: {id_or_name}")))
+}
+
+/// Create a new household.
+pub async fn create(
+    pool: &SqlitePool,
+    name: &str,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    if db::household::get_by_name(pool, name).await?.is_some() {
+        return Err(CliError::Validation(format!(
+            "household already exists: {name}"
+        )));
+    }
+    let household = Household::new(Uuid::new_v4(), name.to_string(), Utc::now().timestamp())
+        .map_err(|e| match e {
+            ValidationError::NameEmpty => {
+                CliError::Validation("name must not be empty".to_string())
+            }
+        })?;
+
+    db::household::insert(pool, &household).await?;
+
+    if output_json {
+        let out = serde_json::json!({
+            "id": household.id().to_string(),
+            "name": household.name(),
+        });
+        writeln!(stdout, "{out}").map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        writeln!(stdout, "Household created: {household}")
+            .map_err(|e| CliError::Other(e.into()))?;
+    }
+
+    Ok(())
+}
+
+/// List all households, ordered by name.
+pub async fn list(
+    pool: &SqlitePool,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let households = db::household::list_all(pool).await?;
+
+    if output_json {
+        let items: Vec<serde_json::Value> = households
+            .iter()
+            .map(|h| {
+                serde_json::json!({
+                    "id": h.id().to_string(),
+                    "name": h.name(),
+                })
+            })
+            .collect();
+        writeln!(
+            stdout,
+            "{}",
+            serde_json::to_string(&items).map_err(|e| CliError::Other(e.into()))?
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        for h in &households {
+            writeln!(stdout, "{}  {}", h.id(), h.name()).map_err(|e| CliError::Other(e.into()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add a user (by UUID or email) to a household (by UUID or name).
+pub async fn add_user(
+    pool: &SqlitePool,
+    household: &str,
+    user: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let household = resolve(pool, household).await?;
+    let user = crate::cli::user::resolve(pool, user).await?;
+
+    if db::household::add_member(pool, household.id(), user.id()).await? {
+        writeln!(
+            stdout,
+            "User {} added to household {}",
+            user.email(),
+            household.name()
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        writeln!(
+            stdout,
+            "User {} is already a member of household {}",
+            user.email(),
+            household.name()
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    }
+
+    Ok(())
+}
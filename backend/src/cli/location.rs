@@ -1,4 +1,4 @@
-//! Location subcommands (create, list, show, update, delete).
+//! Location subcommands (create, list, show, update, delete, restore).
 
 use std::io::Write;
 
@@ -173,8 +173,22 @@ pub async fn delete(
         db::location::hard_delete(pool, id).await?;
         writeln!(stdout, "Location removed: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     } else {
-        db::location::soft_delete(pool, id).await?;
+        db::location::soft_delete(pool, id, None).await?;
         writeln!(stdout, "Location deleted: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     }
     Ok(())
 }
+
+/// Restore a soft-deleted location by id.
+pub async fn restore(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid location id: {id_str}")))?;
+    db::location::restore(pool, id).await?;
+    writeln!(stdout, "Location restored: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
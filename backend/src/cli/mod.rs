@@ -1,10 +1,13 @@
 //! CLI commands and parsing.
 
+mod audit;
 mod category;
 mod database;
+mod household;
 mod location;
 mod product;
 mod purchase;
+mod report;
 mod review;
 mod server;
 mod user;
@@ -14,11 +17,14 @@ use std::io::Write;
 use clap::{CommandFactory, Parser, Subcommand};
 use sqlx::SqlitePool;
 
+use crate::cli::audit as audit_cli;
 use crate::cli::category as category_cli;
 use crate::cli::database as database_cli;
+use crate::cli::household as household_cli;
 use crate::cli::location as location_cli;
 use crate::cli::product as product_cli;
 use crate::cli::purchase as purchase_cli;
+use crate::cli::report as report_cli;
 use crate::cli::review as review_cli;
 use crate::cli::server as server_cli;
 use crate::cli::user as user_cli;
@@ -31,14 +37,30 @@ use crate::cli::user as user_cli;
 pub fn subcommand_needs_db(first: Option<&str>, second: Option<&str>) -> bool {
     matches!(
         (first, second),
-        (Some("user"), Some("register" | "list" | "delete"))
-            | (
-                Some("category" | "location" | "product" | "purchase" | "review"),
-                Some("create" | "list" | "show" | "update" | "delete")
+        (
+            Some("user"),
+            Some(
+                "register"
+                    | "list"
+                    | "update"
+                    | "delete"
+                    | "set-password"
+                    | "reset-password"
+                    | "invite"
+                    | "unlock"
+                    | "two-factor"
+                    | "sessions"
+                    | "token"
             )
-            | (Some("product"), Some("variation-add"))
+        ) | (
+            Some("category" | "location" | "product" | "purchase" | "review"),
+            Some("create" | "list" | "show" | "update" | "delete" | "restore")
+        ) | (Some("product"), Some("variation-add" | "variation-restore"))
             | (Some("server"), Some("start"))
-            | (Some("database"), Some("backup"))
+            | (Some("database"), Some("backup" | "purge"))
+            | (Some("report"), Some("spending"))
+            | (Some("audit"), Some("list"))
+            | (Some("household"), Some("create" | "list" | "add-user"))
     )
 }
 
@@ -49,15 +71,21 @@ pub fn subcommand_needs_db(first: Option<&str>, second: Option<&str>) -> bool {
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+    /// Household (UUID or name) whose data the command works on; defaults to the default household.
+    #[arg(long, global = true)]
+    pub household: Option<String>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
+    Audit(AuditArgs),
     Category(CategoryArgs),
     Database(DatabaseArgs),
+    Household(HouseholdArgs),
     Location(LocationArgs),
     Product(ProductArgs),
     Purchase(PurchaseArgs),
+    Report(ReportArgs),
     Review(ReviewArgs),
     Server(ServerArgs),
     User(UserArgs),
@@ -88,7 +116,8 @@ pub struct ServerStartOpts {
 #[derive(clap::Args)]
 pub struct ServerStopOpts {}
 
-/// Manage user accounts: register, list, and delete users.
+/// Manage user accounts: register, invite, list, update, and delete users, set or reset their
+/// passwords, enable two-factor authentication, and manage their login sessions and API tokens.
 #[derive(clap::Args)]
 pub struct UserArgs {
     #[command(subcommand)]
@@ -99,10 +128,174 @@ pub struct UserArgs {
 pub enum UserCmd {
     Register(RegisterOpts),
     List(ListOpts),
+    /// Change a user's name, email or role.
+    Update(UpdateUserOpts),
     Delete(DeleteOpts),
+    /// Set a user's password and log out all their sessions.
+    SetPassword(SetPasswordOpts),
+    /// Issue a one-time password reset token for a user.
+    ResetPassword(ResetPasswordOpts),
+    /// Issue a single-use invite token that lets someone register themselves.
+    Invite(InviteOpts),
+    /// Clear failed login attempts and lift a login lockout for a user (or a client IP).
+    Unlock(UnlockOpts),
+    /// Enable or disable TOTP two-factor authentication for a user.
+    TwoFactor(UserTwoFactorArgs),
+    /// List or revoke a user's login sessions.
+    Sessions(UserSessionsArgs),
+    /// Create, list, or revoke a user's personal access tokens.
+    Token(UserTokenArgs),
 }
 
-/// Manage product categories: create, list, show, update, and delete.
+#[derive(clap::Args)]
+pub struct UserTwoFactorArgs {
+    #[command(subcommand)]
+    pub command: UserTwoFactorCmd,
+}
+
+#[derive(Subcommand)]
+pub enum UserTwoFactorCmd {
+    /// Enable two-factor login; prints the secret and recovery codes once.
+    Enable(UserTwoFactorEnableOpts),
+    /// Disable two-factor login and remove the secret and recovery codes.
+    Disable(UserTwoFactorDisableOpts),
+}
+
+#[derive(clap::Args)]
+pub struct UserTwoFactorEnableOpts {
+    /// User UUID or email.
+    pub user: String,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct UserTwoFactorDisableOpts {
+    /// User UUID or email.
+    pub user: String,
+}
+
+#[derive(clap::Args)]
+pub struct UserSessionsArgs {
+    #[command(subcommand)]
+    pub command: UserSessionsCmd,
+}
+
+#[derive(Subcommand)]
+pub enum UserSessionsCmd {
+    /// List a user's sessions, newest first.
+    List(UserSessionsListOpts),
+    /// Revoke a session (log it out), or all sessions of a user.
+    Revoke(UserSessionsRevokeOpts),
+}
+
+#[derive(clap::Args)]
+pub struct UserSessionsListOpts {
+    /// User UUID or email.
+    pub user: String,
+    /// Include revoked and expired sessions.
+    #[arg(long)]
+    pub all: bool,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct UserSessionsRevokeOpts {
+    /// Session UUID to revoke.
+    #[arg(required_unless_present = "user", conflicts_with = "user")]
+    pub id: Option<String>,
+    /// Revoke all sessions of this user (UUID or email) instead.
+    #[arg(long)]
+    pub user: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct UserTokenArgs {
+    #[command(subcommand)]
+    pub command: UserTokenCmd,
+}
+
+#[derive(Subcommand)]
+pub enum UserTokenCmd {
+    /// Create a token for a user in the current household; the token is printed once.
+    Create(UserTokenCreateOpts),
+    /// List a user's tokens, newest first.
+    List(UserTokenListOpts),
+    /// Revoke a token.
+    Revoke(UserTokenRevokeOpts),
+}
+
+#[derive(clap::Args)]
+pub struct UserTokenCreateOpts {
+    /// User UUID or email.
+    pub user: String,
+    /// Name describing what the token is for.
+    #[arg(long)]
+    pub name: String,
+    #[arg(long, default_value = "read", value_parser = ["read", "write"])]
+    pub scope: String,
+    /// Expire the token after this long (e.g. 12h, 30d, 52w); never expires when omitted.
+    #[arg(long)]
+    pub expires_in: Option<String>,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct UserTokenListOpts {
+    /// User UUID or email.
+    pub user: String,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct UserTokenRevokeOpts {
+    /// Token UUID to revoke.
+    pub id: String,
+}
+
+/// Manage households: create, list, and add users to them.
+#[derive(clap::Args)]
+pub struct HouseholdArgs {
+    #[command(subcommand)]
+    pub command: HouseholdCmd,
+}
+
+#[derive(Subcommand)]
+pub enum HouseholdCmd {
+    /// Create a household.
+    Create(HouseholdCreateOpts),
+    /// List households.
+    List(HouseholdListOpts),
+    /// Add a user to a household.
+    AddUser(HouseholdAddUserOpts),
+}
+
+#[derive(clap::Args)]
+pub struct HouseholdCreateOpts {
+    /// Household name (unique).
+    pub name: String,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct HouseholdListOpts {
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct HouseholdAddUserOpts {
+    /// Household UUID or name.
+    pub household_ref: String,
+    /// User UUID or email.
+    pub user: String,
+}
+
+/// Manage product categories: create, list, show, update, delete, and restore.
 #[derive(clap::Args)]
 pub struct CategoryArgs {
     #[command(subcommand)]
@@ -121,9 +314,11 @@ pub enum CategoryCmd {
     Update(CategoryUpdateOpts),
     /// Soft-delete a category.
     Delete(CategoryDeleteOpts),
+    /// Restore a soft-deleted category.
+    Restore(CategoryRestoreOpts),
 }
 
-/// Manage products: create, list, show, update, and delete (by category/brand/name).
+/// Manage products: create, list, show, update, delete, and restore (by category/brand/name).
 #[derive(clap::Args)]
 pub struct ProductArgs {
     #[command(subcommand)]
@@ -142,8 +337,12 @@ pub enum ProductCmd {
     Update(ProductUpdateOpts),
     /// Soft-delete or remove a product.
     Delete(ProductDeleteOpts),
+    /// Restore a soft-deleted product.
+    Restore(ProductRestoreOpts),
     /// Add a variation to a product.
     VariationAdd(ProductVariationAddOpts),
+    /// Restore a soft-deleted variation.
+    VariationRestore(ProductVariationRestoreOpts),
 }
 
 #[derive(clap::Args)]
@@ -203,6 +402,12 @@ pub struct ProductDeleteOpts {
     pub force: bool,
 }
 
+#[derive(clap::Args)]
+pub struct ProductRestoreOpts {
+    /// Product UUID to restore.
+    pub id: String,
+}
+
 #[derive(clap::Args)]
 pub struct ProductVariationAddOpts {
     /// Product UUID to add the variation to.
@@ -217,9 +422,18 @@ pub struct ProductVariationAddOpts {
     /// Optional quantity (e.g. 500 for 500g; when unit is milliliters, 1000 for 1L).
     #[arg(long, value_name = "QTY")]
     pub quantity: Option<u32>,
+    /// Optional GTIN barcode (EAN-8, UPC-A, EAN-13 or GTIN-14).
+    #[arg(long, value_name = "GTIN")]
+    pub barcode: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct ProductVariationRestoreOpts {
+    /// Variation UUID to restore.
+    pub id: String,
 }
 
-/// Manage locations (stores): create, list, show, update, and delete.
+/// Manage locations (stores): create, list, show, update, delete, and restore.
 #[derive(clap::Args)]
 pub struct LocationArgs {
     #[command(subcommand)]
@@ -238,6 +452,8 @@ pub enum LocationCmd {
     Update(LocationUpdateOpts),
     /// Soft-delete or remove a location.
     Delete(LocationDeleteOpts),
+    /// Restore a soft-deleted location.
+    Restore(LocationRestoreOpts),
 }
 
 #[derive(clap::Args)]
@@ -284,7 +500,13 @@ pub struct LocationDeleteOpts {
     pub force: bool,
 }
 
-/// Manage reviews: create, list, show, update, and delete.
+#[derive(clap::Args)]
+pub struct LocationRestoreOpts {
+    /// Location UUID to restore.
+    pub id: String,
+}
+
+/// Manage reviews: create, list, show, update, delete, and restore.
 #[derive(clap::Args)]
 pub struct ReviewArgs {
     #[command(subcommand)]
@@ -303,6 +525,8 @@ pub enum ReviewCmd {
     Update(ReviewUpdateOpts),
     /// Soft-delete or remove a review.
     Delete(ReviewDeleteOpts),
+    /// Restore a soft-deleted review.
+    Restore(ReviewRestoreOpts),
 }
 
 #[derive(clap::Args)]
@@ -358,7 +582,13 @@ pub struct ReviewDeleteOpts {
     pub force: bool,
 }
 
-/// Manage purchases: create, list, show, and delete.
+#[derive(clap::Args)]
+pub struct ReviewRestoreOpts {
+    /// Review UUID to restore.
+    pub id: String,
+}
+
+/// Manage purchases: create, list, show, delete, and restore.
 #[derive(clap::Args)]
 pub struct PurchaseArgs {
     #[command(subcommand)]
@@ -375,6 +605,8 @@ pub enum PurchaseCmd {
     Show(PurchaseShowOpts),
     /// Soft-delete or remove a purchase.
     Delete(PurchaseDeleteOpts),
+    /// Restore a soft-deleted purchase.
+    Restore(PurchaseRestoreOpts),
 }
 
 #[derive(clap::Args)]
@@ -429,6 +661,12 @@ pub struct PurchaseDeleteOpts {
     pub force: bool,
 }
 
+#[derive(clap::Args)]
+pub struct PurchaseRestoreOpts {
+    /// Purchase UUID to restore.
+    pub id: String,
+}
+
 #[derive(clap::Args)]
 pub struct RegisterOpts {
     #[arg(long)]
@@ -437,10 +675,25 @@ pub struct RegisterOpts {
     pub email: String,
     #[arg(long)]
     pub password: String,
+    /// admin (everything), member (read and write data) or guest (read-only).
+    #[arg(long, default_value = "member", value_parser = ["admin", "member", "guest"])]
+    pub role: String,
     #[arg(long, default_value = "human", value_parser = ["human", "json"])]
     pub output: String,
 }
 
+#[derive(clap::Args)]
+pub struct UpdateUserOpts {
+    /// User UUID or email.
+    pub user: String,
+    #[arg(long)]
+    pub name: Option<String>,
+    #[arg(long)]
+    pub email: Option<String>,
+    #[arg(long, value_parser = ["admin", "member", "guest"])]
+    pub role: Option<String>,
+}
+
 #[derive(clap::Args)]
 pub struct ListOpts {
     #[arg(long, default_value = "human", value_parser = ["human", "json"])]
@@ -459,6 +712,50 @@ pub struct DeleteOpts {
     pub force: bool,
 }
 
+#[derive(clap::Args)]
+pub struct SetPasswordOpts {
+    /// User UUID or email.
+    pub user: String,
+    #[arg(long)]
+    pub password: String,
+}
+
+#[derive(clap::Args)]
+pub struct ResetPasswordOpts {
+    /// User UUID or email.
+    pub user: String,
+    /// How long the reset token stays valid (e.g. 12h, 1d, 1w).
+    #[arg(long, default_value = "24h")]
+    pub expires_in: String,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct InviteOpts {
+    /// Email address of the account to be registered.
+    #[arg(long)]
+    pub email: String,
+    /// Role of the new account: admin, member or guest.
+    #[arg(long, default_value = "member", value_parser = ["admin", "member", "guest"])]
+    pub role: String,
+    /// How long the invite stays valid (e.g. 12h, 1d, 1w).
+    #[arg(long, default_value = "7d")]
+    pub expires_in: String,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+#[derive(clap::Args)]
+pub struct UnlockOpts {
+    /// User UUID or email.
+    #[arg(required_unless_present = "ip")]
+    pub user: Option<String>,
+    /// Also (or instead) unlock this client IP address.
+    #[arg(long)]
+    pub ip: Option<std::net::IpAddr>,
+}
+
 #[derive(clap::Args)]
 pub struct CategoryCreateOpts {
     #[arg(long)]
@@ -509,7 +806,13 @@ pub struct CategoryDeleteOpts {
     pub force: bool,
 }
 
-/// Manage database operations: backup.
+#[derive(clap::Args)]
+pub struct CategoryRestoreOpts {
+    /// Category UUID to restore.
+    pub id: String,
+}
+
+/// Manage database operations: backup, purge.
 #[derive(clap::Args)]
 pub struct DatabaseArgs {
     #[command(subcommand)]
@@ -520,6 +823,8 @@ pub struct DatabaseArgs {
 pub enum DatabaseCmd {
     /// Create a backup of the database (server can keep running).
     Backup(DatabaseBackupOpts),
+    /// Permanently delete rows that were soft-deleted longer ago than `--older-than`.
+    Purge(DatabasePurgeOpts),
 }
 
 #[derive(clap::Args)]
@@ -529,6 +834,80 @@ pub struct DatabaseBackupOpts {
     pub output: Option<String>,
 }
 
+#[derive(clap::Args)]
+pub struct DatabasePurgeOpts {
+    /// Minimum time since deletion, as a number with unit `h`, `d` or `w` (e.g. `90d`).
+    #[arg(long)]
+    pub older_than: String,
+    /// Report what would be deleted without deleting anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Reports over purchases: spending.
+#[derive(clap::Args)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub command: ReportCmd,
+}
+
+#[derive(Subcommand)]
+pub enum ReportCmd {
+    /// Total spending (price x quantity) grouped by period, category, location or user.
+    Spending(ReportSpendingOpts),
+}
+
+#[derive(clap::Args)]
+pub struct ReportSpendingOpts {
+    /// Grouping: month, week (starting Monday), category, location or user.
+    #[arg(long, default_value = "month", value_parser = ["month", "week", "category", "location", "user"])]
+    pub group_by: String,
+    /// Only products in this category or its descendants; with `--group-by category`, groups
+    /// are its direct children.
+    #[arg(long)]
+    pub category_id: Option<String>,
+    #[arg(long)]
+    pub location_id: Option<String>,
+    #[arg(long)]
+    pub user_id: Option<String>,
+    /// Start of the range (ISO 8601 or YYYY-MM-DD, inclusive).
+    #[arg(long)]
+    pub from: Option<String>,
+    /// End of the range (ISO 8601 or YYYY-MM-DD, inclusive).
+    #[arg(long)]
+    pub to: Option<String>,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
+/// Audit log of changes: list.
+#[derive(clap::Args)]
+pub struct AuditArgs {
+    #[command(subcommand)]
+    pub command: AuditCmd,
+}
+
+#[derive(Subcommand)]
+pub enum AuditCmd {
+    /// List audit log entries, newest first.
+    List(AuditListOpts),
+}
+
+#[derive(clap::Args)]
+pub struct AuditListOpts {
+    /// Only changes of this row (UUID).
+    #[arg(long)]
+    pub entity_id: Option<String>,
+    /// Only changes of this entity type.
+    #[arg(long, value_parser = ["category", "product", "variation", "location", "review", "purchase", "user"])]
+    pub entity_type: Option<String>,
+    /// Maximum number of entries.
+    #[arg(long, default_value_t = 50)]
+    pub limit: u32,
+    #[arg(long, default_value = "human", value_parser = ["human", "json"])]
+    pub output: String,
+}
+
 /// CLI-specific errors for user-facing messages and exit codes.
 #[derive(Debug, thiserror::Error)]
 pub enum CliError {
@@ -552,10 +931,11 @@ impl From<crate::db::DbError> for CliError {
 ///
 /// When the command is `user register`, `pool` must be `Some`; the caller (e.g. `main`) is responsible for creating the pool and running migrations first.
 ///
+/// With `--household`, the command runs scoped to that household (see [`crate::db::household`]).
+///
 /// # Errors
 ///
-/// Returns [`CliError`] on parse failure, missing pool for `user register`, register handler errors, or I/O when writing help or output.
-#[allow(clippy::too_many_lines)]
+/// Returns [`CliError`] on parse failure, missing pool for `user register`, an unknown `--household`, register handler errors, or I/O when writing help or output.
 pub async fn run(
     args: impl Iterator<Item = impl Into<std::ffi::OsString> + Clone>,
     pool: Option<&SqlitePool>,
@@ -565,7 +945,30 @@ pub async fn run(
 ) -> Result<(), CliError> {
     let cli = Cli::parse_from(args);
 
-    match cli.command {
+    let Some(household) = cli.household else {
+        return dispatch(cli.command, pool, config_override, stdout, stderr).await;
+    };
+    let pool_for_household = pool.ok_or_else(|| {
+        CliError::Other(anyhow::anyhow!("database pool required for --household"))
+    })?;
+    let household = household_cli::resolve(pool_for_household, &household).await?;
+    crate::db::household::with_household(
+        household.id(),
+        dispatch(cli.command, pool, config_override, stdout, stderr),
+    )
+    .await
+}
+
+/// Run a parsed command (inside the household scope chosen by [`run`]).
+#[allow(clippy::too_many_lines)]
+async fn dispatch(
+    command: Option<Commands>,
+    pool: Option<&SqlitePool>,
+    config_override: Option<&crate::config::Config>,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    match command {
         Some(Commands::User(user_args)) => match user_args.command {
             UserCmd::Register(opts) => {
                 let pool = pool.ok_or_else(|| {
@@ -577,6 +980,7 @@ pub async fn run(
                     &opts.name,
                     &opts.email,
                     &opts.password,
+                    &opts.role,
                     output_json,
                     stdout,
                     stderr,
@@ -590,12 +994,174 @@ pub async fn run(
                 let output_json = opts.output.as_str() == "json";
                 user_cli::list(pool, output_json, opts.include_deleted, stdout, stderr).await
             }
+            UserCmd::Update(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for user update"))
+                })?;
+                user_cli::update(
+                    pool,
+                    &opts.user,
+                    opts.name.as_deref(),
+                    opts.email.as_deref(),
+                    opts.role.as_deref(),
+                    stdout,
+                    stderr,
+                )
+                .await
+            }
             UserCmd::Delete(opts) => {
                 let pool = pool.ok_or_else(|| {
                     CliError::Other(anyhow::anyhow!("database pool required for user delete"))
                 })?;
                 user_cli::delete(pool, &opts.id, opts.force, stdout, stderr).await
             }
+            UserCmd::SetPassword(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for user set-password"
+                    ))
+                })?;
+                user_cli::set_password(pool, &opts.user, &opts.password, stdout, stderr).await
+            }
+            UserCmd::ResetPassword(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for user reset-password"
+                    ))
+                })?;
+                let output_json = opts.output.as_str() == "json";
+                user_cli::reset_password(
+                    pool,
+                    &opts.user,
+                    &opts.expires_in,
+                    output_json,
+                    stdout,
+                    stderr,
+                )
+                .await
+            }
+            UserCmd::Invite(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for user invite"))
+                })?;
+                let output_json = opts.output.as_str() == "json";
+                user_cli::invite(
+                    pool,
+                    &opts.email,
+                    &opts.role,
+                    &opts.expires_in,
+                    output_json,
+                    stdout,
+                    stderr,
+                )
+                .await
+            }
+            UserCmd::Unlock(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for user unlock"))
+                })?;
+                user_cli::unlock(pool, opts.user.as_deref(), opts.ip, stdout, stderr).await
+            }
+            UserCmd::TwoFactor(two_factor_args) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for user two-factor"
+                    ))
+                })?;
+                match two_factor_args.command {
+                    UserTwoFactorCmd::Enable(opts) => {
+                        let output_json = opts.output.as_str() == "json";
+                        user_cli::two_factor_enable(pool, &opts.user, output_json, stdout, stderr)
+                            .await
+                    }
+                    UserTwoFactorCmd::Disable(opts) => {
+                        user_cli::two_factor_disable(pool, &opts.user, stdout, stderr).await
+                    }
+                }
+            }
+            UserCmd::Sessions(sessions_args) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for user sessions"))
+                })?;
+                match sessions_args.command {
+                    UserSessionsCmd::List(opts) => {
+                        let output_json = opts.output.as_str() == "json";
+                        user_cli::sessions_list(
+                            pool,
+                            &opts.user,
+                            opts.all,
+                            output_json,
+                            stdout,
+                            stderr,
+                        )
+                        .await
+                    }
+                    UserSessionsCmd::Revoke(opts) => {
+                        user_cli::sessions_revoke(
+                            pool,
+                            opts.id.as_deref(),
+                            opts.user.as_deref(),
+                            stdout,
+                            stderr,
+                        )
+                        .await
+                    }
+                }
+            }
+            UserCmd::Token(token_args) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for user token"))
+                })?;
+                match token_args.command {
+                    UserTokenCmd::Create(opts) => {
+                        let output_json = opts.output.as_str() == "json";
+                        user_cli::token_create(
+                            pool,
+                            &opts.user,
+                            &opts.name,
+                            &opts.scope,
+                            opts.expires_in.as_deref(),
+                            output_json,
+                            stdout,
+                            stderr,
+                        )
+                        .await
+                    }
+                    UserTokenCmd::List(opts) => {
+                        let output_json = opts.output.as_str() == "json";
+                        user_cli::token_list(pool, &opts.user, output_json, stdout, stderr).await
+                    }
+                    UserTokenCmd::Revoke(opts) => {
+                        user_cli::token_revoke(pool, &opts.id, stdout, stderr).await
+                    }
+                }
+            }
+        },
+        Some(Commands::Household(household_args)) => match household_args.command {
+            HouseholdCmd::Create(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for household create"
+                    ))
+                })?;
+                let output_json = opts.output.as_str() == "json";
+                household_cli::create(pool, &opts.name, output_json, stdout, stderr).await
+            }
+            HouseholdCmd::List(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for household list"))
+                })?;
+                let output_json = opts.output.as_str() == "json";
+                household_cli::list(pool, output_json, stdout, stderr).await
+            }
+            HouseholdCmd::AddUser(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for household add-user"
+                    ))
+                })?;
+                household_cli::add_user(pool, &opts.household_ref, &opts.user, stdout, stderr).await
+            }
         },
         Some(Commands::Category(cat_args)) => match cat_args.command {
             CategoryCmd::Create(opts) => {
@@ -663,6 +1229,14 @@ pub async fn run(
                 })?;
                 category_cli::delete(pool, &opts.id, opts.force, stdout, stderr).await
             }
+            CategoryCmd::Restore(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for category restore"
+                    ))
+                })?;
+                category_cli::restore(pool, &opts.id, stdout, stderr).await
+            }
         },
         Some(Commands::Location(loc_args)) => match loc_args.command {
             LocationCmd::Create(opts) => {
@@ -713,6 +1287,14 @@ pub async fn run(
                 })?;
                 location_cli::delete(pool, &opts.id, opts.force, stdout, stderr).await
             }
+            LocationCmd::Restore(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for location restore"
+                    ))
+                })?;
+                location_cli::restore(pool, &opts.id, stdout, stderr).await
+            }
         },
         Some(Commands::Product(prod_args)) => match prod_args.command {
             ProductCmd::Create(opts) => {
@@ -777,6 +1359,14 @@ pub async fn run(
                 })?;
                 product_cli::delete(pool, &opts.id, opts.force, stdout, stderr).await
             }
+            ProductCmd::Restore(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for product restore"
+                    ))
+                })?;
+                product_cli::restore(pool, &opts.id, stdout, stderr).await
+            }
             ProductCmd::VariationAdd(opts) => {
                 let pool = pool.ok_or_else(|| {
                     CliError::Other(anyhow::anyhow!(
@@ -789,11 +1379,20 @@ pub async fn run(
                     opts.label.as_deref().unwrap_or(""),
                     &opts.unit,
                     opts.quantity,
+                    opts.barcode.as_deref(),
                     stdout,
                     stderr,
                 )
                 .await
             }
+            ProductCmd::VariationRestore(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for product variation-restore"
+                    ))
+                })?;
+                product_cli::variation_restore(pool, &opts.id, stdout, stderr).await
+            }
         },
         Some(Commands::Purchase(pur_args)) => match pur_args.command {
             PurchaseCmd::Create(opts) => {
@@ -852,6 +1451,14 @@ pub async fn run(
                 })?;
                 purchase_cli::delete(pool, &opts.id, opts.force, stdout, stderr).await
             }
+            PurchaseCmd::Restore(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for purchase restore"
+                    ))
+                })?;
+                purchase_cli::restore(pool, &opts.id, stdout, stderr).await
+            }
         },
         Some(Commands::Review(rev_args)) => match rev_args.command {
             ReviewCmd::Create(opts) => {
@@ -917,6 +1524,12 @@ pub async fn run(
                 })?;
                 review_cli::delete(pool, &opts.id, opts.force, stdout, stderr).await
             }
+            ReviewCmd::Restore(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for review restore"))
+                })?;
+                review_cli::restore(pool, &opts.id, stdout, stderr).await
+            }
         },
         Some(Commands::Server(server_args)) => match server_args.command {
             ServerCmd::Start(opts) => {
@@ -927,6 +1540,47 @@ pub async fn run(
             }
             ServerCmd::Stop(_opts) => server_cli::stop(config_override, stdout, stderr),
         },
+        Some(Commands::Audit(audit_args)) => match audit_args.command {
+            AuditCmd::List(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for audit list"))
+                })?;
+                let output_json = opts.output.as_str() == "json";
+                audit_cli::list(
+                    pool,
+                    opts.entity_id.as_deref(),
+                    opts.entity_type.as_deref(),
+                    opts.limit,
+                    output_json,
+                    stdout,
+                    stderr,
+                )
+                .await
+            }
+        },
+        Some(Commands::Report(report_args)) => match report_args.command {
+            ReportCmd::Spending(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!(
+                        "database pool required for report spending"
+                    ))
+                })?;
+                let output_json = opts.output.as_str() == "json";
+                report_cli::spending(
+                    pool,
+                    &opts.group_by,
+                    opts.category_id.as_deref(),
+                    opts.location_id.as_deref(),
+                    opts.user_id.as_deref(),
+                    opts.from.as_deref(),
+                    opts.to.as_deref(),
+                    output_json,
+                    stdout,
+                    stderr,
+                )
+                .await
+            }
+        },
         Some(Commands::Database(db_args)) => match db_args.command {
             DatabaseCmd::Backup(opts) => {
                 let pool = pool.ok_or_else(|| {
@@ -943,6 +1597,12 @@ pub async fn run(
                 )
                 .await
             }
+            DatabaseCmd::Purge(opts) => {
+                let pool = pool.ok_or_else(|| {
+                    CliError::Other(anyhow::anyhow!("database pool required for database purge"))
+                })?;
+                database_cli::purge(pool, &opts.older_than, opts.dry_run, stdout, stderr).await
+            }
         },
         None => {
             let mut out = Vec::new();
@@ -965,12 +1625,30 @@ mod tests {
     fn subcommand_needs_db_returns_true_for_commands_that_use_pool() {
         assert!(subcommand_needs_db(Some("user"), Some("register")));
         assert!(subcommand_needs_db(Some("user"), Some("list")));
+        assert!(subcommand_needs_db(Some("user"), Some("sessions")));
+        assert!(subcommand_needs_db(Some("user"), Some("token")));
+        assert!(subcommand_needs_db(Some("user"), Some("set-password")));
+        assert!(subcommand_needs_db(Some("user"), Some("reset-password")));
+        assert!(subcommand_needs_db(Some("user"), Some("invite")));
+        assert!(subcommand_needs_db(Some("user"), Some("unlock")));
+        assert!(subcommand_needs_db(Some("user"), Some("two-factor")));
+        assert!(subcommand_needs_db(Some("user"), Some("update")));
         assert!(subcommand_needs_db(Some("category"), Some("create")));
         assert!(subcommand_needs_db(Some("product"), Some("list")));
         assert!(subcommand_needs_db(Some("product"), Some("variation-add")));
+        assert!(subcommand_needs_db(Some("product"), Some("restore")));
+        assert!(subcommand_needs_db(
+            Some("product"),
+            Some("variation-restore")
+        ));
         assert!(subcommand_needs_db(Some("purchase"), Some("create")));
         assert!(subcommand_needs_db(Some("server"), Some("start")));
         assert!(subcommand_needs_db(Some("database"), Some("backup")));
+        assert!(subcommand_needs_db(Some("database"), Some("purge")));
+        assert!(subcommand_needs_db(Some("report"), Some("spending")));
+        assert!(subcommand_needs_db(Some("audit"), Some("list")));
+        assert!(subcommand_needs_db(Some("household"), Some("create")));
+        assert!(subcommand_needs_db(Some("household"), Some("add-user")));
     }
 
     #[test]
@@ -1,4 +1,5 @@
-//! Product subcommands (create, list, show, update, delete).
+//! Product subcommands (create, list, show, update, delete, restore, variation-add,
+//! variation-restore).
 
 use std::collections::HashMap;
 use std::io::Write;
@@ -284,19 +285,35 @@ pub async fn delete(
         db::product::hard_delete(pool, id).await?;
         writeln!(stdout, "Product removed: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     } else {
-        db::product::soft_delete(pool, id).await?;
+        db::product::soft_delete(pool, id, None).await?;
         writeln!(stdout, "Product deleted: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     }
     Ok(())
 }
 
-/// Add a variation to an existing product.
+/// Restore a soft-deleted product by id.
+pub async fn restore(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid product id: {id_str}")))?;
+    db::product::restore(pool, id).await?;
+    writeln!(stdout, "Product restored: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Add a variation to an existing product, optionally with a GTIN barcode.
+#[allow(clippy::too_many_arguments)]
 pub async fn variation_add(
     pool: &SqlitePool,
     product_id_str: &str,
     label: &str,
     unit: &str,
     quantity: Option<u32>,
+    barcode: Option<&str>,
     stdout: &mut impl Write,
     _stderr: &mut impl Write,
 ) -> Result<(), CliError> {
@@ -313,7 +330,16 @@ pub async fn variation_add(
     let var_id = Uuid::new_v4();
     let variation =
         ProductVariation::new(var_id, product_id, label, unit, quantity, now, now, None)
+            .and_then(|v| v.with_barcode(barcode))
             .map_err(|e| CliError::Validation(e.to_string()))?;
+    if let Some(code) = variation.barcode()
+        && let Some(other) = db::product_variation::get_by_barcode(pool, code).await?
+    {
+        return Err(CliError::Validation(format!(
+            "barcode {code} already used by variation {}",
+            other.id()
+        )));
+    }
     db::product_variation::insert(pool, &variation).await?;
 
     writeln!(
@@ -323,3 +349,17 @@ pub async fn variation_add(
     .map_err(|e| CliError::Other(e.into()))?;
     Ok(())
 }
+
+/// Restore a soft-deleted variation by id.
+pub async fn variation_restore(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid variation id: {id_str}")))?;
+    db::product_variation::restore(pool, id).await?;
+    writeln!(stdout, "Variation restored: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
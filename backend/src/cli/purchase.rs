@@ -1,4 +1,4 @@
-//! Purchase subcommands (create, list, show, delete).
+//! Purchase subcommands (create, list, show, delete, restore).
 
 use std::io::Write;
 
@@ -51,7 +51,7 @@ async fn resolve_user_id(
 }
 
 /// Parse ISO 8601 or YYYY-MM-DD date string into UNIX timestamp.
-fn parse_date(s: &str) -> Result<i64, CliError> {
+pub fn parse_date(s: &str) -> Result<i64, CliError> {
     let ts = DateTime::parse_from_rfc3339(s)
         .map(|dt| dt.timestamp())
         .or_else(|_| {
@@ -286,8 +286,22 @@ pub async fn delete(
         db::purchase::hard_delete(pool, id).await?;
         writeln!(stdout, "Purchase removed: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     } else {
-        db::purchase::soft_delete(pool, id).await?;
+        db::purchase::soft_delete(pool, id, None).await?;
         writeln!(stdout, "Purchase deleted: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     }
     Ok(())
 }
+
+/// Restore a soft-deleted purchase by id.
+pub async fn restore(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid purchase id: {id_str}")))?;
+    db::purchase::restore(pool, id).await?;
+    writeln!(stdout, "Purchase restored: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Report subcommands (spending).
+
+use std::io::Write;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::cli::CliError;
+use crate::cli::purchase::parse_date;
+use crate::db;
+use crate::db::stats::{SpendingFilter, SpendingGroup};
+
+fn parse_uuid(value: Option<&str>, name: &str) -> Result<Option<Uuid>, CliError> {
+    value
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| CliError::Validation(format!("invalid {name}")))
+}
+
+/// Print total spending grouped by month, week, category, location or user.
+#[allow(clippy::too_many_arguments)]
+pub async fn spending(
+    pool: &SqlitePool,
+    group_by: &str,
+    category_id: Option<&str>,
+    location_id: Option<&str>,
+    user_id: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let group = SpendingGroup::from_param(group_by)
+        .ok_or_else(|| CliError::Validation(format!("invalid group-by: {group_by}")))?;
+    let filter = SpendingFilter {
+        user_id: parse_uuid(user_id, "user_id")?,
+        location_id: parse_uuid(location_id, "location_id")?,
+        category_id: parse_uuid(category_id, "category_id")?,
+        from_ts: from.map(parse_date).transpose()?,
+        to_ts: to.map(parse_date).transpose()?,
+    };
+    if let Some(cat_id) = filter.category_id
+        && db::category::get_by_id(pool, cat_id, false)
+            .await?
+            .is_none()
+    {
+        return Err(CliError::Validation(format!(
+            "category not found: {cat_id}"
+        )));
+    }
+
+    let report = db::stats::spending(pool, &filter, group).await?;
+
+    if output_json {
+        let groups: Vec<serde_json::Value> = report
+            .groups
+            .iter()
+            .map(|g| {
+                serde_json::json!({
+                    "key": g.key,
+                    "label": g.label,
+                    "total": g.total.to_string(),
+                    "purchase_count": g.purchase_count,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "group_by": group.name(),
+            "from": filter.from_ts,
+            "to": filter.to_ts,
+            "total": report.total.to_string(),
+            "purchase_count": report.purchase_count,
+            "groups": groups,
+        });
+        writeln!(stdout, "{out}").map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        let width = report
+            .groups
+            .iter()
+            .map(|g| g.label.chars().count())
+            .max()
+            .unwrap_or(0);
+        for g in &report.groups {
+            writeln!(
+                stdout,
+                "{:<width$}  {:>10}  ({} purchases)",
+                g.label, g.total, g.purchase_count
+            )
+            .map_err(|e| CliError::Other(e.into()))?;
+        }
+        writeln!(
+            stdout,
+            "Total: {} EUR ({} purchases)",
+            report.total, report.purchase_count
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-//! Review subcommands (create, list, show, update, delete).
+//! Review subcommands (create, list, show, update, delete, restore).
 
 use std::io::Write;
 
@@ -272,8 +272,22 @@ pub async fn delete(
         db::review::hard_delete(pool, id).await?;
         writeln!(stdout, "Review removed: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     } else {
-        db::review::soft_delete(pool, id).await?;
+        db::review::soft_delete(pool, id, None).await?;
         writeln!(stdout, "Review deleted: {id_str}").map_err(|e| CliError::Other(e.into()))?;
     }
     Ok(())
 }
+
+/// Restore a soft-deleted review by id.
+pub async fn restore(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid review id: {id_str}")))?;
+    db::review::restore(pool, id).await?;
+    writeln!(stdout, "Review restored: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
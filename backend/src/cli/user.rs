@@ -1,4 +1,5 @@
-//! User subcommands (e.g. register, list, delete).
+//! User subcommands (e.g. register, list, update, delete, set-password, reset-password, invite,
+//! unlock, two-factor, sessions, token).
 
 use std::io::Write;
 
@@ -6,10 +7,23 @@ use chrono::Utc;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::auth::password;
+use crate::auth::token;
+use crate::auth::{lockout, password, totp};
 use crate::cli::CliError;
 use crate::db;
-use crate::domain::user::{User, ValidationError};
+use crate::db::household::current_household;
+use crate::domain::api_token::{API_TOKEN_PREFIX, ApiToken, TokenScope};
+use crate::domain::invite::Invite;
+use crate::domain::user::{Role, User, ValidationError};
+
+/// Resolve an active user given by UUID or by email.
+pub async fn resolve(pool: &SqlitePool, id_or_email: &str) -> Result<User, CliError> {
+    let found = match Uuid::parse_str(id_or_email) {
+        Ok(id) => db::user::get_by_id(pool, id, false).await?,
+        Err(_) => db::user::get_by_email(pool, id_or_email).await?,
+    };
+    found.ok_or_else(|| CliError::Validation(format!("user not found: {id_or_email}")))
+}
 
 /// Delete a user by id (soft-delete or hard delete with `force`). Writes a success message to stdout.
 pub async fn delete(
@@ -49,6 +63,7 @@ pub async fn list(
                     "id": u.id().to_string(),
                     "email": u.email(),
                     "name": u.name(),
+                    "role": u.role().to_string(),
                 })
             })
             .collect();
@@ -60,20 +75,30 @@ pub async fn list(
         .map_err(|e| CliError::Other(e.into()))?;
     } else {
         for u in &users {
-            writeln!(stdout, "{}  {}  {}", u.id(), u.email(), u.name())
-                .map_err(|e| CliError::Other(e.into()))?;
+            writeln!(
+                stdout,
+                "{}  {}  {}  {}",
+                u.id(),
+                u.email(),
+                u.name(),
+                u.role()
+            )
+            .map_err(|e| CliError::Other(e.into()))?;
         }
     }
 
     Ok(())
 }
 
-/// Register a new user: check email uniqueness, hash password, insert, write result to stdout.
+/// Register a new user: check email uniqueness, hash password, insert, add the user to the
+/// current household, write result to stdout.
+#[allow(clippy::too_many_arguments)]
 pub async fn register(
     pool: &SqlitePool,
     name: &str,
     email: &str,
     plain_password: &str,
+    role: &str,
     output_json: bool,
     stdout: &mut impl Write,
     _stderr: &mut impl Write,
@@ -81,6 +106,9 @@ pub async fn register(
     if db::user::get_by_email(pool, email).await?.is_some() {
         return Err(CliError::EmailAlreadyRegistered);
     }
+    let role: Role = role
+        .parse()
+        .map_err(|e: ValidationError| CliError::Validation(e.to_string()))?;
 
     let hash =
         password::hash_password(plain_password).map_err(|e| CliError::Validation(e.to_string()))?;
@@ -103,10 +131,13 @@ pub async fn register(
             CliError::Validation("password hash must not be empty".to_string())
         }
         ValidationError::CreatedAfterUpdated { .. }
-        | ValidationError::CreatedAfterDeleted { .. } => CliError::Validation(e.to_string()),
-    })?;
+        | ValidationError::CreatedAfterDeleted { .. }
+        | ValidationError::RoleInvalid(_) => CliError::Validation(e.to_string()),
+    })?
+    .with_role(role);
 
     db::user::insert(pool, &user).await?;
+    db::household::add_member(pool, db::household::current_household(), user.id()).await?;
 
     if output_json {
         let out = serde_json::json!({
@@ -121,3 +152,539 @@ pub async fn register(
 
     Ok(())
 }
+
+/// Update a user's name, email and/or role.
+pub async fn update(
+    pool: &SqlitePool,
+    user: &str,
+    name: Option<&str>,
+    email: Option<&str>,
+    role: Option<&str>,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let existing = resolve(pool, user).await?;
+    if let Some(email) = email
+        && email != existing.email()
+        && db::user::get_by_email(pool, email).await?.is_some()
+    {
+        return Err(CliError::EmailAlreadyRegistered);
+    }
+    let role = match role {
+        Some(role) => role
+            .parse()
+            .map_err(|e: ValidationError| CliError::Validation(e.to_string()))?,
+        None => existing.role(),
+    };
+    let updated = User::new(
+        existing.id(),
+        name.unwrap_or_else(|| existing.name()).to_string(),
+        email.unwrap_or_else(|| existing.email()).to_string(),
+        existing.password().to_string(),
+        existing.created_at(),
+        Utc::now().timestamp(),
+        None,
+    )
+    .map_err(|e| CliError::Validation(e.to_string()))?
+    .with_role(role);
+    db::user::update(pool, &updated).await?;
+    writeln!(
+        stdout,
+        "User updated: {} ({}, {})",
+        updated.id(),
+        updated.email(),
+        updated.role()
+    )
+    .map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Set a user's password, then log out all their sessions, revoke their API tokens and invalidate
+/// pending reset tokens.
+pub async fn set_password(
+    pool: &SqlitePool,
+    user: &str,
+    plain_password: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    if plain_password.is_empty() {
+        return Err(CliError::Validation(
+            "password must not be empty".to_string(),
+        ));
+    }
+    let user = resolve(pool, user).await?;
+    let hash =
+        password::hash_password(plain_password).map_err(|e| CliError::Validation(e.to_string()))?;
+    db::user::set_password(pool, user.id(), &hash).await?;
+    let revoked = db::session::revoke_all_for_user(pool, user.id(), None).await?;
+    let tokens_revoked = db::api_token::revoke_all_for_user(pool, user.id()).await?;
+    db::password_reset::invalidate_for_user(pool, user.id()).await?;
+    writeln!(
+        stdout,
+        "Password set for {} (sessions revoked: {revoked}, API tokens revoked: {tokens_revoked})",
+        user.email()
+    )
+    .map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Issue a one-time password reset token for a user, valid for `expires_in`, and print it. Earlier
+/// unused reset tokens of the user stop working.
+pub async fn reset_password(
+    pool: &SqlitePool,
+    user: &str,
+    expires_in: &str,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let user = resolve(pool, user).await?;
+    let secs = crate::cli::database::parse_age(expires_in)
+        .filter(|secs| *secs > 0)
+        .ok_or_else(|| {
+            CliError::Validation(format!(
+                "invalid --expires-in value: {expires_in} (use e.g. 12h, 1d, 1w)"
+            ))
+        })?;
+    let now = Utc::now().timestamp();
+    let expires_at = now.saturating_add(secs);
+    db::password_reset::invalidate_for_user(pool, user.id()).await?;
+    let secret = token::generate();
+    db::password_reset::insert(pool, user.id(), &token::hash(&secret), now, expires_at).await?;
+
+    if output_json {
+        let out = serde_json::json!({
+            "user_id": user.id().to_string(),
+            "expires_at": expires_at,
+            "token": secret,
+        });
+        writeln!(stdout, "{out}").map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        writeln!(
+            stdout,
+            "Password reset token for {} (valid until {}):",
+            user.email(),
+            format_timestamp(expires_at)
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "{secret}").map_err(|e| CliError::Other(e.into()))?;
+        writeln!(
+            stdout,
+            "Redeem it once with POST /api/v1/auth/password-reset."
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    }
+
+    Ok(())
+}
+
+/// Issue a single-use invite token for `email`, valid for `expires_in` (e.g. `7d`). The new user
+/// gets `role` and joins the current household. Supersedes earlier unused invites for the email.
+pub async fn invite(
+    pool: &SqlitePool,
+    email: &str,
+    role: &str,
+    expires_in: &str,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    if db::user::get_by_email(pool, email).await?.is_some() {
+        return Err(CliError::EmailAlreadyRegistered);
+    }
+    let role: Role = role
+        .parse()
+        .map_err(|e: ValidationError| CliError::Validation(e.to_string()))?;
+    let secs = crate::cli::database::parse_age(expires_in)
+        .filter(|secs| *secs > 0)
+        .ok_or_else(|| {
+            CliError::Validation(format!(
+                "invalid --expires-in value: {expires_in} (use e.g. 12h, 1d, 1w)"
+            ))
+        })?;
+    let now = Utc::now().timestamp();
+    let invite = Invite::new(
+        Uuid::new_v4(),
+        email.to_string(),
+        role,
+        current_household(),
+        now,
+        now.saturating_add(secs),
+        None,
+    )
+    .map_err(|e| CliError::Validation(e.to_string()))?;
+    db::invite::invalidate_for_email(pool, email).await?;
+    let secret = token::generate();
+    db::invite::insert(pool, &invite, &token::hash(&secret)).await?;
+
+    if output_json {
+        let out = serde_json::json!({
+            "id": invite.id().to_string(),
+            "email": invite.email(),
+            "role": invite.role().to_string(),
+            "expires_at": invite.expires_at(),
+            "token": secret,
+        });
+        writeln!(stdout, "{out}").map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        writeln!(
+            stdout,
+            "Invite for {} as {} (valid until {}):",
+            invite.email(),
+            invite.role(),
+            format_timestamp(invite.expires_at())
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "{secret}").map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "Redeem it once with POST /api/v1/auth/register.")
+            .map_err(|e| CliError::Other(e.into()))?;
+    }
+
+    Ok(())
+}
+
+/// Enable TOTP two-factor authentication for a user and print the secret, its `otpauth://` URI,
+/// and the recovery codes. They are shown only once.
+pub async fn two_factor_enable(
+    pool: &SqlitePool,
+    user: &str,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let user = resolve(pool, user).await?;
+    if db::totp::get_secret(pool, user.id()).await?.is_some() {
+        return Err(CliError::Validation(format!(
+            "two-factor authentication is already enabled for {}",
+            user.email()
+        )));
+    }
+    let secret = totp::generate_secret();
+    let recovery_codes = totp::generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes.iter().map(|c| token::hash(c)).collect();
+    db::totp::enable(pool, user.id(), &secret, &hashes, Utc::now().timestamp()).await?;
+    let uri = totp::provisioning_uri("Pocket Ratings", user.email(), &secret);
+
+    if output_json {
+        let out = serde_json::json!({
+            "user_id": user.id().to_string(),
+            "secret": secret,
+            "uri": uri,
+            "recovery_codes": recovery_codes,
+        });
+        writeln!(stdout, "{out}").map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        writeln!(
+            stdout,
+            "Two-factor authentication enabled for {}.",
+            user.email()
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "Secret: {secret}").map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "URI: {uri}").map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "Recovery codes (each works once):")
+            .map_err(|e| CliError::Other(e.into()))?;
+        for code in &recovery_codes {
+            writeln!(stdout, "  {code}").map_err(|e| CliError::Other(e.into()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Disable two-factor authentication for a user, removing the secret, the recovery codes and any
+/// pending login challenges.
+pub async fn two_factor_disable(
+    pool: &SqlitePool,
+    user: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let user = resolve(pool, user).await?;
+    if !db::totp::disable(pool, user.id()).await? {
+        return Err(CliError::Validation(format!(
+            "two-factor authentication is not enabled for {}",
+            user.email()
+        )));
+    }
+    writeln!(
+        stdout,
+        "Two-factor authentication disabled for {}.",
+        user.email()
+    )
+    .map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Clear failed login attempts (and any lockout) of a user's email and/or a client IP.
+pub async fn unlock(
+    pool: &SqlitePool,
+    user: Option<&str>,
+    ip: Option<std::net::IpAddr>,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    if let Some(user) = user {
+        let user = resolve(pool, user).await?;
+        db::login_attempt::clear(pool, &lockout::email_key(user.email())).await?;
+        writeln!(stdout, "Login unlocked for {}", user.email())
+            .map_err(|e| CliError::Other(e.into()))?;
+    }
+    if let Some(ip) = ip {
+        db::login_attempt::clear(pool, &lockout::ip_key(ip)).await?;
+        writeln!(stdout, "Login unlocked for IP {ip}").map_err(|e| CliError::Other(e.into()))?;
+    }
+    Ok(())
+}
+
+/// List a user's login sessions, newest first (optionally including revoked and expired ones).
+pub async fn sessions_list(
+    pool: &SqlitePool,
+    user: &str,
+    include_inactive: bool,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let user = resolve(pool, user).await?;
+    let now = Utc::now().timestamp();
+    let sessions = db::session::list_for_user(pool, user.id(), include_inactive, now).await?;
+
+    if output_json {
+        let items: Vec<serde_json::Value> = sessions
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "id": s.id().to_string(),
+                    "household_id": s.household_id().to_string(),
+                    "created_at": s.created_at(),
+                    "last_used_at": s.last_used_at(),
+                    "expires_at": s.expires_at(),
+                    "revoked_at": s.revoked_at(),
+                    "active": s.is_active(now),
+                })
+            })
+            .collect();
+        writeln!(
+            stdout,
+            "{}",
+            serde_json::to_string(&items).map_err(|e| CliError::Other(e.into()))?
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        for s in &sessions {
+            let status = if s.revoked_at().is_some() {
+                "revoked"
+            } else if s.is_active(now) {
+                "active"
+            } else {
+                "expired"
+            };
+            writeln!(
+                stdout,
+                "{}  {}  last used {}  {}",
+                s.id(),
+                format_timestamp(s.created_at()),
+                format_timestamp(s.last_used_at()),
+                status
+            )
+            .map_err(|e| CliError::Other(e.into()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Revoke one session by id, or all sessions of `user`.
+pub async fn sessions_revoke(
+    pool: &SqlitePool,
+    id_str: Option<&str>,
+    user: Option<&str>,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    if let Some(user) = user {
+        let user = resolve(pool, user).await?;
+        let count = db::session::revoke_all_for_user(pool, user.id(), None).await?;
+        writeln!(stdout, "Sessions revoked for {}: {count}", user.email())
+            .map_err(|e| CliError::Other(e.into()))?;
+        return Ok(());
+    }
+    let id_str = id_str
+        .ok_or_else(|| CliError::Validation("session id or --user is required".to_string()))?;
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid session id: {id_str}")))?;
+    if !db::session::revoke(pool, id).await? {
+        return Err(CliError::Validation(format!(
+            "no active session with id: {id_str}"
+        )));
+    }
+    writeln!(stdout, "Session revoked: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Create a personal access token for `user` in the current household and print it once.
+#[allow(clippy::too_many_arguments)]
+pub async fn token_create(
+    pool: &SqlitePool,
+    user: &str,
+    name: &str,
+    scope: &str,
+    expires_in: Option<&str>,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let user = resolve(pool, user).await?;
+    let household_id = current_household();
+    if !db::household::is_member(pool, household_id, user.id()).await? {
+        return Err(CliError::Validation(format!(
+            "user {} is not a member of the household",
+            user.email()
+        )));
+    }
+    let scope: TokenScope = scope
+        .parse()
+        .map_err(|_| CliError::Validation(format!("invalid scope: {scope}")))?;
+    let now = Utc::now().timestamp();
+    let expires_at = match expires_in {
+        Some(age) => {
+            let secs = crate::cli::database::parse_age(age)
+                .filter(|secs| *secs > 0)
+                .ok_or_else(|| {
+                    CliError::Validation(format!(
+                        "invalid --expires-in value: {age} (use e.g. 12h, 30d, 52w)"
+                    ))
+                })?;
+            Some(now.saturating_add(secs))
+        }
+        None => None,
+    };
+    let api_token = ApiToken::new(
+        Uuid::new_v4(),
+        user.id(),
+        household_id,
+        name.trim().to_string(),
+        scope,
+        now,
+        expires_at,
+        None,
+        None,
+    )
+    .map_err(|e| CliError::Validation(e.to_string()))?;
+    let secret = format!("{API_TOKEN_PREFIX}{}", token::generate());
+    db::api_token::insert(pool, &api_token, &token::hash(&secret)).await?;
+
+    if output_json {
+        let out = serde_json::json!({
+            "id": api_token.id().to_string(),
+            "name": api_token.name(),
+            "scope": api_token.scope().to_string(),
+            "household_id": household_id.to_string(),
+            "expires_at": api_token.expires_at(),
+            "token": secret,
+        });
+        writeln!(stdout, "{out}").map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        writeln!(
+            stdout,
+            "Token created: {} ({}, {})",
+            api_token.id(),
+            api_token.name(),
+            api_token.scope()
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "{secret}").map_err(|e| CliError::Other(e.into()))?;
+        writeln!(stdout, "Store it now; it cannot be shown again.")
+            .map_err(|e| CliError::Other(e.into()))?;
+    }
+
+    Ok(())
+}
+
+/// List a user's personal access tokens, newest first.
+pub async fn token_list(
+    pool: &SqlitePool,
+    user: &str,
+    output_json: bool,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let user = resolve(pool, user).await?;
+    let now = Utc::now().timestamp();
+    let tokens = db::api_token::list_for_user(pool, user.id()).await?;
+
+    if output_json {
+        let items: Vec<serde_json::Value> = tokens
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "id": t.id().to_string(),
+                    "name": t.name(),
+                    "scope": t.scope().to_string(),
+                    "household_id": t.household_id().to_string(),
+                    "created_at": t.created_at(),
+                    "expires_at": t.expires_at(),
+                    "last_used_at": t.last_used_at(),
+                    "revoked_at": t.revoked_at(),
+                    "active": t.is_active(now),
+                })
+            })
+            .collect();
+        writeln!(
+            stdout,
+            "{}",
+            serde_json::to_string(&items).map_err(|e| CliError::Other(e.into()))?
+        )
+        .map_err(|e| CliError::Other(e.into()))?;
+    } else {
+        for t in &tokens {
+            let status = if t.revoked_at().is_some() {
+                "revoked"
+            } else if t.is_active(now) {
+                "active"
+            } else {
+                "expired"
+            };
+            let last_used = t
+                .last_used_at()
+                .map_or_else(|| "never".to_string(), format_timestamp);
+            writeln!(
+                stdout,
+                "{}  {}  {}  last used {}  {}",
+                t.id(),
+                t.name(),
+                t.scope(),
+                last_used,
+                status
+            )
+            .map_err(|e| CliError::Other(e.into()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Revoke a personal access token by id.
+pub async fn token_revoke(
+    pool: &SqlitePool,
+    id_str: &str,
+    stdout: &mut impl Write,
+    _stderr: &mut impl Write,
+) -> Result<(), CliError> {
+    let id = Uuid::parse_str(id_str)
+        .map_err(|_| CliError::Validation(format!("invalid token id: {id_str}")))?;
+    if !db::api_token::revoke(pool, id).await? {
+        return Err(CliError::Validation(format!(
+            "no active token with id: {id_str}"
+        )));
+    }
+    writeln!(stdout, "Token revoked: {id_str}").map_err(|e| CliError::Other(e.into()))?;
+    Ok(())
+}
+
+/// Format a UNIX timestamp as RFC 3339 (UTC).
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0).map_or_else(|| ts.to_string(), |dt| dt.to_rfc3339())
+}
@@ -1,12 +1,24 @@
 //! Application configuration loaded from environment variables.
 
 use std::env;
+use std::net::IpAddr;
 
-/// Default JWT expiration: 30 days in seconds.
-const DEFAULT_JWT_EXPIRATION_SECONDS: u64 = 30 * 24 * 3600;
+use crate::auth::jwt_keys::{JwtKeys, KeyError};
 
-/// Default refresh threshold: if token expires within this many seconds, issue a new one (7 days).
-const DEFAULT_JWT_REFRESH_THRESHOLD_SECONDS: u64 = 7 * 24 * 3600;
+/// Default JWT (access token) expiration: 15 minutes in seconds.
+const DEFAULT_JWT_EXPIRATION_SECONDS: u64 = 15 * 60;
+
+/// Default refresh threshold: if token expires within this many seconds, issue a new one (5 minutes).
+const DEFAULT_JWT_REFRESH_THRESHOLD_SECONDS: u64 = 5 * 60;
+
+/// Default refresh token (session) expiration: 30 days in seconds.
+const DEFAULT_REFRESH_TOKEN_EXPIRATION_SECONDS: u64 = 30 * 24 * 3600;
+
+/// Default time a response stored under an `Idempotency-Key` is replayed: 24 hours in seconds.
+const DEFAULT_IDEMPOTENCY_WINDOW_SECONDS: u64 = 24 * 3600;
+
+/// Reverse proxies trusted to report the client IP in `X-Forwarded-For` when unset: loopback only.
+const DEFAULT_TRUSTED_PROXIES: &str = "127.0.0.0/8,::1/128";
 
 /// Application configuration.
 #[derive(Debug, Clone)]
@@ -14,18 +26,36 @@ pub struct Config {
     /// Path to the `SQLite` database file.
     pub database_path: String,
 
-    /// Secret used to sign and verify JWT tokens.
-    pub jwt_secret: String,
+    /// Keys used to sign and verify JWT tokens.
+    pub jwt_keys: JwtKeys,
 
-    /// Token expiration in seconds (default: 30 days).
+    /// Access token expiration in seconds (default: 15 minutes).
     pub jwt_expiration_seconds: u64,
 
-    /// If token expires within this many seconds, issue a new one in X-New-Token (default: 7 days).
+    /// If token expires within this many seconds, issue a new one in X-New-Token (default: 5 minutes).
     pub jwt_refresh_threshold_seconds: u64,
 
+    /// Refresh token (session) expiration in seconds (default: 30 days).
+    pub refresh_token_expiration_seconds: u64,
+
+    /// How long the response of a `POST` sent with an `Idempotency-Key` header is kept and
+    /// replayed for retries with the same key, in seconds (default: 24 hours).
+    pub idempotency_window_seconds: u64,
+
+    /// Networks of reverse proxies whose `X-Forwarded-For` header names the client IP (default:
+    /// loopback). Requests from other peers are attributed to the peer address.
+    pub trusted_proxies: Vec<IpNet>,
+
     /// Address the API server binds to (e.g. `127.0.0.1:3099`).
     pub bind: String,
 
+    /// Separate address for `GET /metrics` (e.g. `0.0.0.0:9099`). When `None`, `/metrics` is
+    /// served on [`Self::bind`] with the API.
+    pub metrics_bind: Option<String>,
+
+    /// Log output format (default: human-readable text).
+    pub log_format: LogFormat,
+
     /// Path to the PID file for daemon mode (e.g. `/tmp/pocketratings.pid` on Unix).
     pub pid_file: String,
 }
@@ -34,20 +64,44 @@ impl Config {
     /// Load configuration from environment variables.
     ///
     /// - `DB_PATH` — database path (default: `./pocketratings.db`)
-    /// - `JWT_SECRET` — JWT signing secret (**required**)
-    /// - `JWT_EXPIRATION_SECONDS` — token expiration in seconds (default: 30 days)
-    /// - `JWT_REFRESH_THRESHOLD_SECONDS` — issue new token if exp within this (default: 7 days)
+    /// - `JWT_SECRET` — JWT signing secret (**required** unless `JWT_KEYS` is set); key ID `default`
+    /// - `JWT_KEYS` — comma-separated JWT keys `<kid>=hs256:<secret>`, `<kid>=eddsa:<public.pem>`
+    ///   or `<kid>=rs256:<public.pem>`; the first one signs unless `JWT_SIGNING_KEY` names another
+    /// - `JWT_SIGNING_KEY` — key ID of the key that signs new tokens
+    /// - `JWT_PRIVATE_KEY_FILE` — PEM private key of an `eddsa` or `rs256` signing key
+    /// - `JWT_RETIRED_KEYS` — comma-separated key IDs whose tokens are no longer accepted
+    /// - `JWT_EXPIRATION_SECONDS` — access token expiration in seconds (default: 15 minutes)
+    /// - `JWT_REFRESH_THRESHOLD_SECONDS` — issue new token if exp within this (default: 5 minutes)
+    /// - `REFRESH_TOKEN_EXPIRATION_SECONDS` — refresh token (session) expiration (default: 30 days)
+    /// - `IDEMPOTENCY_WINDOW_SECONDS` — how long idempotency keys are remembered (default: 24 hours)
+    /// - `TRUSTED_PROXIES` — comma-separated addresses or CIDR networks of reverse proxies whose
+    ///   `X-Forwarded-For` is trusted (default: loopback)
     /// - `BIND` — server bind address (default: `127.0.0.1:3099`)
+    /// - `METRICS_BIND` — serve `GET /metrics` on this address instead of `BIND` (default: unset)
+    /// - `LOG_FORMAT` — `text` or `json` log lines (default: `text`)
     /// - `PID_FILE` — path to PID file for daemon mode (default: temp dir + `pocketratings.pid`)
     ///
     /// # Errors
     ///
-    /// Returns [`ConfigError::Missing`] if a required variable is not set.
+    /// Returns [`ConfigError::Missing`] if a required variable is not set,
+    /// [`ConfigError::JwtKeys`] if the JWT key configuration is invalid, and
+    /// [`ConfigError::TrustedProxies`] if `TRUSTED_PROXIES` has an invalid entry.
     pub fn from_env() -> Result<Self, ConfigError> {
         let database_path =
             env::var("DB_PATH").unwrap_or_else(|_| String::from("./pocketratings.db"));
 
-        let jwt_secret = env::var("JWT_SECRET").map_err(|_| ConfigError::Missing("JWT_SECRET"))?;
+        let jwt_secret = env::var("JWT_SECRET").ok();
+        let jwt_key_list = env::var("JWT_KEYS").ok();
+        if jwt_secret.is_none() && jwt_key_list.is_none() {
+            return Err(ConfigError::Missing("JWT_SECRET"));
+        }
+        let jwt_keys = JwtKeys::load(
+            jwt_secret.as_deref(),
+            jwt_key_list.as_deref(),
+            env::var("JWT_SIGNING_KEY").ok().as_deref(),
+            env::var("JWT_PRIVATE_KEY_FILE").ok().as_deref(),
+            env::var("JWT_RETIRED_KEYS").ok().as_deref(),
+        )?;
 
         let bind = env::var("BIND").unwrap_or_else(|_| String::from("127.0.0.1:3099"));
 
@@ -61,6 +115,26 @@ impl Config {
             .and_then(|s| s.parse().ok())
             .unwrap_or(DEFAULT_JWT_REFRESH_THRESHOLD_SECONDS);
 
+        let refresh_token_expiration_seconds = env::var("REFRESH_TOKEN_EXPIRATION_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_TOKEN_EXPIRATION_SECONDS);
+
+        let idempotency_window_seconds = env::var("IDEMPOTENCY_WINDOW_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_IDEMPOTENCY_WINDOW_SECONDS);
+
+        let trusted_proxies = parse_trusted_proxies(
+            &env::var("TRUSTED_PROXIES").unwrap_or_else(|_| DEFAULT_TRUSTED_PROXIES.to_string()),
+        )?;
+
+        let metrics_bind = env::var("METRICS_BIND")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+
+        let log_format = LogFormat::from_env();
+
         let pid_file = env::var("PID_FILE").unwrap_or_else(|_| {
             env::temp_dir()
                 .join("pocketratings.pid")
@@ -70,19 +144,112 @@ impl Config {
 
         Ok(Self {
             database_path,
-            jwt_secret,
+            jwt_keys,
             jwt_expiration_seconds,
             jwt_refresh_threshold_seconds,
+            refresh_token_expiration_seconds,
+            idempotency_window_seconds,
+            trusted_proxies,
             bind,
+            metrics_bind,
+            log_format,
             pid_file,
         })
     }
 }
 
+/// Parse a comma-separated list of addresses and CIDR networks; blank entries are skipped.
+fn parse_trusted_proxies(value: &str) -> Result<Vec<IpNet>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .parse()
+                .map_err(|()| ConfigError::TrustedProxies(entry.to_string()))
+        })
+        .collect()
+}
+
+/// An IP network in CIDR notation (e.g. `172.16.0.0/12`); a bare address is a single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// Whether `ip` is in this network. IPv4-mapped IPv6 addresses match IPv4 networks.
+    #[must_use]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for IpNet {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.split_once('/').map_or((s, None), |(a, p)| (a, Some(p)));
+        let addr: IpAddr = addr.parse().map_err(|_| ())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.parse().ok().filter(|p| *p <= max).ok_or(())?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+/// Format of log lines written to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the enclosing spans (e.g. the request id).
+    Json,
+}
+
+impl LogFormat {
+    /// Read `LOG_FORMAT` (`text` or `json`, case-insensitive); anything else means text.
+    #[must_use]
+    pub fn from_env() -> Self {
+        match env::var("LOG_FORMAT") {
+            Ok(value) if value.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+}
+
 /// Errors that can occur when loading configuration.
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     /// A required environment variable is not set.
     #[error("missing required environment variable: {0}")]
     Missing(&'static str),
+
+    /// The JWT key configuration is invalid.
+    #[error("invalid JWT key configuration: {0}")]
+    JwtKeys(#[from] KeyError),
+
+    /// A `TRUSTED_PROXIES` entry is not an IP address or CIDR network.
+    #[error("invalid TRUSTED_PROXIES entry: {0}")]
+    TrustedProxies(String),
 }
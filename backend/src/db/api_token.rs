@@ -0,0 +1,174 @@
+//! Personal access token persistence.
+//!
+//! Provides DB functions: [`insert`], [`get_by_id`], [`get_active_by_hash`], [`touch`],
+//! [`revoke`], [`revoke_all_for_user`], and [`list_for_user`]. Tokens are not household scoped;
+//! each one records the household it was created for.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::domain::api_token::{ApiToken, TokenScope};
+
+const TOKEN_COLUMNS: &str =
+    "id, user_id, household_id, name, scope, created_at, expires_at, last_used_at, revoked_at";
+
+/// Map a DB row into an [`ApiToken`]. Fails on invalid UUID, scope or domain validation.
+fn row_to_token(row: &sqlx::sqlite::SqliteRow) -> Result<ApiToken, crate::db::DbError> {
+    let parse = |column: &str| {
+        let value: String = row.get(column);
+        Uuid::parse_str(&value).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+    };
+    let scope: String = row.get("scope");
+    let scope: TokenScope =
+        scope
+            .parse()
+            .map_err(|e: crate::domain::api_token::ValidationError| {
+                crate::db::DbError::InvalidData(e.to_string())
+            })?;
+    ApiToken::new(
+        parse("id")?,
+        parse("user_id")?,
+        parse("household_id")?,
+        row.get("name"),
+        scope,
+        row.get("created_at"),
+        row.get("expires_at"),
+        row.get("last_used_at"),
+        row.get("revoked_at"),
+    )
+    .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+}
+
+/// Insert a token with the hash of its secret.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn insert(
+    pool: &SqlitePool,
+    token: &ApiToken,
+    token_hash: &str,
+) -> Result<(), crate::db::DbError> {
+    sqlx::query(
+        "INSERT INTO api_tokens (id, user_id, household_id, name, scope, token_hash, created_at, expires_at, last_used_at, revoked_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(token.id().to_string())
+    .bind(token.user_id().to_string())
+    .bind(token.household_id().to_string())
+    .bind(token.name())
+    .bind(token.scope().to_string())
+    .bind(token_hash)
+    .bind(token.created_at())
+    .bind(token.expires_at())
+    .bind(token.last_used_at())
+    .bind(token.revoked_at())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Fetch a token by id (including revoked and expired ones).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_by_id(
+    pool: &SqlitePool,
+    id: Uuid,
+) -> Result<Option<ApiToken>, crate::db::DbError> {
+    let sql = format!("SELECT {TOKEN_COLUMNS} FROM api_tokens WHERE id = ?");
+    let row = sqlx::query(&sql)
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_token).transpose()
+}
+
+/// Fetch the token with `token_hash`, if it is still active at `now`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_active_by_hash(
+    pool: &SqlitePool,
+    token_hash: &str,
+    now: i64,
+) -> Result<Option<ApiToken>, crate::db::DbError> {
+    let sql = format!(
+        "SELECT {TOKEN_COLUMNS} FROM api_tokens
+         WHERE token_hash = ? AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > ?)"
+    );
+    let row = sqlx::query(&sql)
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_token).transpose()
+}
+
+/// Record that the token was used at `now`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn touch(pool: &SqlitePool, id: Uuid, now: i64) -> Result<(), crate::db::DbError> {
+    sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Revoke a token. Returns `false` if it does not exist or was already revoked.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn revoke(pool: &SqlitePool, id: Uuid) -> Result<bool, crate::db::DbError> {
+    let result =
+        sqlx::query("UPDATE api_tokens SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke all active tokens of a user. Returns the number of tokens revoked.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn revoke_all_for_user(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<u64, crate::db::DbError> {
+    let result = sqlx::query(
+        "UPDATE api_tokens SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(user_id.to_string())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Fetch a user's tokens, newest first (including revoked and expired ones).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_for_user(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<Vec<ApiToken>, crate::db::DbError> {
+    let sql = format!(
+        "SELECT {TOKEN_COLUMNS} FROM api_tokens WHERE user_id = ? ORDER BY created_at DESC, id"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(user_id.to_string())
+        .fetch_all(pool)
+        .await?;
+    rows.iter().map(row_to_token).collect()
+}
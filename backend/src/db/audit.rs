@@ -0,0 +1,575 @@
+//! Audit log of mutations: who changed which row, and how.
+//!
+//! Every insert, update, soft-delete, restore and hard-delete in the entity modules takes a
+//! [`snapshot`] of the row before the change and calls [`record`] afterwards; `record` takes the
+//! snapshot after the change and stores the changed columns with their before/after values.
+//! User password hashes are never snapshotted.
+//!
+//! Entries belong to the household the change was made in (see [`crate::db::household`]) and
+//! are listed per household. Users belong to several households; their changes are recorded in
+//! each of them with [`record_in`], so every household's versions see them.
+//!
+//! The acting user is task-local: the API auth middleware runs each request inside
+//! [`with_actor`]. Outside such a scope (the CLI, tests) the actor is [`Actor::Cli`].
+//! [`crate::db::trash::purge`] removes rows of all households at once; it records each of them
+//! with [`record_hard_deletes`], in its own transaction.
+
+use std::future::Future;
+
+use serde_json::{Map, Value};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, bind_values};
+
+tokio::task_local! {
+    static ACTOR: Actor;
+}
+
+/// Who made a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Actor {
+    /// An authenticated API user.
+    User(Uuid),
+    /// The command line.
+    Cli,
+}
+
+impl Actor {
+    /// Stored value: the user id, or `cli`.
+    #[must_use]
+    pub fn as_stored(self) -> String {
+        match self {
+            Self::User(id) => id.to_string(),
+            Self::Cli => "cli".to_string(),
+        }
+    }
+
+    fn from_stored(s: &str) -> Result<Self, crate::db::DbError> {
+        if s == "cli" {
+            return Ok(Self::Cli);
+        }
+        Uuid::parse_str(s)
+            .map(Self::User)
+            .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+    }
+}
+
+/// Run `f` with `actor` as the actor of every change it records.
+pub async fn with_actor<F: Future>(actor: Actor, f: F) -> F::Output {
+    ACTOR.scope(actor, f).await
+}
+
+/// Actor of the current task ([`Actor::Cli`] outside [`with_actor`]).
+#[must_use]
+pub fn current_actor() -> Actor {
+    ACTOR.try_with(|a| *a).unwrap_or(Actor::Cli)
+}
+
+/// Audited entity type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEntity {
+    Category,
+    Product,
+    Variation,
+    Location,
+    Review,
+    Purchase,
+    User,
+}
+
+impl AuditEntity {
+    /// Parse an `entity_type` value (`category`, `product`, `variation`, `location`, `review`,
+    /// `purchase`, `user`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "category" => Some(Self::Category),
+            "product" => Some(Self::Product),
+            "variation" => Some(Self::Variation),
+            "location" => Some(Self::Location),
+            "review" => Some(Self::Review),
+            "purchase" => Some(Self::Purchase),
+            "user" => Some(Self::User),
+            _ => None,
+        }
+    }
+
+    /// Stored and query value of this entity type.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Category => "category",
+            Self::Product => "product",
+            Self::Variation => "variation",
+            Self::Location => "location",
+            Self::Review => "review",
+            Self::Purchase => "purchase",
+            Self::User => "user",
+        }
+    }
+
+    /// Table of this entity type.
+    pub(crate) const fn table(self) -> &'static str {
+        match self {
+            Self::Category => "categories",
+            Self::Product => "products",
+            Self::Variation => "product_variations",
+            Self::Location => "locations",
+            Self::Review => "reviews",
+            Self::Purchase => "purchases",
+            Self::User => "users",
+        }
+    }
+}
+
+/// Kind of change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Insert,
+    Update,
+    SoftDelete,
+    Restore,
+    HardDelete,
+}
+
+impl AuditAction {
+    /// Parse a stored action value.
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "insert" => Some(Self::Insert),
+            "update" => Some(Self::Update),
+            "soft_delete" => Some(Self::SoftDelete),
+            "restore" => Some(Self::Restore),
+            "hard_delete" => Some(Self::HardDelete),
+            _ => None,
+        }
+    }
+
+    /// Stored value of this action.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::Update => "update",
+            Self::SoftDelete => "soft_delete",
+            Self::Restore => "restore",
+            Self::HardDelete => "hard_delete",
+        }
+    }
+}
+
+/// Column values of one row, keyed by column name.
+pub type Snapshot = Map<String, Value>;
+
+/// Columns never written to the audit log.
+const REDACTED_COLUMNS: &[&str] = &["password"];
+
+/// Current column values of the row `id` of `entity`, or `None` if it does not exist.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn snapshot(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<Option<Snapshot>, crate::db::DbError> {
+    snapshot_on(&mut *pool.acquire().await?, entity, id).await
+}
+
+/// Like [`snapshot`], on `conn` (e.g. of the transaction that changes the row).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub(crate) async fn snapshot_on(
+    conn: &mut SqliteConnection,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<Option<Snapshot>, crate::db::DbError> {
+    let table = entity.table();
+    let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?;
+    let pairs: Vec<String> = columns
+        .iter()
+        .filter(|c| !REDACTED_COLUMNS.contains(&c.as_str()))
+        .map(|c| format!("'{c}', {c}"))
+        .collect();
+    let sql = format!(
+        "SELECT json_object({}) FROM {table} WHERE id = ?",
+        pairs.join(", ")
+    );
+    let json: Option<String> = sqlx::query_scalar(&sql)
+        .bind(id.to_string())
+        .fetch_optional(&mut *conn)
+        .await?;
+    json.map(|s| match serde_json::from_str(&s) {
+        Ok(Value::Object(map)) => Ok(map),
+        _ => Err(crate::db::DbError::InvalidData(format!(
+            "invalid snapshot of {table}: {id}"
+        ))),
+    })
+    .transpose()
+}
+
+/// Columns whose value differs between `before` and `after`, as
+/// `{"column": {"before": ..., "after": ...}}`. A missing row counts as all columns `null`.
+#[must_use]
+pub fn diff(before: Option<&Snapshot>, after: Option<&Snapshot>) -> Snapshot {
+    let empty = Snapshot::new();
+    let before = before.unwrap_or(&empty);
+    let after = after.unwrap_or(&empty);
+    let mut changes = Snapshot::new();
+    for key in before.keys().chain(after.keys()) {
+        if changes.contains_key(key) {
+            continue;
+        }
+        let b = before.get(key).unwrap_or(&Value::Null);
+        let a = after.get(key).unwrap_or(&Value::Null);
+        if b != a {
+            changes.insert(key.clone(), serde_json::json!({ "before": b, "after": a }));
+        }
+    }
+    changes
+}
+
+/// Record a change of the row `id` of `entity`, made by [`current_actor`], in the current
+/// household.
+///
+/// `before` is the [`snapshot`] taken before the change; the row is snapshotted again now.
+/// Nothing is recorded when no column changed.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn record(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    id: Uuid,
+    action: AuditAction,
+    before: Option<Snapshot>,
+) -> Result<(), crate::db::DbError> {
+    let households = [crate::db::household::current_household()];
+    record_in(pool, &households, entity, id, action, before).await
+}
+
+/// Like [`record`], but with one entry in each of `households`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn record_in(
+    pool: &SqlitePool,
+    households: &[Uuid],
+    entity: AuditEntity,
+    id: Uuid,
+    action: AuditAction,
+    before: Option<Snapshot>,
+) -> Result<(), crate::db::DbError> {
+    let mut conn = pool.acquire().await?;
+    record_on(&mut conn, households, entity, id, action, before).await
+}
+
+/// Like [`record_in`], on `conn`: call it on the connection of the transaction that makes the
+/// change, so the change and its entries are committed together.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub(crate) async fn record_on(
+    conn: &mut SqliteConnection,
+    households: &[Uuid],
+    entity: AuditEntity,
+    id: Uuid,
+    action: AuditAction,
+    before: Option<Snapshot>,
+) -> Result<(), crate::db::DbError> {
+    let after = snapshot_on(&mut *conn, entity, id).await?;
+    let changes = diff(before.as_ref(), after.as_ref());
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let changes = Value::Object(changes).to_string();
+    let now = chrono::Utc::now().timestamp();
+    for household in households {
+        sqlx::query(
+            "INSERT INTO audit_log (actor, entity_type, entity_id, action, changes, created_at, household_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(current_actor().as_stored())
+        .bind(entity.name())
+        .bind(id.to_string())
+        .bind(action.name())
+        .bind(&changes)
+        .bind(now)
+        .bind(household.to_string())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Record a hard-delete, made by [`current_actor`], of each row of `entity` matching `condition`
+/// (an SQL condition on the table with one `?`, bound to `bind`), in the row's own household.
+///
+/// Call it on the connection of the transaction that deletes the rows, just before deleting
+/// them, with the same condition.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or invalid row data.
+pub(crate) async fn record_hard_deletes<T>(
+    conn: &mut SqliteConnection,
+    entity: AuditEntity,
+    condition: &str,
+    bind: T,
+) -> Result<(), crate::db::DbError>
+where
+    T: for<'q> sqlx::Encode<'q, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + 'static,
+{
+    let table = entity.table();
+    let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?;
+    let pairs: Vec<String> = columns
+        .iter()
+        .filter(|c| !REDACTED_COLUMNS.contains(&c.as_str()))
+        .map(|c| format!("'{c}', {c}"))
+        .collect();
+    let sql = format!(
+        "SELECT json_object({}) FROM {table} WHERE {condition}",
+        pairs.join(", ")
+    );
+    let rows: Vec<String> = sqlx::query_scalar(&sql)
+        .bind(bind)
+        .fetch_all(&mut *conn)
+        .await?;
+    let now = chrono::Utc::now().timestamp();
+    for json in rows {
+        let Ok(Value::Object(before)) = serde_json::from_str::<Value>(&json) else {
+            return Err(crate::db::DbError::InvalidData(format!(
+                "invalid snapshot of {table}"
+            )));
+        };
+        let text = |column: &str| {
+            before
+                .get(column)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    crate::db::DbError::InvalidData(format!("{table} row without {column}"))
+                })
+        };
+        let (id, household) = (text("id")?, text("household_id")?);
+        sqlx::query(
+            "INSERT INTO audit_log (actor, entity_type, entity_id, action, changes, created_at, household_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(current_actor().as_stored())
+        .bind(entity.name())
+        .bind(id)
+        .bind(AuditAction::HardDelete.name())
+        .bind(Value::Object(diff(Some(&before), None)).to_string())
+        .bind(now)
+        .bind(household)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Id of the current household's newest entry (0 when there is none). Every recorded change
+/// makes it grow, so it versions all of the household's data at once.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn latest_id(pool: &SqlitePool) -> Result<i64, crate::db::DbError> {
+    let id: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(id) FROM audit_log WHERE household_id = ?")
+            .bind(crate::db::household::current_household().to_string())
+            .fetch_one(pool)
+            .await?;
+    Ok(id.unwrap_or(0))
+}
+
+/// Id of the newest entry of one row in the current household (0 when there is none): a
+/// version of the row that changes with each of its recorded changes.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn entity_version(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<i64, crate::db::DbError> {
+    let version: Option<i64> = sqlx::query_scalar(
+        "SELECT MAX(id) FROM audit_log WHERE entity_id = ? AND entity_type = ? AND household_id = ?",
+    )
+    .bind(id.to_string())
+    .bind(entity.name())
+    .bind(crate::db::household::current_household().to_string())
+    .fetch_one(pool)
+    .await?;
+    Ok(version.unwrap_or(0))
+}
+
+/// One audit log entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: Actor,
+    /// Name of the acting user (`None` for the CLI or a removed user).
+    pub actor_name: Option<String>,
+    pub entity: AuditEntity,
+    pub entity_id: Uuid,
+    pub action: AuditAction,
+    /// Changed columns, see [`diff`].
+    pub changes: Value,
+    pub created_at: i64,
+}
+
+/// Filters for [`list_page`].
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub entity_id: Option<Uuid>,
+    pub entity: Option<AuditEntity>,
+}
+
+const AUDIT_SORT: SortKey<'static> = SortKey {
+    name: "id",
+    expr: "a.id",
+    id_expr: "a.id",
+    kind: KeyKind::Int,
+    direction: Direction::Desc,
+};
+
+/// Sort name of [`list_page`], stored in its cursors.
+pub const AUDIT_SORT_NAME: &str = AUDIT_SORT.name;
+
+/// List one page of the current household's audit entries, newest first.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or invalid row data.
+pub async fn list_page(
+    pool: &SqlitePool,
+    filter: &AuditFilter,
+    page: &PageRequest,
+) -> Result<Page<AuditEntry>, crate::db::DbError> {
+    use crate::db::page::Value as Bind;
+
+    let key = AUDIT_SORT;
+    let mut conditions = vec!["a.household_id = ?".to_string()];
+    let mut binds = vec![Bind::Text(
+        crate::db::household::current_household().to_string(),
+    )];
+    if let Some(entity_id) = filter.entity_id {
+        conditions.push("a.entity_id = ?".to_string());
+        binds.push(Bind::Text(entity_id.to_string()));
+    }
+    if let Some(entity) = filter.entity {
+        conditions.push("a.entity_type = ?".to_string());
+        binds.push(Bind::Text(entity.name().to_string()));
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "SELECT a.id, a.actor, u.name AS actor_name, a.entity_type, a.entity_id, a.action, \
+         a.changes, a.created_at, {} AS sort_key \
+         FROM audit_log a LEFT JOIN users u ON u.id = a.actor \
+         WHERE {} {}",
+        key.expr,
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: i64 = row.get("id");
+        let actor: String = row.get("actor");
+        let entity: String = row.get("entity_type");
+        let entity_id: String = row.get("entity_id");
+        let action: String = row.get("action");
+        let changes: String = row.get("changes");
+        let entry = AuditEntry {
+            id,
+            actor: Actor::from_stored(&actor)?,
+            actor_name: row.get("actor_name"),
+            entity: AuditEntity::from_param(&entity).ok_or_else(|| {
+                crate::db::DbError::InvalidData(format!("unknown audit entity: {entity}"))
+            })?,
+            entity_id: Uuid::parse_str(&entity_id)
+                .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?,
+            action: AuditAction::from_param(&action).ok_or_else(|| {
+                crate::db::DbError::InvalidData(format!("unknown audit action: {action}"))
+            })?,
+            changes: serde_json::from_str(&changes)
+                .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?,
+            created_at: row.get("created_at"),
+        };
+        out.push((entry, key.read_key(&row), id.to_string()));
+    }
+    Ok(key.finish(out, page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(value: Value) -> Snapshot {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("object expected"),
+        }
+    }
+
+    #[test]
+    fn diff_lists_only_changed_columns() {
+        let before = snap(serde_json::json!({ "id": "a", "name": "Milk", "deleted_at": null }));
+        let after =
+            snap(serde_json::json!({ "id": "a", "name": "Whole milk", "deleted_at": null }));
+        let changes = diff(Some(&before), Some(&after));
+        assert_eq!(
+            Value::Object(changes),
+            serde_json::json!({ "name": { "before": "Milk", "after": "Whole milk" } })
+        );
+    }
+
+    #[test]
+    fn diff_of_insert_and_hard_delete_skips_null_columns() {
+        let row = snap(serde_json::json!({ "id": "a", "deleted_at": null }));
+        let inserted = diff(None, Some(&row));
+        assert_eq!(
+            Value::Object(inserted),
+            serde_json::json!({ "id": { "before": null, "after": "a" } })
+        );
+        let removed = diff(Some(&row), None);
+        assert_eq!(
+            Value::Object(removed),
+            serde_json::json!({ "id": { "before": "a", "after": null } })
+        );
+    }
+
+    #[tokio::test]
+    async fn current_actor_defaults_to_cli_and_follows_scope() {
+        assert_eq!(current_actor(), Actor::Cli);
+        let user = Uuid::new_v4();
+        let inside = with_actor(Actor::User(user), async { current_actor() }).await;
+        assert_eq!(inside, Actor::User(user));
+        assert_eq!(
+            Actor::from_stored(&Actor::User(user).as_stored()).ok(),
+            Some(Actor::User(user))
+        );
+    }
+}
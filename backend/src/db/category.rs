@@ -2,7 +2,11 @@
 //!
 //! Provides the [`Categories`] tree type, [`Categories::from_list`] that builds a tree from a flat
 //! list, and DB functions: [`get_by_id`], [`get_parent`], [`get_children`], [`get_all`],
-//! [`get_ancestors`], [`insert`], [`update`], and [`soft_delete`].
+//! [`list_page`], [`get_ancestors`], [`get_ancestors_of`], [`insert`], [`update`],
+//! [`soft_delete`], and [`restore`].
+//!
+//! All functions read and write only categories of the current household (see
+//! [`crate::db::household`]).
 
 use std::collections::{HashMap, HashSet};
 use std::sync::{OnceLock, RwLock};
@@ -10,7 +14,11 @@ use std::sync::{OnceLock, RwLock};
 use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::db::household::current_household;
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, bind_values};
 use crate::domain::category::Category;
+use crate::metrics;
 
 /// Ancestor entry for breadcrumbs: id and name of a parent category (closest first in a list).
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -87,7 +95,7 @@ fn build_ancestor_map(list: &[Category]) -> HashMap<Uuid, Vec<Ancestor>> {
 }
 
 /// Module-level cache for the full category list (including deleted), ancestor map, tree, and
-/// id-to-index map. Used by `get_all(..., include_deleted)`, `get_ancestors`,
+/// id-to-index map of each household. Used by `get_all(..., include_deleted)`, `get_ancestors`,
 /// `get_category_and_descendant_ids`, and `get_by_id`. When the cache is warm, `get_by_id(..., include_deleted)`
 /// looks up the category via the id-to-index map (no DB round-trip) and honours `include_deleted`:
 /// returns the category when active or when `include_deleted` is true.
@@ -96,20 +104,21 @@ fn build_ancestor_map(list: &[Category]) -> HashMap<Uuid, Vec<Ancestor>> {
 /// tests run in parallel with their own DBs; we bypass the cache so they don't see each other's
 /// data. Cache tests enable it via [`set_use_category_list_cache_for_test`] and run under
 /// `#[serial]` so only one runs at a time.
-fn category_list_cache() -> &'static RwLock<Option<CategoryCacheEntry>> {
-    static CACHE: OnceLock<RwLock<Option<CategoryCacheEntry>>> = OnceLock::new();
-    CACHE.get_or_init(|| RwLock::new(None))
+fn category_list_cache() -> &'static RwLock<HashMap<Uuid, CategoryCacheEntry>> {
+    static CACHE: OnceLock<RwLock<HashMap<Uuid, CategoryCacheEntry>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
-fn invalidate_category_list_cache() {
-    let _ = category_list_cache().write().map(|mut g| *g = None);
+pub(crate) fn invalidate_category_list_cache() {
+    let _ = category_list_cache().write().map(|mut g| g.clear());
 }
 
 /// Clear the category list cache. For use by cache tests so they start from a known state.
 pub fn clear_category_list_cache() {
-    let _ = category_list_cache().write().map(|mut g| *g = None);
+    let _ = category_list_cache().write().map(|mut g| g.clear());
 }
 
+/// Set the current household's category list cache. For use by cache tests.
 pub fn set_category_list_cache_for_test(list: Option<Vec<Category>>) {
     let entry = list.map(|l| {
         let map = build_ancestor_map(&l);
@@ -117,14 +126,23 @@ pub fn set_category_list_cache_for_test(list: Option<Vec<Category>>) {
         let by_id: HashMap<Uuid, usize> = l.iter().enumerate().map(|(i, c)| (c.id(), i)).collect();
         (l, map, tree, by_id)
     });
-    let _ = category_list_cache().write().map(|mut g| *g = entry);
+    let household = current_household();
+    let _ = category_list_cache().write().map(|mut g| match entry {
+        Some(entry) => {
+            g.insert(household, entry);
+        }
+        None => {
+            g.remove(&household);
+        }
+    });
 }
 
 /// Fetch all categories from the database (active and soft-deleted). Used to fill the cache.
 async fn fetch_all_categories_raw(pool: &SqlitePool) -> Result<Vec<Category>, crate::db::DbError> {
     let rows = sqlx::query(
-        "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories ORDER BY name",
+        "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE household_id = ? ORDER BY name",
     )
+    .bind(current_household().to_string())
     .fetch_all(pool)
     .await?;
 
@@ -304,29 +322,35 @@ pub async fn get_by_id(
 ) -> Result<Option<Category>, crate::db::DbError> {
     if use_cache()
         && let Ok(guard) = category_list_cache().read()
-        && let Some((ref list, _, _, ref by_id)) = *guard
+        && let Some((list, _, _, by_id)) = guard.get(&current_household())
         && let Some(&index) = by_id.get(&id)
         && let Some(c) = list.get(index)
     {
+        metrics::CATEGORY_LIST_CACHE.hit();
         if include_deleted || c.is_active() {
             return Ok(Some(c.clone()));
         }
         return Ok(None);
     }
+    if use_cache() {
+        metrics::CATEGORY_LIST_CACHE.miss();
+    }
 
     let id_str = id.to_string();
     let row = if include_deleted {
         sqlx::query(
-            "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE id = ?",
+            "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE id = ? AND household_id = ?",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
@@ -384,16 +408,18 @@ pub async fn get_children(
         Some(pid) => {
             let parent_str = pid.to_string();
             sqlx::query(
-                "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE parent_id = ? AND deleted_at IS NULL ORDER BY name",
+                "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE parent_id = ? AND household_id = ? AND deleted_at IS NULL ORDER BY name",
             )
             .bind(&parent_str)
+            .bind(current_household().to_string())
             .fetch_all(pool)
             .await?
         }
         None => {
             sqlx::query(
-                "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE parent_id IS NULL AND deleted_at IS NULL ORDER BY name",
+                "SELECT id, parent_id, name, created_at, updated_at, deleted_at FROM categories WHERE parent_id IS NULL AND household_id = ? AND deleted_at IS NULL ORDER BY name",
             )
+            .bind(current_household().to_string())
             .fetch_all(pool)
             .await?
         }
@@ -426,8 +452,8 @@ pub async fn get_children(
 /// When `true`, soft-deleted categories are included. The cache always stores the full list
 /// (including deleted); when `include_deleted` is `false` the result is filtered on read.
 ///
-/// When not in test, results are cached in memory; the cache is invalidated on any category
-/// insert, update, or delete.
+/// When not in test, results are cached in memory per household; the cache is invalidated on
+/// any category insert, update, or delete.
 ///
 /// # Errors
 ///
@@ -436,10 +462,12 @@ pub async fn get_all(
     pool: &SqlitePool,
     include_deleted: bool,
 ) -> Result<Vec<Category>, crate::db::DbError> {
+    let household = current_household();
     if use_cache()
         && let Ok(guard) = category_list_cache().read()
-        && let Some((ref list, _, _, _)) = *guard
+        && let Some((list, _, _, _)) = guard.get(&household)
     {
+        metrics::CATEGORY_LIST_CACHE.hit();
         return Ok(if include_deleted {
             list.clone()
         } else {
@@ -447,6 +475,9 @@ pub async fn get_all(
         });
     }
 
+    if use_cache() {
+        metrics::CATEGORY_LIST_CACHE.miss();
+    }
     let list = fetch_all_categories_raw(pool).await?;
 
     if use_cache()
@@ -456,7 +487,7 @@ pub async fn get_all(
         let tree = Categories::from_list(list.clone(), None, None, true);
         let by_id: HashMap<Uuid, usize> =
             list.iter().enumerate().map(|(i, c)| (c.id(), i)).collect();
-        *guard = Some((list.clone(), ancestors, tree, by_id));
+        guard.insert(household, (list.clone(), ancestors, tree, by_id));
     }
 
     Ok(if include_deleted {
@@ -466,6 +497,116 @@ pub async fn get_all(
     })
 }
 
+/// Sort order for [`list_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CategorySort {
+    /// Name A-Z.
+    Name,
+    /// Newest first.
+    CreatedAt,
+    /// Most recently updated first.
+    UpdatedAt,
+}
+
+impl CategorySort {
+    /// Parse a `sort` query value (`name`, `created_at`, `updated_at`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "name" => Some(Self::Name),
+            "created_at" => Some(Self::CreatedAt),
+            "updated_at" => Some(Self::UpdatedAt),
+            _ => None,
+        }
+    }
+
+    /// Query value of this sort, also stored in cursors.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        self.key().name
+    }
+
+    const fn key(self) -> SortKey<'static> {
+        let (name, expr, kind, direction) = match self {
+            Self::Name => ("name", "name", KeyKind::Text, Direction::Asc),
+            Self::CreatedAt => ("created_at", "created_at", KeyKind::Int, Direction::Desc),
+            Self::UpdatedAt => ("updated_at", "updated_at", KeyKind::Int, Direction::Desc),
+        };
+        SortKey {
+            name,
+            expr,
+            id_expr: "id",
+            kind,
+            direction,
+        }
+    }
+}
+
+/// List one page of the direct children of `parent_id` (root categories when `None`) in the given
+/// sort order (keyset pagination in SQL; bypasses the list cache).
+///
+/// When `include_deleted` is `false`, only active categories are returned.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_page(
+    pool: &SqlitePool,
+    parent_id: Option<Uuid>,
+    sort: CategorySort,
+    include_deleted: bool,
+    page: &PageRequest,
+) -> Result<Page<Category>, crate::db::DbError> {
+    let key = sort.key();
+    let mut conditions = vec!["household_id = ?".to_string()];
+    let mut binds = vec![crate::db::page::Value::Text(
+        current_household().to_string(),
+    )];
+    match parent_id {
+        Some(pid) => {
+            conditions.push("parent_id = ?".to_string());
+            binds.push(crate::db::page::Value::Text(pid.to_string()));
+        }
+        None => conditions.push("parent_id IS NULL".to_string()),
+    }
+    if !include_deleted {
+        conditions.push("deleted_at IS NULL".to_string());
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "SELECT id, parent_id, name, created_at, updated_at, deleted_at, {} AS sort_key \
+         FROM categories WHERE {} {}",
+        key.expr,
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: String = row.get("id");
+        let parent_id: Option<String> = row.get("parent_id");
+        let name: String = row.get("name");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+        let deleted_at: Option<i64> = row.get("deleted_at");
+        let category = row_to_category(
+            &id,
+            parent_id.as_deref(),
+            &name,
+            created_at,
+            updated_at,
+            deleted_at,
+        )?;
+        out.push((category, key.read_key(&row), id));
+    }
+    Ok(key.finish(out, page))
+}
+
 /// Return the ancestor chain for a category (closest parent first). Uses the category list
 /// cache when warm; otherwise populates the cache via `get_all` then looks up.
 ///
@@ -476,21 +617,43 @@ pub async fn get_ancestors(
     pool: &SqlitePool,
     id: Uuid,
 ) -> Result<Vec<Ancestor>, crate::db::DbError> {
+    Ok(get_ancestors_of(pool, &[id])
+        .await?
+        .remove(&id)
+        .unwrap_or_default())
+}
+
+/// Return the ancestor chains of several categories at once, keyed by category id (ids not in
+/// the household map to no entry). Reads the category list once, like [`get_ancestors`].
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] if the cache cannot be populated (e.g. DB error).
+pub async fn get_ancestors_of(
+    pool: &SqlitePool,
+    ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<Ancestor>>, crate::db::DbError> {
+    let pick = |ancestors: &HashMap<Uuid, Vec<Ancestor>>| -> HashMap<Uuid, Vec<Ancestor>> {
+        ids.iter()
+            .filter_map(|id| Some((*id, ancestors.get(id)?.clone())))
+            .collect()
+    };
     if use_cache() {
         if let Ok(guard) = category_list_cache().read()
-            && let Some((_, ref ancestors, _, _)) = *guard
+            && let Some((_, ancestors, _, _)) = guard.get(&current_household())
         {
-            return Ok(ancestors.get(&id).cloned().unwrap_or_default());
+            metrics::CATEGORY_LIST_CACHE.hit();
+            return Ok(pick(ancestors));
         }
         get_all(pool, true).await?;
         if let Ok(guard) = category_list_cache().read()
-            && let Some((_, ref ancestors, _, _)) = *guard
+            && let Some((_, ancestors, _, _)) = guard.get(&current_household())
         {
-            return Ok(ancestors.get(&id).cloned().unwrap_or_default());
+            return Ok(pick(ancestors));
         }
     }
     let list = fetch_all_categories_raw(pool).await?;
-    Ok(build_ancestor_map(&list).remove(&id).unwrap_or_default())
+    Ok(pick(&build_ancestor_map(&list)))
 }
 
 /// Return the given category id and all its descendant ids up to `max_depth` levels.
@@ -511,8 +674,9 @@ pub async fn get_category_and_descendant_ids(
     let depth = std::cmp::min(max_depth, MAX_CATEGORY_DEPTH);
     if use_cache() {
         if let Ok(guard) = category_list_cache().read()
-            && let Some((ref list, _, ref tree, _)) = *guard
+            && let Some((list, _, tree, _)) = guard.get(&current_household())
         {
+            metrics::CATEGORY_LIST_CACHE.hit();
             let ids = tree
                 .find_subtree_by_id(category_id)
                 .map(|sub| sub.collect_ids_to_depth(depth))
@@ -521,7 +685,7 @@ pub async fn get_category_and_descendant_ids(
         }
         get_all(pool, true).await?;
         if let Ok(guard) = category_list_cache().read()
-            && let Some((ref list, _, ref tree, _)) = *guard
+            && let Some((list, _, tree, _)) = guard.get(&current_household())
         {
             let ids = tree
                 .find_subtree_by_id(category_id)
@@ -564,7 +728,7 @@ fn filter_ids_by_active_if_needed(
 pub async fn insert(pool: &SqlitePool, category: &Category) -> Result<(), crate::db::DbError> {
     let now = chrono::Utc::now().timestamp();
     sqlx::query(
-        "INSERT INTO categories (id, parent_id, name, created_at, updated_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO categories (id, parent_id, name, created_at, updated_at, deleted_at, household_id) VALUES (?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(category.id().to_string())
     .bind(category.parent_id().map(|id| id.to_string()))
@@ -572,31 +736,53 @@ pub async fn insert(pool: &SqlitePool, category: &Category) -> Result<(), crate:
     .bind(now)
     .bind(now)
     .bind(category.deleted_at())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
     invalidate_category_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Category,
+        category.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
 /// Update an existing category's parent, name, `updated_at`, and `deleted_at`.
-/// Does not change `created_at`.
+///
+/// Does not change `created_at`. Re-indexes the search rows of the category's products and those
+/// of its descendants, as their category paths may have changed.
 ///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure (e.g. duplicate name under parent).
 pub async fn update(pool: &SqlitePool, category: &Category) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Category, category.id()).await?;
     let now = chrono::Utc::now().timestamp();
     sqlx::query(
-        "UPDATE categories SET parent_id = ?, name = ?, updated_at = ?, deleted_at = ? WHERE id = ?",
+        "UPDATE categories SET parent_id = ?, name = ?, updated_at = ?, deleted_at = ? WHERE id = ? AND household_id = ?",
     )
     .bind(category.parent_id().map(|id| id.to_string()))
     .bind(category.name())
     .bind(now)
     .bind(category.deleted_at())
     .bind(category.id().to_string())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
     invalidate_category_list_cache();
+    crate::db::product_search::refresh_category(pool, category.id()).await?;
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Category,
+        category.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -604,11 +790,18 @@ pub async fn update(pool: &SqlitePool, category: &Category) -> Result<(), crate:
 ///
 /// Fails if there are any active child categories or active products belonging to this category.
 ///
+/// `deleted_by` is the acting user (`None` when deleted from the CLI).
+///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// the category has child categories, has products, or no active category exists with the given id.
-pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+pub async fn soft_delete(
+    pool: &SqlitePool,
+    id: Uuid,
+    deleted_by: Option<Uuid>,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Category, id).await?;
     let id_str = id.to_string();
 
     // Check for active child categories.
@@ -639,11 +832,13 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
 
     let now = chrono::Utc::now().timestamp();
     let result = sqlx::query(
-        "UPDATE categories SET deleted_at = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
+        "UPDATE categories SET deleted_at = ?, updated_at = ?, deleted_by = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
     )
     .bind(now)
     .bind(now)
+    .bind(deleted_by.map(|u| u.to_string()))
     .bind(&id_str)
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
 
@@ -654,6 +849,66 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     invalidate_category_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Category,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Restore a soft-deleted category. Clears `deleted_at` and sets `updated_at` to the current time.
+///
+/// Fails if the parent category (when any) is not active.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (e.g. an active sibling now has the same
+/// name), or [`crate::db::DbError::InvalidData`] if the parent is deleted or no soft-deleted
+/// category exists with the given id.
+pub async fn restore(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Category, id).await?;
+    let id_str = id.to_string();
+    let parent_id: Option<Option<String>> =
+        sqlx::query_scalar("SELECT parent_id FROM categories WHERE id = ? AND household_id = ?")
+            .bind(&id_str)
+            .bind(current_household().to_string())
+            .fetch_optional(pool)
+            .await?;
+    if let Some(Some(parent_id)) = parent_id {
+        crate::db::ensure_active_parent(pool, "categories", &parent_id, "category", "parent")
+            .await?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE categories SET deleted_at = NULL, deleted_by = NULL, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(now)
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "category not found or not deleted: {id_str}"
+        )));
+    }
+
+    invalidate_category_list_cache();
+    crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Category,
+        id,
+        AuditAction::Restore,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -666,6 +921,7 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// the category has child categories, has products, or no active category exists with the given id.
 pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Category, id).await?;
     let id_str = id.to_string();
 
     // Same checks as soft_delete.
@@ -693,8 +949,9 @@ pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
         )));
     }
 
-    let result = sqlx::query("DELETE FROM categories WHERE id = ?")
+    let result = sqlx::query("DELETE FROM categories WHERE id = ? AND household_id = ?")
         .bind(&id_str)
+        .bind(current_household().to_string())
         .execute(pool)
         .await?;
 
@@ -705,6 +962,14 @@ pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     invalidate_category_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Category,
+        id,
+        AuditAction::HardDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
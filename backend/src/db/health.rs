@@ -0,0 +1,117 @@
+//! Readiness checks of the database: connectivity, applied migrations, and free disk space.
+//!
+//! Provides [`ping`], [`migration_status`], and [`free_disk_space`]. None of them write to the
+//! database.
+
+use std::path::Path;
+
+use sqlx::SqlitePool;
+use sqlx::migrate::Migrate;
+
+use super::MIGRATOR;
+
+/// Migrations embedded in the binary compared with those recorded in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Number of embedded migrations recorded as applied.
+    pub applied: usize,
+    /// Versions of embedded migrations not applied yet.
+    pub pending: Vec<i64>,
+    /// Version of a migration that failed part-way, if any.
+    pub dirty: Option<i64>,
+    /// Versions applied from a different file than the embedded one.
+    pub modified: Vec<i64>,
+}
+
+impl MigrationStatus {
+    /// True when every embedded migration is applied unchanged and none failed.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.pending.is_empty() && self.dirty.is_none() && self.modified.is_empty()
+    }
+}
+
+/// Run a trivial query.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] if no connection can be acquired or the query fails.
+pub async fn ping(pool: &SqlitePool) -> Result<(), crate::db::DbError> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
+/// Compare the embedded migrations with the migrations table. A database that was never migrated
+/// reports every migration as pending.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn migration_status(pool: &SqlitePool) -> Result<MigrationStatus, crate::db::DbError> {
+    let mut conn = pool.acquire().await?;
+    let table_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let (applied, dirty) = if table_exists {
+        (
+            conn.list_applied_migrations().await?,
+            conn.dirty_version().await?,
+        )
+    } else {
+        (Vec::new(), None)
+    };
+
+    let mut status = MigrationStatus {
+        applied: 0,
+        pending: Vec::new(),
+        dirty,
+        modified: Vec::new(),
+    };
+    for migration in MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+    {
+        if dirty == Some(migration.version) {
+            continue;
+        }
+        match applied.iter().find(|a| a.version == migration.version) {
+            Some(a) if a.checksum == migration.checksum => status.applied += 1,
+            Some(_) => status.modified.push(migration.version),
+            None => status.pending.push(migration.version),
+        }
+    }
+    Ok(status)
+}
+
+/// Bytes available to the process on the filesystem holding the database file, or `None` when
+/// unknown (in-memory database, or not supported on this platform).
+#[must_use]
+pub fn free_disk_space(database_path: &str) -> Option<u64> {
+    if database_path.is_empty() || database_path.starts_with(":memory:") {
+        return None;
+    }
+    let dir = Path::new(database_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    available_space(dir)
+}
+
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    let stat = nix::sys::statvfs::statvfs(dir).ok()?;
+    statvfs_u64(stat.blocks_available()).checked_mul(statvfs_u64(stat.fragment_size()))
+}
+
+/// A `statvfs` field as `u64`; the field types differ between platforms.
+#[cfg(unix)]
+fn statvfs_u64<T: TryInto<u64>>(value: T) -> u64 {
+    value.try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(not(unix))]
+const fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
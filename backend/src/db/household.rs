@@ -0,0 +1,179 @@
+//! Household persistence and the household scope of database access.
+//!
+//! Provides DB functions: [`get_by_id`], [`get_by_name`], [`list_all`], [`list_for_user`],
+//! [`insert`], [`add_member`], and [`is_member`].
+//!
+//! Categories, products, variations, locations, reviews, purchases and audit entries belong to
+//! one household. The entity modules read and write only rows of [`current_household`], which
+//! is task-local like the audit actor: the API auth middleware runs each request inside
+//! [`with_household`] with the household from the token, and the CLI does so for `--household`.
+//! Outside such a scope the [`DEFAULT_HOUSEHOLD_ID`] household is used.
+
+use std::future::Future;
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::domain::household::Household;
+
+/// Household created by the migration; holds all data that existed before households.
+pub const DEFAULT_HOUSEHOLD_ID: Uuid = Uuid::from_u128(0x0000_0000_0000_4000_8000_0000_0000_0001);
+
+tokio::task_local! {
+    static HOUSEHOLD: Uuid;
+}
+
+/// Run `f` with all database access scoped to `household_id`.
+pub async fn with_household<F: Future>(household_id: Uuid, f: F) -> F::Output {
+    HOUSEHOLD.scope(household_id, f).await
+}
+
+/// Household of the current task ([`DEFAULT_HOUSEHOLD_ID`] outside [`with_household`]).
+#[must_use]
+pub fn current_household() -> Uuid {
+    HOUSEHOLD.try_with(|h| *h).unwrap_or(DEFAULT_HOUSEHOLD_ID)
+}
+
+/// Map a DB row into a [`Household`]. Fails on invalid UUID or domain validation.
+fn row_to_household(row: &sqlx::sqlite::SqliteRow) -> Result<Household, crate::db::DbError> {
+    let id: String = row.get("id");
+    let id = Uuid::parse_str(&id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+    Household::new(id, row.get("name"), row.get("created_at"))
+        .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+}
+
+/// Fetch a household by id.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_by_id(
+    pool: &SqlitePool,
+    id: Uuid,
+) -> Result<Option<Household>, crate::db::DbError> {
+    let row = sqlx::query("SELECT id, name, created_at FROM households WHERE id = ?")
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_household).transpose()
+}
+
+/// Fetch a household by its (unique) name.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_by_name(
+    pool: &SqlitePool,
+    name: &str,
+) -> Result<Option<Household>, crate::db::DbError> {
+    let row = sqlx::query("SELECT id, name, created_at FROM households WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_household).transpose()
+}
+
+/// Fetch all households, ordered by name.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Household>, crate::db::DbError> {
+    let rows = sqlx::query("SELECT id, name, created_at FROM households ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+    rows.iter().map(row_to_household).collect()
+}
+
+/// Fetch the households `user_id` is a member of, in the order they were joined.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_for_user(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<Vec<Household>, crate::db::DbError> {
+    let rows = sqlx::query(
+        "SELECT h.id, h.name, h.created_at FROM households h
+         JOIN household_members m ON m.household_id = h.id
+         WHERE m.user_id = ?
+         ORDER BY m.created_at, h.name",
+    )
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await?;
+    rows.iter().map(row_to_household).collect()
+}
+
+/// Insert a household.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (including a duplicate name).
+pub async fn insert(pool: &SqlitePool, household: &Household) -> Result<(), crate::db::DbError> {
+    sqlx::query("INSERT INTO households (id, name, created_at) VALUES (?, ?, ?)")
+        .bind(household.id().to_string())
+        .bind(household.name())
+        .bind(household.created_at())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Make `user_id` a member of `household_id`. Returns `false` if it already was one.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (including an unknown household or user).
+pub async fn add_member(
+    pool: &SqlitePool,
+    household_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, crate::db::DbError> {
+    let result = sqlx::query(
+        "INSERT INTO household_members (household_id, user_id, created_at) VALUES (?, ?, ?)
+         ON CONFLICT (household_id, user_id) DO NOTHING",
+    )
+    .bind(household_id.to_string())
+    .bind(user_id.to_string())
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether `user_id` is a member of `household_id`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn is_member(
+    pool: &SqlitePool,
+    household_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, crate::db::DbError> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM household_members WHERE household_id = ? AND user_id = ?",
+    )
+    .bind(household_id.to_string())
+    .bind(user_id.to_string())
+    .fetch_one(pool)
+    .await?;
+    Ok(count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn household_scope_applies_inside_with_household_only() {
+        let id = Uuid::new_v4();
+        assert_eq!(current_household(), DEFAULT_HOUSEHOLD_ID);
+        let inside = with_household(id, async { current_household() }).await;
+        assert_eq!(inside, id);
+        assert_eq!(current_household(), DEFAULT_HOUSEHOLD_ID);
+    }
+}
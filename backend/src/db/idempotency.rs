@@ -0,0 +1,143 @@
+//! Idempotency key persistence: the response to the first `POST` sent with a key, replayed for
+//! retries with the same key (see [`crate::api`]'s idempotency layer).
+//!
+//! Provides DB functions: [`claim`], [`complete`], and [`release`]. Keys are scoped to the user
+//! and the current household (see [`crate::db::household::current_household`]).
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::household::current_household;
+
+/// Response stored for a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// A key that was already claimed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExistingKey {
+    /// Hash of the request that claimed the key.
+    pub request_hash: String,
+    /// `None` while that request is still running.
+    pub response: Option<StoredResponse>,
+}
+
+/// Claim `key` for `user_id` in the current household and the request with `request_hash`.
+///
+/// Returns `None` when the key is new (the caller then runs the request and calls [`complete`]
+/// or [`release`]), or the existing claim otherwise.
+///
+/// Keys created before `expired_before` are forgotten first, and so are claims still running
+/// since before `abandoned_before` (e.g. the server stopped mid-request).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn claim(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    key: &str,
+    request_hash: &str,
+    now: i64,
+    expired_before: i64,
+    abandoned_before: i64,
+) -> Result<Option<ExistingKey>, crate::db::DbError> {
+    sqlx::query(
+        "DELETE FROM idempotency_keys WHERE created_at < ? OR (status IS NULL AND created_at < ?)",
+    )
+    .bind(expired_before)
+    .bind(abandoned_before)
+    .execute(pool)
+    .await?;
+    let inserted = sqlx::query(
+        "INSERT INTO idempotency_keys (household_id, user_id, key, request_hash, created_at)
+         VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT (household_id, user_id, key) DO NOTHING",
+    )
+    .bind(current_household().to_string())
+    .bind(user_id.to_string())
+    .bind(key)
+    .bind(request_hash)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    if inserted.rows_affected() == 1 {
+        return Ok(None);
+    }
+
+    let row = sqlx::query(
+        "SELECT request_hash, status, content_type, body FROM idempotency_keys
+         WHERE household_id = ? AND user_id = ? AND key = ?",
+    )
+    .bind(current_household().to_string())
+    .bind(user_id.to_string())
+    .bind(key)
+    .fetch_one(pool)
+    .await?;
+    let status: Option<i64> = row.try_get("status")?;
+    let response = status
+        .map(|status| -> Result<StoredResponse, crate::db::DbError> {
+            Ok(StoredResponse {
+                status: u16::try_from(status)
+                    .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?,
+                content_type: row.try_get("content_type")?,
+                body: row
+                    .try_get::<Option<Vec<u8>>, _>("body")?
+                    .unwrap_or_default(),
+            })
+        })
+        .transpose()?;
+    Ok(Some(ExistingKey {
+        request_hash: row.try_get("request_hash")?,
+        response,
+    }))
+}
+
+/// Store the response of the request that claimed `key`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn complete(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    key: &str,
+    response: &StoredResponse,
+) -> Result<(), crate::db::DbError> {
+    sqlx::query(
+        "UPDATE idempotency_keys SET status = ?, content_type = ?, body = ?
+         WHERE household_id = ? AND user_id = ? AND key = ?",
+    )
+    .bind(i64::from(response.status))
+    .bind(response.content_type.as_deref())
+    .bind(response.body.as_slice())
+    .bind(current_household().to_string())
+    .bind(user_id.to_string())
+    .bind(key)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Forget `key`, so that a retry runs the request again (used when it failed).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn release(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    key: &str,
+) -> Result<(), crate::db::DbError> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE household_id = ? AND user_id = ? AND key = ?")
+        .bind(current_household().to_string())
+        .bind(user_id.to_string())
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
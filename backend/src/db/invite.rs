@@ -0,0 +1,138 @@
+//! Invite persistence.
+//!
+//! Provides DB functions: [`insert`], [`get_active_by_hash`], [`redeem`], and
+//! [`invalidate_for_email`]. Invites are not household scoped; each one records the household the
+//! new user joins.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::domain::invite::Invite;
+use crate::domain::user::{Role, User};
+
+const INVITE_COLUMNS: &str = "id, email, role, household_id, created_at, expires_at, used_at";
+
+/// Map a DB row into an [`Invite`]. Fails on invalid UUID, role or domain validation.
+fn row_to_invite(row: &sqlx::sqlite::SqliteRow) -> Result<Invite, crate::db::DbError> {
+    let parse = |column: &str| {
+        let value: String = row.get(column);
+        Uuid::parse_str(&value).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+    };
+    let role: String = row.get("role");
+    let role: Role = role
+        .parse()
+        .map_err(|e: crate::domain::user::ValidationError| {
+            crate::db::DbError::InvalidData(e.to_string())
+        })?;
+    Invite::new(
+        parse("id")?,
+        row.get("email"),
+        role,
+        parse("household_id")?,
+        row.get("created_at"),
+        row.get("expires_at"),
+        row.get("used_at"),
+    )
+    .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+}
+
+/// Insert an invite with the hash of its token.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn insert(
+    pool: &SqlitePool,
+    invite: &Invite,
+    token_hash: &str,
+) -> Result<(), crate::db::DbError> {
+    sqlx::query(
+        "INSERT INTO invites (id, email, role, household_id, token_hash, created_at, expires_at, used_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(invite.id().to_string())
+    .bind(invite.email())
+    .bind(invite.role().to_string())
+    .bind(invite.household_id().to_string())
+    .bind(token_hash)
+    .bind(invite.created_at())
+    .bind(invite.expires_at())
+    .bind(invite.used_at())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Fetch the invite with `token_hash`, if it is still unused and unexpired at `now`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_active_by_hash(
+    pool: &SqlitePool,
+    token_hash: &str,
+    now: i64,
+) -> Result<Option<Invite>, crate::db::DbError> {
+    let sql = format!(
+        "SELECT {INVITE_COLUMNS} FROM invites
+         WHERE token_hash = ? AND used_at IS NULL AND expires_at > ?"
+    );
+    let row = sqlx::query(&sql)
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_invite).transpose()
+}
+
+/// Use up invite `id` at `now` and create `user` as a member of the invite's household.
+///
+/// One transaction covers the invite, the user, the membership and the audit entry (in the
+/// invite's household). Returns `false` (creating nothing) if the invite is unknown, used or
+/// expired.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (e.g. the email belongs to another user);
+/// nothing is changed then.
+pub async fn redeem(
+    pool: &SqlitePool,
+    id: Uuid,
+    user: &User,
+    now: i64,
+) -> Result<bool, crate::db::DbError> {
+    let mut tx = pool.begin().await?;
+    let household_id: Option<String> = sqlx::query_scalar(
+        "UPDATE invites SET used_at = ? WHERE id = ? AND used_at IS NULL AND expires_at > ?
+         RETURNING household_id",
+    )
+    .bind(now)
+    .bind(id.to_string())
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(household_id) = household_id else {
+        return Ok(false);
+    };
+    let household_id = Uuid::parse_str(&household_id)
+        .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+    crate::db::user::insert_into_household(&mut tx, user, household_id, now).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Mark all unused invites for `email` as used. Returns how many were invalidated.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn invalidate_for_email(
+    pool: &SqlitePool,
+    email: &str,
+) -> Result<u64, crate::db::DbError> {
+    let result = sqlx::query("UPDATE invites SET used_at = ? WHERE email = ? AND used_at IS NULL")
+        .bind(chrono::Utc::now().timestamp())
+        .bind(email)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -1,18 +1,25 @@
 //! Location persistence.
 //!
-//! Provides DB functions: [`get_by_id`], [`get_all`], [`insert`], [`update`],
-//! [`soft_delete`], and [`hard_delete`].
+//! Provides DB functions: [`get_by_id`], [`get_all`], [`list_page`], [`insert`], [`update`],
+//! [`soft_delete`], [`restore`], and [`hard_delete`].
 //!
-//! When running as production, [`get_all`] results are cached in memory (full list
-//! including deleted); when `include_deleted` is `false` the result is filtered on
-//! read. The cache is invalidated on any insert, update, `soft_delete`, or
-//! `hard_delete`.
+//! All functions read and write only locations of the current household (see
+//! [`crate::db::household`]).
+//!
+//! When running as production, [`get_all`] results are cached in memory per household (full
+//! list including deleted); when `include_deleted` is `false` the result is filtered on
+//! read. The cache is invalidated on any insert, update, `soft_delete`,
+//! `restore`, or `hard_delete`.
 
+use std::collections::HashMap;
 use std::sync::{OnceLock, RwLock};
 
 use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::db::household::current_household;
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, bind_values};
 use crate::domain::location::Location;
 
 /// True when the process is the production binary (`main()` has run). False in test
@@ -47,7 +54,7 @@ pub fn set_use_location_list_cache_for_test(use_cache: bool) {
     USE_CACHE_IN_TEST.with(|v| v.store(use_cache, std::sync::atomic::Ordering::SeqCst));
 }
 
-/// Module-level cache for the full location list (including deleted). Used by
+/// Module-level cache for the full location list (including deleted) of each household. Used by
 /// `get_all(..., include_deleted)`.
 ///
 /// **Disabled in test builds by default:** The cache is a single process-wide
@@ -55,25 +62,33 @@ pub fn set_use_location_list_cache_for_test(use_cache: bool) {
 /// so they don't see each other's data. Cache tests enable it via
 /// [`set_use_location_list_cache_for_test`] and run under `#[serial]` so only one
 /// runs at a time.
-fn location_list_cache() -> &'static RwLock<Option<Vec<Location>>> {
-    static CACHE: OnceLock<RwLock<Option<Vec<Location>>>> = OnceLock::new();
-    CACHE.get_or_init(|| RwLock::new(None))
+fn location_list_cache() -> &'static RwLock<HashMap<Uuid, Vec<Location>>> {
+    static CACHE: OnceLock<RwLock<HashMap<Uuid, Vec<Location>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
-fn invalidate_location_list_cache() {
-    let _ = location_list_cache().write().map(|mut g| *g = None);
+pub(crate) fn invalidate_location_list_cache() {
+    let _ = location_list_cache().write().map(|mut g| g.clear());
 }
 
 /// Clear the location list cache. For use by cache tests so they start from a
 /// known state.
 pub fn clear_location_list_cache() {
-    let _ = location_list_cache().write().map(|mut g| *g = None);
+    let _ = location_list_cache().write().map(|mut g| g.clear());
 }
 
-/// Set the location list cache to a specific value. For use by cache tests that
-/// need to inject stale data to verify invalidation.
+/// Set the current household's location list cache to a specific value. For use by cache tests
+/// that need to inject stale data to verify invalidation.
 pub fn set_location_list_cache_for_test(list: Option<Vec<Location>>) {
-    let _ = location_list_cache().write().map(|mut g| *g = list);
+    let household = current_household();
+    let _ = location_list_cache().write().map(|mut g| match list {
+        Some(list) => {
+            g.insert(household, list);
+        }
+        None => {
+            g.remove(&household);
+        }
+    });
 }
 
 /// Map a DB row into a [`Location`]. Fails on invalid UUID or domain validation.
@@ -102,15 +117,17 @@ pub async fn get_by_id(
 ) -> Result<Option<Location>, crate::db::DbError> {
     let id_str = id.to_string();
     let row = if include_deleted {
-        sqlx::query("SELECT id, name, deleted_at FROM locations WHERE id = ?")
+        sqlx::query("SELECT id, name, deleted_at FROM locations WHERE id = ? AND household_id = ?")
             .bind(&id_str)
+            .bind(current_household().to_string())
             .fetch_optional(pool)
             .await?
     } else {
         sqlx::query(
-            "SELECT id, name, deleted_at FROM locations WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, name, deleted_at FROM locations WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
@@ -130,7 +147,8 @@ pub async fn get_by_id(
 /// Fetch all locations from the database (active and soft-deleted). Used to fill
 /// the cache.
 async fn fetch_all_locations_raw(pool: &SqlitePool) -> Result<Vec<Location>, crate::db::DbError> {
-    let rows = sqlx::query("SELECT id, name, deleted_at FROM locations")
+    let rows = sqlx::query("SELECT id, name, deleted_at FROM locations WHERE household_id = ?")
+        .bind(current_household().to_string())
         .fetch_all(pool)
         .await?;
 
@@ -152,8 +170,8 @@ async fn fetch_all_locations_raw(pool: &SqlitePool) -> Result<Vec<Location>, cra
 /// stores the full list (including deleted); when `include_deleted` is `false`
 /// the result is filtered on read.
 ///
-/// When not in test, results are cached in memory; the cache is invalidated on any
-/// location insert, update, or delete.
+/// When not in test, results are cached in memory per household; the cache is invalidated on
+/// any location insert, update, or delete.
 ///
 /// # Errors
 ///
@@ -162,9 +180,10 @@ pub async fn get_all(
     pool: &SqlitePool,
     include_deleted: bool,
 ) -> Result<Vec<Location>, crate::db::DbError> {
+    let household = current_household();
     if use_cache()
         && let Ok(guard) = location_list_cache().read()
-        && let Some(ref list) = *guard
+        && let Some(list) = guard.get(&household)
     {
         return Ok(if include_deleted {
             list.clone()
@@ -178,7 +197,7 @@ pub async fn get_all(
     if use_cache()
         && let Ok(mut guard) = location_list_cache().write()
     {
-        *guard = Some(list.clone());
+        guard.insert(household, list.clone());
     }
 
     Ok(if include_deleted {
@@ -188,19 +207,113 @@ pub async fn get_all(
     })
 }
 
+/// Sort order for [`list_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationSort {
+    /// Name A-Z.
+    Name,
+}
+
+impl LocationSort {
+    /// Parse a `sort` query value (`name`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "name" => Some(Self::Name),
+            _ => None,
+        }
+    }
+
+    /// Query value of this sort, also stored in cursors.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        self.key().name
+    }
+
+    const fn key(self) -> SortKey<'static> {
+        match self {
+            Self::Name => SortKey {
+                name: "name",
+                expr: "name",
+                id_expr: "id",
+                kind: KeyKind::Text,
+                direction: Direction::Asc,
+            },
+        }
+    }
+}
+
+/// List one page of locations in the given sort order (keyset pagination in SQL; bypasses the
+/// list cache).
+///
+/// When `include_deleted` is `false`, only active locations are returned.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_page(
+    pool: &SqlitePool,
+    sort: LocationSort,
+    include_deleted: bool,
+    page: &PageRequest,
+) -> Result<Page<Location>, crate::db::DbError> {
+    let key = sort.key();
+    let mut conditions = vec!["household_id = ?".to_string()];
+    let mut binds = vec![crate::db::page::Value::Text(
+        current_household().to_string(),
+    )];
+    if !include_deleted {
+        conditions.push("deleted_at IS NULL".to_string());
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "SELECT id, name, deleted_at, {} AS sort_key FROM locations WHERE {} {}",
+        key.expr,
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: String = row.get("id");
+        let name: String = row.get("name");
+        let deleted_at: Option<i64> = row.get("deleted_at");
+        let location = row_to_location(&id, &name, deleted_at)?;
+        out.push((location, key.read_key(&row), id));
+    }
+    Ok(key.finish(out, page))
+}
+
 /// Insert a location into the database.
 ///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure.
 pub async fn insert(pool: &SqlitePool, location: &Location) -> Result<(), crate::db::DbError> {
-    sqlx::query("INSERT INTO locations (id, name, deleted_at) VALUES (?, ?, ?)")
-        .bind(location.id().to_string())
-        .bind(location.name())
-        .bind(location.deleted_at())
-        .execute(pool)
-        .await?;
+    sqlx::query(
+        "INSERT INTO locations (id, name, deleted_at, updated_at, household_id) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(location.id().to_string())
+    .bind(location.name())
+    .bind(location.deleted_at())
+    .bind(chrono::Utc::now().timestamp())
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
     invalidate_location_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Location,
+        location.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
@@ -210,13 +323,26 @@ pub async fn insert(pool: &SqlitePool, location: &Location) -> Result<(), crate:
 ///
 /// Returns [`crate::db::DbError`] on query failure.
 pub async fn update(pool: &SqlitePool, location: &Location) -> Result<(), crate::db::DbError> {
-    sqlx::query("UPDATE locations SET name = ?, deleted_at = ? WHERE id = ?")
-        .bind(location.name())
-        .bind(location.deleted_at())
-        .bind(location.id().to_string())
-        .execute(pool)
-        .await?;
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Location, location.id()).await?;
+    sqlx::query(
+        "UPDATE locations SET name = ?, deleted_at = ?, updated_at = ? WHERE id = ? AND household_id = ?",
+    )
+    .bind(location.name())
+    .bind(location.deleted_at())
+    .bind(chrono::Utc::now().timestamp())
+    .bind(location.id().to_string())
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
     invalidate_location_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Location,
+        location.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -241,21 +367,32 @@ async fn ensure_no_purchases(
 ///
 /// Fails if the location has any purchases.
 ///
+/// `deleted_by` is the acting user (`None` when deleted from the CLI).
+///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// the location has purchases or no active location exists with the given id.
-pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+pub async fn soft_delete(
+    pool: &SqlitePool,
+    id: Uuid,
+    deleted_by: Option<Uuid>,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Location, id).await?;
     let id_str = id.to_string();
     ensure_no_purchases(pool, &id_str).await?;
 
     let now = chrono::Utc::now().timestamp();
-    let result =
-        sqlx::query("UPDATE locations SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
-            .bind(now)
-            .bind(&id_str)
-            .execute(pool)
-            .await?;
+    let result = sqlx::query(
+        "UPDATE locations SET deleted_at = ?, updated_at = ?, deleted_by = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
+    )
+    .bind(now)
+    .bind(now)
+    .bind(deleted_by.map(|u| u.to_string()))
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
 
     if result.rows_affected() == 0 {
         return Err(crate::db::DbError::InvalidData(format!(
@@ -264,6 +401,50 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     invalidate_location_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Location,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Restore a soft-deleted location. Clears `deleted_at`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
+/// no soft-deleted location exists with the given id.
+pub async fn restore(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Location, id).await?;
+    let id_str = id.to_string();
+    let result = sqlx::query(
+        "UPDATE locations SET deleted_at = NULL, deleted_by = NULL, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "location not found or not deleted: {id_str}"
+        )));
+    }
+
+    invalidate_location_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Location,
+        id,
+        AuditAction::Restore,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -276,11 +457,13 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// the location has purchases.
 pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Location, id).await?;
     let id_str = id.to_string();
     ensure_no_purchases(pool, &id_str).await?;
 
-    let result = sqlx::query("DELETE FROM locations WHERE id = ?")
+    let result = sqlx::query("DELETE FROM locations WHERE id = ? AND household_id = ?")
         .bind(&id_str)
+        .bind(current_household().to_string())
         .execute(pool)
         .await?;
 
@@ -291,5 +474,13 @@ pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     invalidate_location_list_cache();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Location,
+        id,
+        AuditAction::HardDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
@@ -0,0 +1,88 @@
+//! Failed login attempt persistence for brute-force lockout (see [`crate::auth::lockout`]).
+//!
+//! Provides DB functions: [`locked_until`], [`record_failure`], and [`clear`]. Attempts are not
+//! household scoped.
+
+use sqlx::{Row, SqlitePool};
+
+use crate::auth::lockout;
+
+/// Latest lockout end after `now` among `keys`, or `None` if none of them is locked.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn locked_until(
+    pool: &SqlitePool,
+    keys: &[String],
+    now: i64,
+) -> Result<Option<i64>, crate::db::DbError> {
+    let mut latest: Option<i64> = None;
+    for key in keys {
+        let row = sqlx::query(
+            "SELECT locked_until FROM login_attempts WHERE key = ? AND locked_until > ?",
+        )
+        .bind(key)
+        .bind(now)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(row) = row {
+            let until: i64 = row.get("locked_until");
+            latest = Some(latest.map_or(until, |l| l.max(until)));
+        }
+    }
+    Ok(latest)
+}
+
+/// Count a failed attempt for `key` at `now` and lock it if the policy says so. Returns the new
+/// lockout end, if any.
+///
+/// The count is bumped in a single upsert, so concurrent failures are all counted; the lockout is
+/// only written while the count is still the one it was computed for, so the attempt that counted
+/// last sets the lockout.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn record_failure(
+    pool: &SqlitePool,
+    key: &str,
+    now: i64,
+) -> Result<Option<i64>, crate::db::DbError> {
+    let row = sqlx::query(
+        "INSERT INTO login_attempts (key, failures, last_failure_at, locked_until) VALUES (?, 1, ?, NULL)
+         ON CONFLICT (key) DO UPDATE SET
+             failures = CASE WHEN excluded.last_failure_at - last_failure_at < ? THEN failures + 1 ELSE 1 END,
+             last_failure_at = excluded.last_failure_at
+         RETURNING failures",
+    )
+    .bind(key)
+    .bind(now)
+    .bind(lockout::FAILURE_WINDOW_SECS)
+    .fetch_one(pool)
+    .await?;
+    let failures: i64 = row.get("failures");
+    let locked_until = lockout::lockout_secs(failures).map(|secs| now.saturating_add(secs));
+    if locked_until.is_some() {
+        sqlx::query("UPDATE login_attempts SET locked_until = ? WHERE key = ? AND failures = ?")
+            .bind(locked_until)
+            .bind(key)
+            .bind(failures)
+            .execute(pool)
+            .await?;
+    }
+    Ok(locked_until)
+}
+
+/// Forget failed attempts (and any lockout) for `key`. Returns `false` if there were none.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn clear(pool: &SqlitePool, key: &str) -> Result<bool, crate::db::DbError> {
+    let result = sqlx::query("DELETE FROM login_attempts WHERE key = ?")
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
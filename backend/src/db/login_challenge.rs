@@ -0,0 +1,93 @@
+//! Two-factor login challenge persistence: issued after a correct password, redeemed once with a
+//! TOTP or recovery code.
+//!
+//! Provides DB functions: [`insert`], [`get_active_by_hash`], and [`consume`]. Challenges are not
+//! household scoped; each one records the household the login is for.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+/// A pending login challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub household_id: Uuid,
+}
+
+/// Insert a challenge (by the hash of its token) for `user_id` in `household_id`, valid until
+/// `expires_at`. Returns the id of the new challenge.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn insert(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    household_id: Uuid,
+    token_hash: &str,
+    created_at: i64,
+    expires_at: i64,
+) -> Result<Uuid, crate::db::DbError> {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO login_challenges (id, user_id, household_id, token_hash, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind(user_id.to_string())
+    .bind(household_id.to_string())
+    .bind(token_hash)
+    .bind(created_at)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+/// Fetch the unused, unexpired challenge with `token_hash`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or invalid stored ids.
+pub async fn get_active_by_hash(
+    pool: &SqlitePool,
+    token_hash: &str,
+    now: i64,
+) -> Result<Option<LoginChallenge>, crate::db::DbError> {
+    let row = sqlx::query(
+        "SELECT id, user_id, household_id FROM login_challenges
+         WHERE token_hash = ? AND used_at IS NULL AND expires_at > ?",
+    )
+    .bind(token_hash)
+    .bind(now)
+    .fetch_optional(pool)
+    .await?;
+    row.map(|row| {
+        let parse = |column: &str| {
+            let value: String = row.get(column);
+            Uuid::parse_str(&value).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+        };
+        Ok(LoginChallenge {
+            id: parse("id")?,
+            user_id: parse("user_id")?,
+            household_id: parse("household_id")?,
+        })
+    })
+    .transpose()
+}
+
+/// Use up a challenge at `now`. Returns `false` if it was already used, so a challenge can only
+/// complete one login.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn consume(pool: &SqlitePool, id: Uuid, now: i64) -> Result<bool, crate::db::DbError> {
+    let result =
+        sqlx::query("UPDATE login_challenges SET used_at = ? WHERE id = ? AND used_at IS NULL")
+            .bind(now)
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
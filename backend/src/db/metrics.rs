@@ -0,0 +1,58 @@
+//! Row counts for the metrics endpoint.
+//!
+//! Provides [`entity_counts`]. Unlike the other `db` modules these counts span all households:
+//! they describe the instance, not one household's data.
+
+use sqlx::{Row, SqlitePool};
+
+/// Tables with soft deletes whose rows are counted, in output order.
+const SOFT_DELETED_TABLES: [&str; 7] = [
+    "users",
+    "categories",
+    "products",
+    "product_variations",
+    "locations",
+    "purchases",
+    "reviews",
+];
+
+/// Number of rows of one entity type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityCount {
+    /// Table name, e.g. `products`.
+    pub entity: &'static str,
+    pub active: i64,
+    /// Soft-deleted rows (in the trash). Always 0 for households.
+    pub deleted: i64,
+}
+
+/// Count active and soft-deleted rows of each entity type, plus households.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn entity_counts(pool: &SqlitePool) -> Result<Vec<EntityCount>, crate::db::DbError> {
+    let mut counts = Vec::with_capacity(SOFT_DELETED_TABLES.len() + 1);
+    let households: i64 = sqlx::query("SELECT COUNT(*) FROM households")
+        .fetch_one(pool)
+        .await?
+        .try_get(0)?;
+    counts.push(EntityCount {
+        entity: "households",
+        active: households,
+        deleted: 0,
+    });
+    for table in SOFT_DELETED_TABLES {
+        let row = sqlx::query(&format!(
+            "SELECT COALESCE(SUM(deleted_at IS NULL), 0), COALESCE(SUM(deleted_at IS NOT NULL), 0) FROM {table}"
+        ))
+        .fetch_one(pool)
+        .await?;
+        counts.push(EntityCount {
+            entity: table,
+            active: row.try_get(0)?,
+            deleted: row.try_get(1)?,
+        });
+    }
+    Ok(counts)
+}
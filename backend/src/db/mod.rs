@@ -1,13 +1,31 @@
 //! Database setup: connection pool and migrations.
 
+use sqlx::migrate::Migrator;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 
+pub mod api_token;
+pub mod audit;
 pub mod category;
+pub mod health;
+pub mod household;
+pub mod idempotency;
+pub mod invite;
 pub mod location;
+pub mod login_attempt;
+pub mod login_challenge;
+pub mod metrics;
+pub mod page;
+pub mod password_reset;
 pub mod product;
+pub mod product_search;
 pub mod product_variation;
 pub mod purchase;
 pub mod review;
+pub mod session;
+pub mod stats;
+pub mod sync;
+pub mod totp;
+pub mod trash;
 pub mod user;
 
 /// Errors that can occur during database operations.
@@ -56,12 +74,39 @@ pub async fn vacuum_into(pool: &SqlitePool, path: &str) -> Result<(), DbError> {
     Ok(())
 }
 
+/// Fail unless `table` holds an active (not soft-deleted) row with the given id. Used by the
+/// `restore` functions so a row is not brought back under a deleted parent.
+///
+/// # Errors
+///
+/// Returns [`DbError::Sqlx`] on query failure, or [`DbError::InvalidData`] naming the `child` and
+/// `parent` kinds when the parent is missing or soft-deleted.
+pub(crate) async fn ensure_active_parent(
+    pool: &SqlitePool,
+    table: &str,
+    id: &str,
+    child: &str,
+    parent: &str,
+) -> Result<(), DbError> {
+    let sql = format!("SELECT COUNT(*) FROM {table} WHERE id = ? AND deleted_at IS NULL");
+    let count: i64 = sqlx::query_scalar(&sql).bind(id).fetch_one(pool).await?;
+    if count == 0 {
+        return Err(DbError::InvalidData(format!(
+            "cannot restore {child} with deleted {parent}: {id}"
+        )));
+    }
+    Ok(())
+}
+
+/// Migrations embedded in the binary from `migrations/`.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 /// Run all pending migrations against the given pool.
 ///
 /// # Errors
 ///
 /// Returns [`DbError::Migrate`] if any migration fails.
 pub async fn run_migrations(pool: &SqlitePool) -> Result<(), DbError> {
-    sqlx::migrate!("./migrations").run(pool).await?;
+    MIGRATOR.run(pool).await?;
     Ok(())
 }
@@ -0,0 +1,255 @@
+//! Keyset (cursor) pagination shared by the `list_page` functions.
+//!
+//! Rows are ordered by a sort key with the row id as tie-breaker, so the order is stable even
+//! when many rows share the same key. A [`Cursor`] records the sort name, key and id of the last
+//! row of a page; the next page continues strictly after it. Cursors are opaque, URL-safe strings
+//! for clients (see [`Cursor::encode`]).
+
+use std::fmt::Write as _;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Row, Sqlite};
+
+/// Maximum number of rows per page.
+pub const MAX_LIMIT: u32 = 500;
+
+/// Sort direction of a sort key. The id tie-breaker always follows the same direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// SQL type of a sort key expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    Int,
+    Real,
+    Text,
+}
+
+/// A typed SQL value: a sort key stored in a cursor, or a bind parameter of a dynamic query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Real(f64),
+    Text(String),
+}
+
+/// Position after which the next page starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    /// Sort name the cursor was issued for (e.g. `"updated_at"`).
+    pub sort: String,
+    /// Sort key of the last row.
+    pub key: Value,
+    /// Id of the last row.
+    pub id: String,
+}
+
+impl Cursor {
+    /// Encode as an opaque URL-safe string.
+    #[must_use]
+    pub fn encode(&self) -> String {
+        let key = match &self.key {
+            Value::Int(n) => format!("i:{n}"),
+            Value::Real(f) => format!("r:{f}"),
+            Value::Text(s) => format!("t:{s}"),
+        };
+        URL_SAFE_NO_PAD.encode(format!("{}\n{}\n{key}", self.sort, self.id))
+    }
+
+    /// Decode a string produced by [`Cursor::encode`]. Returns `None` if it is malformed.
+    #[must_use]
+    pub fn decode(s: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(s).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        let mut parts = text.splitn(3, '\n');
+        let sort = parts.next()?.to_string();
+        let id = parts.next()?.to_string();
+        let key = parts.next()?;
+        let key = if let Some(n) = key.strip_prefix("i:") {
+            Value::Int(n.parse().ok()?)
+        } else if let Some(f) = key.strip_prefix("r:") {
+            Value::Real(f.parse().ok()?)
+        } else {
+            Value::Text(key.strip_prefix("t:")?.to_string())
+        };
+        Some(Self { sort, key, id })
+    }
+}
+
+/// Requested page: at most `limit` rows (all rows when `None`) after `after`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageRequest {
+    pub limit: Option<u32>,
+    pub after: Option<Cursor>,
+}
+
+/// One page of rows and the cursor of the next page (`None` on the last page).
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Sort key definition used to build a keyset query.
+pub struct SortKey<'a> {
+    /// Sort name, stored in cursors.
+    pub name: &'a str,
+    /// SQL expression of the key. Must never be NULL (use `COALESCE` with a sentinel).
+    pub expr: &'a str,
+    /// SQL expression of the row id (tie-breaker).
+    pub id_expr: &'a str,
+    pub kind: KeyKind,
+    pub direction: Direction,
+}
+
+impl SortKey<'_> {
+    /// Condition selecting rows strictly after the cursor, pushing its binds. `None` without
+    /// a cursor.
+    pub(crate) fn after_condition(
+        &self,
+        page: &PageRequest,
+        binds: &mut Vec<Value>,
+    ) -> Option<String> {
+        let cursor = page.after.as_ref()?;
+        binds.push(cursor.key.clone());
+        binds.push(Value::Text(cursor.id.clone()));
+        let op = match self.direction {
+            Direction::Asc => ">",
+            Direction::Desc => "<",
+        };
+        Some(format!("({}, {}) {op} (?, ?)", self.expr, self.id_expr))
+    }
+
+    /// `ORDER BY ... LIMIT ...` clause. Fetches one extra row to detect a next page.
+    pub(crate) fn order_and_limit(&self, page: &PageRequest) -> String {
+        let dir = match self.direction {
+            Direction::Asc => "ASC",
+            Direction::Desc => "DESC",
+        };
+        let mut sql = format!("ORDER BY {} {dir}, {} {dir}", self.expr, self.id_expr);
+        if let Some(limit) = page.limit {
+            let _ = write!(sql, " LIMIT {}", u64::from(limit) + 1);
+        }
+        sql
+    }
+
+    /// Read the `sort_key` column of a row (the key expression selected `AS sort_key`).
+    pub(crate) fn read_key(&self, row: &SqliteRow) -> Value {
+        match self.kind {
+            KeyKind::Int => Value::Int(row.get("sort_key")),
+            KeyKind::Real => Value::Real(row.get("sort_key")),
+            KeyKind::Text => Value::Text(row.get("sort_key")),
+        }
+    }
+
+    /// Trim the extra row and build the page, using the last returned row's key and id as the
+    /// next cursor.
+    pub(crate) fn finish<T>(
+        &self,
+        mut rows: Vec<(T, Value, String)>,
+        page: &PageRequest,
+    ) -> Page<T> {
+        let has_more = page
+            .limit
+            .is_some_and(|limit| rows.len() > usize::try_from(limit).unwrap_or(usize::MAX));
+        if let Some(limit) = page.limit {
+            rows.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        }
+        let next_cursor = if has_more {
+            rows.last().map(|(_, key, id)| {
+                Cursor {
+                    sort: self.name.to_string(),
+                    key: key.clone(),
+                    id: id.clone(),
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+        Page {
+            items: rows.into_iter().map(|(item, _, _)| item).collect(),
+            next_cursor,
+        }
+    }
+}
+
+/// Bind typed values to a dynamic query in order.
+pub(crate) fn bind_values<'q>(
+    mut query: sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>,
+    binds: &'q [Value],
+) -> sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>> {
+    for b in binds {
+        query = match b {
+            Value::Int(n) => query.bind(*n),
+            Value::Real(f) => query.bind(*f),
+            Value::Text(s) => query.bind(s.as_str()),
+        };
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_roundtrip_each_key_type() {
+        for key in [
+            Value::Int(1_708_012_800),
+            Value::Real(2.99),
+            Value::Text("Crème\nfraîche".to_string()),
+        ] {
+            let cursor = Cursor {
+                sort: "name".to_string(),
+                key,
+                id: "0f8e2a3c-0000-4000-8000-000000000001".to_string(),
+            };
+            assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        }
+    }
+
+    #[test]
+    fn cursor_decode_rejects_garbage() {
+        assert_eq!(Cursor::decode("not a cursor!"), None);
+        assert_eq!(
+            Cursor::decode(&URL_SAFE_NO_PAD.encode("name\nid\nx:1")),
+            None
+        );
+        assert_eq!(Cursor::decode(&URL_SAFE_NO_PAD.encode("name")), None);
+    }
+
+    #[test]
+    fn finish_sets_next_cursor_only_when_more_rows() {
+        let key = SortKey {
+            name: "name",
+            expr: "name",
+            id_expr: "id",
+            kind: KeyKind::Text,
+            direction: Direction::Asc,
+        };
+        let page = PageRequest {
+            limit: Some(2),
+            after: None,
+        };
+        let rows = vec![
+            ("a", Value::Text("a".to_string()), "1".to_string()),
+            ("b", Value::Text("b".to_string()), "2".to_string()),
+            ("c", Value::Text("c".to_string()), "3".to_string()),
+        ];
+        let full = key.finish(rows.clone(), &page);
+        assert_eq!(full.items, vec!["a", "b"]);
+        let next = Cursor::decode(full.next_cursor.as_deref().expect("next")).expect("decode");
+        assert_eq!(next.id, "2");
+        assert_eq!(next.key, Value::Text("b".to_string()));
+
+        let last = key.finish(rows[..2].to_vec(), &page);
+        assert_eq!(last.items.len(), 2);
+        assert!(last.next_cursor.is_none());
+    }
+}
@@ -0,0 +1,80 @@
+//! Password reset token persistence.
+//!
+//! Provides DB functions: [`insert`], [`consume`], and [`invalidate_for_user`]. Reset tokens are
+//! not household scoped.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+/// Insert a reset token (by the hash of its secret) for `user_id`, valid until `expires_at`.
+/// Returns the id of the new reset.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn insert(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    token_hash: &str,
+    created_at: i64,
+    expires_at: i64,
+) -> Result<Uuid, crate::db::DbError> {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO password_resets (id, user_id, token_hash, created_at, expires_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind(user_id.to_string())
+    .bind(token_hash)
+    .bind(created_at)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+/// Use up the unused, unexpired reset token with `token_hash` and return its user id. Returns
+/// `None` if there is no such token; a token can only be consumed once.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or an invalid stored user id.
+pub async fn consume(
+    pool: &SqlitePool,
+    token_hash: &str,
+    now: i64,
+) -> Result<Option<Uuid>, crate::db::DbError> {
+    let row = sqlx::query(
+        "UPDATE password_resets SET used_at = ?
+         WHERE token_hash = ? AND used_at IS NULL AND expires_at > ?
+         RETURNING user_id",
+    )
+    .bind(now)
+    .bind(token_hash)
+    .bind(now)
+    .fetch_optional(pool)
+    .await?;
+    row.map(|row| {
+        let user_id: String = row.get("user_id");
+        Uuid::parse_str(&user_id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+    })
+    .transpose()
+}
+
+/// Mark all unused reset tokens of a user as used. Returns how many were invalidated.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn invalidate_for_user(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<u64, crate::db::DbError> {
+    let result =
+        sqlx::query("UPDATE password_resets SET used_at = ? WHERE user_id = ? AND used_at IS NULL")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(user_id.to_string())
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
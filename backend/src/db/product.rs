@@ -1,8 +1,12 @@
 //! Product persistence.
 //!
-//! Provides DB functions: [`get_by_id`], [`get_by_id_with_relations`], [`get_all`],
-//! [`get_all_by_category_id`], [`get_all_filtered`], [`list_with_relations`], [`insert`],
-//! [`update`], [`soft_delete`], and [`hard_delete`].
+//! Provides DB functions: [`get_by_id`], [`get_by_id_with_relations`],
+//! [`get_by_id_with_aggregates`], [`get_all`], [`get_all_by_category_id`], [`get_all_filtered`],
+//! [`list_with_relations`], [`insert`], [`list_page`], [`update`], [`soft_delete`],
+//! [`restore`], and [`hard_delete`].
+//!
+//! All functions read and write only products of the current household (see
+//! [`crate::db::household`]).
 
 use std::collections::HashMap;
 use std::sync::{OnceLock, RwLock};
@@ -11,7 +15,12 @@ use rust_decimal::Decimal;
 use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::db::household::current_household;
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, Value, bind_values};
 use crate::domain::product::Product;
+use crate::domain::product_variation::{Unit, UnitPrice};
+use crate::metrics;
 
 /// True when the process is the production binary (`main()` has run). False in test binaries so the
 /// cache is off unless a test explicitly enables it via [`set_use_product_list_cache_for_test`].
@@ -41,26 +50,39 @@ pub fn set_use_product_list_cache_for_test(use_cache: bool) {
     USE_CACHE_IN_TEST.with(|v| v.store(use_cache, std::sync::atomic::Ordering::SeqCst));
 }
 
-/// Module-level cache for the full product list (plain [`Product`], including deleted). Used by
-/// [`get_all`]. When `include_deleted` is false the result is filtered on read.
-fn simple_product_list_cache() -> &'static RwLock<Option<Vec<Product>>> {
-    static CACHE: OnceLock<RwLock<Option<Vec<Product>>>> = OnceLock::new();
-    CACHE.get_or_init(|| RwLock::new(None))
+/// Module-level cache for the full product list (plain [`Product`], including deleted) of each
+/// household. Used by [`get_all`]. When `include_deleted` is false the result is filtered on read.
+fn simple_product_list_cache() -> &'static RwLock<HashMap<Uuid, Vec<Product>>> {
+    static CACHE: OnceLock<RwLock<HashMap<Uuid, Vec<Product>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
 fn invalidate_simple_product_list_cache() {
-    let _ = simple_product_list_cache().write().map(|mut g| *g = None);
+    let _ = simple_product_list_cache().write().map(|mut g| g.clear());
 }
 
-/// Module-level cache for the full product list with relations (including deleted). Used by
-/// `list_with_relations`.
-fn product_list_cache() -> &'static RwLock<Option<Vec<ProductWithRelations>>> {
-    static CACHE: OnceLock<RwLock<Option<Vec<ProductWithRelations>>>> = OnceLock::new();
-    CACHE.get_or_init(|| RwLock::new(None))
+/// Module-level cache for the full product list with relations (including deleted) of each
+/// household. Used by `list_with_relations`.
+fn product_list_cache() -> &'static RwLock<HashMap<Uuid, Vec<ProductWithRelations>>> {
+    static CACHE: OnceLock<RwLock<HashMap<Uuid, Vec<ProductWithRelations>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
 fn invalidate_product_list_cache() {
-    let _ = product_list_cache().write().map(|mut g| *g = None);
+    let _ = product_list_cache().write().map(|mut g| g.clear());
+}
+
+/// Store `list` as the current household's entry of `cache`, or remove that entry on `None`.
+fn set_household_cache_entry<T>(cache: &RwLock<HashMap<Uuid, T>>, list: Option<T>) {
+    let household = current_household();
+    let _ = cache.write().map(|mut g| match list {
+        Some(list) => {
+            g.insert(household, list);
+        }
+        None => {
+            g.remove(&household);
+        }
+    });
 }
 
 /// Invalidate both product caches. Call on product insert/update/delete, and on review or
@@ -72,23 +94,24 @@ pub fn invalidate_all_product_caches() {
 
 /// Clear the product list cache. For use by cache tests so they start from a known state.
 pub fn clear_product_list_cache() {
-    let _ = product_list_cache().write().map(|mut g| *g = None);
+    invalidate_product_list_cache();
 }
 
 /// Clear the simple product list cache (used by [`get_all`]). For use by cache tests.
 pub fn clear_simple_product_list_cache() {
-    let _ = simple_product_list_cache().write().map(|mut g| *g = None);
+    invalidate_simple_product_list_cache();
 }
 
-/// Set the product list cache to a specific value. For use by cache tests to verify that
-/// `list_with_relations` returns cached data when the cache is populated.
+/// Set the current household's product list cache to a specific value. For use by cache tests
+/// to verify that `list_with_relations` returns cached data when the cache is populated.
 pub fn set_product_list_cache_for_test(list: Option<Vec<ProductWithRelations>>) {
-    let _ = product_list_cache().write().map(|mut g| *g = list);
+    set_household_cache_entry(product_list_cache(), list);
 }
 
-/// Set the simple product list cache. For use by cache tests that verify [`get_all`] uses cache.
+/// Set the current household's simple product list cache. For use by cache tests that verify
+/// [`get_all`] uses cache.
 pub fn set_simple_product_list_cache_for_test(list: Option<Vec<Product>>) {
-    let _ = simple_product_list_cache().write().map(|mut g| *g = list);
+    set_household_cache_entry(simple_product_list_cache(), list);
 }
 
 /// One product row with joined category name and breadcrumb ancestors for API responses.
@@ -108,6 +131,9 @@ pub struct ProductWithRelations {
     pub review_score: Option<Decimal>,
     /// Lowest purchase price (all non-deleted purchases for this product). None if no purchases.
     pub lowest_price: Option<String>,
+    /// Lowest price per kg or l (non-deleted purchases of variations with grams or milliliters
+    /// and a quantity). None if no such purchases.
+    pub lowest_unit_price: Option<UnitPrice>,
 }
 
 /// Map a DB row (with `category_name` from JOIN) into [`ProductWithRelations`].
@@ -137,18 +163,47 @@ fn row_to_product_with_relations(
         deleted_at,
         review_score: None,
         lowest_price: None,
+        lowest_unit_price: None,
     })
 }
 
-/// Fetch per-product median review score and lowest purchase price (non-deleted only).
-/// Returns (`product_id` -> median rating, `product_id` -> min price as string).
+/// Per-product aggregates over non-deleted reviews and purchases, keyed by product id.
+struct ProductAggregates {
+    median_review_score: HashMap<Uuid, Decimal>,
+    lowest_price: HashMap<Uuid, String>,
+    lowest_unit_price: HashMap<Uuid, UnitPrice>,
+}
+
+/// Fetch per-product median review score, lowest purchase price, and lowest price per kg or l
+/// (non-deleted only), for all products of the household or only those in `product_ids`.
 async fn fetch_product_aggregates(
     pool: &SqlitePool,
-) -> Result<(HashMap<Uuid, Decimal>, HashMap<Uuid, String>), crate::db::DbError> {
-    let review_rows =
-        sqlx::query("SELECT product_id, rating FROM reviews WHERE deleted_at IS NULL")
-            .fetch_all(pool)
-            .await?;
+    product_ids: Option<&[Uuid]>,
+) -> Result<ProductAggregates, crate::db::DbError> {
+    let (only_reviews, only_purchases) = product_ids.map_or_else(
+        || (String::new(), String::new()),
+        |ids| {
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            (
+                format!(" AND product_id IN ({placeholders})"),
+                format!(" AND pu.product_id IN ({placeholders})"),
+            )
+        },
+    );
+    let ids: Vec<Value> = product_ids
+        .unwrap_or_default()
+        .iter()
+        .map(|id| Value::Text(id.to_string()))
+        .collect();
+    let household = [Value::Text(current_household().to_string())];
+    let binds: Vec<Value> = household.iter().chain(&ids).cloned().collect();
+
+    let review_sql = format!(
+        "SELECT product_id, rating FROM reviews WHERE household_id = ? AND deleted_at IS NULL{only_reviews}"
+    );
+    let review_rows = bind_values(sqlx::query(&review_sql), &binds)
+        .fetch_all(pool)
+        .await?;
     let mut by_product: HashMap<Uuid, Vec<Decimal>> = HashMap::new();
     for row in review_rows {
         let product_id_str: String = row.get("product_id");
@@ -163,34 +218,49 @@ async fn fetch_product_aggregates(
     let median_by_product: HashMap<Uuid, Decimal> = by_product
         .into_iter()
         .filter_map(|(product_id, mut ratings)| {
-            ratings.sort();
-            let len = ratings.len();
-            if len == 0 {
-                return None;
-            }
-            let median = if len % 2 == 1 {
-                ratings[len / 2]
-            } else {
-                let mid = len / 2;
-                (ratings[mid - 1] + ratings[mid]) / Decimal::from(2)
-            };
-            Some((product_id, median))
+            Some((product_id, crate::db::stats::median(&mut ratings)?))
         })
         .collect();
 
-    let purchase_rows =
-        sqlx::query("SELECT product_id, price FROM purchases WHERE deleted_at IS NULL")
-            .fetch_all(pool)
-            .await?;
+    let purchase_sql = format!(
+        "SELECT pu.product_id, pu.price, v.unit, v.quantity FROM purchases pu \
+         JOIN product_variations v ON pu.variation_id = v.id \
+         WHERE pu.household_id = ? AND pu.deleted_at IS NULL{only_purchases}"
+    );
+    let purchase_rows = bind_values(sqlx::query(&purchase_sql), &binds)
+        .fetch_all(pool)
+        .await?;
     let mut price_by_product: HashMap<Uuid, Vec<Decimal>> = HashMap::new();
+    let mut lowest_unit_price_by_product: HashMap<Uuid, UnitPrice> = HashMap::new();
     for row in purchase_rows {
         let product_id_str: String = row.get("product_id");
         let price_str: String = row.get("price");
+        let unit_str: String = row.get("unit");
+        // SQLite INTEGER is i64; domain uses Option<u32>, so convert (negative -> None).
+        let quantity: Option<i64> = row.get("quantity");
+        let quantity = quantity.and_then(|q| u32::try_from(q).ok());
         let product_id = Uuid::parse_str(&product_id_str)
             .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
         let price: Decimal = price_str
             .parse()
             .map_err(|e: rust_decimal::Error| crate::db::DbError::InvalidData(e.to_string()))?;
+        let unit: Unit =
+            unit_str
+                .parse()
+                .map_err(|e: crate::domain::product_variation::ValidationError| {
+                    crate::db::DbError::InvalidData(e.to_string())
+                })?;
+        if let Some(unit_price) = UnitPrice::from_item_price(price, unit, quantity) {
+            lowest_unit_price_by_product
+                .entry(product_id)
+                .and_modify(|lowest| {
+                    // Per kg before per l, as in the `unit_price` sort.
+                    if (unit_price.per, unit_price.price) < (lowest.per, lowest.price) {
+                        *lowest = unit_price;
+                    }
+                })
+                .or_insert(unit_price);
+        }
         price_by_product.entry(product_id).or_default().push(price);
     }
     let lowest_price_by_product: HashMap<Uuid, String> = price_by_product
@@ -201,7 +271,11 @@ async fn fetch_product_aggregates(
         })
         .collect();
 
-    Ok((median_by_product, lowest_price_by_product))
+    Ok(ProductAggregates {
+        median_review_score: median_by_product,
+        lowest_price: lowest_price_by_product,
+        lowest_unit_price: lowest_unit_price_by_product,
+    })
 }
 
 /// Map a DB row into a [`Product`]. Fails on invalid UUID or domain validation.
@@ -245,16 +319,18 @@ pub async fn get_by_id(
     let id_str = id.to_string();
     let row = if include_deleted {
         sqlx::query(
-            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE id = ?",
+            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE id = ? AND household_id = ?",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
@@ -286,8 +362,9 @@ pub async fn get_by_id(
 /// Fetch all products from the database (active and soft-deleted). Used to fill the cache.
 async fn fetch_all_products_raw(pool: &SqlitePool) -> Result<Vec<Product>, crate::db::DbError> {
     let rows = sqlx::query(
-        "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products",
+        "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE household_id = ?",
     )
+    .bind(current_household().to_string())
     .fetch_all(pool)
     .await?;
     rows_to_products(rows)
@@ -299,8 +376,8 @@ async fn fetch_all_products_raw(pool: &SqlitePool) -> Result<Vec<Product>, crate
 /// `true`, soft-deleted products are included. The cache always stores the full list (including
 /// deleted); when `include_deleted` is `false` the result is filtered on read.
 ///
-/// When not in test, results are cached in memory; the cache is invalidated on any product
-/// insert, update, or delete.
+/// When not in test, results are cached in memory per household; the cache is invalidated on any
+/// product insert, update, or delete.
 ///
 /// # Errors
 ///
@@ -309,10 +386,12 @@ pub async fn get_all(
     pool: &SqlitePool,
     include_deleted: bool,
 ) -> Result<Vec<Product>, crate::db::DbError> {
+    let household = current_household();
     if use_cache()
         && let Ok(guard) = simple_product_list_cache().read()
-        && let Some(ref list) = *guard
+        && let Some(list) = guard.get(&household)
     {
+        metrics::PRODUCT_LIST_CACHE.hit();
         return Ok(if include_deleted {
             list.clone()
         } else {
@@ -320,12 +399,15 @@ pub async fn get_all(
         });
     }
 
+    if use_cache() {
+        metrics::PRODUCT_LIST_CACHE.miss();
+    }
     let list = fetch_all_products_raw(pool).await?;
 
     if use_cache()
         && let Ok(mut guard) = simple_product_list_cache().write()
     {
-        *guard = Some(list.clone());
+        guard.insert(household, list.clone());
     }
 
     Ok(if include_deleted {
@@ -350,8 +432,9 @@ pub async fn get_all_by_category_id(
 ) -> Result<Vec<Product>, crate::db::DbError> {
     if use_cache()
         && let Ok(guard) = simple_product_list_cache().read()
-        && let Some(ref list) = *guard
+        && let Some(list) = guard.get(&current_household())
     {
+        metrics::PRODUCT_LIST_CACHE.hit();
         let filtered: Vec<Product> = list
             .iter()
             .filter(|p| p.category_id() == category_id && (include_deleted || p.is_active()))
@@ -359,14 +442,21 @@ pub async fn get_all_by_category_id(
             .collect();
         return Ok(filtered);
     }
+    if use_cache() {
+        metrics::PRODUCT_LIST_CACHE.miss();
+    }
 
     let cat_str = category_id.to_string();
     let sql = if include_deleted {
-        "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ?"
+        "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND household_id = ?"
     } else {
-        "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND deleted_at IS NULL"
+        "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND household_id = ? AND deleted_at IS NULL"
     };
-    let rows = sqlx::query(sql).bind(&cat_str).fetch_all(pool).await?;
+    let rows = sqlx::query(sql)
+        .bind(&cat_str)
+        .bind(current_household().to_string())
+        .fetch_all(pool)
+        .await?;
     rows_to_products(rows)
 }
 
@@ -387,14 +477,14 @@ pub async fn get_all_by_category_ids(
     let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
     let sql = if include_deleted {
         format!(
-            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id IN ({placeholders})"
+            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE household_id = ? AND category_id IN ({placeholders})"
         )
     } else {
         format!(
-            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id IN ({placeholders}) AND deleted_at IS NULL"
+            "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE household_id = ? AND category_id IN ({placeholders}) AND deleted_at IS NULL"
         )
     };
-    let mut query = sqlx::query(&sql);
+    let mut query = sqlx::query(&sql).bind(current_household().to_string());
     for id in ids {
         query = query.bind(id.to_string());
     }
@@ -444,11 +534,12 @@ pub async fn get_all_filtered(
         (None, Some(term)) => {
             let pattern = format!("%{term}%");
             let sql = if include_deleted {
-                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE (name LIKE ? OR brand LIKE ?)"
+                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE household_id = ? AND (name LIKE ? OR brand LIKE ?)"
             } else {
-                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE deleted_at IS NULL AND (name LIKE ? OR brand LIKE ?)"
+                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE household_id = ? AND deleted_at IS NULL AND (name LIKE ? OR brand LIKE ?)"
             };
             let rows = sqlx::query(sql)
+                .bind(current_household().to_string())
                 .bind(&pattern)
                 .bind(&pattern)
                 .fetch_all(pool)
@@ -459,12 +550,13 @@ pub async fn get_all_filtered(
             let cat_str = cid.to_string();
             let pattern = format!("%{term}%");
             let sql = if include_deleted {
-                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND (name LIKE ? OR brand LIKE ?)"
+                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND household_id = ? AND (name LIKE ? OR brand LIKE ?)"
             } else {
-                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND deleted_at IS NULL AND (name LIKE ? OR brand LIKE ?)"
+                "SELECT id, category_id, brand, name, created_at, updated_at, deleted_at FROM products WHERE category_id = ? AND household_id = ? AND deleted_at IS NULL AND (name LIKE ? OR brand LIKE ?)"
             };
             let rows = sqlx::query(sql)
                 .bind(&cat_str)
+                .bind(current_household().to_string())
                 .bind(&pattern)
                 .bind(&pattern)
                 .fetch_all(pool)
@@ -501,13 +593,16 @@ fn rows_to_products(
 }
 
 const PRODUCT_JOIN_SQL: &str = "SELECT p.id, p.category_id, p.brand, p.name, p.created_at, p.updated_at, p.deleted_at, c.name AS category_name \
-    FROM products p JOIN categories c ON p.category_id = c.id ORDER BY p.updated_at DESC";
+    FROM products p JOIN categories c ON p.category_id = c.id WHERE p.household_id = ? ORDER BY p.updated_at DESC";
 
 /// Fetch all products with relations from the database (active and soft-deleted). Used to fill the cache.
 async fn fetch_all_products_with_relations_raw(
     pool: &SqlitePool,
 ) -> Result<Vec<ProductWithRelations>, crate::db::DbError> {
-    let rows = sqlx::query(PRODUCT_JOIN_SQL).fetch_all(pool).await?;
+    let rows = sqlx::query(PRODUCT_JOIN_SQL)
+        .bind(current_household().to_string())
+        .fetch_all(pool)
+        .await?;
     let mut out = Vec::with_capacity(rows.len());
     for row in rows {
         let id: String = row.get("id");
@@ -529,44 +624,45 @@ async fn fetch_all_products_with_relations_raw(
             deleted_at,
         )?);
     }
-    let mut enriched = Vec::with_capacity(out.len());
-    for p in out {
-        let category_ancestors = crate::db::category::get_ancestors(pool, p.category_id).await?;
-        enriched.push(ProductWithRelations {
-            category_ancestors,
-            ..p
-        });
-    }
-    let (median_by_product, lowest_price_by_product) = fetch_product_aggregates(pool).await?;
-    let result = enriched
+    let aggregates = fetch_product_aggregates(pool, None).await?;
+    with_ancestors_and_aggregates(pool, out, &aggregates).await
+}
+
+/// Fill in category ancestors (one category list read for all products) and aggregates.
+async fn with_ancestors_and_aggregates(
+    pool: &SqlitePool,
+    products: Vec<ProductWithRelations>,
+    aggregates: &ProductAggregates,
+) -> Result<Vec<ProductWithRelations>, crate::db::DbError> {
+    let category_ids: Vec<Uuid> = products.iter().map(|p| p.category_id).collect();
+    let ancestors = crate::db::category::get_ancestors_of(pool, &category_ids).await?;
+    Ok(products
         .into_iter()
-        .map(|p| {
-            let review_score = median_by_product.get(&p.id).copied();
-            let lowest_price = lowest_price_by_product.get(&p.id).cloned();
-            ProductWithRelations {
-                review_score,
-                lowest_price,
-                ..p
-            }
+        .map(|p| ProductWithRelations {
+            category_ancestors: ancestors.get(&p.category_id).cloned().unwrap_or_default(),
+            review_score: aggregates.median_review_score.get(&p.id).copied(),
+            lowest_price: aggregates.lowest_price.get(&p.id).cloned(),
+            lowest_unit_price: aggregates.lowest_unit_price.get(&p.id).copied(),
+            ..p
         })
-        .collect();
-    Ok(result)
+        .collect())
 }
 
 /// Filter products in memory by `category_ids` (when Some, product's category must be in the set),
-/// search term (case-insensitive substring on name, brand, category name, and ancestor category
-/// names), and `deleted_at` when `include_deleted` is false.
+/// search matches (when Some, only these ids are kept, in this order) and `deleted_at` when
+/// `include_deleted` is false.
 fn filter_products(
     list: &[ProductWithRelations],
     category_ids: Option<&[Uuid]>,
-    q: Option<&str>,
+    matches: Option<&[Uuid]>,
     include_deleted: bool,
 ) -> Vec<ProductWithRelations> {
-    let search = q.map(str::trim).filter(|s| !s.is_empty());
-    let q_lower = search.map(str::to_lowercase);
     let category_set: Option<std::collections::HashSet<Uuid>> =
         category_ids.map(|ids| ids.iter().copied().collect());
-    list.iter()
+    let rank: Option<HashMap<Uuid, usize>> =
+        matches.map(|ids| ids.iter().enumerate().map(|(i, id)| (*id, i)).collect());
+    let mut out: Vec<ProductWithRelations> = list
+        .iter()
         .filter(|p| {
             if !include_deleted && p.deleted_at.is_some() {
                 return false;
@@ -576,29 +672,28 @@ fn filter_products(
             {
                 return false;
             }
-            if let Some(ref ql) = q_lower {
-                let name_ok = p.name.to_lowercase().contains(ql);
-                let brand_ok = p.brand.to_lowercase().contains(ql);
-                let category_ok = p.category_name.to_lowercase().contains(ql);
-                let ancestors_ok = p
-                    .category_ancestors
-                    .iter()
-                    .any(|a| a.name.to_lowercase().contains(ql));
-                if !name_ok && !brand_ok && !category_ok && !ancestors_ok {
-                    return false;
-                }
+            if let Some(ref rank) = rank
+                && !rank.contains_key(&p.id)
+            {
+                return false;
             }
             true
         })
         .cloned()
-        .collect()
+        .collect();
+    if let Some(rank) = rank {
+        out.sort_by_key(|p| rank.get(&p.id).copied());
+    }
+    out
 }
 
 /// List products with category name, optionally filtered by category set and/or search.
 ///
 /// When `category_ids` is `Some(ids)`, only products whose category is in `ids` are returned
-/// (e.g. from [`crate::db::category::get_category_and_descendant_ids`]). Excludes soft-deleted
-/// unless `include_deleted`. When cache is enabled, uses cached full list and filters in memory.
+/// (e.g. from [`crate::db::category::get_category_and_descendant_ids`]). When `q` is non-empty,
+/// only products matching the full-text search are returned, best match first (see
+/// [`crate::db::product_search`]). Excludes soft-deleted unless `include_deleted`. When cache is
+/// enabled, uses cached full list and filters in memory.
 ///
 /// # Errors
 ///
@@ -610,22 +705,273 @@ pub async fn list_with_relations(
     include_deleted: bool,
 ) -> Result<Vec<ProductWithRelations>, crate::db::DbError> {
     let cat_ids_ref = category_ids.as_deref();
+    let matches = match q.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(search) => Some(crate::db::product_search::search(pool, search).await?),
+        None => None,
+    };
+    let matches_ref = matches.as_deref();
+    let household = current_household();
     if use_cache()
         && let Ok(guard) = product_list_cache().read()
-        && let Some(ref list) = *guard
+        && let Some(list) = guard.get(&household)
     {
-        return Ok(filter_products(list, cat_ids_ref, q, include_deleted));
+        metrics::PRODUCT_RELATIONS_CACHE.hit();
+        return Ok(filter_products(
+            list,
+            cat_ids_ref,
+            matches_ref,
+            include_deleted,
+        ));
     }
 
+    if use_cache() {
+        metrics::PRODUCT_RELATIONS_CACHE.miss();
+    }
     let list = fetch_all_products_with_relations_raw(pool).await?;
 
     if use_cache()
         && let Ok(mut guard) = product_list_cache().write()
     {
-        *guard = Some(list.clone());
+        guard.insert(household, list.clone());
+    }
+
+    Ok(filter_products(
+        &list,
+        cat_ids_ref,
+        matches_ref,
+        include_deleted,
+    ))
+}
+
+/// Sort order for [`list_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductSort {
+    /// Most recently updated first (same as the unpaged list).
+    UpdatedAt,
+    /// Name A-Z.
+    Name,
+    /// Highest median review score first; products without reviews last.
+    ReviewScore,
+    /// Lowest purchase price first; products without purchases last.
+    Price,
+    /// Lowest price per kg first, then lowest price per l; products without a unit price last.
+    UnitPrice,
+    /// Best full-text match first. Requires a search query.
+    Relevance,
+}
+
+/// Median review score per product (non-deleted reviews), for sorting in SQL.
+const REVIEW_SCORE_JOIN: &str = "LEFT JOIN (SELECT product_id, AVG(r) AS score FROM ( \
+    SELECT product_id, CAST(rating AS REAL) AS r, \
+    ROW_NUMBER() OVER (PARTITION BY product_id ORDER BY CAST(rating AS REAL)) AS rn, \
+    COUNT(*) OVER (PARTITION BY product_id) AS cnt \
+    FROM reviews WHERE deleted_at IS NULL) \
+    WHERE rn IN ((cnt + 1) / 2, (cnt + 2) / 2) GROUP BY product_id) agg ON agg.product_id = p.id";
+
+/// Lowest price per product (non-deleted purchases) in millionths, for sorting in SQL. Prices
+/// are decimal text; scaling to an integer keeps the order exact for any price with up to six
+/// decimals, so equal displayed prices tie (and fall back to the id).
+const PRICE_JOIN: &str = "LEFT JOIN (SELECT product_id, \
+    MIN(CAST(ROUND(CAST(price AS REAL) * 1000000) AS INTEGER)) AS price \
+    FROM purchases WHERE deleted_at IS NULL GROUP BY product_id) agg ON agg.product_id = p.id";
+
+/// Lowest price per kg or l per product (non-deleted purchases), for sorting in SQL.
+///
+/// The key is `kg:` or `l:` followed by the zero-padded price in cents, so per-kg and per-l
+/// prices are never compared with each other: all per-kg prices come first. Cents are computed
+/// with integer arithmetic and rounded half to even, exactly like the displayed
+/// [`UnitPrice::from_item_price`]; `n` is the price in millionths and `d` ten times the
+/// quantity in g or ml.
+const UNIT_PRICE_JOIN: &str = "LEFT JOIN (SELECT product_id, \
+    MIN(CASE unit WHEN 'grams' THEN 'kg:' ELSE 'l:' END || printf('%019d', n / d + \
+    CASE WHEN 2 * (n % d) > d OR (2 * (n % d) = d AND (n / d) % 2 = 1) THEN 1 ELSE 0 END)) \
+    AS unit_price \
+    FROM (SELECT pu.product_id, v.unit, \
+    CAST(ROUND(CAST(pu.price AS REAL) * 1000000) AS INTEGER) AS n, v.quantity * 10 AS d \
+    FROM purchases pu JOIN product_variations v ON pu.variation_id = v.id \
+    WHERE pu.deleted_at IS NULL AND v.unit IN ('grams', 'milliliters') AND v.quantity > 0) \
+    GROUP BY product_id) agg ON agg.product_id = p.id";
+
+impl ProductSort {
+    /// Parse a `sort` query value (`updated_at`, `name`, `review_score`, `price`, `unit_price`,
+    /// `relevance`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "updated_at" => Some(Self::UpdatedAt),
+            "name" => Some(Self::Name),
+            "review_score" => Some(Self::ReviewScore),
+            "price" => Some(Self::Price),
+            "unit_price" => Some(Self::UnitPrice),
+            "relevance" => Some(Self::Relevance),
+            _ => None,
+        }
+    }
+
+    /// Query value of this sort, also stored in cursors.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        self.key().name
+    }
+
+    /// Sort key; missing aggregates are replaced by a sentinel so they sort last.
+    const fn key(self) -> SortKey<'static> {
+        let (name, expr, kind, direction) = match self {
+            Self::UpdatedAt => ("updated_at", "p.updated_at", KeyKind::Int, Direction::Desc),
+            Self::Name => ("name", "p.name", KeyKind::Text, Direction::Asc),
+            Self::ReviewScore => (
+                "review_score",
+                "COALESCE(agg.score, -1.0)",
+                KeyKind::Real,
+                Direction::Desc,
+            ),
+            Self::Price => (
+                "price",
+                "COALESCE(agg.price, 9223372036854775807)",
+                KeyKind::Int,
+                Direction::Asc,
+            ),
+            Self::UnitPrice => (
+                "unit_price",
+                "COALESCE(agg.unit_price, '~')",
+                KeyKind::Text,
+                Direction::Asc,
+            ),
+            Self::Relevance => ("relevance", "fts.rank", KeyKind::Real, Direction::Asc),
+        };
+        SortKey {
+            name,
+            expr,
+            id_expr: "p.id",
+            kind,
+            direction,
+        }
     }
 
-    Ok(filter_products(&list, cat_ids_ref, q, include_deleted))
+    const fn aggregate_join(self) -> &'static str {
+        match self {
+            Self::UpdatedAt | Self::Name | Self::Relevance => "",
+            Self::ReviewScore => REVIEW_SCORE_JOIN,
+            Self::Price => PRICE_JOIN,
+            Self::UnitPrice => UNIT_PRICE_JOIN,
+        }
+    }
+}
+
+/// List one page of products with relations and aggregates, optionally filtered by category set
+/// and/or search, in the given sort order (keyset pagination in SQL; bypasses the list cache).
+///
+/// Filters behave like [`list_with_relations`]: `category_ids` restricts to those categories and
+/// `q` is a full-text search (see [`crate::db::product_search`]). Excludes soft-deleted unless
+/// `include_deleted`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure, or
+/// [`crate::db::DbError::InvalidData`] if `sort` is [`ProductSort::Relevance`] without `q`.
+pub async fn list_page(
+    pool: &SqlitePool,
+    category_ids: Option<&[Uuid]>,
+    q: Option<&str>,
+    sort: ProductSort,
+    include_deleted: bool,
+    page: &PageRequest,
+) -> Result<Page<ProductWithRelations>, crate::db::DbError> {
+    let key = sort.key();
+    let mut conditions = vec!["p.household_id = ?".to_string()];
+    let mut binds = Vec::new();
+    let search = q.map(str::trim).filter(|s| !s.is_empty());
+    let expr = match search {
+        Some(search) => Some(crate::db::product_search::match_expression(pool, search).await?),
+        None => None,
+    };
+    let search_join = match expr {
+        None if sort == ProductSort::Relevance => {
+            return Err(crate::db::DbError::InvalidData(
+                "relevance sort requires a search query".to_string(),
+            ));
+        }
+        None => String::new(),
+        Some(None) => {
+            return Ok(Page {
+                items: Vec::new(),
+                next_cursor: None,
+            });
+        }
+        Some(Some(expr)) => {
+            binds.push(Value::Text(expr));
+            format!(
+                "JOIN (SELECT product_id, {} AS rank FROM products_fts WHERE products_fts MATCH ?) \
+                 fts ON fts.product_id = p.id",
+                crate::db::product_search::RANK_EXPR
+            )
+        }
+    };
+    binds.push(Value::Text(current_household().to_string()));
+    if let Some(ids) = category_ids {
+        if ids.is_empty() {
+            conditions.push("0=1".to_string());
+        } else {
+            conditions.push(format!(
+                "p.category_id IN ({})",
+                vec!["?"; ids.len()].join(", ")
+            ));
+            binds.extend(ids.iter().map(|id| Value::Text(id.to_string())));
+        }
+    }
+    if !include_deleted {
+        conditions.push("p.deleted_at IS NULL".to_string());
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "SELECT p.id, p.category_id, p.brand, p.name, p.created_at, p.updated_at, p.deleted_at, \
+         c.name AS category_name, {} AS sort_key \
+         FROM products p JOIN categories c ON p.category_id = c.id {search_join} {} WHERE {} {}",
+        key.expr,
+        sort.aggregate_join(),
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut products = Vec::with_capacity(rows.len());
+    let mut keys = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: String = row.get("id");
+        let category_id: String = row.get("category_id");
+        let category_name: String = row.get("category_name");
+        let brand: String = row.get("brand");
+        let name: String = row.get("name");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+        let deleted_at: Option<i64> = row.get("deleted_at");
+        products.push(row_to_product_with_relations(
+            &id,
+            &category_id,
+            &category_name,
+            &brand,
+            &name,
+            created_at,
+            updated_at,
+            deleted_at,
+        )?);
+        keys.push((key.read_key(&row), id));
+    }
+    // Aggregates of this page only (all products when unpaged).
+    let page_ids: Vec<Uuid> = products.iter().map(|p| p.id).collect();
+    let aggregates =
+        fetch_product_aggregates(pool, page.limit.map(|_| page_ids.as_slice())).await?;
+    let out = with_ancestors_and_aggregates(pool, products, &aggregates)
+        .await?
+        .into_iter()
+        .zip(keys)
+        .map(|(product, (key, id))| (product, key, id))
+        .collect();
+    Ok(key.finish(out, page))
 }
 
 /// Fetch a product by id with category name.
@@ -645,17 +991,19 @@ pub async fn get_by_id_with_relations(
     let row = if include_deleted {
         sqlx::query(
             "SELECT p.id, p.category_id, p.brand, p.name, p.created_at, p.updated_at, p.deleted_at, c.name AS category_name \
-             FROM products p JOIN categories c ON p.category_id = c.id WHERE p.id = ?",
+             FROM products p JOIN categories c ON p.category_id = c.id WHERE p.id = ? AND p.household_id = ?",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
             "SELECT p.id, p.category_id, p.brand, p.name, p.created_at, p.updated_at, p.deleted_at, c.name AS category_name \
-             FROM products p JOIN categories c ON p.category_id = c.id WHERE p.id = ? AND p.deleted_at IS NULL",
+             FROM products p JOIN categories c ON p.category_id = c.id WHERE p.id = ? AND p.household_id = ? AND p.deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
@@ -689,6 +1037,28 @@ pub async fn get_by_id_with_relations(
     }))
 }
 
+/// Fetch an active product by id with category name, ancestors and aggregates (review score,
+/// lowest prices), computed for this product only.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_by_id_with_aggregates(
+    pool: &SqlitePool,
+    id: Uuid,
+) -> Result<Option<ProductWithRelations>, crate::db::DbError> {
+    let Some(product) = get_by_id_with_relations(pool, id, false).await? else {
+        return Ok(None);
+    };
+    let aggregates = fetch_product_aggregates(pool, Some(&[id])).await?;
+    Ok(Some(ProductWithRelations {
+        review_score: aggregates.median_review_score.get(&id).copied(),
+        lowest_price: aggregates.lowest_price.get(&id).cloned(),
+        lowest_unit_price: aggregates.lowest_unit_price.get(&id).copied(),
+        ..product
+    }))
+}
+
 /// Insert a product into the database.
 ///
 /// # Errors
@@ -697,7 +1067,7 @@ pub async fn get_by_id_with_relations(
 pub async fn insert(pool: &SqlitePool, product: &Product) -> Result<(), crate::db::DbError> {
     let now = chrono::Utc::now().timestamp();
     sqlx::query(
-        "INSERT INTO products (id, category_id, brand, name, created_at, updated_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO products (id, category_id, brand, name, created_at, updated_at, deleted_at, household_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(product.id().to_string())
     .bind(product.category_id().to_string())
@@ -706,9 +1076,19 @@ pub async fn insert(pool: &SqlitePool, product: &Product) -> Result<(), crate::d
     .bind(now)
     .bind(now)
     .bind(product.deleted_at())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
+    crate::db::product_search::refresh_product(pool, product.id()).await?;
     invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Product,
+        product.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
@@ -718,9 +1098,10 @@ pub async fn insert(pool: &SqlitePool, product: &Product) -> Result<(), crate::d
 ///
 /// Returns [`crate::db::DbError`] on query failure.
 pub async fn update(pool: &SqlitePool, product: &Product) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Product, product.id()).await?;
     let now = chrono::Utc::now().timestamp();
     sqlx::query(
-        "UPDATE products SET category_id = ?, brand = ?, name = ?, updated_at = ?, deleted_at = ? WHERE id = ?",
+        "UPDATE products SET category_id = ?, brand = ?, name = ?, updated_at = ?, deleted_at = ? WHERE id = ? AND household_id = ?",
     )
     .bind(product.category_id().to_string())
     .bind(product.brand())
@@ -728,9 +1109,19 @@ pub async fn update(pool: &SqlitePool, product: &Product) -> Result<(), crate::d
     .bind(now)
     .bind(product.deleted_at())
     .bind(product.id().to_string())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
+    crate::db::product_search::refresh_product(pool, product.id()).await?;
     invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Product,
+        product.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -755,21 +1146,30 @@ async fn ensure_no_purchases(
 ///
 /// Fails if the product has any purchases.
 ///
+/// `deleted_by` is the acting user (`None` when deleted from the CLI).
+///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// the product has purchases or no active product exists with the given id.
-pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+pub async fn soft_delete(
+    pool: &SqlitePool,
+    id: Uuid,
+    deleted_by: Option<Uuid>,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Product, id).await?;
     let id_str = id.to_string();
     ensure_no_purchases(pool, &id_str).await?;
 
     let now = chrono::Utc::now().timestamp();
     let result = sqlx::query(
-        "UPDATE products SET deleted_at = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
+        "UPDATE products SET deleted_at = ?, updated_at = ?, deleted_by = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
     )
     .bind(now)
     .bind(now)
+    .bind(deleted_by.map(|u| u.to_string()))
     .bind(&id_str)
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
 
@@ -780,10 +1180,62 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Product,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Restore a soft-deleted product. Clears `deleted_at` and sets `updated_at` to the current time.
+///
+/// Fails if the product's category is not active.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
+/// the category is deleted or no soft-deleted product exists with the given id.
+pub async fn restore(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Product, id).await?;
+    let id_str = id.to_string();
+    let category_id: Option<String> =
+        sqlx::query_scalar("SELECT category_id FROM products WHERE id = ? AND household_id = ?")
+            .bind(&id_str)
+            .bind(current_household().to_string())
+            .fetch_optional(pool)
+            .await?;
+    if let Some(category_id) = category_id {
+        crate::db::ensure_active_parent(pool, "categories", &category_id, "product", "category")
+            .await?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE products SET deleted_at = NULL, deleted_by = NULL, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(now)
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "product not found or not deleted: {id_str}"
+        )));
+    }
+
+    invalidate_all_product_caches();
+    crate::db::audit::record(pool, AuditEntity::Product, id, AuditAction::Restore, before).await?;
     Ok(())
 }
 
-/// Hard-delete a product by id (remove the row).
+/// Hard-delete a product by id (remove the row), together with its variations. Each variation
+/// gets its own hard-delete audit entry, written in the transaction that deletes it.
 ///
 /// Fails if the product has any purchases.
 ///
@@ -792,25 +1244,48 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// the product has purchases.
 pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Product, id).await?;
     let id_str = id.to_string();
     ensure_no_purchases(pool, &id_str).await?;
 
-    sqlx::query("DELETE FROM product_variations WHERE product_id = ?")
+    // The variations go with the product; each one gets its own hard-delete entry.
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query("SELECT 1 FROM products WHERE id = ? AND household_id = ?")
         .bind(&id_str)
-        .execute(pool)
+        .bind(current_household().to_string())
+        .fetch_optional(&mut *tx)
         .await?;
-
-    let result = sqlx::query("DELETE FROM products WHERE id = ?")
-        .bind(&id_str)
-        .execute(pool)
-        .await?;
-
-    if result.rows_affected() == 0 {
+    if result.is_none() {
         return Err(crate::db::DbError::InvalidData(format!(
             "product not found: {id_str}"
         )));
     }
+    crate::db::audit::record_hard_deletes(
+        &mut tx,
+        AuditEntity::Variation,
+        "product_id = ?",
+        id_str.clone(),
+    )
+    .await?;
+    sqlx::query("DELETE FROM product_variations WHERE product_id = ?")
+        .bind(&id_str)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM products WHERE id = ?")
+        .bind(&id_str)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
 
+    crate::db::product_search::remove_product(pool, id).await?;
     invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Product,
+        id,
+        AuditAction::HardDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
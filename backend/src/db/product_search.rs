@@ -0,0 +1,373 @@
+//! Full-text product search backed by the `products_fts` FTS5 table.
+//!
+//! Each product has one row holding its household, name, brand, category path (category and
+//! ancestor names) and the text of its active reviews. The tokenizer folds case and diacritics,
+//! so `creme` matches `Crème`. Rows are refreshed by the product, category and review write
+//! functions. Every search is restricted to the current household's rows in the `MATCH`
+//! expression itself (see [`match_expression`]).
+//!
+//! When a search finds nothing, words of four or more letters are retried with small typos
+//! allowed: terms of the index (`products_fts_vocab`) within one edit (two for words of eight or
+//! more letters) that occur in the household are searched as alternatives, so `choclate` finds
+//! `Chocolate`.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::household::current_household;
+
+/// `bm25` ranking with column weights (`product_id`, household, name, brand, category, reviews).
+/// Lower is a better match.
+pub(crate) const RANK_EXPR: &str = "bm25(products_fts, 0.0, 0.0, 10.0, 5.0, 2.0, 1.0)";
+
+/// Most alternatives searched for one mistyped word.
+const MAX_ALTERNATIVES: usize = 10;
+
+/// Rebuild the search rows of the products selected by `filter` (a condition on `p`).
+fn refresh_sql(filter: &str) -> String {
+    format!(
+        "WITH RECURSIVE chain(product_id, parent_id, names) AS ( \
+             SELECT p.id, c.parent_id, c.name \
+             FROM products p JOIN categories c ON c.id = p.category_id WHERE {filter} \
+             UNION ALL \
+             SELECT chain.product_id, c.parent_id, chain.names || ' ' || c.name \
+             FROM chain JOIN categories c ON c.id = chain.parent_id) \
+         INSERT INTO products_fts (product_id, household, name, brand, category, reviews) \
+         SELECT p.id, replace(p.household_id, '-', ''), p.name, p.brand, chain.names, \
+             (SELECT group_concat(r.text, ' ') FROM reviews r \
+              WHERE r.product_id = p.id AND r.deleted_at IS NULL) \
+         FROM products p JOIN chain ON chain.product_id = p.id AND chain.parent_id IS NULL"
+    )
+}
+
+/// Re-index one product (after insert, update, or a change to its reviews).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn refresh_product(
+    pool: &SqlitePool,
+    product_id: Uuid,
+) -> Result<(), crate::db::DbError> {
+    let id_str = product_id.to_string();
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM products_fts WHERE product_id = ?")
+        .bind(&id_str)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(&refresh_sql("p.id = ?"))
+        .bind(&id_str)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Products whose category is `?` or one of its descendants.
+const SUBTREE_PRODUCTS: &str = "SELECT id FROM products WHERE category_id IN ( \
+    WITH RECURSIVE subtree(id) AS (SELECT ? \
+        UNION ALL SELECT c.id FROM categories c JOIN subtree ON c.parent_id = subtree.id) \
+    SELECT id FROM subtree)";
+
+/// Re-index the products of a category and its descendants (after a category rename or move
+/// changes their category paths).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn refresh_category(
+    pool: &SqlitePool,
+    category_id: Uuid,
+) -> Result<(), crate::db::DbError> {
+    let id_str = category_id.to_string();
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        "DELETE FROM products_fts WHERE product_id IN ({SUBTREE_PRODUCTS})"
+    ))
+    .bind(&id_str)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(&refresh_sql(&format!("p.id IN ({SUBTREE_PRODUCTS})")))
+        .bind(&id_str)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Remove a product from the index (after hard delete).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn remove_product(pool: &SqlitePool, product_id: Uuid) -> Result<(), crate::db::DbError> {
+    sqlx::query("DELETE FROM products_fts WHERE product_id = ?")
+        .bind(product_id.to_string())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Words of a search, without punctuation.
+fn words(q: &str) -> Vec<&str> {
+    q.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Build the FTS5 terms of a search from user input.
+///
+/// Every word must match, the last word as a prefix (for search-as-you-type). Punctuation is
+/// dropped so input can never be parsed as FTS syntax. Returns `None` when no word remains.
+#[must_use]
+pub fn match_query(q: &str) -> Option<String> {
+    let words: Vec<String> = words(q).iter().map(|w| format!("\"{w}\"")).collect();
+    let (last, rest) = words.split_last()?;
+    let mut terms = rest.to_vec();
+    terms.push(format!("{last}*"));
+    Some(terms.join(" "))
+}
+
+/// Restrict `terms` to the text columns of the current household's rows.
+fn in_household(terms: &str) -> String {
+    format!(
+        "household : \"{}\" AND {{name brand category reviews}} : ({terms})",
+        current_household().simple()
+    )
+}
+
+/// Number of typos allowed in a word of `len` characters.
+const fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Edit distance counting insertions, deletions, substitutions and swaps of adjacent characters.
+fn typo_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let above = &rows[i];
+            let mut best = (above[j + 1] + 1)
+                .min(row[j] + 1)
+                .min(above[j] + usize::from(ca != cb));
+            if i > 0 && j > 0 && *ca == b[j - 1] && a[i - 1] == *cb {
+                best = best.min(rows[i - 1][j - 1] + 1);
+            }
+            row[j + 1] = best;
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Terms of `vocabulary` within the typos allowed for `word`, with their distance (the last word
+/// may also be a mistyped prefix).
+fn typo_alternatives<'a>(
+    word: &str,
+    is_last: bool,
+    vocabulary: &'a [String],
+) -> Vec<(usize, &'a str)> {
+    let lower: Vec<char> = word.to_lowercase().chars().collect();
+    let allowed = max_typos(lower.len());
+    vocabulary
+        .iter()
+        .filter(|_| allowed > 0)
+        .filter_map(|term| {
+            let chars: Vec<char> = term.chars().collect();
+            let mut distance = typo_distance(&lower, &chars);
+            if is_last && chars.len() > lower.len() {
+                distance = distance.min(typo_distance(&lower, &chars[..lower.len()]));
+            }
+            (distance > 0 && distance <= allowed).then_some((distance, term.as_str()))
+        })
+        .collect()
+}
+
+/// FTS5 terms like [`match_query`], with each word replaced by the word or index terms within
+/// its allowed typos (see [`typo_alternatives`]). Returns `None` when no word has an
+/// alternative.
+fn typo_query(q: &str, vocabulary: &[String]) -> Option<String> {
+    let words = words(q);
+    let mut any_alternative = false;
+    let terms: Vec<String> = words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let is_last = i + 1 == words.len();
+            let mut alternatives = typo_alternatives(word, is_last, vocabulary);
+            alternatives.sort_unstable();
+            alternatives.truncate(MAX_ALTERNATIVES);
+            any_alternative |= !alternatives.is_empty();
+            let own = if is_last {
+                format!("\"{word}\"*")
+            } else {
+                format!("\"{word}\"")
+            };
+            let mut options = vec![own];
+            options.extend(
+                alternatives
+                    .into_iter()
+                    .map(|(_, term)| format!("\"{}\"", quote_term(term))),
+            );
+            format!("({})", options.join(" OR "))
+        })
+        .collect();
+    any_alternative.then(|| terms.join(" AND "))
+}
+
+/// `term` with its double quotes doubled, for use inside an FTS5 string.
+fn quote_term(term: &str) -> String {
+    term.replace('"', "\"\"")
+}
+
+/// Index terms close to a word of `q` that occur in the current household's rows. The index
+/// vocabulary covers all households, so candidates are checked one by one against this one.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+async fn household_vocabulary(
+    pool: &SqlitePool,
+    q: &str,
+) -> Result<Vec<String>, crate::db::DbError> {
+    let vocabulary: Vec<String> =
+        sqlx::query_scalar("SELECT DISTINCT term FROM products_fts_vocab WHERE col <> 'household'")
+            .fetch_all(pool)
+            .await?;
+    let words = words(q);
+    let mut candidates: Vec<&str> = words
+        .iter()
+        .enumerate()
+        .flat_map(|(i, word)| typo_alternatives(word, i + 1 == words.len(), &vocabulary))
+        .map(|(_, term)| term)
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    let mut own = Vec::new();
+    for term in candidates {
+        let used: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM products_fts WHERE products_fts MATCH ?)",
+        )
+        .bind(in_household(&format!("\"{}\"", quote_term(term))))
+        .fetch_one(pool)
+        .await?;
+        if used {
+            own.push(term.to_string());
+        }
+    }
+    Ok(own)
+}
+
+/// The `MATCH` expression for searching `q` in the current household.
+///
+/// Uses the exact words (see [`match_query`]), or words with typos allowed when that finds
+/// nothing. Returns `None` when `q` contains no words.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn match_expression(
+    pool: &SqlitePool,
+    q: &str,
+) -> Result<Option<String>, crate::db::DbError> {
+    let Some(terms) = match_query(q) else {
+        return Ok(None);
+    };
+    let exact = in_household(&terms);
+    let found: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products_fts WHERE products_fts MATCH ?)")
+            .bind(&exact)
+            .fetch_one(pool)
+            .await?;
+    if found || words(q).iter().all(|w| max_typos(w.chars().count()) == 0) {
+        return Ok(Some(exact));
+    }
+    let vocabulary = household_vocabulary(pool, q).await?;
+    Ok(Some(
+        typo_query(q, &vocabulary).map_or(exact, |terms| in_household(&terms)),
+    ))
+}
+
+/// Ids of products of the current household matching `q`, best match first.
+///
+/// See [`match_expression`]. Includes soft-deleted products; callers filter them. Returns an
+/// empty list when `q` contains no words.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or if a stored id is not a UUID.
+pub async fn search(pool: &SqlitePool, q: &str) -> Result<Vec<Uuid>, crate::db::DbError> {
+    let Some(expr) = match_expression(pool, q).await? else {
+        return Ok(Vec::new());
+    };
+    let sql = format!(
+        "SELECT product_id FROM products_fts WHERE products_fts MATCH ? ORDER BY {RANK_EXPR}"
+    );
+    let rows = sqlx::query(&sql).bind(&expr).fetch_all(pool).await?;
+    rows.iter()
+        .map(|row| {
+            let id: String = row.get("product_id");
+            Uuid::parse_str(&id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_query_quotes_words_and_prefixes_the_last() {
+        assert_eq!(
+            match_query("creme fra").as_deref(),
+            Some("\"creme\" \"fra\"*")
+        );
+        assert_eq!(match_query("  Milk ").as_deref(), Some("\"Milk\"*"));
+    }
+
+    #[test]
+    fn match_query_drops_fts_syntax() {
+        assert_eq!(
+            match_query("a\" OR b* NEAR(c)").as_deref(),
+            Some("\"a\" \"OR\" \"b\" \"NEAR\" \"c\"*")
+        );
+        assert_eq!(match_query("-*\"()"), None);
+        assert_eq!(match_query(""), None);
+    }
+
+    #[test]
+    fn typo_distance_counts_edits_and_swaps() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(typo_distance(&chars("chocolate"), &chars("chocolate")), 0);
+        assert_eq!(typo_distance(&chars("choclate"), &chars("chocolate")), 1);
+        assert_eq!(typo_distance(&chars("chocolaet"), &chars("chocolate")), 1);
+        assert_eq!(typo_distance(&chars("cgocolade"), &chars("chocolate")), 2);
+        assert_eq!(typo_distance(&chars(""), &chars("milk")), 4);
+    }
+
+    #[test]
+    fn typo_query_adds_close_terms_only_for_longer_words() {
+        let vocabulary = vec![
+            "chocolate".to_string(),
+            "milk".to_string(),
+            "silk".to_string(),
+            "oat".to_string(),
+        ];
+        assert_eq!(
+            typo_query("mlik chocl", &vocabulary).as_deref(),
+            Some("(\"mlik\" OR \"milk\") AND (\"chocl\"* OR \"chocolate\")"),
+            "the last word may be a mistyped prefix"
+        );
+        assert_eq!(
+            typo_query("oaf", &vocabulary),
+            None,
+            "short words are exact"
+        );
+        assert_eq!(typo_query("bread", &vocabulary), None);
+    }
+}
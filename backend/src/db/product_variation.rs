@@ -1,31 +1,39 @@
 //! Product variation persistence.
 //!
-//! Provides DB functions: [`get_by_id`], [`list_by_product_id`], [`insert`],
-//! [`update`], [`soft_delete`], [`count_by_product_id`], and [`ensure_no_purchases`].
+//! Provides DB functions: [`get_by_id`], [`get_by_barcode`], [`list_by_product_id`], [`insert`],
+//! [`update`], [`soft_delete`], [`restore`], [`count_by_product_id`], and [`ensure_no_purchases`].
+//!
+//! All functions read and write only variations of the current household (see
+//! [`crate::db::household`]).
 
+use sqlx::sqlite::SqliteRow;
 use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
-use crate::domain::product_variation::ProductVariation;
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::db::household::current_household;
+use crate::domain::product_variation::{ProductVariation, normalize_gtin};
 
 /// Map a DB row into a [`ProductVariation`]. Fails on invalid UUID or domain validation.
-#[allow(clippy::too_many_arguments)]
-fn row_to_variation(
-    id: &str,
-    product_id: &str,
-    label: &str,
-    unit: &str,
-    quantity: Option<u32>,
-    created_at: i64,
-    updated_at: i64,
-    deleted_at: Option<i64>,
-) -> Result<ProductVariation, crate::db::DbError> {
-    let id = Uuid::parse_str(id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+fn row_to_variation(row: &SqliteRow) -> Result<ProductVariation, crate::db::DbError> {
+    let id: String = row.get("id");
+    let product_id: String = row.get("product_id");
+    let label: String = row.get("label");
+    let unit: String = row.get("unit");
+    // SQLite INTEGER is i64; domain uses Option<u32>, so convert (negative -> None).
+    let quantity: Option<i64> = row.get("quantity");
+    let quantity = quantity.and_then(|q| u32::try_from(q).ok());
+    let barcode: Option<String> = row.get("barcode");
+    let created_at: i64 = row.get("created_at");
+    let updated_at: i64 = row.get("updated_at");
+    let deleted_at: Option<i64> = row.get("deleted_at");
+    let id = Uuid::parse_str(&id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
     let product_id =
-        Uuid::parse_str(product_id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+        Uuid::parse_str(&product_id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
     ProductVariation::new(
-        id, product_id, label, unit, quantity, created_at, updated_at, deleted_at,
+        id, product_id, &label, &unit, quantity, created_at, updated_at, deleted_at,
     )
+    .and_then(|v| v.with_barcode(barcode.as_deref()))
     .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
 }
 
@@ -44,48 +52,46 @@ pub async fn get_by_id(
     let id_str = id.to_string();
     let row = if include_deleted {
         sqlx::query(
-            "SELECT id, product_id, label, unit, quantity, created_at, updated_at, deleted_at \
-             FROM product_variations WHERE id = ?",
+            "SELECT id, product_id, label, unit, quantity, barcode, created_at, updated_at, deleted_at \
+             FROM product_variations WHERE id = ? AND household_id = ?",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, product_id, label, unit, quantity, created_at, updated_at, deleted_at \
-             FROM product_variations WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, product_id, label, unit, quantity, barcode, created_at, updated_at, deleted_at \
+             FROM product_variations WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
 
-    let Some(row) = row else {
-        return Ok(None);
-    };
-
-    let id: String = row.get("id");
-    let product_id: String = row.get("product_id");
-    let label: String = row.get("label");
-    let unit: String = row.get("unit");
-    // SQLite INTEGER is i64; domain uses Option<u32>, so convert (negative -> None).
-    let quantity: Option<i64> = row.get("quantity");
-    let quantity = quantity.and_then(|q| u32::try_from(q).ok());
-    let created_at: i64 = row.get("created_at");
-    let updated_at: i64 = row.get("updated_at");
-    let deleted_at: Option<i64> = row.get("deleted_at");
+    row.as_ref().map(row_to_variation).transpose()
+}
 
-    let variation = row_to_variation(
-        &id,
-        &product_id,
-        &label,
-        &unit,
-        quantity,
-        created_at,
-        updated_at,
-        deleted_at,
-    )?;
-    Ok(Some(variation))
+/// Fetch the active variation with the given barcode, in any form of the same GTIN (e.g. UPC-A
+/// or EAN-13; see [`crate::domain::product_variation::normalize_gtin`]).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_by_barcode(
+    pool: &SqlitePool,
+    barcode: &str,
+) -> Result<Option<ProductVariation>, crate::db::DbError> {
+    let row = sqlx::query(
+        "SELECT id, product_id, label, unit, quantity, barcode, created_at, updated_at, deleted_at \
+         FROM product_variations WHERE barcode = ? AND household_id = ? AND deleted_at IS NULL",
+    )
+    .bind(normalize_gtin(barcode).unwrap_or_else(|| barcode.to_string()))
+    .bind(current_household().to_string())
+    .fetch_optional(pool)
+    .await?;
+    row.as_ref().map(row_to_variation).transpose()
 }
 
 /// List variations for a product, ordered by `created_at`.
@@ -103,47 +109,25 @@ pub async fn list_by_product_id(
     let product_id_str = product_id.to_string();
     let rows = if include_deleted {
         sqlx::query(
-            "SELECT id, product_id, label, unit, quantity, created_at, updated_at, deleted_at \
-             FROM product_variations WHERE product_id = ? ORDER BY created_at",
+            "SELECT id, product_id, label, unit, quantity, barcode, created_at, updated_at, deleted_at \
+             FROM product_variations WHERE product_id = ? AND household_id = ? ORDER BY created_at",
         )
         .bind(&product_id_str)
+        .bind(current_household().to_string())
         .fetch_all(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, product_id, label, unit, quantity, created_at, updated_at, deleted_at \
-             FROM product_variations WHERE product_id = ? AND deleted_at IS NULL ORDER BY created_at",
+            "SELECT id, product_id, label, unit, quantity, barcode, created_at, updated_at, deleted_at \
+             FROM product_variations WHERE product_id = ? AND household_id = ? AND deleted_at IS NULL ORDER BY created_at",
         )
         .bind(&product_id_str)
+        .bind(current_household().to_string())
         .fetch_all(pool)
         .await?
     };
 
-    let mut out = Vec::with_capacity(rows.len());
-    for row in rows {
-        let id: String = row.get("id");
-        let product_id: String = row.get("product_id");
-        let label: String = row.get("label");
-        let unit: String = row.get("unit");
-        // SQLite INTEGER is i64; domain uses Option<u32>, so convert (negative -> None).
-        let quantity: Option<i64> = row.get("quantity");
-        let quantity = quantity.and_then(|q| u32::try_from(q).ok());
-        let created_at: i64 = row.get("created_at");
-        let updated_at: i64 = row.get("updated_at");
-        let deleted_at: Option<i64> = row.get("deleted_at");
-        let variation = row_to_variation(
-            &id,
-            &product_id,
-            &label,
-            &unit,
-            quantity,
-            created_at,
-            updated_at,
-            deleted_at,
-        )?;
-        out.push(variation);
-    }
-    Ok(out)
+    rows.iter().map(row_to_variation).collect()
 }
 
 /// Insert a product variation.
@@ -156,19 +140,29 @@ pub async fn insert(
     variation: &ProductVariation,
 ) -> Result<(), crate::db::DbError> {
     sqlx::query(
-        "INSERT INTO product_variations (id, product_id, label, unit, quantity, created_at, updated_at, deleted_at) \
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO product_variations (id, product_id, label, unit, quantity, barcode, created_at, updated_at, deleted_at, household_id) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(variation.id().to_string())
     .bind(variation.product_id().to_string())
     .bind(variation.label())
     .bind(variation.unit().to_string())
     .bind(variation.quantity().map(i64::from))
+    .bind(variation.barcode())
     .bind(variation.created_at())
     .bind(variation.updated_at())
     .bind(variation.deleted_at())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Variation,
+        variation.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
@@ -181,19 +175,22 @@ pub async fn update(
     pool: &SqlitePool,
     variation: &ProductVariation,
 ) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Variation, variation.id()).await?;
     let id_str = variation.id().to_string();
     let result = sqlx::query(
         "UPDATE product_variations \
-         SET product_id = ?, label = ?, unit = ?, quantity = ?, updated_at = ?, deleted_at = ? \
-         WHERE id = ? AND deleted_at IS NULL",
+         SET product_id = ?, label = ?, unit = ?, quantity = ?, barcode = ?, updated_at = ?, deleted_at = ? \
+         WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
     )
     .bind(variation.product_id().to_string())
     .bind(variation.label())
     .bind(variation.unit().to_string())
     .bind(variation.quantity().map(i64::from))
+    .bind(variation.barcode())
     .bind(variation.updated_at())
     .bind(variation.deleted_at())
     .bind(&id_str)
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
 
@@ -202,22 +199,40 @@ pub async fn update(
             "product variation not found or already deleted: {id_str}"
         )));
     }
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Variation,
+        variation.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
 /// Soft-delete a variation by id.
 ///
+/// `deleted_by` is the acting user (`None` when deleted from the CLI).
+///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] if no active row exists.
-pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+pub async fn soft_delete(
+    pool: &SqlitePool,
+    id: Uuid,
+    deleted_by: Option<Uuid>,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Variation, id).await?;
     let id_str = id.to_string();
     let now = chrono::Utc::now().timestamp();
     let result = sqlx::query(
-        "UPDATE product_variations SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+        "UPDATE product_variations SET deleted_at = ?, updated_at = ?, deleted_by = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
     )
     .bind(now)
+    .bind(now)
+    .bind(deleted_by.map(|u| u.to_string()))
     .bind(&id_str)
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
 
@@ -226,6 +241,67 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
             "product variation not found or already deleted: {id_str}"
         )));
     }
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Variation,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Restore a soft-deleted variation. Clears `deleted_at` and sets `updated_at` to the current
+/// time.
+///
+/// Fails if the variation's product is not active.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (e.g. an active variation now has the same
+/// barcode), or [`crate::db::DbError::InvalidData`] if the product is deleted or no soft-deleted
+/// variation exists with the given id.
+pub async fn restore(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Variation, id).await?;
+    let id_str = id.to_string();
+    let product_id: Option<String> = sqlx::query_scalar(
+        "SELECT product_id FROM product_variations WHERE id = ? AND household_id = ?",
+    )
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .fetch_optional(pool)
+    .await?;
+    if let Some(product_id) = product_id {
+        crate::db::ensure_active_parent(pool, "products", &product_id, "variation", "product")
+            .await?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE product_variations SET deleted_at = NULL, deleted_by = NULL, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(now)
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "product variation not found or not deleted: {id_str}"
+        )));
+    }
+
+    crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Variation,
+        id,
+        AuditAction::Restore,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -1,8 +1,11 @@
 //! Purchase persistence.
 //!
 //! Provides DB functions: [`get_by_id`], [`get_by_id_with_relations`], [`list`],
-//! [`list_with_relations`], [`insert`], [`soft_delete`], [`hard_delete`], and
-//! [`count_by_variation_ids`].
+//! [`list_with_relations`], [`list_page`], [`insert`], [`soft_delete`], [`restore`],
+//! [`hard_delete`], and [`count_by_variation_ids`].
+//!
+//! All functions read and write only purchases of the current household (see
+//! [`crate::db::household`]).
 
 use std::collections::HashMap;
 
@@ -10,6 +13,10 @@ use rust_decimal::Decimal;
 use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::db::household::current_household;
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, Value, bind_values};
+use crate::domain::product_variation::{Unit, UnitPrice};
 use crate::domain::purchase::Purchase;
 
 /// One purchase row with joined user, product, location, and variation for API responses.
@@ -33,6 +40,16 @@ pub struct PurchaseWithRelations {
     pub location_name: String,
 }
 
+impl PurchaseWithRelations {
+    /// Price per kg or l of this purchase. `None` when the variation has no weight or volume.
+    #[must_use]
+    pub fn unit_price(&self) -> Option<UnitPrice> {
+        let unit: Unit = self.variation_unit.parse().ok()?;
+        let price: Decimal = self.price.parse().ok()?;
+        UnitPrice::from_item_price(price, unit, self.variation_quantity)
+    }
+}
+
 /// Map a DB row into a [`Purchase`]. Fails on invalid UUID/Decimal or domain validation.
 #[allow(clippy::too_many_arguments)]
 fn row_to_purchase(
@@ -89,16 +106,18 @@ pub async fn get_by_id(
     let id_str = id.to_string();
     let row = if include_deleted {
         sqlx::query(
-            "SELECT id, user_id, product_id, variation_id, location_id, quantity, price, purchased_at, deleted_at FROM purchases WHERE id = ?",
+            "SELECT id, user_id, product_id, variation_id, location_id, quantity, price, purchased_at, deleted_at FROM purchases WHERE id = ? AND household_id = ?",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, user_id, product_id, variation_id, location_id, quantity, price, purchased_at, deleted_at FROM purchases WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, user_id, product_id, variation_id, location_id, quantity, price, purchased_at, deleted_at FROM purchases WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
@@ -145,8 +164,8 @@ pub async fn list(
     to_ts: Option<i64>,
     include_deleted: bool,
 ) -> Result<Vec<Purchase>, crate::db::DbError> {
-    let mut conditions = vec!["1=1".to_string()];
-    let mut binds: Vec<String> = Vec::new();
+    let mut conditions = vec!["household_id = ?".to_string()];
+    let mut binds: Vec<String> = vec![current_household().to_string()];
 
     if let Some(uid) = user_id {
         conditions.push("user_id = ?".to_string());
@@ -286,8 +305,8 @@ pub async fn list_with_relations(
     to_ts: Option<i64>,
     include_deleted: bool,
 ) -> Result<Vec<PurchaseWithRelations>, crate::db::DbError> {
-    let mut conditions = vec!["1=1".to_string()];
-    let mut binds: Vec<String> = Vec::new();
+    let mut conditions = vec!["p.household_id = ?".to_string()];
+    let mut binds: Vec<String> = vec![current_household().to_string()];
 
     if let Some(uid) = user_id {
         conditions.push("p.user_id = ?".to_string());
@@ -331,6 +350,133 @@ pub async fn list_with_relations(
     Ok(out)
 }
 
+/// Filters for [`list_page`]. `from_ts` and `to_ts` bound `purchased_at` (inclusive).
+#[derive(Debug, Clone, Default)]
+pub struct PurchaseFilter {
+    pub user_id: Option<Uuid>,
+    pub product_id: Option<Uuid>,
+    pub variation_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub from_ts: Option<i64>,
+    pub to_ts: Option<i64>,
+    pub include_deleted: bool,
+}
+
+/// Sort order for [`list_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PurchaseSort {
+    /// Most recent purchase first.
+    PurchasedAt,
+    /// Cheapest first.
+    Price,
+}
+
+impl PurchaseSort {
+    /// Parse a `sort` query value (`purchased_at`, `price`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "purchased_at" => Some(Self::PurchasedAt),
+            "price" => Some(Self::Price),
+            _ => None,
+        }
+    }
+
+    /// Query value of this sort, also stored in cursors.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        self.key().name
+    }
+
+    const fn key(self) -> SortKey<'static> {
+        let (name, expr, kind, direction) = match self {
+            Self::PurchasedAt => (
+                "purchased_at",
+                "p.purchased_at",
+                KeyKind::Int,
+                Direction::Desc,
+            ),
+            Self::Price => (
+                "price",
+                "CAST(p.price AS REAL)",
+                KeyKind::Real,
+                Direction::Asc,
+            ),
+        };
+        SortKey {
+            name,
+            expr,
+            id_expr: "p.id",
+            kind,
+            direction,
+        }
+    }
+}
+
+/// List one page of purchases with relations matching `filter`, in the given sort order
+/// (keyset pagination in SQL).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_page(
+    pool: &SqlitePool,
+    filter: &PurchaseFilter,
+    sort: PurchaseSort,
+    page: &PageRequest,
+) -> Result<Page<PurchaseWithRelations>, crate::db::DbError> {
+    let key = sort.key();
+    let mut conditions = vec!["p.household_id = ?".to_string()];
+    let mut binds = vec![Value::Text(current_household().to_string())];
+    if let Some(uid) = filter.user_id {
+        conditions.push("p.user_id = ?".to_string());
+        binds.push(Value::Text(uid.to_string()));
+    }
+    if let Some(pid) = filter.product_id {
+        conditions.push("p.product_id = ?".to_string());
+        binds.push(Value::Text(pid.to_string()));
+    }
+    if let Some(vid) = filter.variation_id {
+        conditions.push("p.variation_id = ?".to_string());
+        binds.push(Value::Text(vid.to_string()));
+    }
+    if let Some(lid) = filter.location_id {
+        conditions.push("p.location_id = ?".to_string());
+        binds.push(Value::Text(lid.to_string()));
+    }
+    if let Some(ts) = filter.from_ts {
+        conditions.push("p.purchased_at >= ?".to_string());
+        binds.push(Value::Int(ts));
+    }
+    if let Some(ts) = filter.to_ts {
+        conditions.push("p.purchased_at <= ?".to_string());
+        binds.push(Value::Int(ts));
+    }
+    if !filter.include_deleted {
+        conditions.push("p.deleted_at IS NULL".to_string());
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "{PURCHASE_JOIN_SELECT}, {} AS sort_key {PURCHASE_JOIN_FROM} WHERE {} {}",
+        key.expr,
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let purchase = row_to_purchase_with_relations(&row)?;
+        let id = purchase.id.to_string();
+        out.push((purchase, key.read_key(&row), id));
+    }
+    Ok(key.finish(out, page))
+}
+
 /// Count non-deleted purchases per variation id. Returns a map from `variation_id` to count;
 /// variations with zero purchases are omitted from the map.
 ///
@@ -348,9 +494,10 @@ pub async fn count_by_variation_ids(
     for id in variation_ids {
         let id_str = id.to_string();
         let row: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM purchases WHERE variation_id = ? AND deleted_at IS NULL",
+            "SELECT COUNT(*) FROM purchases WHERE variation_id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_one(pool)
         .await?;
         if row.0 > 0 {
@@ -375,13 +522,17 @@ pub async fn get_by_id_with_relations(
 ) -> Result<Option<PurchaseWithRelations>, crate::db::DbError> {
     let id_str = id.to_string();
     let sql = if include_deleted {
-        format!("{PURCHASE_JOIN_SELECT} {PURCHASE_JOIN_FROM} WHERE p.id = ?")
+        format!("{PURCHASE_JOIN_SELECT} {PURCHASE_JOIN_FROM} WHERE p.id = ? AND p.household_id = ?")
     } else {
         format!(
-            "{PURCHASE_JOIN_SELECT} {PURCHASE_JOIN_FROM} WHERE p.id = ? AND p.deleted_at IS NULL"
+            "{PURCHASE_JOIN_SELECT} {PURCHASE_JOIN_FROM} WHERE p.id = ? AND p.household_id = ? AND p.deleted_at IS NULL"
         )
     };
-    let row = sqlx::query(&sql).bind(&id_str).fetch_optional(pool).await?;
+    let row = sqlx::query(&sql)
+        .bind(&id_str)
+        .bind(current_household().to_string())
+        .fetch_optional(pool)
+        .await?;
 
     let Some(row) = row else {
         return Ok(None);
@@ -396,7 +547,7 @@ pub async fn get_by_id_with_relations(
 /// Returns [`crate::db::DbError`] on query failure.
 pub async fn insert(pool: &SqlitePool, purchase: &Purchase) -> Result<(), crate::db::DbError> {
     sqlx::query(
-        "INSERT INTO purchases (id, user_id, product_id, variation_id, location_id, quantity, price, purchased_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO purchases (id, user_id, product_id, variation_id, location_id, quantity, price, purchased_at, deleted_at, updated_at, household_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(purchase.id().to_string())
     .bind(purchase.user_id().to_string())
@@ -407,9 +558,19 @@ pub async fn insert(pool: &SqlitePool, purchase: &Purchase) -> Result<(), crate:
     .bind(purchase.price().to_string())
     .bind(purchase.purchased_at())
     .bind(purchase.deleted_at())
+    .bind(chrono::Utc::now().timestamp())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Purchase,
+        purchase.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
@@ -420,9 +581,10 @@ pub async fn insert(pool: &SqlitePool, purchase: &Purchase) -> Result<(), crate:
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// no active purchase exists with the given id.
 pub async fn update(pool: &SqlitePool, purchase: &Purchase) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Purchase, purchase.id()).await?;
     let id_str = purchase.id().to_string();
     let result = sqlx::query(
-        "UPDATE purchases SET user_id = ?, product_id = ?, variation_id = ?, location_id = ?, quantity = ?, price = ?, purchased_at = ? WHERE id = ? AND deleted_at IS NULL",
+        "UPDATE purchases SET user_id = ?, product_id = ?, variation_id = ?, location_id = ?, quantity = ?, price = ?, purchased_at = ?, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
     )
     .bind(purchase.user_id().to_string())
     .bind(purchase.product_id().to_string())
@@ -431,7 +593,9 @@ pub async fn update(pool: &SqlitePool, purchase: &Purchase) -> Result<(), crate:
     .bind(purchase.quantity())
     .bind(purchase.price().to_string())
     .bind(purchase.purchased_at())
+    .bind(chrono::Utc::now().timestamp())
     .bind(&id_str)
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
 
@@ -441,24 +605,43 @@ pub async fn update(pool: &SqlitePool, purchase: &Purchase) -> Result<(), crate:
         )));
     }
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Purchase,
+        purchase.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
 /// Soft-delete a purchase by id. Sets `deleted_at` to the current time.
 ///
+/// `deleted_by` is the acting user (`None` when deleted from the CLI).
+///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// no active purchase exists with the given id.
-pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+pub async fn soft_delete(
+    pool: &SqlitePool,
+    id: Uuid,
+    deleted_by: Option<Uuid>,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Purchase, id).await?;
     let id_str = id.to_string();
     let now = chrono::Utc::now().timestamp();
-    let result =
-        sqlx::query("UPDATE purchases SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
-            .bind(now)
-            .bind(&id_str)
-            .execute(pool)
-            .await?;
+    let result = sqlx::query(
+        "UPDATE purchases SET deleted_at = ?, updated_at = ?, deleted_by = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
+    )
+    .bind(now)
+    .bind(now)
+    .bind(deleted_by.map(|u| u.to_string()))
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
 
     if result.rows_affected() == 0 {
         return Err(crate::db::DbError::InvalidData(format!(
@@ -467,6 +650,74 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Purchase,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Restore a soft-deleted purchase. Clears `deleted_at`.
+///
+/// Fails if the purchase's product, variation or location is not active.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
+/// a parent is deleted or no soft-deleted purchase exists with the given id.
+pub async fn restore(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Purchase, id).await?;
+    let id_str = id.to_string();
+    let parents: Option<(String, String, String)> = sqlx::query_as(
+        "SELECT product_id, variation_id, location_id FROM purchases WHERE id = ? AND household_id = ?",
+    )
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .fetch_optional(pool)
+    .await?;
+    if let Some((product_id, variation_id, location_id)) = parents {
+        crate::db::ensure_active_parent(pool, "products", &product_id, "purchase", "product")
+            .await?;
+        crate::db::ensure_active_parent(
+            pool,
+            "product_variations",
+            &variation_id,
+            "purchase",
+            "variation",
+        )
+        .await?;
+        crate::db::ensure_active_parent(pool, "locations", &location_id, "purchase", "location")
+            .await?;
+    }
+
+    let result = sqlx::query(
+        "UPDATE purchases SET deleted_at = NULL, deleted_by = NULL, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "purchase not found or not deleted: {id_str}"
+        )));
+    }
+
+    crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Purchase,
+        id,
+        AuditAction::Restore,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
@@ -477,9 +728,11 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// no purchase exists with the given id.
 pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Purchase, id).await?;
     let id_str = id.to_string();
-    let result = sqlx::query("DELETE FROM purchases WHERE id = ?")
+    let result = sqlx::query("DELETE FROM purchases WHERE id = ? AND household_id = ?")
         .bind(&id_str)
+        .bind(current_household().to_string())
         .execute(pool)
         .await?;
 
@@ -490,5 +743,13 @@ pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
     }
 
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Purchase,
+        id,
+        AuditAction::HardDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
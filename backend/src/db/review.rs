@@ -1,14 +1,22 @@
 //! Review persistence.
 //!
 //! Provides DB functions: [`get_by_id`], [`get_by_id_with_relations`], [`list`],
-//! [`list_with_relations`], [`insert`], [`update`], [`soft_delete`], and [`hard_delete`].
+//! [`list_with_relations`], [`list_page`], [`insert`], [`update`], [`soft_delete`], [`restore`],
+//! and [`hard_delete`].
+//!
+//! All functions read and write only reviews of the current household (see
+//! [`crate::db::household`]).
 
+use std::collections::HashMap;
 use std::sync::{OnceLock, RwLock};
 
 use rust_decimal::Decimal;
 use sqlx::{Row, SqlitePool};
 use uuid::Uuid;
 
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::db::household::current_household;
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, Value, bind_values};
 use crate::domain::review::Review;
 
 /// True when the process is the production binary (`main()` has run). False in test binaries so the
@@ -39,26 +47,34 @@ pub fn set_use_review_list_cache_for_test(use_cache: bool) {
     USE_CACHE_IN_TEST.with(|v| v.store(use_cache, std::sync::atomic::Ordering::SeqCst));
 }
 
-/// Module-level cache for the full review list with relations (including deleted). Used by
-/// `list_with_relations`.
-fn review_list_cache() -> &'static RwLock<Option<Vec<ReviewWithRelations>>> {
-    static CACHE: OnceLock<RwLock<Option<Vec<ReviewWithRelations>>>> = OnceLock::new();
-    CACHE.get_or_init(|| RwLock::new(None))
+/// Module-level cache for the full review list with relations (including deleted) of each
+/// household. Used by `list_with_relations`.
+fn review_list_cache() -> &'static RwLock<HashMap<Uuid, Vec<ReviewWithRelations>>> {
+    static CACHE: OnceLock<RwLock<HashMap<Uuid, Vec<ReviewWithRelations>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
-fn invalidate_review_list_cache() {
-    let _ = review_list_cache().write().map(|mut g| *g = None);
+pub(crate) fn invalidate_review_list_cache() {
+    let _ = review_list_cache().write().map(|mut g| g.clear());
 }
 
 /// Clear the review list cache. For use by cache tests so they start from a known state.
 pub fn clear_review_list_cache() {
-    let _ = review_list_cache().write().map(|mut g| *g = None);
+    let _ = review_list_cache().write().map(|mut g| g.clear());
 }
 
-/// Set the review list cache to a specific value. For use by cache tests to verify that
-/// `list_with_relations` returns cached data when the cache is populated.
+/// Set the current household's review list cache to a specific value. For use by cache tests to
+/// verify that `list_with_relations` returns cached data when the cache is populated.
 pub fn set_review_list_cache_for_test(list: Option<Vec<ReviewWithRelations>>) {
-    let _ = review_list_cache().write().map(|mut g| *g = list);
+    let household = current_household();
+    let _ = review_list_cache().write().map(|mut g| match list {
+        Some(list) => {
+            g.insert(household, list);
+        }
+        None => {
+            g.remove(&household);
+        }
+    });
 }
 
 /// One review row with joined user and product names for API responses.
@@ -127,16 +143,18 @@ pub async fn get_by_id(
     let id_str = id.to_string();
     let row = if include_deleted {
         sqlx::query(
-            "SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE id = ?",
+            "SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE id = ? AND household_id = ?",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
+        .bind(current_household().to_string())
         .fetch_optional(pool)
         .await?
     };
@@ -181,24 +199,28 @@ pub async fn list(
     let rows = if include_deleted {
         match (product_id, user_id) {
             (None, None) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .fetch_all(pool)
                     .await?
             }
             (Some(pid), None) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE product_id = ? ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND product_id = ? ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .bind(pid.to_string())
                     .fetch_all(pool)
                     .await?
             }
             (None, Some(uid)) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE user_id = ? ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND user_id = ? ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .bind(uid.to_string())
                     .fetch_all(pool)
                     .await?
             }
             (Some(pid), Some(uid)) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE product_id = ? AND user_id = ? ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND product_id = ? AND user_id = ? ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .bind(pid.to_string())
                     .bind(uid.to_string())
                     .fetch_all(pool)
@@ -208,24 +230,28 @@ pub async fn list(
     } else {
         match (product_id, user_id) {
             (None, None) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE deleted_at IS NULL ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .fetch_all(pool)
                     .await?
             }
             (Some(pid), None) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE product_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND product_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .bind(pid.to_string())
                     .fetch_all(pool)
                     .await?
             }
             (None, Some(uid)) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE user_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND user_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .bind(uid.to_string())
                     .fetch_all(pool)
                     .await?
             }
             (Some(pid), Some(uid)) => {
-                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE product_id = ? AND user_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                sqlx::query("SELECT id, product_id, user_id, rating, text, created_at, updated_at, deleted_at FROM reviews WHERE household_id = ? AND product_id = ? AND user_id = ? AND deleted_at IS NULL ORDER BY updated_at DESC")
+                    .bind(current_household().to_string())
                     .bind(pid.to_string())
                     .bind(uid.to_string())
                     .fetch_all(pool)
@@ -305,8 +331,13 @@ fn row_to_review_with_relations(
 async fn fetch_all_reviews_with_relations_raw(
     pool: &SqlitePool,
 ) -> Result<Vec<ReviewWithRelations>, crate::db::DbError> {
-    let sql = format!("{REVIEW_JOIN_SELECT} {REVIEW_JOIN_FROM} ORDER BY r.updated_at DESC");
-    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    let sql = format!(
+        "{REVIEW_JOIN_SELECT} {REVIEW_JOIN_FROM} WHERE r.household_id = ? ORDER BY r.updated_at DESC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(current_household().to_string())
+        .fetch_all(pool)
+        .await?;
     let mut out = Vec::with_capacity(rows.len());
     for row in rows {
         out.push(row_to_review_with_relations(&row)?);
@@ -355,9 +386,10 @@ pub async fn list_with_relations(
     user_id: Option<Uuid>,
     include_deleted: bool,
 ) -> Result<Vec<ReviewWithRelations>, crate::db::DbError> {
+    let household = current_household();
     if use_cache()
         && let Ok(guard) = review_list_cache().read()
-        && let Some(ref list) = *guard
+        && let Some(list) = guard.get(&household)
     {
         return Ok(filter_reviews(list, product_id, user_id, include_deleted));
     }
@@ -367,12 +399,109 @@ pub async fn list_with_relations(
     if use_cache()
         && let Ok(mut guard) = review_list_cache().write()
     {
-        *guard = Some(list.clone());
+        guard.insert(household, list.clone());
     }
 
     Ok(filter_reviews(&list, product_id, user_id, include_deleted))
 }
 
+/// Sort order for [`list_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewSort {
+    /// Most recently updated first.
+    UpdatedAt,
+    /// Newest first.
+    CreatedAt,
+    /// Highest rating first.
+    Rating,
+}
+
+impl ReviewSort {
+    /// Parse a `sort` query value (`updated_at`, `created_at`, `rating`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "updated_at" => Some(Self::UpdatedAt),
+            "created_at" => Some(Self::CreatedAt),
+            "rating" => Some(Self::Rating),
+            _ => None,
+        }
+    }
+
+    /// Query value of this sort, also stored in cursors.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        self.key().name
+    }
+
+    const fn key(self) -> SortKey<'static> {
+        let (name, expr, kind) = match self {
+            Self::UpdatedAt => ("updated_at", "r.updated_at", KeyKind::Int),
+            Self::CreatedAt => ("created_at", "r.created_at", KeyKind::Int),
+            Self::Rating => ("rating", "CAST(r.rating AS REAL)", KeyKind::Real),
+        };
+        SortKey {
+            name,
+            expr,
+            id_expr: "r.id",
+            kind,
+            direction: Direction::Desc,
+        }
+    }
+}
+
+/// List one page of reviews with user and product names, with optional filters, in the given
+/// sort order (keyset pagination in SQL; bypasses the list cache).
+///
+/// Excludes soft-deleted unless `include_deleted`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_page(
+    pool: &SqlitePool,
+    product_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    sort: ReviewSort,
+    include_deleted: bool,
+    page: &PageRequest,
+) -> Result<Page<ReviewWithRelations>, crate::db::DbError> {
+    let key = sort.key();
+    let mut conditions = vec!["r.household_id = ?".to_string()];
+    let mut binds = vec![Value::Text(current_household().to_string())];
+    if let Some(pid) = product_id {
+        conditions.push("r.product_id = ?".to_string());
+        binds.push(Value::Text(pid.to_string()));
+    }
+    if let Some(uid) = user_id {
+        conditions.push("r.user_id = ?".to_string());
+        binds.push(Value::Text(uid.to_string()));
+    }
+    if !include_deleted {
+        conditions.push("r.deleted_at IS NULL".to_string());
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "{REVIEW_JOIN_SELECT}, {} AS sort_key {REVIEW_JOIN_FROM} WHERE {} {}",
+        key.expr,
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let review = row_to_review_with_relations(&row)?;
+        let id = review.id.to_string();
+        out.push((review, key.read_key(&row), id));
+    }
+    Ok(key.finish(out, page))
+}
+
 /// Fetch a review by id with user and product names.
 ///
 /// When `include_deleted` is `false`, only active reviews are returned. When `true`, the row
@@ -388,11 +517,17 @@ pub async fn get_by_id_with_relations(
 ) -> Result<Option<ReviewWithRelations>, crate::db::DbError> {
     let id_str = id.to_string();
     let sql = if include_deleted {
-        format!("{REVIEW_JOIN_SELECT} {REVIEW_JOIN_FROM} WHERE r.id = ?")
+        format!("{REVIEW_JOIN_SELECT} {REVIEW_JOIN_FROM} WHERE r.id = ? AND r.household_id = ?")
     } else {
-        format!("{REVIEW_JOIN_SELECT} {REVIEW_JOIN_FROM} WHERE r.id = ? AND r.deleted_at IS NULL")
+        format!(
+            "{REVIEW_JOIN_SELECT} {REVIEW_JOIN_FROM} WHERE r.id = ? AND r.household_id = ? AND r.deleted_at IS NULL"
+        )
     };
-    let row = sqlx::query(&sql).bind(&id_str).fetch_optional(pool).await?;
+    let row = sqlx::query(&sql)
+        .bind(&id_str)
+        .bind(current_household().to_string())
+        .fetch_optional(pool)
+        .await?;
 
     let Some(row) = row else {
         return Ok(None);
@@ -400,6 +535,22 @@ pub async fn get_by_id_with_relations(
     Ok(Some(row_to_review_with_relations(&row)?))
 }
 
+/// Product id of a review (including soft-deleted), to re-index the product's search row.
+async fn get_product_id(
+    pool: &SqlitePool,
+    id_str: &str,
+) -> Result<Option<Uuid>, crate::db::DbError> {
+    let product_id: Option<String> =
+        sqlx::query_scalar("SELECT product_id FROM reviews WHERE id = ? AND household_id = ?")
+            .bind(id_str)
+            .bind(current_household().to_string())
+            .fetch_optional(pool)
+            .await?;
+    product_id
+        .map(|s| Uuid::parse_str(&s).map_err(|e| crate::db::DbError::InvalidData(e.to_string())))
+        .transpose()
+}
+
 /// Insert a review into the database.
 ///
 /// # Errors
@@ -408,7 +559,7 @@ pub async fn get_by_id_with_relations(
 pub async fn insert(pool: &SqlitePool, review: &Review) -> Result<(), crate::db::DbError> {
     let now = chrono::Utc::now().timestamp();
     sqlx::query(
-        "INSERT INTO reviews (id, product_id, user_id, rating, text, created_at, updated_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO reviews (id, product_id, user_id, rating, text, created_at, updated_at, deleted_at, household_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(review.id().to_string())
     .bind(review.product_id().to_string())
@@ -418,10 +569,20 @@ pub async fn insert(pool: &SqlitePool, review: &Review) -> Result<(), crate::db:
     .bind(now)
     .bind(now)
     .bind(review.deleted_at())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
+    crate::db::product_search::refresh_product(pool, review.product_id()).await?;
     invalidate_review_list_cache();
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Review,
+        review.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
@@ -431,37 +592,58 @@ pub async fn insert(pool: &SqlitePool, review: &Review) -> Result<(), crate::db:
 ///
 /// Returns [`crate::db::DbError`] on query failure.
 pub async fn update(pool: &SqlitePool, review: &Review) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Review, review.id()).await?;
     let now = chrono::Utc::now().timestamp();
     sqlx::query(
-        "UPDATE reviews SET rating = ?, text = ?, updated_at = ?, deleted_at = ? WHERE id = ?",
+        "UPDATE reviews SET rating = ?, text = ?, updated_at = ?, deleted_at = ? WHERE id = ? AND household_id = ?",
     )
     .bind(review.rating().to_string())
     .bind(review.text())
     .bind(now)
     .bind(review.deleted_at())
     .bind(review.id().to_string())
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
+    crate::db::product_search::refresh_product(pool, review.product_id()).await?;
     invalidate_review_list_cache();
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Review,
+        review.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
 /// Soft-delete a review by id. Sets `deleted_at` and `updated_at` to the current time.
 ///
+/// `deleted_by` is the acting user (`None` when deleted from the CLI).
+///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// no active review exists with the given id.
-pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+pub async fn soft_delete(
+    pool: &SqlitePool,
+    id: Uuid,
+    deleted_by: Option<Uuid>,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Review, id).await?;
     let id_str = id.to_string();
+    let product_id = get_product_id(pool, &id_str).await?;
     let now = chrono::Utc::now().timestamp();
     let result = sqlx::query(
-        "UPDATE reviews SET deleted_at = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
+        "UPDATE reviews SET deleted_at = ?, updated_at = ?, deleted_by = ? WHERE id = ? AND household_id = ? AND deleted_at IS NULL",
     )
     .bind(now)
     .bind(now)
+    .bind(deleted_by.map(|u| u.to_string()))
     .bind(&id_str)
+    .bind(current_household().to_string())
     .execute(pool)
     .await?;
 
@@ -471,8 +653,67 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
         )));
     }
 
+    if let Some(product_id) = product_id {
+        crate::db::product_search::refresh_product(pool, product_id).await?;
+    }
     invalidate_review_list_cache();
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Review,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Restore a soft-deleted review. Clears `deleted_at` and sets `updated_at` to the current time.
+///
+/// Fails if the reviewed product is not active.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
+/// the product is deleted or no soft-deleted review exists with the given id.
+pub async fn restore(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Review, id).await?;
+    let id_str = id.to_string();
+    let product_id = get_product_id(pool, &id_str).await?;
+    if let Some(product_id) = product_id {
+        crate::db::ensure_active_parent(
+            pool,
+            "products",
+            &product_id.to_string(),
+            "review",
+            "product",
+        )
+        .await?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE reviews SET deleted_at = NULL, deleted_by = NULL, updated_at = ? WHERE id = ? AND household_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(now)
+    .bind(&id_str)
+    .bind(current_household().to_string())
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "review not found or not deleted: {id_str}"
+        )));
+    }
+
+    if let Some(product_id) = product_id {
+        crate::db::product_search::refresh_product(pool, product_id).await?;
+    }
+    invalidate_review_list_cache();
+    crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(pool, AuditEntity::Review, id, AuditAction::Restore, before).await?;
     Ok(())
 }
 
@@ -483,9 +724,12 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
 /// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
 /// no review exists with the given id.
 pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::Review, id).await?;
     let id_str = id.to_string();
-    let result = sqlx::query("DELETE FROM reviews WHERE id = ?")
+    let product_id = get_product_id(pool, &id_str).await?;
+    let result = sqlx::query("DELETE FROM reviews WHERE id = ? AND household_id = ?")
         .bind(&id_str)
+        .bind(current_household().to_string())
         .execute(pool)
         .await?;
 
@@ -495,7 +739,18 @@ pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
         )));
     }
 
+    if let Some(product_id) = product_id {
+        crate::db::product_search::refresh_product(pool, product_id).await?;
+    }
     invalidate_review_list_cache();
     crate::db::product::invalidate_all_product_caches();
+    crate::db::audit::record(
+        pool,
+        AuditEntity::Review,
+        id,
+        AuditAction::HardDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
@@ -0,0 +1,186 @@
+//! Session persistence: login sessions and their hashed refresh tokens.
+//!
+//! Provides DB functions: [`insert`], [`get_by_id`], [`get_active_by_refresh_hash`], [`rotate`],
+//! [`revoke`], [`revoke_all_for_user`], and [`list_for_user`]. Sessions are not household scoped;
+//! each one records the household it was opened for.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::domain::session::Session;
+
+const SESSION_COLUMNS: &str =
+    "id, user_id, household_id, created_at, last_used_at, expires_at, revoked_at";
+
+/// Map a DB row into a [`Session`]. Fails on invalid UUID or domain validation.
+fn row_to_session(row: &sqlx::sqlite::SqliteRow) -> Result<Session, crate::db::DbError> {
+    let parse = |column: &str| {
+        let value: String = row.get(column);
+        Uuid::parse_str(&value).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+    };
+    Session::new(
+        parse("id")?,
+        parse("user_id")?,
+        parse("household_id")?,
+        row.get("created_at"),
+        row.get("last_used_at"),
+        row.get("expires_at"),
+        row.get("revoked_at"),
+    )
+    .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+}
+
+/// Insert a session with the hash of its first refresh token.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn insert(
+    pool: &SqlitePool,
+    session: &Session,
+    refresh_token_hash: &str,
+) -> Result<(), crate::db::DbError> {
+    sqlx::query(
+        "INSERT INTO sessions (id, user_id, household_id, refresh_token_hash, created_at, last_used_at, expires_at, revoked_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(session.id().to_string())
+    .bind(session.user_id().to_string())
+    .bind(session.household_id().to_string())
+    .bind(refresh_token_hash)
+    .bind(session.created_at())
+    .bind(session.last_used_at())
+    .bind(session.expires_at())
+    .bind(session.revoked_at())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Fetch a session by id (including revoked and expired ones).
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Session>, crate::db::DbError> {
+    let sql = format!("SELECT {SESSION_COLUMNS} FROM sessions WHERE id = ?");
+    let row = sqlx::query(&sql)
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_session).transpose()
+}
+
+/// Fetch the session whose current refresh token has `refresh_token_hash`, if it is still active
+/// at `now`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn get_active_by_refresh_hash(
+    pool: &SqlitePool,
+    refresh_token_hash: &str,
+    now: i64,
+) -> Result<Option<Session>, crate::db::DbError> {
+    let sql = format!(
+        "SELECT {SESSION_COLUMNS} FROM sessions
+         WHERE refresh_token_hash = ? AND revoked_at IS NULL AND expires_at > ?"
+    );
+    let row = sqlx::query(&sql)
+        .bind(refresh_token_hash)
+        .bind(now)
+        .fetch_optional(pool)
+        .await?;
+    row.as_ref().map(row_to_session).transpose()
+}
+
+/// Replace the refresh token of an active session and mark it used at `now`. Matching on the old
+/// hash makes a refresh token single-use: returns `false` if it was already rotated or revoked.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn rotate(
+    pool: &SqlitePool,
+    id: Uuid,
+    old_refresh_token_hash: &str,
+    new_refresh_token_hash: &str,
+    now: i64,
+) -> Result<bool, crate::db::DbError> {
+    let result = sqlx::query(
+        "UPDATE sessions SET refresh_token_hash = ?, last_used_at = ?
+         WHERE id = ? AND refresh_token_hash = ? AND revoked_at IS NULL",
+    )
+    .bind(new_refresh_token_hash)
+    .bind(now)
+    .bind(id.to_string())
+    .bind(old_refresh_token_hash)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke a session. Returns `false` if it does not exist or was already revoked.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn revoke(pool: &SqlitePool, id: Uuid) -> Result<bool, crate::db::DbError> {
+    let result =
+        sqlx::query("UPDATE sessions SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke all of a user's sessions, except `keep` if given. Returns how many were revoked.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn revoke_all_for_user(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    keep: Option<Uuid>,
+) -> Result<u64, crate::db::DbError> {
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = ?
+         WHERE user_id = ? AND revoked_at IS NULL AND (? IS NULL OR id <> ?)",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(user_id.to_string())
+    .bind(keep.map(|k| k.to_string()))
+    .bind(keep.map(|k| k.to_string()))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Fetch a user's sessions, newest first. Without `include_inactive`, only sessions still active
+/// at `now`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_for_user(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    include_inactive: bool,
+    now: i64,
+) -> Result<Vec<Session>, crate::db::DbError> {
+    let filter = if include_inactive {
+        ""
+    } else {
+        " AND revoked_at IS NULL AND expires_at > ?"
+    };
+    let sql = format!(
+        "SELECT {SESSION_COLUMNS} FROM sessions WHERE user_id = ?{filter} ORDER BY created_at DESC, id"
+    );
+    let mut query = sqlx::query(&sql).bind(user_id.to_string());
+    if !include_inactive {
+        query = query.bind(now);
+    }
+    let rows = query.fetch_all(pool).await?;
+    rows.iter().map(row_to_session).collect()
+}
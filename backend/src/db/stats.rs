@@ -0,0 +1,455 @@
+//! Spending and price statistics over purchases.
+//!
+//! Provides [`spending`], which totals `price` x `quantity` of non-deleted purchases and groups
+//! the totals by period, category, location or user, and [`price_history`], which summarizes
+//! the prices paid for one variation. Amounts are computed as decimals in Rust (as in the
+//! product aggregates) so no precision is lost to `REAL` arithmetic. Only purchases of the
+//! current household are counted.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use rust_decimal::Decimal;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::page::PageRequest;
+use crate::db::purchase::{PurchaseFilter, PurchaseSort, PurchaseWithRelations};
+
+/// How [`spending`] groups purchases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendingGroup {
+    /// Calendar month (UTC), key `YYYY-MM`.
+    Month,
+    /// ISO week starting Monday (UTC), key is the Monday as `YYYY-MM-DD`.
+    Week,
+    /// Direct child categories of the filtered category (root categories when unfiltered); each
+    /// group includes its whole subtree.
+    Category,
+    Location,
+    User,
+}
+
+impl SpendingGroup {
+    /// Parse a `group_by` value (`month`, `week`, `category`, `location`, `user`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "month" => Some(Self::Month),
+            "week" => Some(Self::Week),
+            "category" => Some(Self::Category),
+            "location" => Some(Self::Location),
+            "user" => Some(Self::User),
+            _ => None,
+        }
+    }
+
+    /// Query value of this grouping.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Month => "month",
+            Self::Week => "week",
+            Self::Category => "category",
+            Self::Location => "location",
+            Self::User => "user",
+        }
+    }
+
+    const fn is_period(self) -> bool {
+        matches!(self, Self::Month | Self::Week)
+    }
+}
+
+/// Filters for [`spending`]. `from_ts` and `to_ts` are inclusive UNIX timestamps.
+#[derive(Debug, Clone, Default)]
+pub struct SpendingFilter {
+    pub user_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    /// Restrict to products in this category or any of its descendants.
+    pub category_id: Option<Uuid>,
+    pub from_ts: Option<i64>,
+    pub to_ts: Option<i64>,
+}
+
+/// Total spent in one group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendingRow {
+    /// Period key, or the id of the category, location or user (`other` for purchases whose
+    /// category is outside the active category tree).
+    pub key: String,
+    /// Period key again, or the name of the category, location or user.
+    pub label: String,
+    pub total: Decimal,
+    pub purchase_count: u64,
+}
+
+/// Result of [`spending`]: overall total plus one row per group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendingReport {
+    pub total: Decimal,
+    pub purchase_count: u64,
+    /// Periods in chronological order; other groups by total, largest first.
+    pub groups: Vec<SpendingRow>,
+}
+
+/// Group key and label of every active category in the subtree of each group category.
+async fn category_groups(
+    pool: &SqlitePool,
+    parent_id: Option<Uuid>,
+) -> Result<HashMap<Uuid, (String, String)>, crate::db::DbError> {
+    let mut out = HashMap::new();
+    if let Some(pid) = parent_id
+        && let Some(parent) = crate::db::category::get_by_id(pool, pid, false).await?
+    {
+        out.insert(pid, (pid.to_string(), parent.name().to_string()));
+    }
+    for child in crate::db::category::get_children(pool, parent_id).await? {
+        let ids = crate::db::category::get_category_and_descendant_ids(
+            pool,
+            child.id(),
+            crate::db::category::MAX_CATEGORY_DEPTH,
+            false,
+        )
+        .await?;
+        for id in ids {
+            out.insert(id, (child.id().to_string(), child.name().to_string()));
+        }
+    }
+    Ok(out)
+}
+
+/// Key and label of the group `row` belongs to.
+fn group_of(
+    row: &SqliteRow,
+    group: SpendingGroup,
+    categories: &HashMap<Uuid, (String, String)>,
+) -> Result<(String, String), crate::db::DbError> {
+    Ok(match group {
+        SpendingGroup::Month | SpendingGroup::Week => {
+            let purchased_at: i64 = row.get("purchased_at");
+            let date = DateTime::from_timestamp(purchased_at, 0)
+                .ok_or_else(|| {
+                    crate::db::DbError::InvalidData(format!("invalid purchased_at: {purchased_at}"))
+                })?
+                .date_naive();
+            let key = if group == SpendingGroup::Month {
+                date.format("%Y-%m").to_string()
+            } else {
+                let monday =
+                    date - Duration::days(i64::from(date.weekday().num_days_from_monday()));
+                monday.format("%Y-%m-%d").to_string()
+            };
+            (key.clone(), key)
+        }
+        SpendingGroup::Category => {
+            let category_id: String = row.get("category_id");
+            let category_id = Uuid::parse_str(&category_id)
+                .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+            categories
+                .get(&category_id)
+                .cloned()
+                .unwrap_or_else(|| ("other".to_string(), "Other".to_string()))
+        }
+        SpendingGroup::Location => (row.get("location_id"), row.get("location_name")),
+        SpendingGroup::User => (row.get("user_id"), row.get("user_name")),
+    })
+}
+
+/// Total spending matching `filter`, grouped by `group`. Periods are computed in UTC and only
+/// periods with purchases are returned.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or if a stored price or id is invalid.
+pub async fn spending(
+    pool: &SqlitePool,
+    filter: &SpendingFilter,
+    group: SpendingGroup,
+) -> Result<SpendingReport, crate::db::DbError> {
+    let mut conditions = vec![
+        "pu.household_id = ?".to_string(),
+        "pu.deleted_at IS NULL".to_string(),
+    ];
+    let mut binds: Vec<String> = vec![crate::db::household::current_household().to_string()];
+    if let Some(uid) = filter.user_id {
+        conditions.push("pu.user_id = ?".to_string());
+        binds.push(uid.to_string());
+    }
+    if let Some(lid) = filter.location_id {
+        conditions.push("pu.location_id = ?".to_string());
+        binds.push(lid.to_string());
+    }
+    if let Some(cid) = filter.category_id {
+        let ids = crate::db::category::get_category_and_descendant_ids(
+            pool,
+            cid,
+            crate::db::category::MAX_CATEGORY_DEPTH,
+            false,
+        )
+        .await?;
+        if ids.is_empty() {
+            return Ok(SpendingReport {
+                total: Decimal::ZERO,
+                purchase_count: 0,
+                groups: Vec::new(),
+            });
+        }
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        conditions.push(format!("p.category_id IN ({placeholders})"));
+        binds.extend(ids.iter().map(Uuid::to_string));
+    }
+    if let Some(ts) = filter.from_ts {
+        conditions.push("pu.purchased_at >= ?".to_string());
+        binds.push(ts.to_string());
+    }
+    if let Some(ts) = filter.to_ts {
+        conditions.push("pu.purchased_at <= ?".to_string());
+        binds.push(ts.to_string());
+    }
+    let sql = format!(
+        "SELECT pu.price, pu.quantity, pu.purchased_at, p.category_id, pu.location_id, \
+             l.name AS location_name, pu.user_id, u.name AS user_name \
+         FROM purchases pu \
+         JOIN products p ON p.id = pu.product_id \
+         JOIN locations l ON l.id = pu.location_id \
+         JOIN users u ON u.id = pu.user_id \
+         WHERE {}",
+        conditions.join(" AND ")
+    );
+    let mut query = sqlx::query(&sql);
+    for b in &binds {
+        query = query.bind(b);
+    }
+    let rows = query.fetch_all(pool).await?;
+
+    let categories = if group == SpendingGroup::Category {
+        category_groups(pool, filter.category_id).await?
+    } else {
+        HashMap::new()
+    };
+    let mut total = Decimal::ZERO;
+    let mut groups: HashMap<String, SpendingRow> = HashMap::new();
+    for row in &rows {
+        let price: String = row.get("price");
+        let price: Decimal = price
+            .parse()
+            .map_err(|e: rust_decimal::Error| crate::db::DbError::InvalidData(e.to_string()))?;
+        let quantity: i32 = row.get("quantity");
+        let amount = price * Decimal::from(quantity);
+        let (key, label) = group_of(row, group, &categories)?;
+        total += amount;
+        let entry = groups.entry(key.clone()).or_insert_with(|| SpendingRow {
+            key,
+            label,
+            total: Decimal::ZERO,
+            purchase_count: 0,
+        });
+        entry.total += amount;
+        entry.purchase_count += 1;
+    }
+
+    let mut groups: Vec<SpendingRow> = groups.into_values().collect();
+    if group.is_period() {
+        groups.sort_by(|a, b| a.key.cmp(&b.key));
+    } else {
+        groups.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.label.cmp(&b.label)));
+    }
+    Ok(SpendingReport {
+        total,
+        purchase_count: rows.len() as u64,
+        groups,
+    })
+}
+
+/// Median of `values` (mean of the two middle values for an even count); `None` when empty.
+pub(crate) fn median(values: &mut [Decimal]) -> Option<Decimal> {
+    values.sort();
+    let len = values.len();
+    if len == 0 {
+        return None;
+    }
+    Some(if len % 2 == 1 {
+        values[len / 2]
+    } else {
+        (values[len / 2 - 1] + values[len / 2]) / Decimal::from(2)
+    })
+}
+
+/// Lowest, median, highest and most recent of a series of item prices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceStats {
+    pub purchase_count: u64,
+    pub min: Decimal,
+    /// Rounded to cents.
+    pub median: Decimal,
+    pub max: Decimal,
+    /// Price of the most recent purchase.
+    pub last: Decimal,
+}
+
+impl PriceStats {
+    /// Summarize `prices`, given oldest first. `None` when empty.
+    #[must_use]
+    pub fn from_prices(prices: &[Decimal]) -> Option<Self> {
+        let last = *prices.last()?;
+        let mut sorted = prices.to_vec();
+        let median = median(&mut sorted)?.round_dp(2);
+        Some(Self {
+            purchase_count: sorted.len() as u64,
+            min: sorted[0],
+            median,
+            max: sorted[sorted.len() - 1],
+            last,
+        })
+    }
+}
+
+/// [`PriceStats`] of the purchases at one location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationPriceStats {
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub stats: PriceStats,
+}
+
+/// Prices paid for one variation: the purchases oldest first, a summary overall and per
+/// location, and the change of the last price over a trailing window.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    pub purchases: Vec<PurchaseWithRelations>,
+    /// `None` when the variation has no purchases.
+    pub overall: Option<PriceStats>,
+    /// Ordered by location name.
+    pub by_location: Vec<LocationPriceStats>,
+    /// Percent change (one decimal) from the price in effect at `window_start` to the last
+    /// price. `None` with fewer than two purchases or when the base price is zero.
+    pub change_percent: Option<Decimal>,
+    pub window_start: i64,
+}
+
+/// Percent change from the last price paid at or before `window_start` (or the first price
+/// after it) to the most recent price. `purchases` are oldest first.
+fn change_percent(purchases: &[(i64, Decimal)], window_start: i64) -> Option<Decimal> {
+    let base_index = purchases
+        .iter()
+        .rposition(|&(at, _)| at <= window_start)
+        .unwrap_or(0);
+    let last_index = purchases.len().checked_sub(1)?;
+    if base_index >= last_index {
+        return None;
+    }
+    let base = purchases[base_index].1;
+    if base.is_zero() {
+        return None;
+    }
+    let last = purchases[last_index].1;
+    let mut change = ((last - base) * Decimal::from(100) / base).round_dp(1);
+    change.rescale(1);
+    Some(change)
+}
+
+/// Price history of a variation's non-deleted purchases; the trend covers the last `months`
+/// months.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or if a stored price is invalid.
+pub async fn price_history(
+    pool: &SqlitePool,
+    variation_id: Uuid,
+    months: u32,
+) -> Result<PriceHistory, crate::db::DbError> {
+    let filter = PurchaseFilter {
+        variation_id: Some(variation_id),
+        ..PurchaseFilter::default()
+    };
+    let mut purchases = crate::db::purchase::list_page(
+        pool,
+        &filter,
+        PurchaseSort::PurchasedAt,
+        &PageRequest::default(),
+    )
+    .await?
+    .items;
+    purchases.reverse();
+
+    let mut series = Vec::with_capacity(purchases.len());
+    let mut by_location: HashMap<Uuid, (String, Vec<Decimal>)> = HashMap::new();
+    for p in &purchases {
+        let price: Decimal = p
+            .price
+            .parse()
+            .map_err(|e: rust_decimal::Error| crate::db::DbError::InvalidData(e.to_string()))?;
+        series.push((p.purchased_at, price));
+        by_location
+            .entry(p.location_id)
+            .or_insert_with(|| (p.location_name.clone(), Vec::new()))
+            .1
+            .push(price);
+    }
+    let prices: Vec<Decimal> = series.iter().map(|&(_, price)| price).collect();
+    let mut by_location: Vec<LocationPriceStats> = by_location
+        .into_iter()
+        .filter_map(|(location_id, (location_name, prices))| {
+            Some(LocationPriceStats {
+                location_id,
+                location_name,
+                stats: PriceStats::from_prices(&prices)?,
+            })
+        })
+        .collect();
+    by_location.sort_by(|a, b| a.location_name.cmp(&b.location_name));
+
+    let now = Utc::now();
+    let window_start = now
+        .checked_sub_months(Months::new(months))
+        .unwrap_or(now)
+        .timestamp();
+    Ok(PriceHistory {
+        overall: PriceStats::from_prices(&prices),
+        by_location,
+        change_percent: change_percent(&series, window_start),
+        window_start,
+        purchases,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().expect("decimal")
+    }
+
+    #[test]
+    fn price_stats_use_sorted_values_and_keep_last() {
+        let stats = PriceStats::from_prices(&[dec("3"), dec("1"), dec("4.25"), dec("2.25")])
+            .expect("stats");
+        assert_eq!(stats.min, dec("1"));
+        assert_eq!(stats.median, dec("2.62"), "2.625 rounded to cents");
+        assert_eq!(stats.max, dec("4.25"));
+        assert_eq!(stats.last, dec("2.25"));
+        assert_eq!(stats.purchase_count, 4);
+        assert_eq!(PriceStats::from_prices(&[]), None);
+    }
+
+    #[test]
+    fn change_percent_starts_from_price_in_effect_at_window_start() {
+        let series = [
+            (10, dec("8.00")),
+            (20, dec("10.00")),
+            (30, dec("9.00")),
+            (40, dec("11.50")),
+        ];
+        // Price in effect at 25 is the one paid at 20.
+        assert_eq!(change_percent(&series, 25), Some(dec("15.0")));
+        // No purchase before the window: start from the first one.
+        assert_eq!(change_percent(&series, 5), Some(dec("43.8")));
+        // Last purchase is the base: no change to report.
+        assert_eq!(change_percent(&series, 40), None);
+        assert_eq!(change_percent(&series[..1], 5), None);
+    }
+}
@@ -0,0 +1,327 @@
+//! Change feed for offline clients: every category, product, variation, location, review and
+//! purchase changed after a change token.
+//!
+//! Provides [`current_token`], [`changes`], [`record`], [`id_taken`], and [`id_used_elsewhere`]. A change token is the
+//! id of the latest [`crate::db::audit`] entry a client has seen: every insert, update,
+//! soft-delete, restore and hard-delete is recorded there in commit order, so the entities
+//! changed after a token are the ones with later entries. Records are flat rows (ids instead of
+//! nested objects), including soft-deleted ones, with a `version`: the id of the row's latest
+//! audit entry (see [`crate::db::audit::entity_version`]), which changes with every change of
+//! the row. Only the current household is read.
+
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::audit::AuditEntity;
+use crate::db::household::current_household;
+
+/// Entity types in the feed, in dependency order (parents before children).
+pub const SYNCED: [AuditEntity; 6] = [
+    AuditEntity::Category,
+    AuditEntity::Product,
+    AuditEntity::Variation,
+    AuditEntity::Location,
+    AuditEntity::Review,
+    AuditEntity::Purchase,
+];
+
+/// Largest number of ids bound in one `IN` list.
+const ID_CHUNK: usize = 500;
+
+/// One flat row, keyed by field name.
+pub type Record = Map<String, Value>;
+
+/// `(field, SQL expression)` pairs of a record.
+type Fields = &'static [(&'static str, &'static str)];
+
+/// Table and fields of the records of `entity`; `None` for users.
+const fn fields(entity: AuditEntity) -> Option<(&'static str, Fields)> {
+    match entity {
+        AuditEntity::Category => Some((
+            "categories",
+            &[
+                ("id", "id"),
+                ("parent_id", "parent_id"),
+                ("name", "name"),
+                ("created_at", "created_at"),
+                ("updated_at", "updated_at"),
+                ("deleted_at", "deleted_at"),
+            ],
+        )),
+        AuditEntity::Product => Some((
+            "products",
+            &[
+                ("id", "id"),
+                ("category_id", "category_id"),
+                ("brand", "brand"),
+                ("name", "name"),
+                ("created_at", "created_at"),
+                ("updated_at", "updated_at"),
+                ("deleted_at", "deleted_at"),
+            ],
+        )),
+        AuditEntity::Variation => Some((
+            "product_variations",
+            &[
+                ("id", "id"),
+                ("product_id", "product_id"),
+                ("label", "label"),
+                ("unit", "unit"),
+                ("quantity", "quantity"),
+                ("barcode", "barcode"),
+                ("created_at", "created_at"),
+                ("updated_at", "updated_at"),
+                ("deleted_at", "deleted_at"),
+            ],
+        )),
+        AuditEntity::Location => Some((
+            "locations",
+            &[
+                ("id", "id"),
+                ("name", "name"),
+                ("updated_at", "updated_at"),
+                ("deleted_at", "deleted_at"),
+            ],
+        )),
+        AuditEntity::Review => Some((
+            "reviews",
+            &[
+                ("id", "id"),
+                ("product_id", "product_id"),
+                ("user_id", "user_id"),
+                ("rating", "CAST(rating AS REAL)"),
+                ("text", "text"),
+                ("created_at", "created_at"),
+                ("updated_at", "updated_at"),
+                ("deleted_at", "deleted_at"),
+            ],
+        )),
+        AuditEntity::Purchase => Some((
+            "purchases",
+            &[
+                ("id", "id"),
+                ("user_id", "user_id"),
+                ("product_id", "product_id"),
+                ("variation_id", "variation_id"),
+                ("location_id", "location_id"),
+                ("quantity", "quantity"),
+                ("price", "price"),
+                ("purchased_at", "purchased_at"),
+                ("updated_at", "updated_at"),
+                ("deleted_at", "deleted_at"),
+            ],
+        )),
+        AuditEntity::User => None,
+    }
+}
+
+/// Records of `entity` in the current household: all of them, or those with the given ids.
+async fn fetch_records(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    ids: Option<&[Uuid]>,
+) -> Result<Vec<Record>, crate::db::DbError> {
+    let Some((table, fields)) = fields(entity) else {
+        return Ok(Vec::new());
+    };
+    let object = fields
+        .iter()
+        .map(|(name, expr)| format!("'{name}', {expr}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let version = format!(
+        "(SELECT COALESCE(MAX(a.id), 0) FROM audit_log a
+          WHERE a.entity_id = t.id AND a.entity_type = '{}' AND a.household_id = t.household_id)",
+        entity.name()
+    );
+    let select = format!(
+        "SELECT json_object({object}, 'version', {version}) FROM {table} t WHERE household_id = ?"
+    );
+
+    let mut json: Vec<String> = Vec::new();
+    match ids {
+        None => {
+            json = sqlx::query_scalar(&format!("{select} ORDER BY id"))
+                .bind(current_household().to_string())
+                .fetch_all(pool)
+                .await?;
+        }
+        Some(ids) => {
+            for chunk in ids.chunks(ID_CHUNK) {
+                let placeholders = vec!["?"; chunk.len()].join(", ");
+                let sql = format!("{select} AND id IN ({placeholders}) ORDER BY id");
+                let mut query = sqlx::query_scalar(&sql).bind(current_household().to_string());
+                for id in chunk {
+                    query = query.bind(id.to_string());
+                }
+                json.extend(query.fetch_all(pool).await?);
+            }
+        }
+    }
+    json.iter()
+        .map(|s| match serde_json::from_str(s) {
+            Ok(Value::Object(map)) => Ok(map),
+            _ => Err(crate::db::DbError::InvalidData(format!(
+                "invalid record of {table}"
+            ))),
+        })
+        .collect()
+}
+
+/// Current record of the row `id` of `entity` (soft-deleted or not), or `None` if it does not
+/// exist in the current household.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn record(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<Option<Record>, crate::db::DbError> {
+    Ok(fetch_records(pool, entity, Some(&[id]))
+        .await?
+        .into_iter()
+        .next())
+}
+
+/// True when a row with this id exists in the table of `entity` in the current household
+/// (soft-deleted or not), so a client-chosen id cannot be used for a new row.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn id_taken(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<bool, crate::db::DbError> {
+    let Some((table, _)) = fields(entity) else {
+        return Ok(false);
+    };
+    let taken: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM {table} WHERE id = ? AND household_id = ?)"
+    ))
+    .bind(id.to_string())
+    .bind(current_household().to_string())
+    .fetch_one(pool)
+    .await?;
+    Ok(taken)
+}
+
+/// True when a row with this id exists in the table of `entity` in another household. Ids are
+/// primary keys, so such an id cannot be used for a new row either.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn id_used_elsewhere(
+    pool: &SqlitePool,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<bool, crate::db::DbError> {
+    let Some((table, _)) = fields(entity) else {
+        return Ok(false);
+    };
+    let used: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM {table} WHERE id = ? AND household_id <> ?)"
+    ))
+    .bind(id.to_string())
+    .bind(current_household().to_string())
+    .fetch_one(pool)
+    .await?;
+    Ok(used)
+}
+
+/// The latest change token: the id of the newest audit entry (0 when there is none). Ids are
+/// shared by all households, so a token only ever grows.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn current_token(pool: &SqlitePool) -> Result<i64, crate::db::DbError> {
+    let token: Option<i64> = sqlx::query_scalar("SELECT MAX(id) FROM audit_log")
+        .fetch_one(pool)
+        .await?;
+    Ok(token.unwrap_or(0))
+}
+
+/// Entities changed after a token.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Token to pass as `since` next time.
+    pub token: i64,
+    /// Current records per entity type, in [`SYNCED`] order.
+    pub records: Vec<(AuditEntity, Vec<Record>)>,
+    /// Entities removed for good (hard-deleted) since the token.
+    pub removed: Vec<(AuditEntity, Uuid)>,
+}
+
+/// Records changed after `since`, or every record when `since` is `None`.
+///
+/// Returns `None` when `since` is newer than [`current_token`] (e.g. after restoring an older
+/// backup); the client must then start over without `since`.
+///
+/// Records are read after the token, so they may already include later changes; those are sent
+/// again next time.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or invalid row data.
+pub async fn changes(
+    pool: &SqlitePool,
+    since: Option<i64>,
+) -> Result<Option<Changes>, crate::db::DbError> {
+    let token = current_token(pool).await?;
+    let mut changes = Changes {
+        token,
+        ..Changes::default()
+    };
+    let Some(since) = since else {
+        for entity in SYNCED {
+            changes
+                .records
+                .push((entity, fetch_records(pool, entity, None).await?));
+        }
+        return Ok(Some(changes));
+    };
+    if since > token {
+        return Ok(None);
+    }
+
+    let entries: Vec<(String, String)> = sqlx::query_as(
+        "SELECT DISTINCT entity_type, entity_id FROM audit_log
+         WHERE household_id = ? AND id > ? AND id <= ?",
+    )
+    .bind(current_household().to_string())
+    .bind(since)
+    .bind(token)
+    .fetch_all(pool)
+    .await?;
+    for entity in SYNCED {
+        let ids = entries
+            .iter()
+            .filter(|(kind, _)| kind == entity.name())
+            .map(|(_, id)| {
+                Uuid::parse_str(id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if ids.is_empty() {
+            changes.records.push((entity, Vec::new()));
+            continue;
+        }
+        let records = fetch_records(pool, entity, Some(&ids)).await?;
+        for id in ids {
+            let id_str = id.to_string();
+            if !records
+                .iter()
+                .any(|r| r.get("id") == Some(&Value::String(id_str.clone())))
+            {
+                changes.removed.push((entity, id));
+            }
+        }
+        changes.records.push((entity, records));
+    }
+    Ok(Some(changes))
+}
@@ -0,0 +1,153 @@
+//! TOTP two-factor persistence: per-user secrets and recovery codes.
+//!
+//! Provides DB functions: [`enable`], [`disable`], [`get_secret`], [`mark_step_used`], and
+//! [`consume_recovery_code`]. Two-factor settings are not household scoped.
+
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+/// A user's TOTP secret and the last time step a code was accepted for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TotpSecret {
+    pub secret: String,
+    pub last_used_step: Option<i64>,
+}
+
+/// Enable two-factor authentication for `user_id` with `secret` (base32) and the hashes of its
+/// recovery codes. Fails if it is already enabled.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if
+/// two-factor authentication is already enabled for the user.
+pub async fn enable(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    secret: &str,
+    recovery_code_hashes: &[String],
+    now: i64,
+) -> Result<(), crate::db::DbError> {
+    let user_id = user_id.to_string();
+    let mut tx = pool.begin().await?;
+    let inserted = sqlx::query(
+        "INSERT INTO user_totp (user_id, secret, created_at) VALUES (?, ?, ?)
+         ON CONFLICT (user_id) DO NOTHING",
+    )
+    .bind(&user_id)
+    .bind(secret)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if inserted == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "two-factor authentication already enabled: {user_id}"
+        )));
+    }
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = ?")
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await?;
+    for code_hash in recovery_code_hashes {
+        sqlx::query("INSERT INTO totp_recovery_codes (id, user_id, code_hash) VALUES (?, ?, ?)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(&user_id)
+            .bind(code_hash)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Disable two-factor authentication for `user_id`: remove its secret, recovery codes and
+/// pending login challenges. Returns `false` if it was not enabled.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn disable(pool: &SqlitePool, user_id: Uuid) -> Result<bool, crate::db::DbError> {
+    let user_id = user_id.to_string();
+    let mut tx = pool.begin().await?;
+    let removed = sqlx::query("DELETE FROM user_totp WHERE user_id = ?")
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = ?")
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM login_challenges WHERE user_id = ?")
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(removed > 0)
+}
+
+/// Fetch the TOTP secret of `user_id`; `None` if two-factor authentication is not enabled.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn get_secret(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<Option<TotpSecret>, crate::db::DbError> {
+    let row = sqlx::query("SELECT secret, last_used_step FROM user_totp WHERE user_id = ?")
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| TotpSecret {
+        secret: row.get("secret"),
+        last_used_step: row.get("last_used_step"),
+    }))
+}
+
+/// Record that a code for time step `step` was accepted. Returns `false` if a code for this or a
+/// later step was already used, so each code works only once.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn mark_step_used(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    step: i64,
+) -> Result<bool, crate::db::DbError> {
+    let result = sqlx::query(
+        "UPDATE user_totp SET last_used_step = ?
+         WHERE user_id = ? AND (last_used_step IS NULL OR last_used_step < ?)",
+    )
+    .bind(step)
+    .bind(user_id.to_string())
+    .bind(step)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Use up the unused recovery code with `code_hash` of `user_id`. Returns `false` if there is no
+/// such code.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure.
+pub async fn consume_recovery_code(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    code_hash: &str,
+    now: i64,
+) -> Result<bool, crate::db::DbError> {
+    let result = sqlx::query(
+        "UPDATE totp_recovery_codes SET used_at = ?
+         WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+    )
+    .bind(now)
+    .bind(user_id.to_string())
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
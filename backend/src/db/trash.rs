@@ -0,0 +1,308 @@
+//! Trash: soft-deleted rows of all entity types, and retention-based purge.
+//!
+//! Provides [`list_page`], which lists soft-deleted categories, products, variations, locations,
+//! reviews and purchases newest deletion first, and [`purge`], which hard-deletes soft-deleted
+//! rows older than a cutoff in dependency order. Users are managed separately and never appear
+//! here. The listing covers the current household; the purge covers all households.
+
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::audit::AuditEntity;
+use crate::db::page::{Direction, KeyKind, Page, PageRequest, SortKey, bind_values};
+
+/// Kind of a soft-deleted row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashKind {
+    Category,
+    Product,
+    Variation,
+    Location,
+    Review,
+    Purchase,
+}
+
+impl TrashKind {
+    /// Parse a `type` query value (`category`, `product`, `variation`, `location`, `review`,
+    /// `purchase`).
+    #[must_use]
+    pub fn from_param(s: &str) -> Option<Self> {
+        match s {
+            "category" => Some(Self::Category),
+            "product" => Some(Self::Product),
+            "variation" => Some(Self::Variation),
+            "location" => Some(Self::Location),
+            "review" => Some(Self::Review),
+            "purchase" => Some(Self::Purchase),
+            _ => None,
+        }
+    }
+
+    /// Query value of this kind.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Category => "category",
+            Self::Product => "product",
+            Self::Variation => "variation",
+            Self::Location => "location",
+            Self::Review => "review",
+            Self::Purchase => "purchase",
+        }
+    }
+}
+
+/// One soft-deleted row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: Uuid,
+    /// Human-readable name: the category, product or location name; for variations the product
+    /// name and variation label; for reviews and purchases the product name.
+    pub label: String,
+    pub deleted_at: i64,
+    /// User who deleted the row; `None` when deleted from the CLI or before this was recorded.
+    pub deleted_by: Option<Uuid>,
+    pub deleted_by_name: Option<String>,
+}
+
+/// Union of all soft-deleted rows as `(kind, id, label, deleted_at, deleted_by, household_id)`.
+const TRASH_SQL: &str = "\
+    SELECT 'category' AS kind, id, name AS label, deleted_at, deleted_by, household_id
+    FROM categories WHERE deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'product', id, name, deleted_at, deleted_by, household_id
+    FROM products WHERE deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'variation', v.id, trim(p.name || ' ' || v.label), v.deleted_at, v.deleted_by,
+        v.household_id
+    FROM product_variations v JOIN products p ON p.id = v.product_id
+    WHERE v.deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'location', id, name, deleted_at, deleted_by, household_id
+    FROM locations WHERE deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'review', r.id, p.name, r.deleted_at, r.deleted_by, r.household_id
+    FROM reviews r JOIN products p ON p.id = r.product_id
+    WHERE r.deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'purchase', pu.id, p.name, pu.deleted_at, pu.deleted_by, pu.household_id
+    FROM purchases pu JOIN products p ON p.id = pu.product_id
+    WHERE pu.deleted_at IS NOT NULL";
+
+const TRASH_SORT: SortKey<'static> = SortKey {
+    name: "deleted_at",
+    expr: "t.deleted_at",
+    id_expr: "t.id",
+    kind: KeyKind::Int,
+    direction: Direction::Desc,
+};
+
+/// Sort name of [`list_page`], stored in its cursors.
+pub const TRASH_SORT_NAME: &str = TRASH_SORT.name;
+
+fn parse_uuid(s: &str) -> Result<Uuid, crate::db::DbError> {
+    Uuid::parse_str(s).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
+}
+
+/// List one page of the current household's soft-deleted rows, most recently deleted first,
+/// optionally only of `kind`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure or invalid row data.
+pub async fn list_page(
+    pool: &SqlitePool,
+    kind: Option<TrashKind>,
+    page: &PageRequest,
+) -> Result<Page<TrashItem>, crate::db::DbError> {
+    let key = TRASH_SORT;
+    let mut conditions = vec!["t.household_id = ?".to_string()];
+    let mut binds = vec![crate::db::page::Value::Text(
+        crate::db::household::current_household().to_string(),
+    )];
+    if let Some(kind) = kind {
+        conditions.push("t.kind = ?".to_string());
+        binds.push(crate::db::page::Value::Text(kind.name().to_string()));
+    }
+    if let Some(after) = key.after_condition(page, &mut binds) {
+        conditions.push(after);
+    }
+    let sql = format!(
+        "SELECT t.kind, t.id, t.label, t.deleted_at, t.deleted_by, u.name AS deleted_by_name, \
+         {} AS sort_key \
+         FROM ({TRASH_SQL}) t LEFT JOIN users u ON u.id = t.deleted_by \
+         WHERE {} {}",
+        key.expr,
+        conditions.join(" AND "),
+        key.order_and_limit(page)
+    );
+    let rows = bind_values(sqlx::query(&sql), &binds)
+        .fetch_all(pool)
+        .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let kind_str: String = row.get("kind");
+        let kind = TrashKind::from_param(&kind_str).ok_or_else(|| {
+            crate::db::DbError::InvalidData(format!("unknown trash kind: {kind_str}"))
+        })?;
+        let id: String = row.get("id");
+        let deleted_by: Option<String> = row.get("deleted_by");
+        let item = TrashItem {
+            kind,
+            id: parse_uuid(&id)?,
+            label: row.get("label"),
+            deleted_at: row.get("deleted_at"),
+            deleted_by: deleted_by.as_deref().map(parse_uuid).transpose()?,
+            deleted_by_name: row.get("deleted_by_name"),
+        };
+        out.push((item, key.read_key(&row), id));
+    }
+    Ok(key.finish(out, page))
+}
+
+/// Number of rows removed (or that would be removed) per table by [`purge`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    pub reviews: u64,
+    pub purchases: u64,
+    /// Includes the variations removed together with their purged product.
+    pub variations: u64,
+    pub products: u64,
+    pub categories: u64,
+    pub locations: u64,
+}
+
+impl PurgeReport {
+    /// Total number of rows.
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.reviews
+            + self.purchases
+            + self.variations
+            + self.products
+            + self.categories
+            + self.locations
+    }
+}
+
+/// Record and delete the rows of `entity` matching `condition` (with one `?`, bound to
+/// `before`). Returns the number of rows deleted.
+async fn purge_rows(
+    conn: &mut SqliteConnection,
+    entity: AuditEntity,
+    condition: &str,
+    before: i64,
+) -> Result<u64, crate::db::DbError> {
+    crate::db::audit::record_hard_deletes(conn, entity, condition, before).await?;
+    Ok(
+        sqlx::query(&format!("DELETE FROM {} WHERE {condition}", entity.table()))
+            .bind(before)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected(),
+    )
+}
+
+/// Permanently delete rows of all households soft-deleted before `before` (UNIX timestamp).
+///
+/// Rows are removed in dependency order: reviews and purchases, then variations, products
+/// (together with their remaining variations), categories (children before parents) and
+/// locations. A row still referenced by another row that is kept (e.g. a deleted product with a
+/// recently deleted purchase or an active review) is skipped and purged by a later run. Each
+/// removed row gets a hard-delete entry in the audit log of its household. Runs in one
+/// transaction; with `dry_run` the transaction is rolled back and the report tells what would
+/// have been removed.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure; nothing is deleted in that case.
+pub async fn purge(
+    pool: &SqlitePool,
+    before: i64,
+    dry_run: bool,
+) -> Result<PurgeReport, crate::db::DbError> {
+    let mut tx = pool.begin().await?;
+    let soft_deleted = "deleted_at IS NOT NULL AND deleted_at < ?";
+    let reviews = purge_rows(&mut tx, AuditEntity::Review, soft_deleted, before).await?;
+    let purchases = purge_rows(&mut tx, AuditEntity::Purchase, soft_deleted, before).await?;
+
+    let mut variations = purge_rows(
+        &mut tx,
+        AuditEntity::Variation,
+        "deleted_at IS NOT NULL AND deleted_at < ?
+           AND NOT EXISTS (SELECT 1 FROM purchases pu WHERE pu.variation_id = product_variations.id)",
+        before,
+    )
+    .await?;
+
+    let purgeable_products = "SELECT id FROM products
+         WHERE deleted_at IS NOT NULL AND deleted_at < ?
+           AND NOT EXISTS (SELECT 1 FROM purchases pu WHERE pu.product_id = products.id)
+           AND NOT EXISTS (SELECT 1 FROM reviews r WHERE r.product_id = products.id)";
+    variations += purge_rows(
+        &mut tx,
+        AuditEntity::Variation,
+        &format!("product_id IN ({purgeable_products})"),
+        before,
+    )
+    .await?;
+    let products = purge_rows(
+        &mut tx,
+        AuditEntity::Product,
+        &format!("id IN ({purgeable_products})"),
+        before,
+    )
+    .await?;
+    sqlx::query("DELETE FROM products_fts WHERE product_id NOT IN (SELECT id FROM products)")
+        .execute(&mut *tx)
+        .await?;
+
+    let mut categories = 0;
+    // Each pass removes the leaves of the deleted subtrees; repeat until nothing is left.
+    loop {
+        let removed = purge_rows(
+            &mut tx,
+            AuditEntity::Category,
+            "deleted_at IS NOT NULL AND deleted_at < ?
+               AND NOT EXISTS (SELECT 1 FROM products p WHERE p.category_id = categories.id)
+               AND NOT EXISTS (SELECT 1 FROM categories c WHERE c.parent_id = categories.id)",
+            before,
+        )
+        .await?;
+        if removed == 0 {
+            break;
+        }
+        categories += removed;
+    }
+
+    let locations = purge_rows(
+        &mut tx,
+        AuditEntity::Location,
+        "deleted_at IS NOT NULL AND deleted_at < ?
+           AND NOT EXISTS (SELECT 1 FROM purchases pu WHERE pu.location_id = locations.id)",
+        before,
+    )
+    .await?;
+
+    let report = PurgeReport {
+        reviews,
+        purchases,
+        variations,
+        products,
+        categories,
+        locations,
+    };
+    if dry_run || report.total() == 0 {
+        tx.rollback().await?;
+        return Ok(report);
+    }
+    tx.commit().await?;
+
+    crate::db::category::invalidate_category_list_cache();
+    crate::db::location::invalidate_location_list_cache();
+    crate::db::review::invalidate_review_list_cache();
+    crate::db::product::invalidate_all_product_caches();
+    Ok(report)
+}
@@ -1,23 +1,33 @@
 //! User persistence.
 //!
-//! Provides [`get_by_id`], [`get_by_email`], [`list_all`], [`insert`], [`soft_delete`], and [`hard_delete`] for loading, creating, and deleting users.
+//! Provides [`get_by_id`], [`get_by_email`], [`list_all`], [`list_members`], [`insert`], [`insert_member`], [`update`], [`set_password`], [`soft_delete`], and [`hard_delete`] for loading, creating, updating, and deleting users.
+//!
+//! Users are not household scoped; [`list_members`] lists those of the current household.
 
-use sqlx::{Row, SqlitePool};
+use sqlx::{Row, SqliteConnection, SqlitePool};
 use uuid::Uuid;
 
-use crate::domain::user::User;
+use crate::db::audit::{AuditAction, AuditEntity};
+use crate::domain::user::{Role, User};
 
-/// Map a DB row into a [`User`]. Fails on invalid UUID or domain validation.
+/// Map a DB row into a [`User`]. Fails on invalid UUID, role or domain validation.
+#[allow(clippy::too_many_arguments)]
 fn row_to_user(
     id: &str,
     name: &str,
     email: &str,
     password: &str,
+    role: &str,
     created_at: i64,
     updated_at: i64,
     deleted_at: Option<i64>,
 ) -> Result<User, crate::db::DbError> {
     let id = Uuid::parse_str(id).map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+    let role: Role = role
+        .parse()
+        .map_err(|e: crate::domain::user::ValidationError| {
+            crate::db::DbError::InvalidData(e.to_string())
+        })?;
     User::new(
         id,
         name.to_owned(),
@@ -27,6 +37,7 @@ fn row_to_user(
         updated_at,
         deleted_at,
     )
+    .map(|user| user.with_role(role))
     .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))
 }
 
@@ -46,14 +57,14 @@ pub async fn get_by_id(
     let id_str = id.to_string();
     let row = if include_deleted {
         sqlx::query(
-            "SELECT id, name, email, password, created_at, updated_at, deleted_at FROM users WHERE id = ?",
+            "SELECT id, name, email, password, role, created_at, updated_at, deleted_at FROM users WHERE id = ?",
         )
         .bind(&id_str)
         .fetch_optional(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, name, email, password, created_at, updated_at, deleted_at FROM users WHERE id = ? AND deleted_at IS NULL",
+            "SELECT id, name, email, password, role, created_at, updated_at, deleted_at FROM users WHERE id = ? AND deleted_at IS NULL",
         )
         .bind(&id_str)
         .fetch_optional(pool)
@@ -68,12 +79,13 @@ pub async fn get_by_id(
     let name: String = row.get("name");
     let email: String = row.get("email");
     let password: String = row.get("password");
+    let role: String = row.get("role");
     let created_at: i64 = row.get("created_at");
     let updated_at: i64 = row.get("updated_at");
     let deleted_at: Option<i64> = row.get("deleted_at");
 
     let user = row_to_user(
-        &id, &name, &email, &password, created_at, updated_at, deleted_at,
+        &id, &name, &email, &password, &role, created_at, updated_at, deleted_at,
     )?;
     Ok(Some(user))
 }
@@ -88,7 +100,7 @@ pub async fn get_by_email(
     email: &str,
 ) -> Result<Option<User>, crate::db::DbError> {
     let row = sqlx::query(
-        "SELECT id, name, email, password, created_at, updated_at, deleted_at FROM users WHERE email = ? AND deleted_at IS NULL",
+        "SELECT id, name, email, password, role, created_at, updated_at, deleted_at FROM users WHERE email = ? AND deleted_at IS NULL",
     )
     .bind(email)
     .fetch_optional(pool)
@@ -102,12 +114,13 @@ pub async fn get_by_email(
     let name: String = row.get("name");
     let email: String = row.get("email");
     let password: String = row.get("password");
+    let role: String = row.get("role");
     let created_at: i64 = row.get("created_at");
     let updated_at: i64 = row.get("updated_at");
     let deleted_at: Option<i64> = row.get("deleted_at");
 
     let user = row_to_user(
-        &id, &name, &email, &password, created_at, updated_at, deleted_at,
+        &id, &name, &email, &password, &role, created_at, updated_at, deleted_at,
     )?;
     Ok(Some(user))
 }
@@ -126,13 +139,13 @@ pub async fn list_all(
 ) -> Result<Vec<User>, crate::db::DbError> {
     let rows = if include_deleted {
         sqlx::query(
-            "SELECT id, name, email, password, created_at, updated_at, deleted_at FROM users ORDER BY email",
+            "SELECT id, name, email, password, role, created_at, updated_at, deleted_at FROM users ORDER BY email",
         )
         .fetch_all(pool)
         .await?
     } else {
         sqlx::query(
-            "SELECT id, name, email, password, created_at, updated_at, deleted_at FROM users WHERE deleted_at IS NULL ORDER BY email",
+            "SELECT id, name, email, password, role, created_at, updated_at, deleted_at FROM users WHERE deleted_at IS NULL ORDER BY email",
         )
         .fetch_all(pool)
         .await?
@@ -144,47 +157,271 @@ pub async fn list_all(
         let name: String = row.get("name");
         let email: String = row.get("email");
         let password: String = row.get("password");
+        let role: String = row.get("role");
         let created_at: i64 = row.get("created_at");
         let updated_at: i64 = row.get("updated_at");
         let deleted_at: Option<i64> = row.get("deleted_at");
         let user = row_to_user(
-            &id, &name, &email, &password, created_at, updated_at, deleted_at,
+            &id, &name, &email, &password, &role, created_at, updated_at, deleted_at,
         )?;
         users.push(user);
     }
     Ok(users)
 }
 
+/// List users that are members of the current household (see
+/// [`crate::db::household::current_household`]), ordered by email.
+///
+/// When `include_deleted` is `false`, only active users are returned.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query or row mapping failure.
+pub async fn list_members(
+    pool: &SqlitePool,
+    include_deleted: bool,
+) -> Result<Vec<User>, crate::db::DbError> {
+    let rows = sqlx::query(
+        "SELECT u.id, u.name, u.email, u.password, u.role, u.created_at, u.updated_at, u.deleted_at
+         FROM users u
+         JOIN household_members m ON m.user_id = u.id AND m.household_id = ?
+         WHERE ? OR u.deleted_at IS NULL
+         ORDER BY u.email",
+    )
+    .bind(crate::db::household::current_household().to_string())
+    .bind(include_deleted)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            row_to_user(
+                row.get("id"),
+                row.get("name"),
+                row.get("email"),
+                row.get("password"),
+                row.get("role"),
+                row.get("created_at"),
+                row.get("updated_at"),
+                row.get("deleted_at"),
+            )
+        })
+        .collect()
+}
+
+/// Households to record a change of user `id` in: the current one and every one the user is a
+/// member of, since each of them shows the user (e.g. the name on purchases and reviews).
+async fn audit_households(pool: &SqlitePool, id: Uuid) -> Result<Vec<Uuid>, crate::db::DbError> {
+    let current = crate::db::household::current_household();
+    let members: Vec<String> =
+        sqlx::query_scalar("SELECT household_id FROM household_members WHERE user_id = ?")
+            .bind(id.to_string())
+            .fetch_all(pool)
+            .await?;
+    let mut households = vec![current];
+    for household in members {
+        let household = Uuid::parse_str(&household)
+            .map_err(|e| crate::db::DbError::InvalidData(e.to_string()))?;
+        if household != current {
+            households.push(household);
+        }
+    }
+    Ok(households)
+}
+
 /// Insert a user into the database.
 ///
 /// # Errors
 ///
 /// Returns [`crate::db::DbError`] on query failure (e.g. duplicate email).
 pub async fn insert(pool: &SqlitePool, user: &User) -> Result<(), crate::db::DbError> {
+    insert_row(&mut *pool.acquire().await?, user).await?;
+    crate::db::audit::record(
+        pool,
+        AuditEntity::User,
+        user.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Insert a user as a member of `household_id`, in one transaction: either both the user and the
+/// membership are stored, or neither. The insert is recorded in that household.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (e.g. duplicate email).
+pub async fn insert_member(
+    pool: &SqlitePool,
+    user: &User,
+    household_id: Uuid,
+) -> Result<(), crate::db::DbError> {
+    let mut tx = pool.begin().await?;
+    insert_into_household(&mut tx, user, household_id, chrono::Utc::now().timestamp()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Insert a user, their membership of `household_id` (joined at `now`) and the audit entry in
+/// that household on `conn`. Call it on the connection of a transaction.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (e.g. duplicate email).
+pub(crate) async fn insert_into_household(
+    conn: &mut SqliteConnection,
+    user: &User,
+    household_id: Uuid,
+    now: i64,
+) -> Result<(), crate::db::DbError> {
+    insert_row(&mut *conn, user).await?;
     sqlx::query(
-        "INSERT INTO users (id, name, email, password, created_at, updated_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO household_members (household_id, user_id, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(household_id.to_string())
+    .bind(user.id().to_string())
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+    crate::db::audit::record_on(
+        conn,
+        &[household_id],
+        AuditEntity::User,
+        user.id(),
+        AuditAction::Insert,
+        None,
+    )
+    .await
+}
+
+/// Insert the row of `user` on `conn`, without recording it.
+async fn insert_row(conn: &mut SqliteConnection, user: &User) -> Result<(), crate::db::DbError> {
+    sqlx::query(
+        "INSERT INTO users (id, name, email, password, role, created_at, updated_at, deleted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(user.id().to_string())
     .bind(user.name())
     .bind(user.email())
     .bind(user.password())
+    .bind(user.role().to_string())
     .bind(user.created_at())
     .bind(user.updated_at())
     .bind(user.deleted_at())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Update an active user's name, email and role; bumps `updated_at`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure (e.g. duplicate email), or [`crate::db::DbError::InvalidData`] if no active user exists with the given id.
+pub async fn update(pool: &SqlitePool, user: &User) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::User, user.id()).await?;
+    let id_str = user.id().to_string();
+    let result = sqlx::query(
+        "UPDATE users SET name = ?, email = ?, role = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(user.name())
+    .bind(user.email())
+    .bind(user.role().to_string())
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&id_str)
     .execute(pool)
     .await?;
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "user not found or deleted: {id_str}"
+        )));
+    }
+    let households = audit_households(pool, user.id()).await?;
+    crate::db::audit::record_in(
+        pool,
+        &households,
+        AuditEntity::User,
+        user.id(),
+        AuditAction::Update,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Replace an active user's password hash and bump `updated_at`.
+///
+/// # Errors
+///
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if no active user exists with the given id.
+pub async fn set_password(
+    pool: &SqlitePool,
+    id: Uuid,
+    password_hash: &str,
+) -> Result<(), crate::db::DbError> {
+    let before = crate::db::audit::snapshot(pool, AuditEntity::User, id).await?;
+    let id_str = id.to_string();
+    let result = sqlx::query(
+        "UPDATE users SET password = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(password_hash)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&id_str)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "user not found or deleted: {id_str}"
+        )));
+    }
+    let households = audit_households(pool, id).await?;
+    crate::db::audit::record_in(
+        pool,
+        &households,
+        AuditEntity::User,
+        id,
+        AuditAction::Update,
+        before,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Fail with [`crate::db::DbError::InvalidData`] if the user has made purchases or written
+/// reviews (including soft-deleted ones).
+async fn ensure_no_purchases_or_reviews(
+    pool: &SqlitePool,
+    user_id: &str,
+) -> Result<(), crate::db::DbError> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM purchases WHERE user_id = ?) + (SELECT COUNT(*) FROM reviews WHERE user_id = ?)",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    if count > 0 {
+        return Err(crate::db::DbError::InvalidData(format!(
+            "cannot delete user with purchases or reviews: {user_id}"
+        )));
+    }
     Ok(())
 }
 
 /// Soft-delete a user by id. Sets `deleted_at` and `updated_at` to the current time.
 /// Only affects rows where `deleted_at` IS NULL.
 ///
+/// Fails if the user has any purchases or reviews.
+///
 /// # Errors
 ///
-/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if no active user exists with the given id.
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if the user has purchases or reviews or no active user exists with the given id.
 pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
-    let now = chrono::Utc::now().timestamp();
     let id_str = id.to_string();
+    ensure_no_purchases_or_reviews(pool, &id_str).await?;
+    let before = crate::db::audit::snapshot(pool, AuditEntity::User, id).await?;
+    let now = chrono::Utc::now().timestamp();
     let result = sqlx::query(
         "UPDATE users SET deleted_at = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL",
     )
@@ -198,16 +435,31 @@ pub async fn soft_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
             "user not found or already deleted: {id_str}"
         )));
     }
+    let households = audit_households(pool, id).await?;
+    crate::db::audit::record_in(
+        pool,
+        &households,
+        AuditEntity::User,
+        id,
+        AuditAction::SoftDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
 
 /// Permanently remove a user from the database (`DELETE`). Use with care.
 ///
+/// Fails if the user has any purchases or reviews.
+///
 /// # Errors
 ///
-/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if no user exists with the given id.
+/// Returns [`crate::db::DbError`] on query failure, or [`crate::db::DbError::InvalidData`] if the user has purchases or reviews or no user exists with the given id.
 pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::DbError> {
     let id_str = id.to_string();
+    ensure_no_purchases_or_reviews(pool, &id_str).await?;
+    let before = crate::db::audit::snapshot(pool, AuditEntity::User, id).await?;
+    let households = audit_households(pool, id).await?;
     let result = sqlx::query("DELETE FROM users WHERE id = ?")
         .bind(&id_str)
         .execute(pool)
@@ -217,5 +469,14 @@ pub async fn hard_delete(pool: &SqlitePool, id: Uuid) -> Result<(), crate::db::D
             "user not found: {id_str}"
         )));
     }
+    crate::db::audit::record_in(
+        pool,
+        &households,
+        AuditEntity::User,
+        id,
+        AuditAction::HardDelete,
+        before,
+    )
+    .await?;
     Ok(())
 }
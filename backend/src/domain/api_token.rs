@@ -0,0 +1,230 @@
+//! Personal access token domain type: a named, scoped API credential for scripts.
+
+use std::fmt;
+use std::str::FromStr;
+
+use uuid::Uuid;
+
+/// Prefix of every personal access token, so it can be told apart from a JWT.
+pub const API_TOKEN_PREFIX: &str = "prt_";
+
+/// Validation errors for [`ApiToken`] fields.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// The name field is empty.
+    #[error("name must not be empty")]
+    NameEmpty,
+
+    /// The scope is not one of `read`, `write`.
+    #[error("invalid scope: {scope} (expected read or write)")]
+    ScopeInvalid {
+        /// The rejected value.
+        scope: String,
+    },
+
+    /// `expires_at` is not after `created_at`.
+    #[error("expires_at ({expires_at}) must be after created_at ({created_at})")]
+    ExpiresNotAfterCreated {
+        /// The `created_at` value.
+        created_at: i64,
+        /// The `expires_at` value.
+        expires_at: i64,
+    },
+}
+
+/// What a token may do: read-only tokens are limited to `GET` requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScope {
+    Read,
+    Write,
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        })
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = ValidationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            other => Err(ValidationError::ScopeInvalid {
+                scope: other.to_string(),
+            }),
+        }
+    }
+}
+
+/// A validated personal access token. The token itself is only stored as a hash (see
+/// [`crate::auth::token`]) and is not part of this type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiToken {
+    id: Uuid,
+    user_id: Uuid,
+    household_id: Uuid,
+    name: String,
+    scope: TokenScope,
+    created_at: i64,
+    expires_at: Option<i64>,
+    last_used_at: Option<i64>,
+    revoked_at: Option<i64>,
+}
+
+impl ApiToken {
+    /// Create a new `ApiToken` after validating all fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError`] if any field is invalid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: Uuid,
+        user_id: Uuid,
+        household_id: Uuid,
+        name: String,
+        scope: TokenScope,
+        created_at: i64,
+        expires_at: Option<i64>,
+        last_used_at: Option<i64>,
+        revoked_at: Option<i64>,
+    ) -> Result<Self, ValidationError> {
+        if name.trim().is_empty() {
+            return Err(ValidationError::NameEmpty);
+        }
+
+        if let Some(expires_at) = expires_at
+            && expires_at <= created_at
+        {
+            return Err(ValidationError::ExpiresNotAfterCreated {
+                created_at,
+                expires_at,
+            });
+        }
+
+        Ok(Self {
+            id,
+            user_id,
+            household_id,
+            name,
+            scope,
+            created_at,
+            expires_at,
+            last_used_at,
+            revoked_at,
+        })
+    }
+
+    /// Whether the token can be used at `now` (not revoked and not expired).
+    #[must_use]
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|e| now < e)
+    }
+
+    /// The token's unique identifier.
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The user the token acts as.
+    #[must_use]
+    pub const fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+
+    /// The household the token works in.
+    #[must_use]
+    pub const fn household_id(&self) -> Uuid {
+        self.household_id
+    }
+
+    /// The token name (e.g. "home-assistant").
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// What the token may do.
+    #[must_use]
+    pub const fn scope(&self) -> TokenScope {
+        self.scope
+    }
+
+    /// UNIX timestamp when the token was created.
+    #[must_use]
+    pub const fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// UNIX timestamp after which the token is rejected (`None` = never expires).
+    #[must_use]
+    pub const fn expires_at(&self) -> Option<i64> {
+        self.expires_at
+    }
+
+    /// UNIX timestamp of the last request made with the token.
+    #[must_use]
+    pub const fn last_used_at(&self) -> Option<i64> {
+        self.last_used_at
+    }
+
+    /// UNIX timestamp when the token was revoked, if it was.
+    #[must_use]
+    pub const fn revoked_at(&self) -> Option<i64> {
+        self.revoked_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_token(expires_at: Option<i64>) -> Result<ApiToken, ValidationError> {
+        ApiToken::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "script".to_string(),
+            TokenScope::Read,
+            1_000,
+            expires_at,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn token_without_expiry_stays_active() {
+        let token = make_token(None).expect("valid");
+        assert!(token.is_active(i64::MAX));
+        let expiring = make_token(Some(2_000)).expect("valid");
+        assert!(expiring.is_active(1_999));
+        assert!(!expiring.is_active(2_000));
+    }
+
+    #[test]
+    fn expiry_must_be_after_creation() {
+        let err = make_token(Some(1_000)).unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::ExpiresNotAfterCreated {
+                created_at: 1_000,
+                expires_at: 1_000,
+            }
+        );
+    }
+
+    #[test]
+    fn scope_parses_and_displays() {
+        assert_eq!("write".parse::<TokenScope>(), Ok(TokenScope::Write));
+        assert_eq!(TokenScope::Read.to_string(), "read");
+        assert!("admin".parse::<TokenScope>().is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Household domain type with field validation.
+
+use std::fmt;
+
+use uuid::Uuid;
+
+/// Validation errors for [`Household`] fields.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// The name field is empty.
+    #[error("name must not be empty")]
+    NameEmpty,
+}
+
+/// A validated household: a separate data space shared by its member users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Household {
+    id: Uuid,
+    name: String,
+    created_at: i64,
+}
+
+impl Household {
+    /// Create a new `Household` after validating all fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError`] if any field is invalid.
+    pub fn new(id: Uuid, name: String, created_at: i64) -> Result<Self, ValidationError> {
+        if name.trim().is_empty() {
+            return Err(ValidationError::NameEmpty);
+        }
+
+        Ok(Self {
+            id,
+            name,
+            created_at,
+        })
+    }
+
+    /// The household's unique identifier.
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The household name.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// UNIX timestamp when the household was created.
+    #[must_use]
+    pub const fn created_at(&self) -> i64 {
+        self.created_at
+    }
+}
+
+impl fmt::Display for Household {
+    /// Format as `uuid (name)` for use in list output.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.id(), self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_household() {
+        let household = Household::new(Uuid::new_v4(), "Smiths".to_owned(), 1_000).expect("valid");
+        assert_eq!(household.name(), "Smiths");
+        assert_eq!(household.created_at(), 1_000);
+    }
+
+    #[test]
+    fn whitespace_only_name_is_rejected() {
+        let err = Household::new(Uuid::new_v4(), "  ".to_owned(), 1_000).unwrap_err();
+        assert_eq!(err, ValidationError::NameEmpty);
+    }
+}
@@ -0,0 +1,163 @@
+//! Invite domain type: a single-use, expiring permission to register one account.
+
+use uuid::Uuid;
+
+use crate::domain::user::Role;
+
+/// Validation errors for [`Invite`] fields.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// The email address is not structurally valid.
+    #[error("invalid email address: {0}")]
+    EmailInvalid(String),
+
+    /// `expires_at` is before `created_at`.
+    #[error("expires_at ({expires_at}) must not be before created_at ({created_at})")]
+    ExpiresBeforeCreated {
+        /// The `created_at` value.
+        created_at: i64,
+        /// The `expires_at` value.
+        expires_at: i64,
+    },
+}
+
+/// A validated invite. The invite token itself is only stored as a hash (see
+/// [`crate::auth::token`]) and is not part of this type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invite {
+    id: Uuid,
+    email: String,
+    role: Role,
+    household_id: Uuid,
+    created_at: i64,
+    expires_at: i64,
+    used_at: Option<i64>,
+}
+
+impl Invite {
+    /// Create a new `Invite` after validating all fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError`] if any field is invalid.
+    pub fn new(
+        id: Uuid,
+        email: String,
+        role: Role,
+        household_id: Uuid,
+        created_at: i64,
+        expires_at: i64,
+        used_at: Option<i64>,
+    ) -> Result<Self, ValidationError> {
+        if !crate::domain::user::is_valid_email(&email) {
+            return Err(ValidationError::EmailInvalid(email));
+        }
+
+        if expires_at < created_at {
+            return Err(ValidationError::ExpiresBeforeCreated {
+                created_at,
+                expires_at,
+            });
+        }
+
+        Ok(Self {
+            id,
+            email,
+            role,
+            household_id,
+            created_at,
+            expires_at,
+            used_at,
+        })
+    }
+
+    /// Whether the invite can still be redeemed at `now` (not used and not expired).
+    #[must_use]
+    pub const fn is_active(&self, now: i64) -> bool {
+        self.used_at.is_none() && now < self.expires_at
+    }
+
+    /// The invite's unique identifier.
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The email address the new account will have.
+    #[must_use]
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    /// The role the new account will have.
+    #[must_use]
+    pub const fn role(&self) -> Role {
+        self.role
+    }
+
+    /// The household the new account joins.
+    #[must_use]
+    pub const fn household_id(&self) -> Uuid {
+        self.household_id
+    }
+
+    /// UNIX timestamp when the invite was issued.
+    #[must_use]
+    pub const fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// UNIX timestamp after which the invite is no longer accepted.
+    #[must_use]
+    pub const fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    /// UNIX timestamp when the invite was redeemed or superseded, if it was.
+    #[must_use]
+    pub const fn used_at(&self) -> Option<i64> {
+        self.used_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_invite(
+        email: &str,
+        expires_at: i64,
+        used_at: Option<i64>,
+    ) -> Result<Invite, ValidationError> {
+        Invite::new(
+            Uuid::new_v4(),
+            email.to_string(),
+            Role::Member,
+            Uuid::new_v4(),
+            1_000,
+            expires_at,
+            used_at,
+        )
+    }
+
+    #[test]
+    fn invite_is_active_until_expiry_or_use() {
+        let invite = make_invite("bob@example.com", 2_000, None).expect("valid");
+        assert!(invite.is_active(1_999));
+        assert!(!invite.is_active(2_000));
+        let used = make_invite("bob@example.com", 2_000, Some(1_500)).expect("valid");
+        assert!(!used.is_active(1_600));
+    }
+
+    #[test]
+    fn invalid_email_is_rejected() {
+        let err = make_invite("bob", 2_000, None).unwrap_err();
+        assert_eq!(err, ValidationError::EmailInvalid("bob".to_string()));
+    }
+
+    #[test]
+    fn expires_before_created_is_rejected() {
+        let err = make_invite("bob@example.com", 999, None).unwrap_err();
+        assert!(matches!(err, ValidationError::ExpiresBeforeCreated { .. }));
+    }
+}
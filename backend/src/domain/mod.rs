@@ -1,9 +1,13 @@
 //! Domain types: validated structs for each entity.
 
+pub mod api_token;
 pub mod category;
+pub mod household;
+pub mod invite;
 pub mod location;
 pub mod product;
 pub mod product_variation;
 pub mod purchase;
 pub mod review;
+pub mod session;
 pub mod user;
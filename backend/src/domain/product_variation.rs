@@ -2,6 +2,8 @@
 
 use std::fmt;
 use std::str::FromStr;
+
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
 /// Valid unit values for a product variation.
@@ -19,6 +21,42 @@ impl Unit {
     pub const fn all() -> [Self; 4] {
         [Self::Grams, Self::Milliliters, Self::Other, Self::None]
     }
+
+    /// Base unit that prices are normalized to: "kg" for grams, "l" for milliliters.
+    /// `None` for units without a measurable amount.
+    #[must_use]
+    pub const fn base_unit(self) -> Option<&'static str> {
+        match self {
+            Self::Grams => Some("kg"),
+            Self::Milliliters => Some("l"),
+            Self::Other | Self::None => None,
+        }
+    }
+}
+
+/// A price normalized to one kilogram or one litre (e.g. 2.99 for 500 g -> 5.98 per kg).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitPrice {
+    /// Price per base unit, rounded to two decimals.
+    pub price: Decimal,
+    /// Base unit: "kg" or "l".
+    pub per: &'static str,
+}
+
+impl UnitPrice {
+    /// Normalize the price of one item holding `quantity` of `unit` to a price per kg or l.
+    /// Returns `None` when the unit is other/none or the quantity is missing or zero.
+    #[must_use]
+    pub fn from_item_price(price: Decimal, unit: Unit, quantity: Option<u32>) -> Option<Self> {
+        let per = unit.base_unit()?;
+        let quantity = quantity.filter(|&q| q > 0)?;
+        let mut normalized = (price * Decimal::from(1000) / Decimal::from(quantity)).round_dp(2);
+        normalized.rescale(2);
+        Some(Self {
+            price: normalized,
+            per,
+        })
+    }
 }
 
 impl fmt::Display for Unit {
@@ -54,6 +92,45 @@ pub enum ValidationError {
     /// The unit is not one of the allowed values.
     #[error("unit must be one of: grams, milliliters, other, none (got {unit:?})")]
     UnitInvalid { unit: String },
+
+    /// The barcode is not a GTIN-8, GTIN-12, GTIN-13 or GTIN-14 with a valid check digit.
+    #[error(
+        "barcode must be an 8, 12, 13 or 14 digit GTIN with a valid check digit (got {barcode:?})"
+    )]
+    BarcodeInvalid { barcode: String },
+}
+
+/// Whether `code` is a GTIN-8, GTIN-12 (UPC-A), GTIN-13 (EAN-13) or GTIN-14 with a valid
+/// check digit.
+#[must_use]
+pub fn is_valid_gtin(code: &str) -> bool {
+    if !matches!(code.len(), 8 | 12 | 13 | 14) || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let digits: Vec<u32> = code.bytes().map(|b| u32::from(b - b'0')).collect();
+    let (check, payload) = digits.split_last().unwrap_or((&0, &[]));
+    // Weights alternate 3, 1, 3, ... starting from the digit next to the check digit.
+    let sum: u32 = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+    (10 - sum % 10) % 10 == *check
+}
+
+/// The canonical form of a valid GTIN, or `None` if `code` is not one (see [`is_valid_gtin`]).
+///
+/// Leading zeros do not change a GTIN, so the UPC-A `036000291452` and the EAN-13
+/// `0036000291452` are the same product. Codes are zero-padded to 13 digits (EAN-13); a GTIN-14
+/// keeps its 14 digits only when its first digit (the packaging indicator) is not zero.
+#[must_use]
+pub fn normalize_gtin(code: &str) -> Option<String> {
+    if !is_valid_gtin(code) {
+        return None;
+    }
+    let padded = format!("{code:0>14}");
+    Some(padded.strip_prefix('0').unwrap_or(&padded).to_string())
 }
 
 /// A validated product variation (e.g. size or unit; belongs to a product).
@@ -64,6 +141,7 @@ pub struct ProductVariation {
     label: String,
     unit: Unit,
     quantity: Option<u32>,
+    barcode: Option<String>,
     created_at: i64,
     updated_at: i64,
     deleted_at: Option<i64>,
@@ -128,12 +206,32 @@ impl ProductVariation {
             label,
             unit: unit_val,
             quantity,
+            barcode: None,
             created_at,
             updated_at,
             deleted_at,
         })
     }
 
+    /// Set or clear the barcode (GTIN), stored in its canonical form (see [`normalize_gtin`]).
+    /// Surrounding whitespace is trimmed; an empty string clears it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError::BarcodeInvalid`] if the barcode is not a valid GTIN.
+    pub fn with_barcode(mut self, barcode: Option<&str>) -> Result<Self, ValidationError> {
+        self.barcode = barcode
+            .map(str::trim)
+            .filter(|b| !b.is_empty())
+            .map(|b| {
+                normalize_gtin(b).ok_or_else(|| ValidationError::BarcodeInvalid {
+                    barcode: b.to_string(),
+                })
+            })
+            .transpose()?;
+        Ok(self)
+    }
+
     /// Whether the variation is active (not soft-deleted).
     #[must_use]
     pub const fn is_active(&self) -> bool {
@@ -165,6 +263,11 @@ impl ProductVariation {
         self.quantity
     }
 
+    #[must_use]
+    pub fn barcode(&self) -> Option<&str> {
+        self.barcode.as_deref()
+    }
+
     #[must_use]
     pub const fn created_at(&self) -> i64 {
         self.created_at
@@ -179,6 +282,13 @@ impl ProductVariation {
     pub const fn deleted_at(&self) -> Option<i64> {
         self.deleted_at
     }
+
+    /// Price per kg or l for one item of this variation bought at `price`.
+    /// `None` when the variation has no weight or volume.
+    #[must_use]
+    pub fn unit_price(&self, price: Decimal) -> Option<UnitPrice> {
+        UnitPrice::from_item_price(price, self.unit, self.quantity)
+    }
 }
 
 #[cfg(test)]
@@ -306,4 +416,106 @@ mod tests {
         assert!(v.is_ok());
         assert_eq!(v.unwrap().label(), "75 cl");
     }
+
+    #[test]
+    fn gtin_check_digit_validated_for_all_lengths() {
+        assert!(is_valid_gtin("96385074"));
+        assert!(is_valid_gtin("036000291452"));
+        assert!(is_valid_gtin("4006381333931"));
+        assert!(is_valid_gtin("10012345678902"));
+        assert!(!is_valid_gtin("4006381333932"), "wrong check digit");
+        assert!(
+            !is_valid_gtin("400638133393"),
+            "12 digits, wrong check digit"
+        );
+        assert!(!is_valid_gtin("40063813339"), "unsupported length");
+        assert!(!is_valid_gtin("40063813339a1"), "non-digit");
+        assert!(!is_valid_gtin(""));
+    }
+
+    #[test]
+    fn gtin_normalized_to_ean13_unless_packaging_indicator() {
+        for code in ["036000291452", "0036000291452", "00036000291452"] {
+            assert_eq!(normalize_gtin(code).as_deref(), Some("0036000291452"));
+        }
+        assert_eq!(normalize_gtin("96385074").as_deref(), Some("0000096385074"));
+        assert_eq!(
+            normalize_gtin("4006381333931").as_deref(),
+            Some("4006381333931")
+        );
+        assert_eq!(
+            normalize_gtin("10012345678902").as_deref(),
+            Some("10012345678902")
+        );
+        assert_eq!(normalize_gtin("036000291453"), None);
+    }
+
+    #[test]
+    fn with_barcode_trims_validates_and_clears() {
+        let v = ProductVariation::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "",
+            "none",
+            None,
+            1_000,
+            1_000,
+            None,
+        )
+        .unwrap();
+        let v = v.with_barcode(Some(" 4006381333931 ")).unwrap();
+        assert_eq!(v.barcode(), Some("4006381333931"));
+        assert!(matches!(
+            v.clone().with_barcode(Some("4006381333932")),
+            Err(ValidationError::BarcodeInvalid { .. })
+        ));
+        assert_eq!(
+            v.clone()
+                .with_barcode(Some("036000291452"))
+                .unwrap()
+                .barcode(),
+            Some("0036000291452"),
+            "UPC-A stored as EAN-13"
+        );
+        assert_eq!(v.clone().with_barcode(Some("  ")).unwrap().barcode(), None);
+        assert_eq!(v.with_barcode(None).unwrap().barcode(), None);
+    }
+
+    #[test]
+    fn unit_price_grams_normalized_to_kg() {
+        let price: Decimal = "2.99".parse().unwrap();
+        let up = UnitPrice::from_item_price(price, Unit::Grams, Some(500)).unwrap();
+        assert_eq!(up.price.to_string(), "5.98");
+        assert_eq!(up.per, "kg");
+    }
+
+    #[test]
+    fn unit_price_milliliters_normalized_to_l() {
+        let price: Decimal = "1.50".parse().unwrap();
+        let up = UnitPrice::from_item_price(price, Unit::Milliliters, Some(750)).unwrap();
+        assert_eq!(up.price.to_string(), "2.00");
+        assert_eq!(up.per, "l");
+    }
+
+    #[test]
+    fn unit_price_rounds_to_cents() {
+        let price: Decimal = "1.00".parse().unwrap();
+        let up = UnitPrice::from_item_price(price, Unit::Grams, Some(300)).unwrap();
+        assert_eq!(up.price.to_string(), "3.33");
+    }
+
+    #[test]
+    fn unit_price_none_without_measurable_amount() {
+        let price = Decimal::from(3);
+        assert_eq!(
+            UnitPrice::from_item_price(price, Unit::Other, Some(6)),
+            None
+        );
+        assert_eq!(UnitPrice::from_item_price(price, Unit::None, None), None);
+        assert_eq!(UnitPrice::from_item_price(price, Unit::Grams, None), None);
+        assert_eq!(
+            UnitPrice::from_item_price(price, Unit::Grams, Some(0)),
+            None
+        );
+    }
 }
@@ -0,0 +1,149 @@
+//! Session domain type: one login, renewed by its refresh token until it expires or is revoked.
+
+use uuid::Uuid;
+
+/// Validation errors for [`Session`] fields.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// `expires_at` is before `created_at`.
+    #[error("expires_at ({expires_at}) must not be before created_at ({created_at})")]
+    ExpiresBeforeCreated {
+        /// The `created_at` value.
+        created_at: i64,
+        /// The `expires_at` value.
+        expires_at: i64,
+    },
+}
+
+/// A validated login session. The refresh token itself is only stored as a hash (see
+/// [`crate::auth::token`]) and is not part of this type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    id: Uuid,
+    user_id: Uuid,
+    household_id: Uuid,
+    created_at: i64,
+    last_used_at: i64,
+    expires_at: i64,
+    revoked_at: Option<i64>,
+}
+
+impl Session {
+    /// Create a new `Session` after validating all fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError`] if any field is invalid.
+    pub const fn new(
+        id: Uuid,
+        user_id: Uuid,
+        household_id: Uuid,
+        created_at: i64,
+        last_used_at: i64,
+        expires_at: i64,
+        revoked_at: Option<i64>,
+    ) -> Result<Self, ValidationError> {
+        if expires_at < created_at {
+            return Err(ValidationError::ExpiresBeforeCreated {
+                created_at,
+                expires_at,
+            });
+        }
+
+        Ok(Self {
+            id,
+            user_id,
+            household_id,
+            created_at,
+            last_used_at,
+            expires_at,
+            revoked_at,
+        })
+    }
+
+    /// Whether the session can still be used at `now` (not revoked and not expired).
+    #[must_use]
+    pub const fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && now < self.expires_at
+    }
+
+    /// The session's unique identifier (the `jti` of its access tokens).
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The user who logged in.
+    #[must_use]
+    pub const fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+
+    /// The household the session works in.
+    #[must_use]
+    pub const fn household_id(&self) -> Uuid {
+        self.household_id
+    }
+
+    /// UNIX timestamp of the login.
+    #[must_use]
+    pub const fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// UNIX timestamp of the last login or refresh.
+    #[must_use]
+    pub const fn last_used_at(&self) -> i64 {
+        self.last_used_at
+    }
+
+    /// UNIX timestamp after which the refresh token is no longer accepted.
+    #[must_use]
+    pub const fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    /// UNIX timestamp when the session was revoked (logout), if it was.
+    #[must_use]
+    pub const fn revoked_at(&self) -> Option<i64> {
+        self.revoked_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_session(expires_at: i64, revoked_at: Option<i64>) -> Result<Session, ValidationError> {
+        Session::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            1_000,
+            1_000,
+            expires_at,
+            revoked_at,
+        )
+    }
+
+    #[test]
+    fn session_is_active_until_expiry_or_revocation() {
+        let session = make_session(2_000, None).expect("valid");
+        assert!(session.is_active(1_999));
+        assert!(!session.is_active(2_000));
+        let revoked = make_session(2_000, Some(1_500)).expect("valid");
+        assert!(!revoked.is_active(1_600));
+    }
+
+    #[test]
+    fn expires_before_created_is_rejected() {
+        let err = make_session(999, None).unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::ExpiresBeforeCreated {
+                created_at: 1_000,
+                expires_at: 999,
+            }
+        );
+    }
+}
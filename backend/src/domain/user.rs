@@ -1,5 +1,8 @@
 //! User domain type with field validation.
 
+use std::fmt;
+use std::str::FromStr;
+
 use uuid::Uuid;
 
 /// Validation errors for [`User`] fields.
@@ -34,6 +37,43 @@ pub enum ValidationError {
         /// The `deleted_at` value.
         deleted_at: i64,
     },
+
+    /// The role is not one of `admin`, `member`, `guest`.
+    #[error("invalid role: {0} (expected admin, member or guest)")]
+    RoleInvalid(String),
+}
+
+/// What a user may do: admins everything, members read and write data (but not manage
+/// categories or permanently delete), guests only read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    #[default]
+    Member,
+    Guest,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Admin => "admin",
+            Self::Member => "member",
+            Self::Guest => "guest",
+        })
+    }
+}
+
+impl FromStr for Role {
+    type Err = ValidationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "admin" => Ok(Self::Admin),
+            "member" => Ok(Self::Member),
+            "guest" => Ok(Self::Guest),
+            other => Err(ValidationError::RoleInvalid(other.to_string())),
+        }
+    }
 }
 
 /// A validated user.
@@ -43,13 +83,15 @@ pub struct User {
     name: String,
     email: String,
     password: String,
+    role: Role,
     created_at: i64,
     updated_at: i64,
     deleted_at: Option<i64>,
 }
 
 impl User {
-    /// Create a new `User` after validating all fields.
+    /// Create a new `User` after validating all fields. The role is [`Role::Member`]; see
+    /// [`User::with_role`].
     ///
     /// # Errors
     ///
@@ -96,12 +138,20 @@ impl User {
             name,
             email,
             password,
+            role: Role::default(),
             created_at,
             updated_at,
             deleted_at,
         })
     }
 
+    /// Return this user with `role`.
+    #[must_use]
+    pub const fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
     /// Whether the user account is active (not soft-deleted).
     #[must_use]
     pub const fn is_active(&self) -> bool {
@@ -132,6 +182,12 @@ impl User {
         &self.password
     }
 
+    /// The user's role.
+    #[must_use]
+    pub const fn role(&self) -> Role {
+        self.role
+    }
+
     /// UNIX timestamp when the user was created.
     #[must_use]
     pub const fn created_at(&self) -> i64 {
@@ -169,7 +225,7 @@ impl User {
 ///
 /// Checks that there is exactly one `@`, a non-empty local part, and a
 /// domain part that contains at least one `.` with no empty labels.
-fn is_valid_email(email: &str) -> bool {
+pub(crate) fn is_valid_email(email: &str) -> bool {
     let Some((local, domain)) = email.split_once('@') else {
         return false;
     };
@@ -339,6 +395,26 @@ mod tests {
         assert!(make_user("A", "a@b.com", "hash", 100, 100, Some(100)).is_ok());
     }
 
+    // -- role --
+
+    #[test]
+    fn new_user_is_member_and_with_role_overrides() {
+        let user = make_user("U", "u@x.co", "h", 1, 1, None).unwrap();
+        assert_eq!(user.role(), Role::Member);
+        assert_eq!(user.with_role(Role::Guest).role(), Role::Guest);
+    }
+
+    #[test]
+    fn role_round_trips_through_strings() {
+        for role in [Role::Admin, Role::Member, Role::Guest] {
+            assert_eq!(role.to_string().parse::<Role>(), Ok(role));
+        }
+        assert_eq!(
+            "owner".parse::<Role>(),
+            Err(ValidationError::RoleInvalid("owner".to_string()))
+        );
+    }
+
     // -- email helper unit tests --
 
     #[test]
@@ -8,6 +8,7 @@ pub mod cli;
 pub mod config;
 pub mod db;
 pub mod domain;
+pub mod metrics;
 
 #[cfg(test)]
 pub mod test_helpers;
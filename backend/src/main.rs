@@ -32,13 +32,21 @@ async fn main() {
     pocketratings::db::location::set_running_as_production();
     dotenvy::dotenv().ok();
 
-    tracing_subscriber::fmt()
+    let config = pocketratings::config::Config::from_env().context("failed to load configuration");
+    let log_format = config.as_ref().map_or_else(
+        |_| pocketratings::config::LogFormat::from_env(),
+        |c| c.log_format,
+    );
+    let subscriber = tracing_subscriber::fmt()
         .with_writer(|| StderrWriter)
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
+        );
+    match log_format {
+        pocketratings::config::LogFormat::Text => subscriber.init(),
+        pocketratings::config::LogFormat::Json => subscriber.json().init(),
+    }
 
     let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
     let first = args.get(1).and_then(|a| a.to_str());
@@ -46,8 +54,6 @@ async fn main() {
     let needs_db = pocketratings::cli::subcommand_needs_db(first, second);
 
     let pool = if needs_db {
-        let config =
-            pocketratings::config::Config::from_env().context("failed to load configuration");
         match config {
             Ok(c) => match pocketratings::db::create_pool(&c.database_path).await {
                 Ok(p) => match pocketratings::db::run_migrations(&p).await {
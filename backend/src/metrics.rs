@@ -0,0 +1,287 @@
+//! Process-wide metrics in the Prometheus text format: HTTP request counts and latency
+//! histograms, and hit/miss counts of the in-memory list caches.
+//!
+//! Counters live here so any module can update them; `GET /metrics` (see [`crate::api`]) renders
+//! them together with gauges read at scrape time (pool usage, entity counts).
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Prefix of every metric name.
+pub const PREFIX: &str = "pocketratings";
+
+/// Upper bounds, in seconds, of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Hit and miss counts of one in-memory cache.
+#[derive(Debug)]
+pub struct CacheCounter {
+    name: &'static str,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounter {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Count a lookup answered from the cache.
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a lookup that had to query the database.
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of hits so far.
+    #[must_use]
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of misses so far.
+    #[must_use]
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// Cache of plain product lists ([`crate::db::product::get_all`] and `list_by_category`).
+pub static PRODUCT_LIST_CACHE: CacheCounter = CacheCounter::new("product_list");
+
+/// Cache of product lists with relations ([`crate::db::product::list_with_relations`]).
+pub static PRODUCT_RELATIONS_CACHE: CacheCounter = CacheCounter::new("product_relations");
+
+/// Cache of category lists, ancestors and trees ([`crate::db::category`]).
+pub static CATEGORY_LIST_CACHE: CacheCounter = CacheCounter::new("category_list");
+
+const fn caches() -> [&'static CacheCounter; 3] {
+    [
+        &PRODUCT_LIST_CACHE,
+        &PRODUCT_RELATIONS_CACHE,
+        &CATEGORY_LIST_CACHE,
+    ]
+}
+
+/// Request count, latency sum and per-bucket counts of one method, route and status.
+#[derive(Debug, Default)]
+struct RequestStats {
+    count: u64,
+    seconds_sum: f64,
+    /// Non-cumulative counts per [`LATENCY_BUCKETS`] entry; slower requests only count in
+    /// `count` (the `+Inf` bucket).
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+type RequestKey = (String, String, u16);
+
+fn requests() -> &'static Mutex<BTreeMap<RequestKey, RequestStats>> {
+    static REQUESTS: OnceLock<Mutex<BTreeMap<RequestKey, RequestStats>>> = OnceLock::new();
+    REQUESTS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Record a finished HTTP request. `route` should be the matched route pattern (not the raw
+/// path), so the number of series stays bounded.
+pub fn record_request(method: &str, route: &str, status: u16, seconds: f64) {
+    let Ok(mut map) = requests().lock() else {
+        return;
+    };
+    let entry = map
+        .entry((method.to_string(), route.to_string(), status))
+        .or_default();
+    entry.count += 1;
+    entry.seconds_sum += seconds;
+    if let Some(i) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+        entry.buckets[i] += 1;
+    }
+}
+
+/// Escape a label value for the text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Append a `# HELP` and `# TYPE` header for metric `name` (without [`PREFIX`]).
+pub fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} {kind}");
+}
+
+/// Append one sample of metric `name` (without [`PREFIX`]) with `labels`.
+pub fn write_sample(
+    out: &mut String,
+    name: &str,
+    labels: &[(&str, &str)],
+    value: &dyn std::fmt::Display,
+) {
+    let labels = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+        .collect::<Vec<_>>()
+        .join(",");
+    if labels.is_empty() {
+        let _ = writeln!(out, "{PREFIX}_{name} {value}");
+    } else {
+        let _ = writeln!(out, "{PREFIX}_{name}{{{labels}}} {value}");
+    }
+}
+
+/// Append the request and cache counters.
+pub fn render(out: &mut String) {
+    if let Ok(map) = requests().lock() {
+        write_header(
+            out,
+            "http_requests_total",
+            "counter",
+            "HTTP requests by method, route and status.",
+        );
+        for ((method, route, status), stats) in map.iter() {
+            let status = status.to_string();
+            let labels = [
+                ("method", method.as_str()),
+                ("route", route),
+                ("status", &status),
+            ];
+            write_sample(out, "http_requests_total", &labels, &stats.count);
+        }
+        write_header(
+            out,
+            "http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency in seconds by method, route and status.",
+        );
+        for ((method, route, status), stats) in map.iter() {
+            let status = status.to_string();
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                cumulative += count;
+                let le = le.to_string();
+                let labels = [
+                    ("method", method.as_str()),
+                    ("route", route),
+                    ("status", &status),
+                    ("le", &le),
+                ];
+                write_sample(
+                    out,
+                    "http_request_duration_seconds_bucket",
+                    &labels,
+                    &cumulative,
+                );
+            }
+            let labels = [
+                ("method", method.as_str()),
+                ("route", route),
+                ("status", &status),
+                ("le", "+Inf"),
+            ];
+            write_sample(
+                out,
+                "http_request_duration_seconds_bucket",
+                &labels,
+                &stats.count,
+            );
+            let labels = [
+                ("method", method.as_str()),
+                ("route", route),
+                ("status", &status),
+            ];
+            write_sample(
+                out,
+                "http_request_duration_seconds_sum",
+                &labels,
+                &stats.seconds_sum,
+            );
+            write_sample(
+                out,
+                "http_request_duration_seconds_count",
+                &labels,
+                &stats.count,
+            );
+        }
+    }
+
+    write_header(
+        out,
+        "cache_hits_total",
+        "counter",
+        "Lookups answered from an in-memory cache.",
+    );
+    for cache in caches() {
+        write_sample(
+            out,
+            "cache_hits_total",
+            &[("cache", cache.name)],
+            &cache.hits(),
+        );
+    }
+    write_header(
+        out,
+        "cache_misses_total",
+        "counter",
+        "Lookups that missed an in-memory cache and queried the database.",
+    );
+    for cache in caches() {
+        write_sample(
+            out,
+            "cache_misses_total",
+            &[("cache", cache.name)],
+            &cache.misses(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_render_as_cumulative_histogram() {
+        record_request("GET", "/test/histogram/{id}", 200, 0.003);
+        record_request("GET", "/test/histogram/{id}", 200, 0.2);
+        record_request("GET", "/test/histogram/{id}", 200, 30.0);
+        let mut out = String::new();
+        render(&mut out);
+
+        let series = r#"method="GET",route="/test/histogram/{id}",status="200""#;
+        for line in [
+            format!("pocketratings_http_requests_total{{{series}}} 3"),
+            format!(
+                "pocketratings_http_request_duration_seconds_bucket{{{series},le=\"0.005\"}} 1"
+            ),
+            format!("pocketratings_http_request_duration_seconds_bucket{{{series},le=\"0.1\"}} 1"),
+            format!("pocketratings_http_request_duration_seconds_bucket{{{series},le=\"0.25\"}} 2"),
+            format!("pocketratings_http_request_duration_seconds_bucket{{{series},le=\"10\"}} 2"),
+            format!("pocketratings_http_request_duration_seconds_bucket{{{series},le=\"+Inf\"}} 3"),
+            format!("pocketratings_http_request_duration_seconds_count{{{series}}} 3"),
+        ] {
+            assert!(out.lines().any(|l| l == line), "missing {line} in:\n{out}");
+        }
+        assert!(out.contains("# TYPE pocketratings_http_request_duration_seconds histogram"));
+        assert!(out.contains("pocketratings_cache_hits_total{cache=\"category_list\"}"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let mut out = String::new();
+        write_sample(&mut out, "x", &[("route", "a\"b\\c\nd")], &1);
+        assert_eq!(out, "pocketratings_x{route=\"a\\\"b\\\\c\\nd\"} 1\n");
+        out.clear();
+        write_sample(&mut out, "y", &[], &2.5);
+        assert_eq!(out, "pocketratings_y 2.5\n");
+    }
+}
@@ -0,0 +1,180 @@
+//! Integration tests for the audit log.
+
+use pocketratings::db;
+use pocketratings::db::audit::{Actor, AuditAction, AuditEntity, AuditEntry, AuditFilter};
+use pocketratings::db::page::PageRequest;
+use pocketratings::domain::category::Category;
+use pocketratings::domain::location::Location;
+use pocketratings::domain::product::Product;
+use pocketratings::domain::product_variation::ProductVariation;
+use pocketratings::domain::user::User;
+use uuid::Uuid;
+
+async fn test_pool(dir: &tempfile::TempDir) -> sqlx::SqlitePool {
+    let db_path = dir.path().join("audit_test.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+async fn insert_user(pool: &sqlx::SqlitePool) -> Uuid {
+    let id = Uuid::new_v4();
+    let user = User::new(
+        id,
+        "Alice".to_string(),
+        "alice@example.com".to_string(),
+        "secret-hash".to_string(),
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid user");
+    db::user::insert(pool, &user).await.expect("insert user");
+    id
+}
+
+/// Audit entries of one row, oldest first.
+async fn entries_of(pool: &sqlx::SqlitePool, id: Uuid) -> Vec<AuditEntry> {
+    let filter = AuditFilter {
+        entity_id: Some(id),
+        entity: None,
+    };
+    let mut items = db::audit::list_page(pool, &filter, &PageRequest::default())
+        .await
+        .expect("list_page")
+        .items;
+    items.reverse();
+    items
+}
+
+#[tokio::test]
+async fn location_lifecycle_is_recorded_with_actor_and_changes() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+    let user_id = insert_user(&pool).await;
+
+    let id = Uuid::new_v4();
+    let location = Location::new(id, "Corner Shop".to_string(), None).expect("valid");
+    db::location::insert(&pool, &location)
+        .await
+        .expect("insert");
+    let renamed = Location::new(id, "Corner Store".to_string(), None).expect("valid");
+    db::audit::with_actor(Actor::User(user_id), async {
+        db::location::update(&pool, &renamed).await?;
+        db::location::update(&pool, &renamed).await?;
+        db::location::soft_delete(&pool, id, Some(user_id)).await
+    })
+    .await
+    .expect("update and delete");
+    db::location::restore(&pool, id).await.expect("restore");
+    db::location::hard_delete(&pool, id)
+        .await
+        .expect("hard delete");
+
+    let entries = entries_of(&pool, id).await;
+    let actions: Vec<_> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        [
+            AuditAction::Insert,
+            AuditAction::Update,
+            AuditAction::SoftDelete,
+            AuditAction::Restore,
+            AuditAction::HardDelete,
+        ],
+        "the no-op update is not recorded"
+    );
+    assert!(entries.iter().all(|e| e.entity == AuditEntity::Location));
+
+    assert_eq!(entries[0].actor, Actor::Cli);
+    assert_eq!(
+        entries[0].changes["name"]["before"],
+        serde_json::Value::Null
+    );
+    assert_eq!(entries[0].changes["name"]["after"], "Corner Shop");
+
+    assert_eq!(entries[1].actor, Actor::User(user_id));
+    assert_eq!(entries[1].actor_name.as_deref(), Some("Alice"));
+    assert_eq!(
+        entries[1].changes,
+        serde_json::json!({ "name": { "before": "Corner Shop", "after": "Corner Store" } })
+    );
+
+    assert_eq!(entries[2].actor, Actor::User(user_id));
+    assert!(entries[2].changes["deleted_at"]["after"].is_i64());
+    assert_eq!(
+        entries[2].changes["deleted_by"]["after"],
+        user_id.to_string()
+    );
+
+    assert_eq!(entries[4].actor, Actor::Cli);
+    assert_eq!(entries[4].changes["name"]["before"], "Corner Store");
+    assert_eq!(entries[4].changes["name"]["after"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn user_password_is_not_recorded() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+    let user_id = insert_user(&pool).await;
+
+    let entries = entries_of(&pool, user_id).await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].entity, AuditEntity::User);
+    assert_eq!(entries[0].changes["email"]["after"], "alice@example.com");
+    assert!(entries[0].changes.get("password").is_none());
+    assert!(!entries[0].changes.to_string().contains("secret-hash"));
+}
+
+#[tokio::test]
+async fn product_hard_delete_records_its_variations() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "Dairy".to_string(), 1_000, 1_000, None).expect("valid");
+    db::category::insert(&pool, &cat).await.expect("insert");
+    let product_id = Uuid::new_v4();
+    let product = Product::new(
+        product_id,
+        cat_id,
+        "Brand".to_string(),
+        "Milk".to_string(),
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(&pool, &product).await.expect("insert");
+    let variation_id = Uuid::new_v4();
+    let variation = ProductVariation::new(
+        variation_id,
+        product_id,
+        "1 l",
+        "none",
+        None,
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid");
+    db::product_variation::insert(&pool, &variation)
+        .await
+        .expect("insert");
+
+    db::product::hard_delete(&pool, product_id)
+        .await
+        .expect("hard delete");
+
+    let entries = entries_of(&pool, variation_id).await;
+    let actions: Vec<_> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(actions, [AuditAction::Insert, AuditAction::HardDelete]);
+    assert!(entries.iter().all(|e| e.entity == AuditEntity::Variation));
+    assert_eq!(entries[1].changes["label"]["before"], "1 l");
+    assert_eq!(
+        entries[1].changes["label"]["after"],
+        serde_json::Value::Null
+    );
+}
@@ -227,7 +227,7 @@ async fn category_soft_delete_sets_deleted_at_and_excludes_from_get_all() {
         .await
         .expect("insert");
 
-    db::category::soft_delete(&pool, id)
+    db::category::soft_delete(&pool, id, None)
         .await
         .expect("soft_delete");
 
@@ -282,7 +282,7 @@ async fn category_soft_delete_fails_when_category_has_products() {
     .await
     .expect("insert product");
 
-    let result = db::category::soft_delete(&pool, cat_id).await;
+    let result = db::category::soft_delete(&pool, cat_id, None).await;
     assert!(
         result.is_err(),
         "soft_delete should fail when category has products"
@@ -319,13 +319,85 @@ async fn category_soft_delete_fails_when_category_has_children() {
         .await
         .expect("insert child");
 
-    let result = db::category::soft_delete(&pool, parent_id).await;
+    let result = db::category::soft_delete(&pool, parent_id, None).await;
     assert!(
         result.is_err(),
         "soft_delete should fail when category has child categories"
     );
 }
 
+#[tokio::test]
+async fn category_restore_clears_deleted_at() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("category_restore.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let id = Uuid::new_v4();
+    let category =
+        Category::new(id, None, "Restored".to_string(), 1_000, 1_000, None).expect("valid");
+    db::category::insert(&pool, &category)
+        .await
+        .expect("insert");
+    db::category::soft_delete(&pool, id, None)
+        .await
+        .expect("soft_delete");
+
+    db::category::restore(&pool, id).await.expect("restore");
+
+    let loaded = db::category::get_by_id(&pool, id, false)
+        .await
+        .expect("get_by_id")
+        .expect("restored category should be active");
+    assert!(loaded.is_active());
+
+    let again = db::category::restore(&pool, id).await;
+    assert!(again.is_err(), "restoring an active category should fail");
+}
+
+#[tokio::test]
+async fn category_restore_fails_when_parent_is_deleted() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("category_restore_parent.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let parent_id = Uuid::new_v4();
+    let child_id = Uuid::new_v4();
+    let parent = Category::new(parent_id, None, "Parent".to_string(), 1, 1, None).expect("valid");
+    let child =
+        Category::new(child_id, Some(parent_id), "Child".to_string(), 2, 2, None).expect("valid");
+    db::category::insert(&pool, &parent)
+        .await
+        .expect("insert parent");
+    db::category::insert(&pool, &child)
+        .await
+        .expect("insert child");
+    db::category::soft_delete(&pool, child_id, None)
+        .await
+        .expect("soft_delete child");
+    db::category::soft_delete(&pool, parent_id, None)
+        .await
+        .expect("soft_delete parent");
+
+    let result = db::category::restore(&pool, child_id).await;
+    assert!(
+        matches!(result, Err(db::DbError::InvalidData(ref msg)) if msg.contains("cannot restore")),
+        "restore should fail while the parent is deleted: {result:?}"
+    );
+
+    db::category::restore(&pool, parent_id)
+        .await
+        .expect("restore parent");
+    db::category::restore(&pool, child_id)
+        .await
+        .expect("restore child once parent is back");
+}
+
 // --- Category list cache tests (run serially; they enable the cache for the process) ---
 
 #[tokio::test]
@@ -440,7 +512,7 @@ async fn category_list_cache_invalidated_after_soft_delete() {
     let all = db::category::get_all(&pool, false).await.expect("get_all");
     assert_eq!(all.len(), 1);
 
-    db::category::soft_delete(&pool, id)
+    db::category::soft_delete(&pool, id, None)
         .await
         .expect("soft_delete");
 
@@ -455,6 +527,34 @@ async fn category_list_cache_invalidated_after_soft_delete() {
     assert_eq!(with_del.len(), 1);
 }
 
+#[tokio::test]
+#[serial]
+async fn category_list_cache_invalidated_after_restore() {
+    db::category::clear_category_list_cache();
+    db::category::set_use_category_list_cache_for_test(true);
+    let _guard = CacheTestGuard;
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("category_cache_restore.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let id = Uuid::new_v4();
+    let c = Category::new(id, None, "Restored".to_string(), 1, 1, None).expect("valid");
+    db::category::insert(&pool, &c).await.expect("insert");
+    db::category::soft_delete(&pool, id, None)
+        .await
+        .expect("soft_delete");
+    let all = db::category::get_all(&pool, false).await.expect("get_all");
+    assert!(all.is_empty());
+
+    db::category::restore(&pool, id).await.expect("restore");
+
+    let all = db::category::get_all(&pool, false).await.expect("get_all");
+    assert_eq!(all.len(), 1, "cache must be invalidated after restore");
+}
+
 #[tokio::test]
 #[serial]
 async fn category_list_cache_invalidated_after_hard_delete() {
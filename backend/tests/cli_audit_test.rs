@@ -0,0 +1,73 @@
+//! Integration tests for `pocketratings audit list` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::location::Location;
+use uuid::Uuid;
+
+async fn run_audit_list(
+    pool: &sqlx::SqlitePool,
+    extra_args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut args: Vec<std::ffi::OsString> =
+        vec!["pocketratings".into(), "audit".into(), "list".into()];
+    for a in extra_args {
+        args.push(std::ffi::OsString::from(*a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(args.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+#[tokio::test]
+async fn audit_list_shows_changes_of_entity() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("cli_audit_test.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let id = Uuid::new_v4();
+    let other = Uuid::new_v4();
+    for (loc_id, name) in [(id, "Corner Shop"), (other, "Market")] {
+        let location = Location::new(loc_id, name.to_string(), None).expect("valid");
+        db::location::insert(&pool, &location)
+            .await
+            .expect("insert");
+    }
+    db::location::soft_delete(&pool, id, None)
+        .await
+        .expect("soft delete");
+
+    let id_str = id.to_string();
+    let (result, stdout, stderr) = run_audit_list(&pool, &["--entity-id", &id_str]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "stdout: {stdout}");
+    assert!(
+        lines[0].contains(" cli soft_delete location "),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[1].contains(" cli insert location "), "{}", lines[1]);
+    assert!(lines[1].contains("Corner Shop"), "{}", lines[1]);
+
+    let (result, stdout, stderr) =
+        run_audit_list(&pool, &["--entity-type", "location", "--output", "json"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    let items = json.as_array().expect("array");
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0]["action"], "soft_delete");
+    assert_eq!(items[0]["actor"], "cli");
+
+    let (result, _, _) = run_audit_list(&pool, &["--entity-id", "not-a-uuid"]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
@@ -2,8 +2,9 @@
 
 use std::io::Cursor;
 
+use pocketratings::auth::jwt_keys::JwtKeys;
 use pocketratings::cli;
-use pocketratings::config::Config;
+use pocketratings::config::{Config, LogFormat};
 use pocketratings::db;
 
 /// Run `pocketratings database backup` with optional extra args (e.g. `--output path`).
@@ -36,10 +37,15 @@ async fn run_db_backup(
 fn test_config(database_path: &str) -> Config {
     Config {
         database_path: database_path.to_string(),
-        jwt_secret: "test-secret".to_string(),
+        jwt_keys: JwtKeys::from_secret("test-secret"),
         jwt_expiration_seconds: 3600,
         jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
         bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
         pid_file: std::env::temp_dir()
             .join("pocketratings-db-backup-test.pid")
             .to_string_lossy()
@@ -0,0 +1,99 @@
+//! Integration tests for `pocketratings database purge` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::location::Location;
+use uuid::Uuid;
+
+/// Run `pocketratings database purge` with extra args (e.g. `--older-than 90d`).
+async fn run_db_purge(
+    pool: &sqlx::SqlitePool,
+    extra_args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut args: Vec<std::ffi::OsString> =
+        vec!["pocketratings".into(), "database".into(), "purge".into()];
+    for a in extra_args {
+        args.push(std::ffi::OsString::from(*a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(args.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+/// Insert a location soft-deleted `days` ago.
+async fn insert_deleted_location(pool: &sqlx::SqlitePool, name: &str, days: i64) -> Uuid {
+    let id = Uuid::new_v4();
+    let deleted_at = chrono::Utc::now().timestamp() - days * 86_400;
+    let location = Location::new(id, name.to_string(), Some(deleted_at)).expect("valid");
+    db::location::insert(pool, &location)
+        .await
+        .expect("insert location");
+    id
+}
+
+/// Dry run reports eligible rows without deleting; a real run deletes only rows past the cutoff.
+#[tokio::test]
+async fn db_purge_dry_run_then_purge_old_rows() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("purge_test.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let old = insert_deleted_location(&pool, "Old", 120).await;
+    let recent = insert_deleted_location(&pool, "Recent", 10).await;
+
+    let (result, stdout, stderr) = run_db_purge(&pool, &["--older-than", "90d", "--dry-run"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("Dry run"), "stdout: {stdout}");
+    assert!(stdout.contains("locations: 1"), "stdout: {stdout}");
+    assert!(
+        db::location::get_by_id(&pool, old, true)
+            .await
+            .expect("get")
+            .is_some(),
+        "dry run must not delete"
+    );
+
+    let (result, stdout, stderr) = run_db_purge(&pool, &["--older-than", "90d"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.starts_with("Purged:"), "stdout: {stdout}");
+    assert!(stdout.contains("total: 1"), "stdout: {stdout}");
+    assert!(
+        db::location::get_by_id(&pool, old, true)
+            .await
+            .expect("get")
+            .is_none()
+    );
+    assert!(
+        db::location::get_by_id(&pool, recent, true)
+            .await
+            .expect("get")
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn db_purge_rejects_invalid_age() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("purge_invalid_test.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    for age in ["90", "90x", "d", "-5d"] {
+        let (result, _, _) = run_db_purge(&pool, &[&format!("--older-than={age}")]).await;
+        assert!(
+            matches!(result, Err(cli::CliError::Validation(_))),
+            "{age} should be rejected"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Integration tests for `pocketratings household` CLI and the global `--household` flag.
+
+use std::io::Cursor;
+
+use pocketratings::cli;
+use pocketratings::db;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+async fn test_pool(dir: &tempfile::TempDir, name: &str) -> sqlx::SqlitePool {
+    let db_path = dir.path().join(name);
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+#[tokio::test]
+async fn household_create_and_list() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "cli_household_create.db").await;
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &["household", "create", "Parents", "--output", "json"],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("line")).expect("json");
+    assert_eq!(json.get("name").and_then(|v| v.as_str()), Some("Parents"));
+
+    let (result, stdout, stderr) = run_cli(&pool, &["household", "list"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let names: Vec<&str> = stdout
+        .lines()
+        .filter_map(|l| l.split_whitespace().nth(1))
+        .collect();
+    assert_eq!(names, vec!["Default", "Parents"]);
+
+    let (result, _, _) = run_cli(&pool, &["household", "create", "Parents"]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
+
+#[tokio::test]
+async fn register_and_add_user_manage_membership() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "cli_household_add_user.db").await;
+    let (result, _, stderr) = run_cli(&pool, &["household", "create", "Parents"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Bob",
+            "--email",
+            "bob@example.com",
+            "--password",
+            "secret",
+            "--household",
+            "Parents",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let user = db::user::get_by_email(&pool, "bob@example.com")
+        .await
+        .expect("get_by_email")
+        .expect("user");
+    let households = db::household::list_for_user(&pool, user.id())
+        .await
+        .expect("list_for_user");
+    assert_eq!(households.len(), 1);
+    assert_eq!(households[0].name(), "Parents");
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &["household", "add-user", "Default", "bob@example.com"],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("added to household Default"));
+    assert!(
+        db::household::is_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, user.id())
+            .await
+            .expect("is_member")
+    );
+}
+
+#[tokio::test]
+async fn household_flag_scopes_commands() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "cli_household_scope.db").await;
+    let (result, _, stderr) = run_cli(&pool, &["household", "create", "Parents"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "location",
+            "create",
+            "--name",
+            "Market",
+            "--household",
+            "Parents",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+
+    let (_, stdout, _) = run_cli(&pool, &["location", "list", "--household", "Parents"]).await;
+    assert!(stdout.contains("Market"));
+    let (_, stdout, _) = run_cli(&pool, &["location", "list"]).await;
+    assert!(!stdout.contains("Market"));
+
+    let (result, _, _) = run_cli(&pool, &["location", "list", "--household", "Nope"]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
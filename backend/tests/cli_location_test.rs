@@ -214,6 +214,50 @@ async fn location_delete_soft_deletes() {
     );
 }
 
+#[tokio::test]
+async fn location_restore_undoes_soft_delete() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("cli_location_restore.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let (_, create_stdout, _) = run_location(
+        &pool,
+        &[
+            "location",
+            "create",
+            "--name",
+            "ToRestore",
+            "--output",
+            "json",
+        ],
+    )
+    .await;
+    let id = serde_json::from_str::<serde_json::Value>(create_stdout.lines().next().expect("line"))
+        .expect("json")
+        .get("id")
+        .and_then(|v| v.as_str())
+        .expect("id")
+        .to_string();
+
+    let (early, _, _) = run_location(&pool, &["location", "restore", &id]).await;
+    assert!(early.is_err(), "restore of an active location should fail");
+
+    let (del_result, _, _) = run_location(&pool, &["location", "delete", &id]).await;
+    assert!(del_result.is_ok());
+
+    let (result, stdout, stderr) = run_location(&pool, &["location", "restore", &id]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("Location restored"));
+
+    let location = db::location::get_by_id(&pool, id.parse().expect("uuid"), false)
+        .await
+        .expect("get_by_id");
+    assert!(location.is_some(), "restored location should be active");
+}
+
 #[tokio::test]
 async fn location_delete_force_removes_row() {
     let dir = tempfile::tempdir().expect("temp dir");
@@ -271,6 +271,67 @@ async fn product_variation_add_with_quantity_persists_quantity() {
     assert_eq!(with_qty.unit(), Unit::Grams);
 }
 
+#[tokio::test]
+async fn product_variation_add_with_barcode_rejects_duplicates() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("cli_product_variation_add_barcode.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let cat_id = create_category_and_get_id(&pool, "Food").await;
+    let (create_res, create_stdout, create_stderr) = run_product(
+        &pool,
+        &[
+            "product",
+            "create",
+            "--name",
+            "Cheese",
+            "--brand",
+            "Farm",
+            "--category-id",
+            &cat_id,
+            "--output",
+            "json",
+        ],
+    )
+    .await;
+    assert!(create_res.is_ok(), "stderr: {create_stderr}");
+    let product_id =
+        serde_json::from_str::<serde_json::Value>(create_stdout.lines().next().expect("line"))
+            .expect("json")
+            .get("id")
+            .and_then(|v| v.as_str())
+            .expect("id")
+            .to_string();
+    let add = [
+        "product",
+        "variation-add",
+        "--product-id",
+        &product_id,
+        "--barcode",
+        "4006381333931",
+    ];
+
+    let (add_res, _, add_stderr) = run_product(&pool, &add).await;
+    assert!(add_res.is_ok(), "stderr: {add_stderr}");
+    let found = db::product_variation::get_by_barcode(&pool, "4006381333931")
+        .await
+        .expect("get_by_barcode")
+        .expect("variation with barcode");
+    assert_eq!(found.product_id().to_string(), product_id);
+
+    let (dup_res, _, _) = run_product(&pool, &add).await;
+    let err = dup_res.expect_err("duplicate barcode");
+    assert!(err.to_string().contains("already used"), "error: {err}");
+
+    let mut invalid = add;
+    invalid[5] = "4006381333932";
+    let (invalid_res, _, _) = run_product(&pool, &invalid).await;
+    assert!(invalid_res.is_err(), "invalid check digit is rejected");
+}
+
 #[tokio::test]
 async fn product_variation_add_fails_when_product_not_found() {
     let dir = tempfile::tempdir().expect("temp dir");
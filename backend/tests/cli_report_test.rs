@@ -0,0 +1,147 @@
+//! Integration tests for `pocketratings report` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::category::Category;
+use pocketratings::domain::location::Location;
+use pocketratings::domain::product::Product;
+use pocketratings::domain::product_variation::ProductVariation;
+use pocketratings::domain::purchase::Purchase;
+use pocketratings::domain::user::User;
+use uuid::Uuid;
+
+async fn run_report(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+/// Inserts one user, location and product with a purchase at 2026-03-10 and 2026-04-02 (UTC).
+async fn seed(pool: &sqlx::SqlitePool) {
+    let now = 1_000_i64;
+    let user_id = Uuid::new_v4();
+    let user = User::new(
+        user_id,
+        "Alice".to_string(),
+        "alice@example.com".to_string(),
+        "hash".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid user");
+    db::user::insert(pool, &user).await.expect("insert user");
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "Food".to_string(), now, now, None).expect("valid");
+    db::category::insert(pool, &cat)
+        .await
+        .expect("insert category");
+    let product_id = Uuid::new_v4();
+    let product = Product::new(
+        product_id,
+        cat_id,
+        "Farm".to_string(),
+        "Milk".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(pool, &product)
+        .await
+        .expect("insert product");
+    let var_id = Uuid::new_v4();
+    let var = ProductVariation::new(var_id, product_id, "", "none", None, now, now, None)
+        .expect("valid variation");
+    db::product_variation::insert(pool, &var)
+        .await
+        .expect("insert variation");
+    let location_id = Uuid::new_v4();
+    let location = Location::new(location_id, "Market".to_string(), None).expect("valid");
+    db::location::insert(pool, &location)
+        .await
+        .expect("insert location");
+    for (price, at) in [("1.25", 1_773_100_800), ("2.00", 1_775_088_000)] {
+        let purchase = Purchase::new(
+            Uuid::new_v4(),
+            user_id,
+            product_id,
+            var_id,
+            location_id,
+            2,
+            price.parse().expect("decimal"),
+            at,
+            None,
+        )
+        .expect("valid purchase");
+        db::purchase::insert(pool, &purchase)
+            .await
+            .expect("insert purchase");
+    }
+}
+
+#[tokio::test]
+async fn report_spending_prints_months_and_total() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("cli_report_spending.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    seed(&pool).await;
+
+    let (res, stdout, stderr) = run_report(&pool, &["report", "spending"]).await;
+    assert!(res.is_ok(), "stderr: {stderr}");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "two months and a total: {stdout}");
+    assert!(lines[0].starts_with("2026-03") && lines[0].contains("2.50"));
+    assert!(lines[1].starts_with("2026-04") && lines[1].contains("4.00"));
+    assert_eq!(lines[2], "Total: 6.50 EUR (2 purchases)");
+}
+
+#[tokio::test]
+async fn report_spending_json_filters_by_date_and_groups_by_location() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("cli_report_spending_json.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    seed(&pool).await;
+
+    let (res, stdout, stderr) = run_report(
+        &pool,
+        &[
+            "report",
+            "spending",
+            "--group-by",
+            "location",
+            "--from",
+            "2026-04-01",
+            "--output",
+            "json",
+        ],
+    )
+    .await;
+    assert!(res.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value = serde_json::from_str(stdout.trim()).expect("json");
+    assert_eq!(json["group_by"], "location");
+    assert_eq!(json["total"], "4.00");
+    assert_eq!(json["groups"][0]["label"], "Market");
+    assert_eq!(json["groups"][0]["purchase_count"], 1);
+
+    let (res, _, _) = run_report(&pool, &["report", "spending", "--from", "soon"]).await;
+    assert!(res.is_err(), "invalid date is rejected");
+}
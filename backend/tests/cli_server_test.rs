@@ -6,8 +6,9 @@ use std::sync::Arc;
 
 use nix::sys::signal::{Signal, kill};
 use nix::unistd::Pid;
+use pocketratings::auth::jwt_keys::JwtKeys;
 use pocketratings::cli;
-use pocketratings::config::Config;
+use pocketratings::config::{Config, LogFormat};
 use serial_test::serial;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::{Mutex, Notify};
@@ -134,10 +135,15 @@ async fn server_stop_no_pid_file_returns_error() {
     let pid_path = dir.path().join("nonexistent.pid");
     let config = Config {
         database_path: "./pocketratings.db".to_string(),
-        jwt_secret: "test".to_string(),
+        jwt_keys: JwtKeys::from_secret("test"),
         jwt_expiration_seconds: 3600,
         jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
         bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
         pid_file: pid_path.to_string_lossy().into_owned(),
     };
 
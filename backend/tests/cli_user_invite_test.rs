@@ -0,0 +1,117 @@
+//! Integration tests for `pocketratings user invite` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::auth::token;
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::user::Role;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+async fn setup(dir: &tempfile::TempDir) -> sqlx::SqlitePool {
+    let db_path = dir.path().join("cli_user_invite.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+fn token_of(out: &str) -> String {
+    let json: serde_json::Value = serde_json::from_str(out.trim()).expect("json");
+    json["token"].as_str().expect("token").to_string()
+}
+
+#[tokio::test]
+async fn invite_issues_token_and_supersedes_older_ones() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+    let args = [
+        "user",
+        "invite",
+        "--email",
+        "bob@example.com",
+        "--role",
+        "guest",
+        "--expires-in",
+        "1d",
+        "--output",
+        "json",
+    ];
+
+    let (result, first, stderr) = run_cli(&pool, &args).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let (result, second, stderr) = run_cli(&pool, &args).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let (first, second) = (token_of(&first), token_of(&second));
+
+    let now = chrono::Utc::now().timestamp();
+    let old = db::invite::get_active_by_hash(&pool, &token::hash(&first), now)
+        .await
+        .expect("lookup");
+    assert!(old.is_none(), "a newer invite supersedes the older one");
+    let invite = db::invite::get_active_by_hash(&pool, &token::hash(&second), now)
+        .await
+        .expect("lookup")
+        .expect("active invite");
+    assert_eq!(invite.email(), "bob@example.com");
+    assert_eq!(invite.role(), Role::Guest);
+    assert!(invite.expires_at() <= now + 86_400);
+    assert_eq!(invite.household_id(), db::household::DEFAULT_HOUSEHOLD_ID);
+}
+
+#[tokio::test]
+async fn invite_rejects_registered_or_invalid_email_and_bad_expiry() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Alice",
+            "--email",
+            "alice@example.com",
+            "--password",
+            "secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+
+    let (result, _, _) = run_cli(&pool, &["user", "invite", "--email", "alice@example.com"]).await;
+    assert!(matches!(result, Err(cli::CliError::EmailAlreadyRegistered)));
+    let (result, _, _) = run_cli(&pool, &["user", "invite", "--email", "not-an-email"]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+    let (result, _, _) = run_cli(
+        &pool,
+        &[
+            "user",
+            "invite",
+            "--email",
+            "bob@example.com",
+            "--expires-in",
+            "soon",
+        ],
+    )
+    .await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
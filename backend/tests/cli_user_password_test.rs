@@ -0,0 +1,164 @@
+//! Integration tests for `pocketratings user set-password` and `user reset-password` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::auth::{password, token};
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::session::Session;
+use uuid::Uuid;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+/// Create a pool with one registered user; returns the pool and the user id.
+async fn setup(dir: &tempfile::TempDir) -> (sqlx::SqlitePool, Uuid) {
+    let db_path = dir.path().join("cli_user_password.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Alice",
+            "--email",
+            "alice@example.com",
+            "--password",
+            "old-secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let user = db::user::get_by_email(&pool, "alice@example.com")
+        .await
+        .expect("get_by_email")
+        .expect("user");
+    (pool, user.id())
+}
+
+#[tokio::test]
+async fn set_password_replaces_hash_and_revokes_sessions() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (pool, user_id) = setup(&dir).await;
+    let now = chrono::Utc::now().timestamp();
+    let session = Session::new(
+        Uuid::new_v4(),
+        user_id,
+        db::household::DEFAULT_HOUSEHOLD_ID,
+        now,
+        now,
+        now + 3600,
+        None,
+    )
+    .expect("valid session");
+    db::session::insert(&pool, &session, "hash")
+        .await
+        .expect("insert session");
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "set-password",
+            "alice@example.com",
+            "--password",
+            "new-secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("sessions revoked: 1, API tokens revoked: 0"));
+
+    let user = db::user::get_by_id(&pool, user_id, false)
+        .await
+        .expect("get_by_id")
+        .expect("user");
+    assert!(password::verify_password("new-secret", user.password()).expect("verify"));
+    assert!(!password::verify_password("old-secret", user.password()).expect("verify"));
+    let session = db::session::get_by_id(&pool, session.id())
+        .await
+        .expect("get session")
+        .expect("session");
+    assert!(session.revoked_at().is_some());
+}
+
+#[tokio::test]
+async fn set_password_rejects_empty_password_and_unknown_user() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (pool, _) = setup(&dir).await;
+
+    let (result, _, _) = run_cli(
+        &pool,
+        &[
+            "user",
+            "set-password",
+            "alice@example.com",
+            "--password",
+            "",
+        ],
+    )
+    .await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+    let (result, _, _) = run_cli(
+        &pool,
+        &["user", "set-password", "bob@example.com", "--password", "x"],
+    )
+    .await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
+
+#[tokio::test]
+async fn reset_password_prints_single_use_token_superseding_earlier_ones() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (pool, user_id) = setup(&dir).await;
+    let args = [
+        "user",
+        "reset-password",
+        "alice@example.com",
+        "--output",
+        "json",
+    ];
+
+    let (result, first, stderr) = run_cli(&pool, &args).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let (result, second, stderr) = run_cli(&pool, &args).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let token_of = |out: &str| {
+        let json: serde_json::Value = serde_json::from_str(out.trim()).expect("json");
+        json["token"].as_str().expect("token").to_string()
+    };
+    let (first, second) = (token_of(&first), token_of(&second));
+
+    let now = chrono::Utc::now().timestamp();
+    let consumed = db::password_reset::consume(&pool, &token::hash(&first), now)
+        .await
+        .expect("consume");
+    assert_eq!(consumed, None, "a newer reset token supersedes older ones");
+    let consumed = db::password_reset::consume(&pool, &token::hash(&second), now)
+        .await
+        .expect("consume");
+    assert_eq!(consumed, Some(user_id));
+    let consumed = db::password_reset::consume(&pool, &token::hash(&second), now)
+        .await
+        .expect("consume");
+    assert_eq!(consumed, None, "reset tokens are single-use");
+}
@@ -0,0 +1,133 @@
+//! Integration tests for `pocketratings user sessions` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::session::Session;
+use uuid::Uuid;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+/// Create a pool with one registered user and two sessions; returns the pool and session ids.
+async fn setup(dir: &tempfile::TempDir) -> (sqlx::SqlitePool, Vec<Uuid>) {
+    let db_path = dir.path().join("cli_user_sessions.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Alice",
+            "--email",
+            "alice@example.com",
+            "--password",
+            "secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let user = db::user::get_by_email(&pool, "alice@example.com")
+        .await
+        .expect("get_by_email")
+        .expect("user");
+
+    let now = chrono::Utc::now().timestamp();
+    let mut ids = Vec::new();
+    for offset in 0..2 {
+        let session = Session::new(
+            Uuid::new_v4(),
+            user.id(),
+            db::household::DEFAULT_HOUSEHOLD_ID,
+            now + offset,
+            now + offset,
+            now + 3600,
+            None,
+        )
+        .expect("valid session");
+        db::session::insert(&pool, &session, &format!("hash-{offset}"))
+            .await
+            .expect("insert session");
+        ids.push(session.id());
+    }
+    (pool, ids)
+}
+
+#[tokio::test]
+async fn sessions_list_shows_active_sessions_newest_first() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (pool, ids) = setup(&dir).await;
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "sessions",
+            "list",
+            "alice@example.com",
+            "--output",
+            "json",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value = serde_json::from_str(stdout.trim()).expect("json");
+    let listed: Vec<&str> = json
+        .as_array()
+        .expect("array")
+        .iter()
+        .filter_map(|s| s.get("id").and_then(|v| v.as_str()))
+        .collect();
+    assert_eq!(listed, vec![ids[1].to_string(), ids[0].to_string()]);
+}
+
+#[tokio::test]
+async fn sessions_revoke_by_id_and_by_user() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let (pool, ids) = setup(&dir).await;
+    let first = ids[0].to_string();
+
+    let (result, stdout, stderr) = run_cli(&pool, &["user", "sessions", "revoke", &first]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("Session revoked"));
+    let (result, _, _) = run_cli(&pool, &["user", "sessions", "revoke", &first]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &["user", "sessions", "revoke", "--user", "alice@example.com"],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains(": 1"));
+
+    let (_, stdout, _) = run_cli(&pool, &["user", "sessions", "list", "alice@example.com"]).await;
+    assert!(stdout.trim().is_empty());
+    let (_, stdout, _) = run_cli(
+        &pool,
+        &["user", "sessions", "list", "alice@example.com", "--all"],
+    )
+    .await;
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(stdout.lines().all(|l| l.ends_with("revoked")));
+}
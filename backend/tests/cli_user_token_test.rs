@@ -0,0 +1,161 @@
+//! Integration tests for `pocketratings user token` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::auth::token;
+use pocketratings::cli;
+use pocketratings::db;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+/// Create a pool with one registered user.
+async fn setup(dir: &tempfile::TempDir) -> sqlx::SqlitePool {
+    let db_path = dir.path().join("cli_user_token.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Alice",
+            "--email",
+            "alice@example.com",
+            "--password",
+            "secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    pool
+}
+
+#[tokio::test]
+async fn token_create_stores_only_the_hash_and_list_shows_it() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "token",
+            "create",
+            "alice@example.com",
+            "--name",
+            "home-assistant",
+            "--scope",
+            "write",
+            "--expires-in",
+            "30d",
+            "--output",
+            "json",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value = serde_json::from_str(stdout.trim()).expect("json");
+    let secret = json.get("token").and_then(|v| v.as_str()).expect("token");
+    assert!(secret.starts_with("prt_"));
+    assert_eq!(json.get("scope").and_then(|v| v.as_str()), Some("write"));
+    assert!(
+        json.get("expires_at")
+            .and_then(serde_json::Value::as_i64)
+            .is_some()
+    );
+
+    let now = chrono::Utc::now().timestamp();
+    let stored = db::api_token::get_active_by_hash(&pool, &token::hash(secret), now)
+        .await
+        .expect("get_active_by_hash")
+        .expect("token stored by hash");
+    assert_eq!(stored.name(), "home-assistant");
+    let plain: Option<(i64,)> = sqlx::query_as("SELECT 1 FROM api_tokens WHERE token_hash = ?")
+        .bind(secret)
+        .fetch_optional(&pool)
+        .await
+        .expect("query");
+    assert!(plain.is_none(), "secret must not be stored in plain text");
+
+    let (result, stdout, stderr) =
+        run_cli(&pool, &["user", "token", "list", "alice@example.com"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("home-assistant"));
+    assert!(stdout.contains("last used never"));
+    assert!(!stdout.contains(secret));
+}
+
+#[tokio::test]
+async fn token_revoke_deactivates_token() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "token",
+            "create",
+            "alice@example.com",
+            "--name",
+            "script",
+            "--output",
+            "json",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value = serde_json::from_str(stdout.trim()).expect("json");
+    let id = json.get("id").and_then(|v| v.as_str()).expect("id");
+    assert_eq!(json.get("scope").and_then(|v| v.as_str()), Some("read"));
+
+    let (result, stdout, stderr) = run_cli(&pool, &["user", "token", "revoke", id]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("Token revoked"));
+    let (result, _, _) = run_cli(&pool, &["user", "token", "revoke", id]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+
+    let (_, stdout, _) = run_cli(&pool, &["user", "token", "list", "alice@example.com"]).await;
+    assert!(stdout.trim_end().ends_with("revoked"));
+}
+
+#[tokio::test]
+async fn token_create_rejects_invalid_expiry() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+
+    let (result, _, _) = run_cli(
+        &pool,
+        &[
+            "user",
+            "token",
+            "create",
+            "alice@example.com",
+            "--name",
+            "script",
+            "--expires-in",
+            "soon",
+        ],
+    )
+    .await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
@@ -0,0 +1,131 @@
+//! Integration tests for `pocketratings user two-factor` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::auth::{token, totp};
+use pocketratings::cli;
+use pocketratings::db;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+async fn setup_with_user(dir: &tempfile::TempDir) -> sqlx::SqlitePool {
+    let db_path = dir.path().join("cli_user_two_factor.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Alice",
+            "--email",
+            "alice@example.com",
+            "--password",
+            "secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    pool
+}
+
+#[tokio::test]
+async fn enable_stores_secret_and_recovery_codes_once() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup_with_user(&dir).await;
+    let args = [
+        "user",
+        "two-factor",
+        "enable",
+        "alice@example.com",
+        "--output",
+        "json",
+    ];
+
+    let (result, out, stderr) = run_cli(&pool, &args).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    let json: serde_json::Value = serde_json::from_str(out.trim()).expect("json");
+    let secret = json["secret"].as_str().expect("secret");
+    assert!(
+        json["uri"]
+            .as_str()
+            .expect("uri")
+            .starts_with("otpauth://totp/Pocket%20Ratings:alice@example.com?secret=")
+    );
+    let codes = json["recovery_codes"].as_array().expect("codes");
+    assert_eq!(codes.len(), totp::RECOVERY_CODE_COUNT);
+
+    let user_id = json["user_id"]
+        .as_str()
+        .expect("user_id")
+        .parse()
+        .expect("uuid");
+    let stored = db::totp::get_secret(&pool, user_id)
+        .await
+        .expect("lookup")
+        .expect("enabled");
+    assert_eq!(stored.secret, secret);
+    let code = codes[0].as_str().expect("code");
+    assert!(
+        db::totp::consume_recovery_code(&pool, user_id, &token::hash(code), 1_700_000_000)
+            .await
+            .expect("consume")
+    );
+
+    let (result, _, _) = run_cli(&pool, &args).await;
+    assert!(
+        matches!(result, Err(cli::CliError::Validation(_))),
+        "already enabled"
+    );
+}
+
+#[tokio::test]
+async fn disable_removes_two_factor_and_fails_when_not_enabled() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup_with_user(&dir).await;
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &["user", "two-factor", "enable", "alice@example.com"],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+
+    let disable = ["user", "two-factor", "disable", "alice@example.com"];
+    let (result, out, stderr) = run_cli(&pool, &disable).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(out.contains("disabled"));
+    let user = db::user::get_by_email(&pool, "alice@example.com")
+        .await
+        .expect("lookup")
+        .expect("user");
+    assert!(
+        db::totp::get_secret(&pool, user.id())
+            .await
+            .expect("lookup")
+            .is_none()
+    );
+
+    let (result, _, _) = run_cli(&pool, &disable).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+    let (result, _, _) = run_cli(&pool, &["user", "two-factor", "disable", "nobody"]).await;
+    assert!(matches!(result, Err(cli::CliError::Validation(_))));
+}
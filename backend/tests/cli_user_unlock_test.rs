@@ -0,0 +1,82 @@
+//! Integration tests for `pocketratings user unlock` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::auth::lockout;
+use pocketratings::cli;
+use pocketratings::db;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+#[tokio::test]
+async fn unlock_clears_lockout_of_user_and_ip() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("cli_user_unlock.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "register",
+            "--name",
+            "Alice",
+            "--email",
+            "alice@example.com",
+            "--password",
+            "secret",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+
+    let email_key = lockout::email_key("alice@example.com");
+    let ip_key = lockout::ip_key("203.0.113.7".parse().expect("ip"));
+    let keys = [email_key.clone(), ip_key.clone()];
+    let now = chrono::Utc::now().timestamp();
+    for _ in 0..=lockout::FREE_ATTEMPTS {
+        for key in &keys {
+            db::login_attempt::record_failure(&pool, key, now)
+                .await
+                .expect("record failure");
+        }
+    }
+    let locked = db::login_attempt::locked_until(&pool, &keys, now)
+        .await
+        .expect("locked_until");
+    assert_eq!(locked, Some(now + lockout::BASE_LOCKOUT_SECS));
+
+    let (result, stdout, stderr) = run_cli(&pool, &["user", "unlock", "alice@example.com"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("Login unlocked for alice@example.com"));
+    let locked = db::login_attempt::locked_until(&pool, &[email_key], now)
+        .await
+        .expect("locked_until");
+    assert_eq!(locked, None);
+
+    let (result, stdout, stderr) = run_cli(&pool, &["user", "unlock", "--ip", "203.0.113.7"]).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("203.0.113.7"));
+    let locked = db::login_attempt::locked_until(&pool, &keys, now)
+        .await
+        .expect("locked_until");
+    assert_eq!(locked, None);
+}
@@ -0,0 +1,141 @@
+//! Integration tests for `pocketratings user update` and `user register --role` CLI.
+
+use std::io::Cursor;
+
+use pocketratings::cli;
+use pocketratings::db;
+use pocketratings::domain::user::Role;
+
+async fn run_cli(
+    pool: &sqlx::SqlitePool,
+    args: &[&str],
+) -> (Result<(), cli::CliError>, String, String) {
+    let mut full: Vec<std::ffi::OsString> = Vec::with_capacity(args.len() + 1);
+    full.push(std::ffi::OsString::from("pocketratings"));
+    for a in args {
+        full.push(std::ffi::OsString::from(a));
+    }
+
+    let mut stdout = Cursor::new(Vec::new());
+    let mut stderr = Cursor::new(Vec::new());
+    let result = cli::run(full.into_iter(), Some(pool), None, &mut stdout, &mut stderr).await;
+    let stdout_str = String::from_utf8(stdout.into_inner()).expect("stdout UTF-8");
+    let stderr_str = String::from_utf8(stderr.into_inner()).expect("stderr UTF-8");
+    (result, stdout_str, stderr_str)
+}
+
+async fn register(pool: &sqlx::SqlitePool, name: &str, email: &str, role: Option<&str>) {
+    let mut args = vec![
+        "user",
+        "register",
+        "--name",
+        name,
+        "--email",
+        email,
+        "--password",
+        "secret",
+    ];
+    if let Some(role) = role {
+        args.extend(["--role", role]);
+    }
+    let (result, _, stderr) = run_cli(pool, &args).await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+}
+
+async fn setup(dir: &tempfile::TempDir) -> sqlx::SqlitePool {
+    let db_path = dir.path().join("cli_user_update.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+async fn role_of(pool: &sqlx::SqlitePool, email: &str) -> Role {
+    db::user::get_by_email(pool, email)
+        .await
+        .expect("get_by_email")
+        .expect("user")
+        .role()
+}
+
+#[tokio::test]
+async fn register_defaults_to_member_and_accepts_role() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+
+    register(&pool, "Alice", "alice@example.com", Some("admin")).await;
+    register(&pool, "Tablet", "tablet@example.com", Some("guest")).await;
+    register(&pool, "Bob", "bob@example.com", None).await;
+
+    assert_eq!(role_of(&pool, "alice@example.com").await, Role::Admin);
+    assert_eq!(role_of(&pool, "tablet@example.com").await, Role::Guest);
+    assert_eq!(role_of(&pool, "bob@example.com").await, Role::Member);
+}
+
+#[tokio::test]
+async fn update_changes_role_name_and_email() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+    register(&pool, "Bob", "bob@example.com", None).await;
+
+    let (result, stdout, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "update",
+            "bob@example.com",
+            "--role",
+            "admin",
+            "--name",
+            "Robert",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert!(stdout.contains("User updated"));
+    let user = db::user::get_by_email(&pool, "bob@example.com")
+        .await
+        .expect("get_by_email")
+        .expect("user");
+    assert_eq!(user.role(), Role::Admin);
+    assert_eq!(user.name(), "Robert");
+
+    let (result, _, stderr) = run_cli(
+        &pool,
+        &[
+            "user",
+            "update",
+            "bob@example.com",
+            "--email",
+            "robert@example.com",
+        ],
+    )
+    .await;
+    assert!(result.is_ok(), "stderr: {stderr}");
+    assert_eq!(role_of(&pool, "robert@example.com").await, Role::Admin);
+
+    let (_, stdout, _) = run_cli(&pool, &["user", "list"]).await;
+    assert!(stdout.contains("robert@example.com  Robert  admin"));
+}
+
+#[tokio::test]
+async fn update_rejects_taken_email() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = setup(&dir).await;
+    register(&pool, "Alice", "alice@example.com", None).await;
+    register(&pool, "Bob", "bob@example.com", None).await;
+
+    let (result, _, _) = run_cli(
+        &pool,
+        &[
+            "user",
+            "update",
+            "bob@example.com",
+            "--email",
+            "alice@example.com",
+        ],
+    )
+    .await;
+    assert!(matches!(result, Err(cli::CliError::EmailAlreadyRegistered)));
+}
@@ -0,0 +1,141 @@
+//! Integration tests for household DB functions and household scoping of entity data.
+
+use pocketratings::db;
+use pocketratings::db::household::{DEFAULT_HOUSEHOLD_ID, with_household};
+use pocketratings::domain::category::Category;
+use pocketratings::domain::household::Household;
+use pocketratings::domain::location::Location;
+use pocketratings::domain::user::User;
+use uuid::Uuid;
+
+async fn test_pool(dir: &tempfile::TempDir, name: &str) -> sqlx::SqlitePool {
+    let db_path = dir.path().join(name);
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+async fn insert_household(pool: &sqlx::SqlitePool, name: &str) -> Uuid {
+    let household = Household::new(Uuid::new_v4(), name.to_string(), 1_700_000_000).expect("valid");
+    db::household::insert(pool, &household)
+        .await
+        .expect("insert household");
+    household.id()
+}
+
+async fn insert_user(pool: &sqlx::SqlitePool) -> Uuid {
+    let id = Uuid::new_v4();
+    let user = User::new(
+        id,
+        "Alice".to_string(),
+        "alice@example.com".to_string(),
+        "secret-hash".to_string(),
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid user");
+    db::user::insert(pool, &user).await.expect("insert user");
+    id
+}
+
+#[tokio::test]
+async fn migration_creates_default_household() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "household_default.db").await;
+
+    let default = db::household::get_by_id(&pool, DEFAULT_HOUSEHOLD_ID)
+        .await
+        .expect("get_by_id")
+        .expect("default household should exist");
+    assert_eq!(default.name(), "Default");
+}
+
+#[tokio::test]
+async fn add_member_and_list_for_user() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "household_members.db").await;
+    let user_id = insert_user(&pool).await;
+    let other = insert_household(&pool, "Parents").await;
+
+    assert!(
+        db::household::add_member(&pool, DEFAULT_HOUSEHOLD_ID, user_id)
+            .await
+            .expect("add")
+    );
+    assert!(
+        db::household::add_member(&pool, other, user_id)
+            .await
+            .expect("add")
+    );
+    assert!(
+        !db::household::add_member(&pool, other, user_id)
+            .await
+            .expect("add again")
+    );
+
+    let households = db::household::list_for_user(&pool, user_id)
+        .await
+        .expect("list_for_user");
+    assert_eq!(households.len(), 2);
+    assert!(
+        db::household::is_member(&pool, other, user_id)
+            .await
+            .expect("is_member")
+    );
+    assert!(
+        !db::household::is_member(&pool, Uuid::new_v4(), user_id)
+            .await
+            .expect("is_member")
+    );
+}
+
+#[tokio::test]
+async fn entities_are_only_visible_in_their_household() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "household_scope.db").await;
+    let other = insert_household(&pool, "Parents").await;
+
+    let loc_id = Uuid::new_v4();
+    let location = Location::new(loc_id, "Corner shop".to_string(), None).expect("valid");
+    db::location::insert(&pool, &location)
+        .await
+        .expect("insert");
+
+    let in_other = with_household(other, db::location::get_by_id(&pool, loc_id, true))
+        .await
+        .expect("get_by_id");
+    assert!(in_other.is_none());
+    let all_in_other = with_household(other, db::location::get_all(&pool, true))
+        .await
+        .expect("get_all");
+    assert!(all_in_other.is_empty());
+    assert!(
+        db::location::get_by_id(&pool, loc_id, false)
+            .await
+            .expect("get_by_id")
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn category_names_are_unique_per_household_only() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir, "household_category_names.db").await;
+    let other = insert_household(&pool, "Parents").await;
+    let now = 1_700_000_000;
+
+    let first =
+        Category::new(Uuid::new_v4(), None, "Dairy".to_string(), now, now, None).expect("valid");
+    db::category::insert(&pool, &first).await.expect("insert");
+    let second =
+        Category::new(Uuid::new_v4(), None, "Dairy".to_string(), now, now, None).expect("valid");
+    with_household(other, db::category::insert(&pool, &second))
+        .await
+        .expect("same name in another household");
+
+    let duplicate =
+        Category::new(Uuid::new_v4(), None, "Dairy".to_string(), now, now, None).expect("valid");
+    assert!(db::category::insert(&pool, &duplicate).await.is_err());
+}
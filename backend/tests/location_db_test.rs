@@ -48,7 +48,7 @@ async fn location_get_all_and_get_all_with_deleted() {
     let all = db::location::get_all(&pool, false).await.expect("get_all");
     assert_eq!(all.len(), 2);
 
-    db::location::soft_delete(&pool, l1.id())
+    db::location::soft_delete(&pool, l1.id(), None)
         .await
         .expect("soft_delete");
 
@@ -67,6 +67,40 @@ async fn location_get_all_and_get_all_with_deleted() {
     );
 }
 
+#[tokio::test]
+async fn location_list_page_returns_names_in_order_across_pages() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("location_list_page.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    for name in ["Market", "Bakery", "Corner shop"] {
+        let location = Location::new(Uuid::new_v4(), name.to_string(), None).expect("valid");
+        db::location::insert(&pool, &location)
+            .await
+            .expect("insert");
+    }
+
+    let mut names = Vec::new();
+    let mut page = db::page::PageRequest {
+        limit: Some(1),
+        after: None,
+    };
+    loop {
+        let result = db::location::list_page(&pool, db::location::LocationSort::Name, false, &page)
+            .await
+            .expect("list_page");
+        names.extend(result.items.iter().map(|l| l.name().to_string()));
+        let Some(cursor) = result.next_cursor else {
+            break;
+        };
+        page.after = db::page::Cursor::decode(&cursor);
+    }
+    assert_eq!(names, vec!["Bakery", "Corner shop", "Market"]);
+}
+
 #[tokio::test]
 async fn location_update_changes_name() {
     let dir = tempfile::tempdir().expect("temp dir");
@@ -107,7 +141,7 @@ async fn location_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
         .await
         .expect("insert");
 
-    db::location::soft_delete(&pool, loc_id)
+    db::location::soft_delete(&pool, loc_id, None)
         .await
         .expect("soft_delete");
 
@@ -244,7 +278,7 @@ async fn location_soft_delete_fails_when_location_has_purchases() {
         .await
         .expect("insert purchase");
 
-    let result = db::location::soft_delete(&pool, loc_id).await;
+    let result = db::location::soft_delete(&pool, loc_id, None).await;
     assert!(
         result.is_err(),
         "soft_delete should fail when location has purchases"
@@ -452,7 +486,7 @@ async fn location_list_cache_invalidated_after_soft_delete() {
     let all = db::location::get_all(&pool, false).await.expect("get_all");
     assert_eq!(all.len(), 1);
 
-    db::location::soft_delete(&pool, id)
+    db::location::soft_delete(&pool, id, None)
         .await
         .expect("soft_delete");
 
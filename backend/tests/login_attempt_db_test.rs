@@ -0,0 +1,41 @@
+//! Integration tests for failed login attempt counting in the DB.
+
+use pocketratings::auth::lockout;
+use pocketratings::db;
+
+#[tokio::test]
+async fn record_failure_counts_concurrent_failures_and_restarts_after_window() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("login_attempt.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    let key = lockout::ip_key("203.0.113.7".parse().expect("ip"));
+    let now = 1_700_000_000i64;
+
+    let attempts = lockout::FREE_ATTEMPTS + 1;
+    let mut tasks = tokio::task::JoinSet::new();
+    for _ in 0..attempts {
+        let pool = pool.clone();
+        let key = key.clone();
+        tasks.spawn(async move { db::login_attempt::record_failure(&pool, &key, now).await });
+    }
+    while let Some(result) = tasks.join_next().await {
+        result.expect("join").expect("record_failure");
+    }
+    let locked = db::login_attempt::locked_until(&pool, std::slice::from_ref(&key), now)
+        .await
+        .expect("locked_until");
+    assert_eq!(
+        locked,
+        Some(now + lockout::BASE_LOCKOUT_SECS),
+        "every concurrent failure is counted"
+    );
+
+    let later = now + lockout::FAILURE_WINDOW_SECS;
+    let locked = db::login_attempt::record_failure(&pool, &key, later)
+        .await
+        .expect("record_failure");
+    assert_eq!(locked, None, "count starts over after the window");
+}
@@ -1,7 +1,9 @@
 //! Integration tests for product DB functions.
 
 use pocketratings::db;
+use pocketratings::db::household::with_household;
 use pocketratings::domain::category::Category;
+use pocketratings::domain::household::Household;
 use pocketratings::domain::location::Location;
 use pocketratings::domain::product::Product;
 use pocketratings::domain::product_variation::ProductVariation;
@@ -284,7 +286,7 @@ async fn product_get_all_with_deleted() {
     let active = db::product::get_all(&pool, false).await.expect("get_all");
     assert_eq!(active.len(), 1);
 
-    db::product::soft_delete(&pool, p.id())
+    db::product::soft_delete(&pool, p.id(), None)
         .await
         .expect("soft_delete");
 
@@ -388,7 +390,7 @@ async fn product_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
     .expect("valid");
     db::product::insert(&pool, &product).await.expect("insert");
 
-    db::product::soft_delete(&pool, product_id)
+    db::product::soft_delete(&pool, product_id, None)
         .await
         .expect("soft_delete");
 
@@ -413,6 +415,59 @@ async fn product_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
     assert!(!with_deleted[0].is_active());
 }
 
+#[tokio::test]
+async fn product_restore_requires_active_category() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_restore.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let cat_id = Uuid::new_v4();
+    let now = 1_000_i64;
+    let cat = Category::new(cat_id, None, "C".to_string(), now, now, None).expect("valid");
+    db::category::insert(&pool, &cat).await.expect("insert");
+
+    let product_id = Uuid::new_v4();
+    let product = Product::new(
+        product_id,
+        cat_id,
+        "B".to_string(),
+        "Restorable".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(&pool, &product).await.expect("insert");
+    db::product::soft_delete(&pool, product_id, None)
+        .await
+        .expect("soft_delete product");
+    db::category::soft_delete(&pool, cat_id, None)
+        .await
+        .expect("soft_delete category");
+
+    let result = db::product::restore(&pool, product_id).await;
+    assert!(
+        matches!(result, Err(db::DbError::InvalidData(ref msg)) if msg.contains("cannot restore")),
+        "restore should fail while the category is deleted: {result:?}"
+    );
+
+    db::category::restore(&pool, cat_id)
+        .await
+        .expect("restore category");
+    db::product::restore(&pool, product_id)
+        .await
+        .expect("restore product");
+
+    let restored = db::product::get_by_id(&pool, product_id, false)
+        .await
+        .expect("get_by_id")
+        .expect("restored product should be active");
+    assert_eq!(restored.name(), "Restorable");
+}
+
 #[tokio::test]
 async fn product_hard_delete_removes_row() {
     let dir = tempfile::tempdir().expect("temp dir");
@@ -547,7 +602,7 @@ async fn product_soft_delete_fails_when_product_has_purchases() {
         .await
         .expect("insert purchase");
 
-    let result = db::product::soft_delete(&pool, product_id).await;
+    let result = db::product::soft_delete(&pool, product_id, None).await;
     assert!(
         result.is_err(),
         "soft_delete should fail when product has purchases"
@@ -693,7 +748,7 @@ async fn product_get_all_by_category_ids_include_deleted() {
     )
     .expect("valid");
     db::product::insert(&pool, &product).await.expect("insert");
-    db::product::soft_delete(&pool, product_id)
+    db::product::soft_delete(&pool, product_id, None)
         .await
         .expect("soft_delete");
 
@@ -757,7 +812,7 @@ async fn product_get_by_id_with_relations_returns_none_for_deleted_product() {
     )
     .expect("valid");
     db::product::insert(&pool, &product).await.expect("insert");
-    db::product::soft_delete(&pool, product_id)
+    db::product::soft_delete(&pool, product_id, None)
         .await
         .expect("soft_delete");
 
@@ -785,7 +840,7 @@ async fn product_soft_delete_fails_when_not_found() {
     db::run_migrations(&pool).await.expect("migrations");
 
     let missing_id = Uuid::new_v4();
-    let result = db::product::soft_delete(&pool, missing_id).await;
+    let result = db::product::soft_delete(&pool, missing_id, None).await;
     assert!(result.is_err());
 }
 
@@ -941,7 +996,7 @@ async fn product_list_with_relations_filter_by_category_id_and_q_and_include_del
     assert_eq!(both.len(), 1);
     assert_eq!(both[0].name, "Milk");
 
-    db::product::soft_delete(&pool, p2.id())
+    db::product::soft_delete(&pool, p2.id(), None)
         .await
         .expect("soft_delete");
     let active_only = db::product::list_with_relations(&pool, None, None, false)
@@ -1020,6 +1075,228 @@ async fn product_list_with_relations_search_by_category_and_ancestor_name() {
     assert_eq!(by_direct[0].id, product.id());
 }
 
+#[tokio::test]
+async fn product_search_folds_diacritics_matches_prefixes_and_ranks_name_first() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_search_fts.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let now = 1_000_i64;
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "Dairy".to_string(), now, now, None).expect("valid");
+    db::category::insert(&pool, &cat).await.expect("insert");
+    let creme = Product::new(
+        Uuid::new_v4(),
+        cat_id,
+        "Elvea".to_string(),
+        "Crème fraîche".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid");
+    let butter = Product::new(
+        Uuid::new_v4(),
+        cat_id,
+        "Crémerie".to_string(),
+        "Butter".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(&pool, &creme).await.expect("insert");
+    db::product::insert(&pool, &butter).await.expect("insert");
+
+    let by_folded = db::product::list_with_relations(&pool, None, Some("creme FRAICHE"), false)
+        .await
+        .expect("list_with_relations");
+    assert_eq!(by_folded.len(), 1);
+    assert_eq!(by_folded[0].id, creme.id());
+
+    let by_word_order = db::product::list_with_relations(&pool, None, Some("fraiche creme"), false)
+        .await
+        .expect("list_with_relations");
+    assert_eq!(by_word_order.len(), 1, "word order does not matter");
+
+    let by_prefix = db::product::list_with_relations(&pool, None, Some("crem"), false)
+        .await
+        .expect("list_with_relations");
+    let ids: Vec<Uuid> = by_prefix.iter().map(|p| p.id).collect();
+    assert_eq!(
+        ids,
+        vec![creme.id(), butter.id()],
+        "name match ranks above brand match"
+    );
+
+    let none = db::product::list_with_relations(&pool, None, Some("\"*()"), false)
+        .await
+        .expect("list_with_relations");
+    assert!(none.is_empty(), "punctuation only matches nothing");
+}
+
+#[tokio::test]
+async fn product_search_tolerates_typos_when_nothing_matches_exactly() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_search_typos.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let now = 1_000_i64;
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "Sweets".to_string(), now, now, None).expect("valid");
+    db::category::insert(&pool, &cat).await.expect("insert");
+    let chocolate = Product::new(
+        Uuid::new_v4(),
+        cat_id,
+        "Lindt".to_string(),
+        "Dark chocolate".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(&pool, &chocolate)
+        .await
+        .expect("insert");
+
+    for q in [
+        "dark choclate",
+        "drak chocolate",
+        "chocolaet",
+        "lidnt choco",
+    ] {
+        let found = db::product::list_with_relations(&pool, None, Some(q), false)
+            .await
+            .expect("list_with_relations");
+        let ids: Vec<Uuid> = found.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![chocolate.id()], "{q} should find the product");
+    }
+    for q in ["dark vanilla", "crak"] {
+        let found = db::product::list_with_relations(&pool, None, Some(q), false)
+            .await
+            .expect("list_with_relations");
+        assert!(found.is_empty(), "{q} should find nothing");
+    }
+}
+
+#[tokio::test]
+async fn product_search_typo_alternatives_come_from_the_household() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_search_typo_household.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let now = 1_000_i64;
+    let other = Household::new(Uuid::new_v4(), "Other".to_string(), now).expect("valid");
+    db::household::insert(&pool, &other).await.expect("insert");
+    let insert_product = |name: String| {
+        let pool = pool.clone();
+        async move {
+            let cat_id = Uuid::new_v4();
+            let cat =
+                Category::new(cat_id, None, "Sweets".to_string(), now, now, None).expect("valid");
+            db::category::insert(&pool, &cat).await.expect("insert");
+            let product = Product::new(
+                Uuid::new_v4(),
+                cat_id,
+                "Brand".to_string(),
+                name,
+                now,
+                now,
+                None,
+            )
+            .expect("valid");
+            db::product::insert(&pool, &product).await.expect("insert");
+            product.id()
+        }
+    };
+    let own = insert_product("Chocolate".to_string()).await;
+    // Ten terms one typo from the search that sort before the household's own term.
+    let crowd: Vec<String> = ('a'..='k')
+        .filter(|c| *c != 'e')
+        .map(|c| format!("choclat{c}"))
+        .collect();
+    with_household(other.id(), insert_product(crowd.join(" "))).await;
+
+    let found = db::product::list_with_relations(&pool, None, Some("choclate"), false)
+        .await
+        .expect("list_with_relations");
+    let ids: Vec<Uuid> = found.iter().map(|p| p.id).collect();
+    assert_eq!(
+        ids,
+        vec![own],
+        "other households' terms are no alternatives"
+    );
+}
+
+#[tokio::test]
+async fn product_search_index_follows_category_rename_and_reviews() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_search_sync.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let ids = setup_aggregate_test_products_and_reviews(&pool).await;
+    let search = |q: &'static str| {
+        let pool = pool.clone();
+        async move {
+            db::product::list_with_relations(&pool, None, Some(q), false)
+                .await
+                .expect("list_with_relations")
+                .into_iter()
+                .map(|p| p.id)
+                .collect::<Vec<Uuid>>()
+        }
+    };
+    assert_eq!(search("Cat").await.len(), 2);
+
+    let product = db::product::get_by_id(&pool, ids.product1_id, false)
+        .await
+        .expect("get")
+        .expect("product1");
+    let cat = db::category::get_by_id(&pool, product.category_id(), false)
+        .await
+        .expect("get")
+        .expect("category");
+    let renamed = Category::new(
+        cat.id(),
+        None,
+        "Pantry".to_string(),
+        cat.created_at(),
+        cat.updated_at(),
+        None,
+    )
+    .expect("valid");
+    db::category::update(&pool, &renamed).await.expect("update");
+    assert!(search("Cat").await.is_empty());
+    assert_eq!(search("pantry").await.len(), 2);
+
+    let review = Review::new(
+        Uuid::new_v4(),
+        ids.product2_id,
+        ids.user_id,
+        Decimal::from(5),
+        Some("Wonderfully crunchy".to_string()),
+        ids.now,
+        ids.now,
+        None,
+    )
+    .expect("valid");
+    db::review::insert(&pool, &review).await.expect("insert");
+    assert_eq!(search("crunchy").await, vec![ids.product2_id]);
+
+    db::review::soft_delete(&pool, review.id(), None)
+        .await
+        .expect("soft_delete");
+    assert!(search("crunchy").await.is_empty());
+}
+
 struct AggregateTestIds {
     product1_id: Uuid,
     product2_id: Uuid,
@@ -1232,6 +1509,366 @@ async fn product_list_with_relations_includes_median_review_score_and_lowest_pri
     assert_eq!(row2.lowest_price.as_deref(), Some("3.00"));
 }
 
+#[tokio::test]
+async fn product_get_by_id_with_aggregates_covers_only_that_product() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_get_aggregates.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let ids = setup_aggregate_test_products_and_reviews(&pool).await;
+    insert_aggregate_test_purchases(&pool, &ids).await;
+
+    let product = db::product::get_by_id_with_aggregates(&pool, ids.product1_id)
+        .await
+        .expect("get_by_id_with_aggregates")
+        .expect("product1");
+    assert_eq!(product.category_name, "Cat");
+    assert_eq!(product.review_score, Some(Decimal::from(4)));
+    assert_eq!(product.lowest_price.as_deref(), Some("1.50"));
+
+    let missing = db::product::get_by_id_with_aggregates(&pool, Uuid::new_v4())
+        .await
+        .expect("get_by_id_with_aggregates");
+    assert!(missing.is_none());
+}
+
+#[tokio::test]
+async fn product_list_with_relations_includes_lowest_unit_price_and_sorts_by_it() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_list_unit_price.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let ids = setup_aggregate_test_products_and_reviews(&pool).await;
+    let small_id = Uuid::new_v4();
+    let small = ProductVariation::new(
+        small_id,
+        ids.product1_id,
+        "",
+        "grams",
+        Some(500),
+        ids.now,
+        ids.now,
+        None,
+    )
+    .expect("valid");
+    db::product_variation::insert(&pool, &small)
+        .await
+        .expect("insert");
+    let large_id = Uuid::new_v4();
+    let large = ProductVariation::new(
+        large_id,
+        ids.product1_id,
+        "",
+        "grams",
+        Some(1000),
+        ids.now,
+        ids.now,
+        None,
+    )
+    .expect("valid");
+    db::product_variation::insert(&pool, &large)
+        .await
+        .expect("insert");
+    let bottle_id = Uuid::new_v4();
+    let bottle = ProductVariation::new(
+        bottle_id,
+        ids.product2_id,
+        "",
+        "milliliters",
+        Some(500),
+        ids.now,
+        ids.now,
+        None,
+    )
+    .expect("valid");
+    db::product_variation::insert(&pool, &bottle)
+        .await
+        .expect("insert");
+
+    for (product_id, var_id, price) in [
+        (ids.product1_id, small_id, "2.99"),
+        (ids.product1_id, large_id, "4.99"),
+        (ids.product2_id, bottle_id, "1.00"),
+    ] {
+        let purchase = Purchase::new(
+            Uuid::new_v4(),
+            ids.user_id,
+            product_id,
+            var_id,
+            ids.loc_id,
+            1,
+            price.parse().expect("decimal"),
+            ids.now,
+            None,
+        )
+        .expect("valid");
+        db::purchase::insert(&pool, &purchase)
+            .await
+            .expect("insert");
+    }
+
+    let list = db::product::list_with_relations(&pool, None, None, false)
+        .await
+        .expect("list_with_relations");
+    let row1 = list
+        .iter()
+        .find(|p| p.id == ids.product1_id)
+        .expect("product1 in list");
+    let unit_price = row1.lowest_unit_price.expect("unit price");
+    assert_eq!(
+        unit_price.price.to_string(),
+        "4.99",
+        "1 kg pack is cheaper per kg"
+    );
+    assert_eq!(unit_price.per, "kg");
+    assert_eq!(row1.lowest_price.as_deref(), Some("2.99"));
+
+    let sort = db::product::ProductSort::UnitPrice;
+    let page = db::page::PageRequest::default();
+    let list = db::product::list_page(&pool, None, None, sort, false, &page)
+        .await
+        .expect("list_page")
+        .items;
+    let order: Vec<Uuid> = list.iter().map(|p| p.id).collect();
+    // Per-kg prices come before per-l prices (the two are not comparable).
+    assert_eq!(order, vec![ids.product1_id, ids.product2_id]);
+    let unit_price = list[1].lowest_unit_price.expect("unit price");
+    assert_eq!(unit_price.price.to_string(), "2.00");
+    assert_eq!(unit_price.per, "l");
+}
+
+#[tokio::test]
+async fn product_list_page_sorts_unit_price_at_displayed_cents() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_list_page_unit_cents.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let ids = setup_aggregate_test_products_and_reviews(&pool).await;
+    let cat_id = db::product::get_by_id(&pool, ids.product1_id, false)
+        .await
+        .expect("get")
+        .expect("product1")
+        .category_id();
+    let product3 = Product::new(
+        Uuid::new_v4(),
+        cat_id,
+        "B3".to_string(),
+        "P3".to_string(),
+        ids.now,
+        ids.now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(&pool, &product3).await.expect("insert");
+
+    // 0.125 per kg rounds half to even to 0.12, the same as 0.12 per kg.
+    for (product_id, grams, price) in [
+        (ids.product1_id, 2000, "0.25"),
+        (ids.product2_id, 1000, "0.12"),
+        (product3.id(), 1000, "0.13"),
+    ] {
+        let var_id = Uuid::new_v4();
+        let var = ProductVariation::new(
+            var_id,
+            product_id,
+            "pack",
+            "grams",
+            Some(grams),
+            ids.now,
+            ids.now,
+            None,
+        )
+        .expect("valid");
+        db::product_variation::insert(&pool, &var)
+            .await
+            .expect("insert");
+        let purchase = Purchase::new(
+            Uuid::new_v4(),
+            ids.user_id,
+            product_id,
+            var_id,
+            ids.loc_id,
+            1,
+            price.parse().expect("decimal"),
+            ids.now,
+            None,
+        )
+        .expect("valid");
+        db::purchase::insert(&pool, &purchase)
+            .await
+            .expect("insert");
+    }
+
+    let mut seen = Vec::new();
+    let mut page = db::page::PageRequest {
+        limit: Some(2),
+        after: None,
+    };
+    loop {
+        let result = db::product::list_page(
+            &pool,
+            None,
+            None,
+            db::product::ProductSort::UnitPrice,
+            false,
+            &page,
+        )
+        .await
+        .expect("list_page");
+        seen.extend(result.items.into_iter().map(|p| {
+            let unit_price = p.lowest_unit_price.expect("unit price on every page");
+            (unit_price.price.to_string(), p.id)
+        }));
+        let Some(cursor) = result.next_cursor else {
+            break;
+        };
+        page.after = Some(db::page::Cursor::decode(&cursor).expect("valid cursor"));
+    }
+
+    let mut tied = [ids.product1_id, ids.product2_id];
+    tied.sort();
+    assert_eq!(
+        seen,
+        vec![
+            ("0.12".to_string(), tied[0]),
+            ("0.12".to_string(), tied[1]),
+            ("0.13".to_string(), product3.id()),
+        ],
+        "equal displayed unit prices tie and are ordered by id"
+    );
+}
+
+#[tokio::test]
+async fn product_list_page_sorts_by_aggregates() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_list_page_aggregates.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let ids = setup_aggregate_test_products_and_reviews(&pool).await;
+    insert_aggregate_test_purchases(&pool, &ids).await;
+    let cat_id = db::product::get_by_id(&pool, ids.product1_id, false)
+        .await
+        .expect("get")
+        .expect("product1")
+        .category_id();
+    let unrated = Product::new(
+        Uuid::new_v4(),
+        cat_id,
+        "B3".to_string(),
+        "P3".to_string(),
+        ids.now,
+        ids.now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(&pool, &unrated).await.expect("insert");
+
+    let by_price = db::product::list_page(
+        &pool,
+        None,
+        None,
+        db::product::ProductSort::Price,
+        false,
+        &db::page::PageRequest::default(),
+    )
+    .await
+    .expect("list_page");
+    let order: Vec<Uuid> = by_price.items.iter().map(|p| p.id).collect();
+    assert_eq!(
+        order,
+        vec![ids.product1_id, ids.product2_id, unrated.id()],
+        "cheapest first, products without purchases last"
+    );
+    assert_eq!(by_price.items[0].lowest_price.as_deref(), Some("1.50"));
+    assert!(by_price.next_cursor.is_none());
+
+    let by_score = db::product::list_page(
+        &pool,
+        None,
+        Some("P3"),
+        db::product::ProductSort::ReviewScore,
+        false,
+        &db::page::PageRequest::default(),
+    )
+    .await
+    .expect("list_page");
+    assert_eq!(by_score.items.len(), 1);
+    assert_eq!(by_score.items[0].id, unrated.id());
+    assert!(by_score.items[0].review_score.is_none());
+}
+
+#[tokio::test]
+async fn product_list_page_follows_cursor_through_ties_without_gaps() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("product_list_page_cursor.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let now = 1_000_i64;
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "Cat".to_string(), now, now, None).expect("valid");
+    db::category::insert(&pool, &cat).await.expect("insert");
+    let mut expected = Vec::new();
+    for i in 0..5 {
+        let product = Product::new(
+            Uuid::new_v4(),
+            cat_id,
+            "Brand".to_string(),
+            format!("Product {}", i % 2),
+            now,
+            now,
+            None,
+        )
+        .expect("valid");
+        db::product::insert(&pool, &product).await.expect("insert");
+        expected.push(product.id());
+    }
+
+    let mut seen = Vec::new();
+    let mut page = db::page::PageRequest {
+        limit: Some(2),
+        after: None,
+    };
+    loop {
+        let result = db::product::list_page(
+            &pool,
+            None,
+            None,
+            db::product::ProductSort::Name,
+            false,
+            &page,
+        )
+        .await
+        .expect("list_page");
+        let count = result.items.len();
+        seen.extend(result.items.into_iter().map(|p| (p.name, p.id)));
+        let Some(cursor) = result.next_cursor else {
+            assert!(count <= 2);
+            break;
+        };
+        assert_eq!(count, 2, "only the last page may be short");
+        page.after = Some(db::page::Cursor::decode(&cursor).expect("valid cursor"));
+    }
+
+    assert_eq!(seen.len(), 5);
+    let mut sorted = seen.clone();
+    sorted.sort();
+    assert_eq!(seen, sorted, "ordered by name, then id");
+    let mut ids: Vec<Uuid> = seen.into_iter().map(|(_, id)| id).collect();
+    ids.sort();
+    expected.sort();
+    assert_eq!(ids, expected, "every product exactly once");
+}
+
 // --- Product list cache tests (run serially) ---
 
 struct ProductCacheTestGuard;
@@ -1256,9 +1893,12 @@ async fn product_simple_list_cache_is_warmed_on_first_call_and_used_on_subsequen
     let db_path_str = db_path.to_str().expect("path UTF-8");
     let pool = db::create_pool(db_path_str).await.expect("pool");
     db::run_migrations(&pool).await.expect("migrations");
+    let counter = &pocketratings::metrics::PRODUCT_LIST_CACHE;
+    let (hits, misses) = (counter.hits(), counter.misses());
 
     let all = db::product::get_all(&pool, false).await.expect("get_all");
     assert!(all.is_empty(), "first call: empty DB -> empty list");
+    assert_eq!(counter.misses(), misses + 1, "first call is a cache miss");
 
     let cat_id = Uuid::new_v4();
     let now = 1_000_i64;
@@ -1294,6 +1934,8 @@ async fn product_simple_list_cache_is_warmed_on_first_call_and_used_on_subsequen
         .expect("get_all with include_deleted");
     assert_eq!(with_del.len(), 1);
     assert_eq!(with_del[0].name(), "CachedProduct");
+    assert_eq!(counter.hits(), hits + 2, "cached calls count as hits");
+    assert_eq!(counter.misses(), misses + 1);
 }
 
 #[tokio::test]
@@ -1460,7 +2102,7 @@ async fn product_simple_list_cache_invalidated_after_soft_delete() {
     let all = db::product::get_all(&pool, false).await.expect("get_all");
     assert_eq!(all.len(), 1);
 
-    db::product::soft_delete(&pool, product_id)
+    db::product::soft_delete(&pool, product_id, None)
         .await
         .expect("soft_delete");
 
@@ -1572,6 +2214,7 @@ async fn product_list_cache_is_warmed_on_first_call_and_used_on_subsequent_calls
         deleted_at: None,
         review_score: None,
         lowest_price: None,
+        lowest_unit_price: None,
     };
     db::product::set_product_list_cache_for_test(Some(vec![cached.clone()]));
 
@@ -1692,6 +2335,7 @@ async fn product_list_cache_invalidated_after_update() {
         deleted_at: None,
         review_score: None,
         lowest_price: None,
+        lowest_unit_price: None,
     };
     db::product::set_product_list_cache_for_test(Some(vec![stale]));
 
@@ -1764,7 +2408,7 @@ async fn product_list_cache_invalidated_after_soft_delete() {
         .expect("list_with_relations");
     assert_eq!(all.len(), 1);
 
-    db::product::soft_delete(&pool, product_id)
+    db::product::soft_delete(&pool, product_id, None)
         .await
         .expect("soft_delete");
 
@@ -77,6 +77,84 @@ async fn product_variation_insert_and_get_by_id_roundtrip() {
     assert!(loaded.is_active());
 }
 
+#[tokio::test]
+async fn product_variation_get_by_barcode_finds_active_variation_only() {
+    let (_dir, pool) = setup_pool().await;
+    let (_cat_id, product_id) = insert_category_and_product(&pool).await;
+
+    let now = 1_000_i64;
+    let var_id = Uuid::new_v4();
+    let var = ProductVariation::new(var_id, product_id, "", "none", None, now, now, None)
+        .and_then(|v| v.with_barcode(Some("4006381333931")))
+        .expect("valid");
+    db::product_variation::insert(&pool, &var)
+        .await
+        .expect("insert");
+
+    let found = db::product_variation::get_by_barcode(&pool, "4006381333931")
+        .await
+        .expect("get_by_barcode")
+        .expect("variation should exist");
+    assert_eq!(found.id(), var_id);
+    assert_eq!(found.barcode(), Some("4006381333931"));
+
+    let duplicate =
+        ProductVariation::new(Uuid::new_v4(), product_id, "", "none", None, now, now, None)
+            .and_then(|v| v.with_barcode(Some("4006381333931")))
+            .expect("valid");
+    assert!(
+        db::product_variation::insert(&pool, &duplicate)
+            .await
+            .is_err(),
+        "active barcodes are unique"
+    );
+
+    db::product_variation::soft_delete(&pool, var_id, None)
+        .await
+        .expect("soft delete");
+    assert!(
+        db::product_variation::get_by_barcode(&pool, "4006381333931")
+            .await
+            .expect("get_by_barcode")
+            .is_none()
+    );
+    db::product_variation::insert(&pool, &duplicate)
+        .await
+        .expect("barcode is free again after soft delete");
+}
+
+#[tokio::test]
+async fn product_variation_barcode_upc_a_and_ean13_are_the_same() {
+    let (_dir, pool) = setup_pool().await;
+    let (_cat_id, product_id) = insert_category_and_product(&pool).await;
+
+    let now = 1_000_i64;
+    let var_id = Uuid::new_v4();
+    let var = ProductVariation::new(var_id, product_id, "", "none", None, now, now, None)
+        .and_then(|v| v.with_barcode(Some("036000291452")))
+        .expect("valid");
+    db::product_variation::insert(&pool, &var)
+        .await
+        .expect("insert");
+
+    for code in ["036000291452", "0036000291452", "00036000291452"] {
+        let found = db::product_variation::get_by_barcode(&pool, code)
+            .await
+            .expect("get_by_barcode")
+            .unwrap_or_else(|| panic!("{code} should find the variation"));
+        assert_eq!(found.id(), var_id);
+        assert_eq!(found.barcode(), Some("0036000291452"));
+    }
+
+    let ean13 = ProductVariation::new(Uuid::new_v4(), product_id, "", "none", None, now, now, None)
+        .and_then(|v| v.with_barcode(Some("0036000291452")))
+        .expect("valid");
+    assert!(
+        db::product_variation::insert(&pool, &ean13).await.is_err(),
+        "the EAN-13 form of an active UPC-A is taken"
+    );
+}
+
 #[tokio::test]
 async fn product_variation_get_by_id_returns_none_for_nonexistent() {
     let (_dir, pool) = setup_pool().await;
@@ -99,7 +177,7 @@ async fn product_variation_get_by_id_excludes_soft_deleted_when_include_deleted_
     db::product_variation::insert(&pool, &var)
         .await
         .expect("insert");
-    db::product_variation::soft_delete(&pool, var_id)
+    db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect("soft_delete");
 
@@ -121,7 +199,7 @@ async fn product_variation_get_by_id_includes_soft_deleted_when_include_deleted_
     db::product_variation::insert(&pool, &var)
         .await
         .expect("insert");
-    db::product_variation::soft_delete(&pool, var_id)
+    db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect("soft_delete");
 
@@ -196,7 +274,7 @@ async fn product_variation_list_by_product_id_include_deleted_filters_soft_delet
     db::product_variation::insert(&pool, &var1)
         .await
         .expect("insert");
-    db::product_variation::soft_delete(&pool, var1_id)
+    db::product_variation::soft_delete(&pool, var1_id, None)
         .await
         .expect("soft_delete");
     let var2_id = Uuid::new_v4();
@@ -282,7 +360,7 @@ async fn product_variation_update_returns_error_when_already_deleted() {
     db::product_variation::insert(&pool, &var)
         .await
         .expect("insert");
-    db::product_variation::soft_delete(&pool, var_id)
+    db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect("soft_delete");
 
@@ -317,7 +395,7 @@ async fn product_variation_soft_delete_then_excluded_from_get_and_list() {
         .await
         .expect("insert");
 
-    db::product_variation::soft_delete(&pool, var_id)
+    db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect("soft_delete");
 
@@ -336,7 +414,7 @@ async fn product_variation_soft_delete_then_excluded_from_get_and_list() {
 async fn product_variation_soft_delete_returns_error_when_not_found() {
     let (_dir, pool) = setup_pool().await;
     let id = Uuid::new_v4();
-    let err = db::product_variation::soft_delete(&pool, id)
+    let err = db::product_variation::soft_delete(&pool, id, None)
         .await
         .expect_err("soft_delete should fail");
     assert!(matches!(err, db::DbError::InvalidData(_)));
@@ -354,10 +432,10 @@ async fn product_variation_soft_delete_returns_error_when_already_deleted() {
     db::product_variation::insert(&pool, &var)
         .await
         .expect("insert");
-    db::product_variation::soft_delete(&pool, var_id)
+    db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect("first soft_delete");
-    let err = db::product_variation::soft_delete(&pool, var_id)
+    let err = db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect_err("second soft_delete should fail");
     assert!(matches!(err, db::DbError::InvalidData(_)));
@@ -386,7 +464,7 @@ async fn product_variation_count_by_product_id() {
         .expect("count");
     assert_eq!(count, 1);
 
-    db::product_variation::soft_delete(&pool, var_id)
+    db::product_variation::soft_delete(&pool, var_id, None)
         .await
         .expect("soft_delete");
     let count_active = db::product_variation::count_by_product_id(&pool, product_id, false)
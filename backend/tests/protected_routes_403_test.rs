@@ -4,8 +4,9 @@ use axum::body::Body;
 use axum::http::{Method, Request, StatusCode};
 use http_body_util::BodyExt;
 use pocketratings::api::{AppState, router};
+use pocketratings::auth::jwt_keys::JwtKeys;
 use pocketratings::auth::password;
-use pocketratings::config::Config;
+use pocketratings::config::{Config, LogFormat};
 use pocketratings::db;
 use tower::ServiceExt;
 use uuid::Uuid;
@@ -19,8 +20,10 @@ fn protected_routes() -> Vec<(Method, String)> {
     let id_path_loc = format!("/api/v1/locations/{PLACEHOLDER_ID}");
     let id_path_prod = format!("/api/v1/products/{PLACEHOLDER_ID}");
     let id_path_rev = format!("/api/v1/reviews/{PLACEHOLDER_ID}");
+    let id_path_user = format!("/api/v1/users/{PLACEHOLDER_ID}");
     vec![
         (Method::GET, "/api/v1/me".to_string()),
+        (Method::PATCH, "/api/v1/me".to_string()),
         (Method::GET, "/api/v1/categories".to_string()),
         (Method::GET, id_path_cat.clone()),
         (Method::POST, "/api/v1/categories".to_string()),
@@ -41,6 +44,20 @@ fn protected_routes() -> Vec<(Method, String)> {
         (Method::POST, "/api/v1/reviews".to_string()),
         (Method::PATCH, id_path_rev.clone()),
         (Method::DELETE, id_path_rev),
+        (Method::GET, "/api/v1/me/tokens".to_string()),
+        (Method::POST, "/api/v1/me/tokens".to_string()),
+        (
+            Method::DELETE,
+            format!("/api/v1/me/tokens/{PLACEHOLDER_ID}"),
+        ),
+        (Method::POST, "/api/v1/me/password".to_string()),
+        (Method::GET, "/api/v1/users".to_string()),
+        (Method::POST, "/api/v1/users".to_string()),
+        (Method::GET, id_path_user.clone()),
+        (Method::PATCH, id_path_user.clone()),
+        (Method::DELETE, id_path_user),
+        // Last: logging out revokes the session used for the routes above.
+        (Method::POST, "/api/v1/auth/logout".to_string()),
     ]
 }
 
@@ -52,10 +69,11 @@ async fn test_state_with_user() -> (AppState, String, tempfile::TempDir) {
     db::run_migrations(&pool).await.expect("migrate");
     let hash = password::hash_password("testpass").expect("hash");
     let now = 1_700_000_000i64;
+    let user_id = Uuid::new_v4();
     sqlx::query(
         "INSERT INTO users (id, name, email, password, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
     )
-    .bind(Uuid::new_v4().to_string())
+    .bind(user_id.to_string())
     .bind("Test User")
     .bind("test@example.com")
     .bind(&hash)
@@ -64,12 +82,20 @@ async fn test_state_with_user() -> (AppState, String, tempfile::TempDir) {
     .execute(&pool)
     .await
     .expect("insert user");
+    db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, user_id)
+        .await
+        .expect("add member");
     let config = Config {
         database_path: path_str,
-        jwt_secret: "test-secret".to_string(),
+        jwt_keys: JwtKeys::from_secret("test-secret"),
         jwt_expiration_seconds: 3600,
         jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
         bind: "127.0.0.1:0".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
         pid_file: std::env::temp_dir()
             .join("pocketratings-protected-403-test.pid")
             .to_string_lossy()
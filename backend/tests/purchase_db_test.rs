@@ -196,7 +196,7 @@ async fn setup_list_with_relations_data(
     .expect("valid");
     db::purchase::insert(pool, &p4).await.expect("insert");
 
-    db::purchase::soft_delete(pool, p2.id())
+    db::purchase::soft_delete(pool, p2.id(), None)
         .await
         .expect("soft_delete");
 
@@ -286,7 +286,7 @@ async fn purchase_list_with_filters_and_include_deleted() {
         .expect("list");
     assert_eq!(by_user.len(), 1);
 
-    db::purchase::soft_delete(&pool, p1.id())
+    db::purchase::soft_delete(&pool, p1.id(), None)
         .await
         .expect("soft_delete");
 
@@ -302,6 +302,82 @@ async fn purchase_list_with_filters_and_include_deleted() {
     assert!(!with_deleted[0].is_active());
 }
 
+#[tokio::test]
+async fn purchase_list_page_sorts_filters_and_pages() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("purchase_list_page.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let (user_id, product_id, variation_id, location_id) =
+        insert_user_product_location(&pool).await;
+    for (price, purchased_at) in [("3.00", 1_000), ("1.00", 2_000), ("2.00", 3_000)] {
+        let purchase = Purchase::new(
+            Uuid::new_v4(),
+            user_id,
+            product_id,
+            variation_id,
+            location_id,
+            1,
+            price.parse().expect("decimal"),
+            purchased_at,
+            None,
+        )
+        .expect("valid");
+        db::purchase::insert(&pool, &purchase)
+            .await
+            .expect("insert");
+    }
+
+    let filter = db::purchase::PurchaseFilter::default();
+    let first = db::purchase::list_page(
+        &pool,
+        &filter,
+        db::purchase::PurchaseSort::Price,
+        &db::page::PageRequest {
+            limit: Some(2),
+            after: None,
+        },
+    )
+    .await
+    .expect("list_page");
+    let prices: Vec<String> = first.items.iter().map(|p| p.price.clone()).collect();
+    assert_eq!(prices, vec!["1.00", "2.00"]);
+    let cursor = first.next_cursor.expect("more pages");
+
+    let second = db::purchase::list_page(
+        &pool,
+        &filter,
+        db::purchase::PurchaseSort::Price,
+        &db::page::PageRequest {
+            limit: Some(2),
+            after: db::page::Cursor::decode(&cursor),
+        },
+    )
+    .await
+    .expect("list_page");
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].price, "3.00");
+    assert!(second.next_cursor.is_none());
+
+    let filter = db::purchase::PurchaseFilter {
+        from_ts: Some(1_500),
+        ..Default::default()
+    };
+    let recent = db::purchase::list_page(
+        &pool,
+        &filter,
+        db::purchase::PurchaseSort::PurchasedAt,
+        &db::page::PageRequest::default(),
+    )
+    .await
+    .expect("list_page");
+    let times: Vec<i64> = recent.items.iter().map(|p| p.purchased_at).collect();
+    assert_eq!(times, vec![3_000, 2_000], "newest first");
+}
+
 #[tokio::test]
 async fn purchase_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
     let dir = tempfile::tempdir().expect("temp dir");
@@ -332,7 +408,7 @@ async fn purchase_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
         .await
         .expect("insert");
 
-    db::purchase::soft_delete(&pool, purchase_id)
+    db::purchase::soft_delete(&pool, purchase_id, None)
         .await
         .expect("soft_delete");
 
@@ -364,6 +440,62 @@ async fn purchase_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
     assert!(!with_deleted[0].is_active());
 }
 
+#[tokio::test]
+async fn purchase_restore_requires_active_variation() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("purchase_restore.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+
+    let (user_id, product_id, variation_id, location_id) =
+        insert_user_product_location(&pool).await;
+
+    let purchase_id = Uuid::new_v4();
+    let price: Decimal = "5".parse().expect("decimal");
+    let purchase = Purchase::new(
+        purchase_id,
+        user_id,
+        product_id,
+        variation_id,
+        location_id,
+        1,
+        price,
+        1_000,
+        None,
+    )
+    .expect("valid");
+    db::purchase::insert(&pool, &purchase)
+        .await
+        .expect("insert");
+    db::purchase::soft_delete(&pool, purchase_id, None)
+        .await
+        .expect("soft_delete purchase");
+    db::product_variation::soft_delete(&pool, variation_id, None)
+        .await
+        .expect("soft_delete variation");
+
+    let result = db::purchase::restore(&pool, purchase_id).await;
+    assert!(
+        matches!(result, Err(db::DbError::InvalidData(ref msg)) if msg.contains("cannot restore")),
+        "restore should fail while the variation is deleted: {result:?}"
+    );
+
+    db::product_variation::restore(&pool, variation_id)
+        .await
+        .expect("restore variation");
+    db::purchase::restore(&pool, purchase_id)
+        .await
+        .expect("restore purchase");
+
+    let restored = db::purchase::get_by_id(&pool, purchase_id, false)
+        .await
+        .expect("get_by_id")
+        .expect("restored purchase should be active");
+    assert!(restored.is_active());
+}
+
 #[tokio::test]
 async fn purchase_hard_delete_removes_row() {
     let dir = tempfile::tempdir().expect("temp dir");
@@ -1,11 +1,12 @@
-//! Integration tests for REST auth: login and protected route (GET /api/v1/me).
+//! Integration tests for REST auth: login, refresh, logout, API tokens, passwords, invites and protected route (GET /api/v1/me).
 
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use http_body_util::BodyExt;
 use pocketratings::api::{AppState, router};
+use pocketratings::auth::jwt_keys::JwtKeys;
 use pocketratings::auth::password;
-use pocketratings::config::Config;
+use pocketratings::config::{Config, LogFormat};
 use pocketratings::db;
 use tower::ServiceExt;
 use uuid::Uuid;
@@ -34,12 +35,20 @@ async fn test_pool_with_user(
     .execute(&pool)
     .await
     .expect("insert user");
+    db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+        .await
+        .expect("add member");
     let config = Config {
         database_path: path_str.clone(),
-        jwt_secret: "test-secret".to_string(),
+        jwt_keys: JwtKeys::from_secret("test-secret"),
         jwt_expiration_seconds: 3600,
         jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
         bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
         pid_file: std::env::temp_dir()
             .join("pocketratings-rest-auth-test.pid")
             .to_string_lossy()
@@ -109,3 +118,723 @@ async fn login_then_get_me_returns_current_user_id() {
     );
     assert_eq!(me_json.get("name").and_then(|v| v.as_str()), Some("Alice"));
 }
+
+/// Send a request to a fresh router and return status and JSON body (`Null` when empty).
+async fn send(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+    let request = match body {
+        Some(json) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json).expect("json")))
+            .expect("request"),
+        None => builder.body(Body::empty()).expect("request"),
+    };
+    let response = router(state.clone())
+        .oneshot(request)
+        .await
+        .expect("service");
+    let status = response.status();
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes();
+    let json = if bytes.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&bytes).expect("json")
+    };
+    (status, json)
+}
+
+#[tokio::test]
+async fn refresh_rotates_refresh_token_and_logout_revokes_session() {
+    let (state, _user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let (status, login) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let first_refresh = login["refresh_token"].as_str().expect("refresh token");
+
+    let (status, refreshed) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/refresh",
+        None,
+        Some(serde_json::json!({ "refresh_token": first_refresh })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let token = refreshed["token"].as_str().expect("token");
+    let second_refresh = refreshed["refresh_token"].as_str().expect("refresh token");
+    assert_ne!(first_refresh, second_refresh);
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/refresh",
+        None,
+        Some(serde_json::json!({ "refresh_token": first_refresh })),
+    )
+    .await;
+    assert_eq!(
+        status,
+        StatusCode::UNAUTHORIZED,
+        "refresh tokens are single-use"
+    );
+
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(&state, "POST", "/api/v1/auth/logout", Some(token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/refresh",
+        None,
+        Some(serde_json::json!({ "refresh_token": second_refresh })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_tokens_authenticate_with_their_scope_until_revoked() {
+    let (state, user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let (_, login) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    let jwt = login["token"].as_str().expect("token");
+
+    let (status, read) = send(
+        &state,
+        "POST",
+        "/api/v1/me/tokens",
+        Some(jwt),
+        Some(serde_json::json!({ "name": "dashboard" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(read["scope"], "read");
+    let read_token = read["token"].as_str().expect("token");
+    let (status, write) = send(
+        &state,
+        "POST",
+        "/api/v1/me/tokens",
+        Some(jwt),
+        Some(serde_json::json!({ "name": "automation", "scope": "write", "expires_in_days": 30 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let write_token = write["token"].as_str().expect("token");
+
+    let (status, me) = send(&state, "GET", "/api/v1/me", Some(read_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["user_id"], user_id.to_string());
+
+    let location = serde_json::json!({ "name": "Corner shop" });
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/locations",
+        Some(read_token),
+        Some(location.clone()),
+    )
+    .await;
+    assert_eq!(
+        status,
+        StatusCode::FORBIDDEN,
+        "read-only token cannot write"
+    );
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/locations",
+        Some(write_token),
+        Some(location),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = send(&state, "GET", "/api/v1/me/tokens", Some(write_token), None).await;
+    assert_eq!(
+        status,
+        StatusCode::FORBIDDEN,
+        "API tokens cannot manage API tokens"
+    );
+    let (status, listed) = send(&state, "GET", "/api/v1/me/tokens", Some(jwt), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().expect("array").len(), 2);
+    assert!(listed[0].get("token").is_none(), "secrets are never listed");
+
+    let read_id = read["id"].as_str().expect("id");
+    let (status, _) = send(
+        &state,
+        "DELETE",
+        &format!("/api/v1/me/tokens/{read_id}"),
+        Some(jwt),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(read_token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some("prt_unknown"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn change_password_requires_current_password_and_revokes_other_sessions() {
+    let (state, _user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let credentials = serde_json::json!({ "email": "alice@example.com", "password": "secret123" });
+    let (_, first) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(credentials.clone()),
+    )
+    .await;
+    let (_, other) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(credentials),
+    )
+    .await;
+    let token = first["token"].as_str().expect("token");
+    let other_token = other["token"].as_str().expect("token");
+    let (_, api_token) = send(
+        &state,
+        "POST",
+        "/api/v1/me/tokens",
+        Some(token),
+        Some(serde_json::json!({ "name": "dashboard" })),
+    )
+    .await;
+    let api_token = api_token["token"].as_str().expect("token");
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/me/password",
+        Some(token),
+        Some(serde_json::json!({ "current_password": "wrong", "new_password": "new-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/me/password",
+        Some(token),
+        Some(serde_json::json!({ "current_password": "secret123", "new_password": "new-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK, "current session stays logged in");
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(other_token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(api_token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "API tokens are revoked");
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "new-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn change_password_locks_out_repeated_wrong_current_passwords() {
+    let (state, _user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let (_, login) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    let token = login["token"].as_str().expect("token");
+
+    let wrong = serde_json::json!({ "current_password": "wrong", "new_password": "new-secret" });
+    for _ in 0..=pocketratings::auth::lockout::FREE_ATTEMPTS {
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/api/v1/me/password",
+            Some(token),
+            Some(wrong.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/me/password",
+        Some(token),
+        Some(serde_json::json!({ "current_password": "secret123", "new_password": "new-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    assert_eq!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS,
+        "shares the login lockout"
+    );
+}
+
+#[tokio::test]
+async fn password_reset_token_sets_new_password_once() {
+    let (state, user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let (_, login) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    let token = login["token"].as_str().expect("token");
+    let (_, api_token) = send(
+        &state,
+        "POST",
+        "/api/v1/me/tokens",
+        Some(token),
+        Some(serde_json::json!({ "name": "dashboard" })),
+    )
+    .await;
+    let api_token = api_token["token"].as_str().expect("token").to_string();
+    let now = chrono::Utc::now().timestamp();
+    db::password_reset::insert(
+        &state.pool,
+        user_id,
+        &pocketratings::auth::token::hash("reset-me"),
+        now,
+        now + 3600,
+    )
+    .await
+    .expect("insert reset");
+
+    let reset = serde_json::json!({ "token": "reset-me", "new_password": "new-secret" });
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/password-reset",
+        None,
+        Some(reset.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/password-reset",
+        None,
+        Some(reset),
+    )
+    .await;
+    assert_eq!(
+        status,
+        StatusCode::UNAUTHORIZED,
+        "reset tokens are single-use"
+    );
+
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "all sessions are revoked");
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(&api_token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "API tokens are revoked");
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "new-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Store an invite to the default household for `email` with token `secret`, expiring at
+/// `expires_at`.
+async fn insert_invite(state: &AppState, email: &str, secret: &str, expires_at: i64) {
+    insert_invite_to(
+        state,
+        db::household::DEFAULT_HOUSEHOLD_ID,
+        email,
+        secret,
+        expires_at,
+    )
+    .await;
+}
+
+/// Store an invite to `household` for `email` with token `secret`, expiring at `expires_at`.
+async fn insert_invite_to(
+    state: &AppState,
+    household: Uuid,
+    email: &str,
+    secret: &str,
+    expires_at: i64,
+) {
+    let now = chrono::Utc::now().timestamp();
+    let invite = pocketratings::domain::invite::Invite::new(
+        Uuid::new_v4(),
+        email.to_string(),
+        pocketratings::domain::user::Role::Guest,
+        household,
+        now - 10,
+        expires_at,
+        None,
+    )
+    .expect("valid invite");
+    db::invite::insert(
+        &state.pool,
+        &invite,
+        &pocketratings::auth::token::hash(secret),
+    )
+    .await
+    .expect("insert invite");
+}
+
+#[tokio::test]
+async fn register_with_invite_creates_user_once() {
+    let (state, _, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let now = chrono::Utc::now().timestamp();
+    insert_invite(&state, "bob@example.com", "invite-me", now + 3600).await;
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/register",
+        None,
+        Some(serde_json::json!({ "token": "invite-me", "name": " ", "password": "bob-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "name is validated");
+
+    let register =
+        serde_json::json!({ "token": "invite-me", "name": "Bob", "password": "bob-secret" });
+    let (status, pair) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/register",
+        None,
+        Some(register.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let token = pair["token"].as_str().expect("token");
+    let (status, me) = send(&state, "GET", "/api/v1/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["email"], "bob@example.com");
+    assert_eq!(me["role"], "guest");
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/register",
+        None,
+        Some(register),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "invites are single-use");
+}
+
+#[tokio::test]
+async fn register_rejects_unknown_expired_and_taken_invites() {
+    let (state, _, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let now = chrono::Utc::now().timestamp();
+    insert_invite(&state, "old@example.com", "expired", now - 1).await;
+    insert_invite(&state, "alice@example.com", "taken", now + 3600).await;
+
+    for (secret, expected) in [
+        ("unknown", StatusCode::UNAUTHORIZED),
+        ("expired", StatusCode::UNAUTHORIZED),
+        ("taken", StatusCode::CONFLICT),
+    ] {
+        let (status, _) = send(
+            &state,
+            "POST",
+            "/api/v1/auth/register",
+            None,
+            Some(serde_json::json!({ "token": secret, "name": "Eve", "password": "x" })),
+        )
+        .await;
+        assert_eq!(status, expected, "{secret}");
+    }
+}
+
+#[tokio::test]
+async fn register_with_email_of_deleted_user_conflicts_and_keeps_invite() {
+    let (state, user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    db::user::soft_delete(&state.pool, user_id)
+        .await
+        .expect("soft delete");
+    let now = chrono::Utc::now().timestamp();
+    insert_invite(&state, "alice@example.com", "invite-me", now + 3600).await;
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/register",
+        None,
+        Some(serde_json::json!({ "token": "invite-me", "name": "Alice", "password": "x" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let invite = db::invite::get_active_by_hash(
+        &state.pool,
+        &pocketratings::auth::token::hash("invite-me"),
+        now,
+    )
+    .await
+    .expect("get invite");
+    assert!(
+        invite.is_some(),
+        "a failed registration leaves the invite usable"
+    );
+}
+
+#[tokio::test]
+async fn register_records_the_user_in_the_invite_household() {
+    let (state, _, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let other = pocketratings::domain::household::Household::new(
+        Uuid::new_v4(),
+        "Other".to_string(),
+        1_700_000_000,
+    )
+    .expect("household");
+    db::household::insert(&state.pool, &other)
+        .await
+        .expect("insert household");
+    let now = chrono::Utc::now().timestamp();
+    insert_invite_to(
+        &state,
+        other.id(),
+        "bob@example.com",
+        "invite-me",
+        now + 3600,
+    )
+    .await;
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/register",
+        None,
+        Some(serde_json::json!({ "token": "invite-me", "name": "Bob", "password": "x" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let bob = db::user::get_by_email(&state.pool, "bob@example.com")
+        .await
+        .expect("get user")
+        .expect("bob");
+    let households: Vec<String> = sqlx::query_scalar(
+        "SELECT household_id FROM audit_log WHERE entity_type = 'user' AND entity_id = ?",
+    )
+    .bind(bob.id().to_string())
+    .fetch_all(&state.pool)
+    .await
+    .expect("audit entries");
+    assert_eq!(households, [other.id().to_string()]);
+}
+
+/// Log in with the password and return the two-factor challenge.
+async fn login_challenge(state: &AppState) -> String {
+    let (status, body) = send(
+        state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["two_factor_required"], true);
+    assert!(
+        body.get("token").is_none(),
+        "no tokens before the second step"
+    );
+    body["challenge"].as_str().expect("challenge").to_string()
+}
+
+#[tokio::test]
+async fn two_factor_login_requires_a_fresh_code_or_recovery_code() {
+    use pocketratings::auth::{token, totp};
+
+    let (state, user_id, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let secret = totp::generate_secret();
+    db::totp::enable(
+        &state.pool,
+        user_id,
+        &secret,
+        &[token::hash("abcde-fghij")],
+        chrono::Utc::now().timestamp(),
+    )
+    .await
+    .expect("enable 2fa");
+    let totp_login = |challenge: &str, code: &str| {
+        Some(serde_json::json!({ "challenge": challenge, "code": code }))
+    };
+
+    let challenge = login_challenge(&state).await;
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login/totp",
+        None,
+        totp_login(&challenge, "not-a-code"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let code = totp::code_at(&secret, chrono::Utc::now().timestamp()).expect("code");
+    let (status, pair) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login/totp",
+        None,
+        totp_login(&challenge, &code),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, me) = send(
+        &state,
+        "GET",
+        "/api/v1/me",
+        Some(pair["token"].as_str().expect("token")),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["user_id"], user_id.to_string());
+
+    let challenge = login_challenge(&state).await;
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login/totp",
+        None,
+        totp_login(&challenge, &code),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "codes are single-use");
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login/totp",
+        None,
+        totp_login(&challenge, " ABCDE-FGHIJ "),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login/totp",
+        None,
+        totp_login(&challenge, "abcde-fghij"),
+    )
+    .await;
+    assert_eq!(
+        status,
+        StatusCode::UNAUTHORIZED,
+        "challenges and recovery codes are single-use"
+    );
+}
+
+#[tokio::test]
+async fn access_tokens_survive_key_rotation_until_the_old_key_is_retired() {
+    use pocketratings::auth::jwt_keys::JwtKey;
+
+    let (mut state, _, _dir) = test_pool_with_user("alice@example.com", "secret123").await;
+    let (_, login) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "alice@example.com", "password": "secret123" })),
+    )
+    .await;
+    let old_token = login["token"].as_str().expect("token").to_string();
+    let rotated = |retired: Vec<String>| {
+        JwtKeys::new(
+            vec![
+                JwtKey::secret("2026-10", "rotated-secret"),
+                JwtKey::secret("default", "test-secret"),
+            ],
+            None,
+            retired,
+        )
+        .expect("keys")
+    };
+
+    state.config.jwt_keys = rotated(Vec::new());
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(&old_token), None).await;
+    assert_eq!(status, StatusCode::OK, "previous key still verifies");
+    let (_, pair) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/refresh",
+        None,
+        Some(serde_json::json!({ "refresh_token": login["refresh_token"] })),
+    )
+    .await;
+    let new_token = pair["token"].as_str().expect("token").to_string();
+
+    state.config.jwt_keys = rotated(vec!["default".to_string()]);
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(&old_token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "retired key is rejected");
+    let (status, _) = send(&state, "GET", "/api/v1/me", Some(&new_token), None).await;
+    assert_eq!(status, StatusCode::OK, "new tokens use the new key");
+}
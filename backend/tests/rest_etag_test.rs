@@ -0,0 +1,388 @@
+//! Integration tests for conditional requests: `ETag`, `If-None-Match` and `If-Match`.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use http_body_util::BodyExt;
+use pocketratings::api::{AppState, router};
+use pocketratings::auth::jwt_keys::JwtKeys;
+use pocketratings::auth::password;
+use pocketratings::config::{Config, LogFormat};
+use pocketratings::db;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Create a state with one member (`member@example.com`, password `secret`).
+async fn test_state() -> (AppState, tempfile::TempDir) {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("rest_etag.db");
+    let path_str = db_path.to_str().expect("path utf-8").to_string();
+    let pool = db::create_pool(&path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrate");
+    let hash = password::hash_password("secret").expect("hash");
+    let now = 1_700_000_000i64;
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, name, email, password, role, created_at, updated_at) VALUES (?, ?, ?, ?, 'member', ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind("Member")
+    .bind("member@example.com")
+    .bind(&hash)
+    .bind(now)
+    .bind(now)
+    .execute(&pool)
+    .await
+    .expect("insert user");
+    db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+        .await
+        .expect("add member");
+    let config = Config {
+        database_path: path_str,
+        jwt_keys: JwtKeys::from_secret("test-secret"),
+        jwt_expiration_seconds: 3600,
+        jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
+        bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
+        pid_file: std::env::temp_dir()
+            .join("pocketratings-rest-etag-test.pid")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    (AppState { config, pool }, dir)
+}
+
+/// Response status, `ETag` header and JSON body (`Null` when empty).
+struct Reply {
+    status: StatusCode,
+    etag: Option<String>,
+    json: serde_json::Value,
+}
+
+/// Send a request with an optional bearer token, conditional header and JSON body.
+async fn send(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    condition: Option<(header::HeaderName, &str)>,
+    body: Option<serde_json::Value>,
+) -> Reply {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+    if let Some((name, value)) = condition {
+        builder = builder.header(name, value);
+    }
+    let request = match body {
+        Some(json) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json).expect("json")))
+            .expect("request"),
+        None => builder.body(Body::empty()).expect("request"),
+    };
+    let response = router(state.clone())
+        .oneshot(request)
+        .await
+        .expect("service");
+    let status = response.status();
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .map(|v| v.to_str().expect("etag").to_string());
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes();
+    let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    Reply { status, etag, json }
+}
+
+async fn login(state: &AppState) -> String {
+    let reply = send(
+        state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        None,
+        Some(serde_json::json!({ "email": "member@example.com", "password": "secret" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    reply.json["token"].as_str().expect("token").to_string()
+}
+
+async fn create_location(state: &AppState, token: &str, name: &str) -> String {
+    let reply = send(
+        state,
+        "POST",
+        "/api/v1/locations",
+        Some(token),
+        None,
+        Some(serde_json::json!({ "name": name })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::CREATED);
+    reply.json["id"].as_str().expect("id").to_string()
+}
+
+#[tokio::test]
+async fn list_returns_304_until_something_changes() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+    create_location(&state, &token, "Market").await;
+
+    let first = send(&state, "GET", "/api/v1/locations", Some(&token), None, None).await;
+    assert_eq!(first.status, StatusCode::OK);
+    let etag = first.etag.expect("etag");
+
+    let unchanged = send(
+        &state,
+        "GET",
+        "/api/v1/locations",
+        Some(&token),
+        Some((header::IF_NONE_MATCH, &etag)),
+        None,
+    )
+    .await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(unchanged.etag.as_deref(), Some(etag.as_str()));
+    assert_eq!(unchanged.json, serde_json::Value::Null);
+
+    create_location(&state, &token, "Bakery").await;
+    let changed = send(
+        &state,
+        "GET",
+        "/api/v1/locations",
+        Some(&token),
+        Some((header::IF_NONE_MATCH, &etag)),
+        None,
+    )
+    .await;
+    assert_eq!(changed.status, StatusCode::OK);
+    assert_ne!(changed.etag.as_deref(), Some(etag.as_str()));
+    assert_eq!(changed.json.as_array().expect("array").len(), 2);
+}
+
+#[tokio::test]
+async fn patch_with_stale_if_match_returns_412() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+    let id = create_location(&state, &token, "Market").await;
+    let uri = format!("/api/v1/locations/{id}");
+
+    let read = send(&state, "GET", &uri, Some(&token), None, None).await;
+    let etag = read.etag.expect("etag");
+
+    // Changes to other records do not make the tag stale.
+    create_location(&state, &token, "Bakery").await;
+    let updated = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&token),
+        Some((header::IF_MATCH, &etag)),
+        Some(serde_json::json!({ "name": "Corner store" })),
+    )
+    .await;
+    assert_eq!(updated.status, StatusCode::OK);
+    let new_etag = updated.etag.expect("new etag");
+    assert_ne!(new_etag, etag);
+
+    let outdated = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&token),
+        Some((header::IF_MATCH, &etag)),
+        Some(serde_json::json!({ "name": "Overwritten" })),
+    )
+    .await;
+    assert_eq!(outdated.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(outdated.json["error"], "precondition_failed");
+
+    let outdated_delete = send(
+        &state,
+        "DELETE",
+        &uri,
+        Some(&token),
+        Some((header::IF_MATCH, &etag)),
+        None,
+    )
+    .await;
+    assert_eq!(outdated_delete.status, StatusCode::PRECONDITION_FAILED);
+
+    let reread = send(&state, "GET", &uri, Some(&token), None, None).await;
+    assert_eq!(reread.json["name"], "Corner store");
+    assert_eq!(reread.etag.as_deref(), Some(new_etag.as_str()));
+    let fresh = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&token),
+        Some((header::IF_MATCH, &new_etag)),
+        Some(serde_json::json!({ "name": "Corner shop" })),
+    )
+    .await;
+    assert_eq!(fresh.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn trash_tag_changes_after_purge() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+    let id = create_location(&state, &token, "Market").await;
+    let deleted = send(
+        &state,
+        "DELETE",
+        &format!("/api/v1/locations/{id}"),
+        Some(&token),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(deleted.status, StatusCode::NO_CONTENT);
+
+    let listed = send(&state, "GET", "/api/v1/trash", Some(&token), None, None).await;
+    assert_eq!(listed.status, StatusCode::OK);
+    assert_eq!(listed.json.as_array().expect("array").len(), 1);
+    let etag = listed.etag.expect("etag");
+
+    let report = db::trash::purge(&state.pool, i64::MAX, false)
+        .await
+        .expect("purge");
+    assert_eq!(report.locations, 1);
+    let purged = send(
+        &state,
+        "GET",
+        "/api/v1/trash",
+        Some(&token),
+        Some((header::IF_NONE_MATCH, &etag)),
+        None,
+    )
+    .await;
+    assert_eq!(purged.status, StatusCode::OK, "a purge is a change");
+    assert!(purged.json.as_array().expect("array").is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_patches_with_same_if_match_apply_once() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+    let id = create_location(&state, &token, "Market").await;
+    let uri = format!("/api/v1/locations/{id}");
+    let etag = send(&state, "GET", &uri, Some(&token), None, None)
+        .await
+        .etag
+        .expect("etag");
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for i in 0..8 {
+        let (state, token, uri, etag) = (state.clone(), token.clone(), uri.clone(), etag.clone());
+        tasks.spawn(async move {
+            send(
+                &state,
+                "PATCH",
+                &uri,
+                Some(&token),
+                Some((header::IF_MATCH, &etag)),
+                Some(serde_json::json!({ "name": format!("Name {i}") })),
+            )
+            .await
+            .status
+        });
+    }
+    let mut applied = 0;
+    while let Some(status) = tasks.join_next().await {
+        match status.expect("join") {
+            StatusCode::OK => applied += 1,
+            StatusCode::PRECONDITION_FAILED => {}
+            other => panic!("unexpected status {other}"),
+        }
+    }
+    assert_eq!(applied, 1, "only one write may pass the same If-Match");
+}
+
+#[tokio::test]
+async fn user_rename_changes_tags_in_all_their_households() {
+    let (state, _dir) = test_state().await;
+    let other = db_household(&state, "Other").await;
+    let user = db::user::get_by_email(&state.pool, "member@example.com")
+        .await
+        .expect("get user")
+        .expect("user");
+    db::household::add_member(&state.pool, other, user.id())
+        .await
+        .expect("add member");
+
+    let reply = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        None,
+        Some(serde_json::json!({
+            "email": "member@example.com",
+            "password": "secret",
+            "household_id": other,
+        })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    let token = reply.json["token"].as_str().expect("token").to_string();
+    let listed = send(&state, "GET", "/api/v1/reviews", Some(&token), None, None).await;
+    let etag = listed.etag.expect("etag");
+
+    let renamed = pocketratings::domain::user::User::new(
+        user.id(),
+        "Renamed".to_string(),
+        user.email().to_string(),
+        user.password().to_string(),
+        user.created_at(),
+        user.updated_at(),
+        None,
+    )
+    .expect("user")
+    .with_role(user.role());
+    db::household::with_household(
+        db::household::DEFAULT_HOUSEHOLD_ID,
+        db::user::update(&state.pool, &renamed),
+    )
+    .await
+    .expect("rename");
+
+    let after = send(
+        &state,
+        "GET",
+        "/api/v1/reviews",
+        Some(&token),
+        Some((header::IF_NONE_MATCH, &etag)),
+        None,
+    )
+    .await;
+    assert_eq!(
+        after.status,
+        StatusCode::OK,
+        "a rename is a change in every household of the user"
+    );
+}
+
+/// Insert a household and return its id.
+async fn db_household(state: &AppState, name: &str) -> Uuid {
+    let household = pocketratings::domain::household::Household::new(
+        Uuid::new_v4(),
+        name.to_string(),
+        1_700_000_000,
+    )
+    .expect("household");
+    db::household::insert(&state.pool, &household)
+        .await
+        .expect("insert household");
+    household.id()
+}
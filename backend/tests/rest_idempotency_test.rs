@@ -0,0 +1,275 @@
+//! Integration tests for `Idempotency-Key` on POST requests.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use pocketratings::api::{AppState, IDEMPOTENT_REPLAYED, router};
+use pocketratings::auth::jwt_keys::JwtKeys;
+use pocketratings::auth::password;
+use pocketratings::config::{Config, LogFormat};
+use pocketratings::db;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Create a state with one member (`member@example.com`, password `secret`).
+async fn test_state() -> (AppState, tempfile::TempDir) {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("rest_idempotency.db");
+    let path_str = db_path.to_str().expect("path utf-8").to_string();
+    let pool = db::create_pool(&path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrate");
+    let hash = password::hash_password("secret").expect("hash");
+    let now = 1_700_000_000i64;
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, name, email, password, role, created_at, updated_at) VALUES (?, ?, ?, ?, 'member', ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind("Member")
+    .bind("member@example.com")
+    .bind(&hash)
+    .bind(now)
+    .bind(now)
+    .execute(&pool)
+    .await
+    .expect("insert user");
+    db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+        .await
+        .expect("add member");
+    let config = Config {
+        database_path: path_str,
+        jwt_keys: JwtKeys::from_secret("test-secret"),
+        jwt_expiration_seconds: 3600,
+        jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
+        bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
+        pid_file: std::env::temp_dir()
+            .join("pocketratings-rest-idempotency-test.pid")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    (AppState { config, pool }, dir)
+}
+
+/// POST JSON and return status, whether the response was replayed, and the JSON body.
+async fn post(
+    state: &AppState,
+    uri: &str,
+    token: Option<&str>,
+    key: Option<&str>,
+    body: &serde_json::Value,
+) -> (StatusCode, bool, serde_json::Value) {
+    let mut builder = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+    if let Some(key) = key {
+        builder = builder.header("idempotency-key", key);
+    }
+    let request = builder
+        .body(Body::from(serde_json::to_vec(body).expect("json")))
+        .expect("request");
+    let response = router(state.clone())
+        .oneshot(request)
+        .await
+        .expect("service");
+    let status = response.status();
+    let replayed = response.headers().contains_key(IDEMPOTENT_REPLAYED);
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes();
+    let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, replayed, json)
+}
+
+async fn login(state: &AppState) -> String {
+    let (status, _, json) = post(
+        state,
+        "/api/v1/auth/login",
+        None,
+        None,
+        &serde_json::json!({ "email": "member@example.com", "password": "secret" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    json["token"].as_str().expect("token").to_string()
+}
+
+async fn location_count(state: &AppState) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM locations")
+        .fetch_one(&state.pool)
+        .await
+        .expect("count")
+}
+
+#[tokio::test]
+async fn retry_with_same_key_replays_original_response() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+    let body = serde_json::json!({ "name": "Market" });
+
+    let (status, replayed, first) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-1"),
+        &body,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(!replayed);
+
+    let (status, replayed, second) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-1"),
+        &body,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(replayed);
+    assert_eq!(second, first);
+    assert_eq!(location_count(&state).await, 1);
+
+    let (status, _, _) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-2"),
+        &body,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _, _) = post(&state, "/api/v1/locations", Some(&token), None, &body).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(location_count(&state).await, 3);
+}
+
+#[tokio::test]
+async fn key_reused_for_different_request_is_rejected() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+
+    let (status, _, _) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-1"),
+        &serde_json::json!({ "name": "Market" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _, json) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-1"),
+        &serde_json::json!({ "name": "Bakery" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json["message"],
+        "Idempotency-Key was already used for a different request."
+    );
+    assert_eq!(location_count(&state).await, 1);
+}
+
+#[tokio::test]
+async fn failed_request_is_not_stored() {
+    let (state, _dir) = test_state().await;
+    let token = login(&state).await;
+
+    let (status, _, _) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-1"),
+        &serde_json::json!({ "name": "" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, replayed, _) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some("key-1"),
+        &serde_json::json!({ "name": "" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(!replayed);
+
+    let (status, _, _) = post(
+        &state,
+        "/api/v1/locations",
+        Some(&token),
+        Some(""),
+        &serde_json::json!({ "name": "Market" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(location_count(&state).await, 0);
+}
+
+#[tokio::test]
+async fn same_key_runs_once_per_household() {
+    let (state, _dir) = test_state().await;
+    let other = pocketratings::domain::household::Household::new(
+        Uuid::new_v4(),
+        "Other".to_string(),
+        1_700_000_000,
+    )
+    .expect("household");
+    db::household::insert(&state.pool, &other)
+        .await
+        .expect("insert household");
+    let user = db::user::get_by_email(&state.pool, "member@example.com")
+        .await
+        .expect("get user")
+        .expect("user");
+    db::household::add_member(&state.pool, other.id(), user.id())
+        .await
+        .expect("add member");
+    let token = login(&state).await;
+    let (status, _, json) = post(
+        &state,
+        "/api/v1/auth/login",
+        None,
+        None,
+        &serde_json::json!({
+            "email": "member@example.com",
+            "password": "secret",
+            "household_id": other.id(),
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let other_token = json["token"].as_str().expect("token");
+    let body = serde_json::json!({ "name": "Market" });
+
+    for token in [token.as_str(), other_token] {
+        let (status, replayed, _) = post(
+            &state,
+            "/api/v1/locations",
+            Some(token),
+            Some("key-1"),
+            &body,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(!replayed);
+    }
+    assert_eq!(location_count(&state).await, 2);
+}
@@ -0,0 +1,222 @@
+//! Integration tests for role-based access: admins, members and read-only guests.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use pocketratings::api::{AppState, router};
+use pocketratings::auth::jwt_keys::JwtKeys;
+use pocketratings::auth::password;
+use pocketratings::config::{Config, LogFormat};
+use pocketratings::db;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Create a state with one user per role (`<role>@example.com`, password `secret`).
+async fn test_state() -> (AppState, tempfile::TempDir) {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("rest_roles.db");
+    let path_str = db_path.to_str().expect("path utf-8").to_string();
+    let pool = db::create_pool(&path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrate");
+    let hash = password::hash_password("secret").expect("hash");
+    let now = 1_700_000_000i64;
+    for role in ["admin", "member", "guest"] {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO users (id, name, email, password, role, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id.to_string())
+        .bind(role)
+        .bind(format!("{role}@example.com"))
+        .bind(&hash)
+        .bind(role)
+        .bind(now)
+        .bind(now)
+        .execute(&pool)
+        .await
+        .expect("insert user");
+        db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+            .await
+            .expect("add member");
+    }
+    let config = Config {
+        database_path: path_str,
+        jwt_keys: JwtKeys::from_secret("test-secret"),
+        jwt_expiration_seconds: 3600,
+        jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
+        bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
+        pid_file: std::env::temp_dir()
+            .join("pocketratings-rest-roles-test.pid")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    (AppState { config, pool }, dir)
+}
+
+/// Send a request and return status and JSON body (`Null` when empty or not JSON).
+async fn send(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    use http_body_util::BodyExt;
+
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+    let request = match body {
+        Some(json) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json).expect("json")))
+            .expect("request"),
+        None => builder.body(Body::empty()).expect("request"),
+    };
+    let response = router(state.clone())
+        .oneshot(request)
+        .await
+        .expect("service");
+    let status = response.status();
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes();
+    let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, json)
+}
+
+async fn login(state: &AppState, role: &str) -> String {
+    let (status, json) = send(
+        state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": format!("{role}@example.com"), "password": "secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    json["token"].as_str().expect("token").to_string()
+}
+
+#[tokio::test]
+async fn guests_can_read_but_not_write() {
+    let (state, _dir) = test_state().await;
+    let guest = login(&state, "guest").await;
+
+    let (status, me) = send(&state, "GET", "/api/v1/me", Some(&guest), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["role"], "guest");
+    let (status, _) = send(&state, "GET", "/api/v1/categories", Some(&guest), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/locations",
+        Some(&guest),
+        Some(serde_json::json!({ "name": "Corner shop" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&state, "POST", "/api/v1/auth/logout", Some(&guest), None).await;
+    assert_eq!(
+        status,
+        StatusCode::NO_CONTENT,
+        "guests can still manage their own session"
+    );
+}
+
+#[tokio::test]
+async fn only_admins_manage_categories() {
+    let (state, _dir) = test_state().await;
+    let member = login(&state, "member").await;
+    let admin = login(&state, "admin").await;
+    let category = serde_json::json!({ "name": "Snacks" });
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/categories",
+        Some(&member),
+        Some(category.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, created) = send(
+        &state,
+        "POST",
+        "/api/v1/categories",
+        Some(&admin),
+        Some(category),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().expect("id");
+
+    let (status, _) = send(
+        &state,
+        "DELETE",
+        &format!("/api/v1/categories/{id}"),
+        Some(&member),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        &state,
+        "GET",
+        &format!("/api/v1/categories/{id}"),
+        Some(&member),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn only_admins_delete_permanently() {
+    let (state, _dir) = test_state().await;
+    let member = login(&state, "member").await;
+    let admin = login(&state, "admin").await;
+
+    let (status, created) = send(
+        &state,
+        "POST",
+        "/api/v1/locations",
+        Some(&member),
+        Some(serde_json::json!({ "name": "Corner shop" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().expect("id");
+    let uri = format!("/api/v1/locations/{id}?force=true");
+
+    let (status, _) = send(&state, "DELETE", &uri, Some(&member), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    for encoded in [
+        "force=%74rue",
+        "%66orce=true",
+        "force=TRUE",
+        "other=1&force=1",
+    ] {
+        let (status, _) = send(
+            &state,
+            "DELETE",
+            &format!("/api/v1/locations/{id}?{encoded}"),
+            Some(&member),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{encoded}");
+    }
+    let (status, _) = send(&state, "DELETE", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,432 @@
+//! Integration tests for user management: admin-only `/api/v1/users` and `PATCH /api/v1/me`.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use pocketratings::api::{AppState, router};
+use pocketratings::auth::jwt_keys::JwtKeys;
+use pocketratings::auth::password;
+use pocketratings::config::{Config, LogFormat};
+use pocketratings::db;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Create a state with one user per role (`<role>@example.com`, password `secret`).
+async fn test_state() -> (AppState, tempfile::TempDir) {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("rest_users.db");
+    let path_str = db_path.to_str().expect("path utf-8").to_string();
+    let pool = db::create_pool(&path_str).await.expect("pool");
+    db::run_migrations(&pool).await.expect("migrate");
+    let hash = password::hash_password("secret").expect("hash");
+    let now = 1_700_000_000i64;
+    for role in ["admin", "member", "guest"] {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO users (id, name, email, password, role, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id.to_string())
+        .bind(role)
+        .bind(format!("{role}@example.com"))
+        .bind(&hash)
+        .bind(role)
+        .bind(now)
+        .bind(now)
+        .execute(&pool)
+        .await
+        .expect("insert user");
+        db::household::add_member(&pool, db::household::DEFAULT_HOUSEHOLD_ID, id)
+            .await
+            .expect("add member");
+    }
+    let config = Config {
+        database_path: path_str,
+        jwt_keys: JwtKeys::from_secret("test-secret"),
+        jwt_expiration_seconds: 3600,
+        jwt_refresh_threshold_seconds: 600,
+        refresh_token_expiration_seconds: 86400,
+        idempotency_window_seconds: 86400,
+        trusted_proxies: Vec::new(),
+        bind: "127.0.0.1:3099".to_string(),
+        metrics_bind: None,
+        log_format: LogFormat::Text,
+        pid_file: std::env::temp_dir()
+            .join("pocketratings-rest-users-test.pid")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    (AppState { config, pool }, dir)
+}
+
+/// Send a request and return status and JSON body (`Null` when empty or not JSON).
+async fn send(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    use http_body_util::BodyExt;
+
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+    let request = match body {
+        Some(json) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json).expect("json")))
+            .expect("request"),
+        None => builder.body(Body::empty()).expect("request"),
+    };
+    let response = router(state.clone())
+        .oneshot(request)
+        .await
+        .expect("service");
+    let status = response.status();
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes();
+    let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, json)
+}
+
+async fn login(state: &AppState, role: &str) -> String {
+    let (status, json) = send(
+        state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": format!("{role}@example.com"), "password": "secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    json["token"].as_str().expect("token").to_string()
+}
+
+#[tokio::test]
+async fn only_admins_manage_users() {
+    let (state, _dir) = test_state().await;
+    let member = login(&state, "member").await;
+    let guest = login(&state, "guest").await;
+    let admin = login(&state, "admin").await;
+
+    for token in [&member, &guest] {
+        let (status, _) = send(&state, "GET", "/api/v1/users", Some(token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/users",
+        Some(&member),
+        Some(serde_json::json!({ "name": "Eve", "email": "eve@example.com", "password": "x" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, users) = send(&state, "GET", "/api/v1/users", Some(&admin), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(users.as_array().expect("array").len(), 3);
+}
+
+#[tokio::test]
+async fn admin_creates_updates_and_deletes_a_user() {
+    let (state, _dir) = test_state().await;
+    let admin = login(&state, "admin").await;
+
+    let (status, created) = send(
+        &state,
+        "POST",
+        "/api/v1/users",
+        Some(&admin),
+        Some(serde_json::json!({
+            "name": "Kid",
+            "email": "kid@example.com",
+            "password": "kid-secret",
+            "role": "guest",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["role"], "guest");
+    let uri = format!("/api/v1/users/{}", created["id"].as_str().expect("id"));
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(serde_json::json!({ "email": "kid@example.com", "password": "kid-secret" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "new user can log in");
+
+    let (status, updated) = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&admin),
+        Some(serde_json::json!({ "role": "member" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["role"], "member");
+    assert_eq!(updated["name"], "Kid");
+
+    let (status, _) = send(&state, "DELETE", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&state, "GET", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn users_with_reviews_cannot_be_deleted() {
+    let (state, _dir) = test_state().await;
+    let admin = login(&state, "admin").await;
+    let member = login(&state, "member").await;
+
+    let (_, category) = send(
+        &state,
+        "POST",
+        "/api/v1/categories",
+        Some(&admin),
+        Some(serde_json::json!({ "name": "Snacks" })),
+    )
+    .await;
+    let (_, product) = send(
+        &state,
+        "POST",
+        "/api/v1/products",
+        Some(&member),
+        Some(serde_json::json!({
+            "name": "Crisps",
+            "brand": "Crunch",
+            "category_id": category["id"],
+        })),
+    )
+    .await;
+    let (status, review) = send(
+        &state,
+        "POST",
+        "/api/v1/reviews",
+        Some(&member),
+        Some(serde_json::json!({ "product_id": product["id"], "rating": 4 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, users) = send(&state, "GET", "/api/v1/users", Some(&admin), None).await;
+    let member_id = users
+        .as_array()
+        .expect("array")
+        .iter()
+        .find(|u| u["role"] == "member")
+        .and_then(|u| u["id"].as_str())
+        .expect("member id")
+        .to_string();
+    assert_eq!(review["user"]["id"], member_id);
+
+    for uri in [
+        format!("/api/v1/users/{member_id}"),
+        format!("/api/v1/users/{member_id}?force=true"),
+    ] {
+        let (status, _) = send(&state, "DELETE", &uri, Some(&admin), None).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn admins_cannot_delete_or_demote_themselves() {
+    let (state, _dir) = test_state().await;
+    let admin = login(&state, "admin").await;
+    let (_, me) = send(&state, "GET", "/api/v1/me", Some(&admin), None).await;
+    let uri = format!("/api/v1/users/{}", me["user_id"].as_str().expect("id"));
+
+    let (status, _) = send(&state, "DELETE", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&admin),
+        Some(serde_json::json!({ "role": "member" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn any_user_can_update_own_name_and_email() {
+    let (state, _dir) = test_state().await;
+    let guest = login(&state, "guest").await;
+
+    let (status, me) = send(
+        &state,
+        "PATCH",
+        "/api/v1/me",
+        Some(&guest),
+        Some(serde_json::json!({ "name": "Grandma", "email": "grandma@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["name"], "Grandma");
+    assert_eq!(me["email"], "grandma@example.com");
+    assert_eq!(me["role"], "guest");
+
+    let (status, _) = send(
+        &state,
+        "PATCH",
+        "/api/v1/me",
+        Some(&guest),
+        Some(serde_json::json!({ "email": "member@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "email of another user");
+    let (status, _) = send(
+        &state,
+        "PATCH",
+        "/api/v1/me",
+        Some(&guest),
+        Some(serde_json::json!({ "name": "" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &state,
+        "PATCH",
+        "/api/v1/me",
+        Some(&guest),
+        Some(serde_json::json!({ "role": "admin" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, me) = send(&state, "GET", "/api/v1/me", Some(&guest), None).await;
+    assert_eq!(me["role"], "guest", "role is not changed through /me");
+}
+
+#[tokio::test]
+async fn admins_only_manage_users_of_their_household() {
+    let (state, _dir) = test_state().await;
+    let admin = login(&state, "admin").await;
+    let other = pocketratings::domain::household::Household::new(
+        Uuid::new_v4(),
+        "Other".to_string(),
+        1_700_000_000,
+    )
+    .expect("household");
+    db::household::insert(&state.pool, &other)
+        .await
+        .expect("insert household");
+    let outsider = pocketratings::domain::user::User::new(
+        Uuid::new_v4(),
+        "Olga".to_string(),
+        "olga@example.com".to_string(),
+        password::hash_password("secret").expect("hash"),
+        1_700_000_000,
+        1_700_000_000,
+        None,
+    )
+    .expect("user");
+    db::user::insert(&state.pool, &outsider)
+        .await
+        .expect("insert user");
+    db::household::add_member(&state.pool, other.id(), outsider.id())
+        .await
+        .expect("add member");
+
+    let (status, listed) = send(&state, "GET", "/api/v1/users", Some(&admin), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        !listed
+            .as_array()
+            .expect("array")
+            .iter()
+            .any(|u| u["email"] == "olga@example.com")
+    );
+
+    let uri = format!("/api/v1/users/{}", outsider.id());
+    let (status, _) = send(&state, "GET", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&admin),
+        Some(serde_json::json!({ "role": "admin" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&state, "DELETE", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn admins_cannot_manage_users_shared_with_other_households() {
+    let (state, _dir) = test_state().await;
+    let admin = login(&state, "admin").await;
+    let other = pocketratings::domain::household::Household::new(
+        Uuid::new_v4(),
+        "Other".to_string(),
+        1_700_000_000,
+    )
+    .expect("household");
+    db::household::insert(&state.pool, &other)
+        .await
+        .expect("insert household");
+    let shared = db::user::get_by_email(&state.pool, "member@example.com")
+        .await
+        .expect("get user")
+        .expect("member");
+    db::household::add_member(&state.pool, other.id(), shared.id())
+        .await
+        .expect("add member");
+
+    let uri = format!("/api/v1/users/{}", shared.id());
+    let (status, _) = send(&state, "GET", &uri, Some(&admin), None).await;
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "members of the household are visible"
+    );
+    let (status, json) = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&admin),
+        Some(serde_json::json!({ "role": "guest" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        json["message"],
+        "This user also belongs to a household you do not administer."
+    );
+    let (status, _) = send(&state, "DELETE", &uri, Some(&admin), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let unchanged = db::user::get_by_id(&state.pool, shared.id(), false)
+        .await
+        .expect("get user")
+        .expect("still active");
+    assert_eq!(unchanged.role(), shared.role());
+
+    let admin_id = db::user::get_by_email(&state.pool, "admin@example.com")
+        .await
+        .expect("get user")
+        .expect("admin")
+        .id();
+    db::household::add_member(&state.pool, other.id(), admin_id)
+        .await
+        .expect("add member");
+    let (status, _) = send(
+        &state,
+        "PATCH",
+        &uri,
+        Some(&admin),
+        Some(serde_json::json!({ "role": "guest" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "admins of both households may");
+}
@@ -122,7 +122,7 @@ async fn review_list_with_filters_and_include_deleted() {
         .expect("list");
     assert_eq!(by_user.len(), 1);
 
-    db::review::soft_delete(&pool, r1.id())
+    db::review::soft_delete(&pool, r1.id(), None)
         .await
         .expect("soft_delete");
 
@@ -229,7 +229,7 @@ async fn review_list_with_relations_returns_relation_names_and_filters() {
         .expect("list_with_relations");
     assert_eq!(by_user.len(), 1);
 
-    db::review::soft_delete(&pool, r1.id())
+    db::review::soft_delete(&pool, r1.id(), None)
         .await
         .expect("soft_delete");
     let active = db::review::list_with_relations(&pool, None, None, false)
@@ -317,7 +317,7 @@ async fn review_soft_delete_sets_deleted_at_and_excludes_from_get_by_id() {
     .expect("valid");
     db::review::insert(&pool, &review).await.expect("insert");
 
-    db::review::soft_delete(&pool, review_id)
+    db::review::soft_delete(&pool, review_id, None)
         .await
         .expect("soft_delete");
 
@@ -583,7 +583,7 @@ async fn review_list_cache_invalidated_after_soft_delete() {
         .expect("list_with_relations");
     assert_eq!(all.len(), 1);
 
-    db::review::soft_delete(&pool, review_id)
+    db::review::soft_delete(&pool, review_id, None)
         .await
         .expect("soft_delete");
 
@@ -0,0 +1,165 @@
+//! Integration tests for spending statistics DB functions.
+
+use pocketratings::db;
+use pocketratings::db::stats::{SpendingFilter, SpendingGroup};
+use pocketratings::domain::category::Category;
+use pocketratings::domain::location::Location;
+use pocketratings::domain::product::Product;
+use pocketratings::domain::product_variation::ProductVariation;
+use pocketratings::domain::purchase::Purchase;
+use pocketratings::domain::user::User;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+async fn setup_pool() -> (tempfile::TempDir, sqlx::SqlitePool) {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let db_path = dir.path().join("stats.db");
+    let db_path_str = db_path.to_str().expect("path UTF-8");
+    let pool = db::create_pool(db_path_str).await.expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    (dir, pool)
+}
+
+async fn insert_user(pool: &sqlx::SqlitePool, name: &str, email: &str) -> Uuid {
+    let id = Uuid::new_v4();
+    let user = User::new(
+        id,
+        name.to_string(),
+        email.to_string(),
+        "hash".to_string(),
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid user");
+    db::user::insert(pool, &user).await.expect("insert user");
+    id
+}
+
+async fn insert_location(pool: &sqlx::SqlitePool, name: &str) -> Uuid {
+    let id = Uuid::new_v4();
+    let location = Location::new(id, name.to_string(), None).expect("valid");
+    db::location::insert(pool, &location)
+        .await
+        .expect("insert location");
+    id
+}
+
+/// Inserts a category, product and variation; returns `(product_id, variation_id)`.
+async fn insert_product(pool: &sqlx::SqlitePool) -> (Uuid, Uuid) {
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "C".to_string(), 1_000, 1_000, None).expect("valid");
+    db::category::insert(pool, &cat)
+        .await
+        .expect("insert category");
+    let product_id = Uuid::new_v4();
+    let product = Product::new(
+        product_id,
+        cat_id,
+        "Brand".to_string(),
+        "Product".to_string(),
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(pool, &product)
+        .await
+        .expect("insert product");
+    let var_id = Uuid::new_v4();
+    let var = ProductVariation::new(var_id, product_id, "", "none", None, 1_000, 1_000, None)
+        .expect("valid variation");
+    db::product_variation::insert(pool, &var)
+        .await
+        .expect("insert variation");
+    (product_id, var_id)
+}
+
+async fn insert_purchase(
+    pool: &sqlx::SqlitePool,
+    user_id: Uuid,
+    (product_id, variation_id): (Uuid, Uuid),
+    location_id: Uuid,
+    price: &str,
+) -> Uuid {
+    let id = Uuid::new_v4();
+    let purchase = Purchase::new(
+        id,
+        user_id,
+        product_id,
+        variation_id,
+        location_id,
+        1,
+        price.parse().expect("decimal"),
+        2_000,
+        None,
+    )
+    .expect("valid purchase");
+    db::purchase::insert(pool, &purchase)
+        .await
+        .expect("insert purchase");
+    id
+}
+
+#[tokio::test]
+async fn spending_groups_by_location_and_user_excluding_deleted() {
+    let (_dir, pool) = setup_pool().await;
+    let alice = insert_user(&pool, "Alice", "alice@example.com").await;
+    let bob = insert_user(&pool, "Bob", "bob@example.com").await;
+    let market = insert_location(&pool, "Market").await;
+    let corner = insert_location(&pool, "Corner").await;
+    let product = insert_product(&pool).await;
+    insert_purchase(&pool, alice, product, market, "3.00").await;
+    insert_purchase(&pool, bob, product, market, "1.00").await;
+    insert_purchase(&pool, bob, product, corner, "5.00").await;
+    let deleted = insert_purchase(&pool, alice, product, corner, "100.00").await;
+    db::purchase::soft_delete(&pool, deleted, None)
+        .await
+        .expect("soft delete");
+
+    let by_location =
+        db::stats::spending(&pool, &SpendingFilter::default(), SpendingGroup::Location)
+            .await
+            .expect("spending");
+    assert_eq!(by_location.total, Decimal::from(9));
+    assert_eq!(by_location.purchase_count, 3);
+    let labels: Vec<&str> = by_location
+        .groups
+        .iter()
+        .map(|g| g.label.as_str())
+        .collect();
+    assert_eq!(labels, ["Corner", "Market"], "largest total first");
+    assert_eq!(by_location.groups[1].purchase_count, 2);
+
+    let filter = SpendingFilter {
+        location_id: Some(market),
+        ..SpendingFilter::default()
+    };
+    let by_user = db::stats::spending(&pool, &filter, SpendingGroup::User)
+        .await
+        .expect("spending");
+    assert_eq!(by_user.total, Decimal::from(4));
+    assert_eq!(by_user.groups[0].key, alice.to_string());
+    assert_eq!(by_user.groups[0].label, "Alice");
+    assert_eq!(by_user.groups[1].total, Decimal::from(1));
+}
+
+#[tokio::test]
+async fn spending_is_empty_without_purchases_in_range() {
+    let (_dir, pool) = setup_pool().await;
+    let user = insert_user(&pool, "Alice", "alice@example.com").await;
+    let location = insert_location(&pool, "Market").await;
+    let product = insert_product(&pool).await;
+    insert_purchase(&pool, user, product, location, "3.00").await;
+
+    let filter = SpendingFilter {
+        from_ts: Some(3_000),
+        ..SpendingFilter::default()
+    };
+    let report = db::stats::spending(&pool, &filter, SpendingGroup::Month)
+        .await
+        .expect("spending");
+    assert_eq!(report.total, Decimal::ZERO);
+    assert_eq!(report.purchase_count, 0);
+    assert!(report.groups.is_empty());
+}
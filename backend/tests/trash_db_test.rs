@@ -0,0 +1,322 @@
+//! Integration tests for trash listing and purge DB functions.
+
+use pocketratings::db;
+use pocketratings::db::page::PageRequest;
+use pocketratings::db::trash::TrashKind;
+use pocketratings::domain::category::Category;
+use pocketratings::domain::location::Location;
+use pocketratings::domain::product::Product;
+use pocketratings::domain::product_variation::ProductVariation;
+use pocketratings::domain::purchase::Purchase;
+use pocketratings::domain::review::Review;
+use pocketratings::domain::user::User;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+const DAY: i64 = 86_400;
+
+async fn test_pool(dir: &tempfile::TempDir) -> sqlx::SqlitePool {
+    let db_path = dir.path().join("trash_test.db");
+    let pool = db::create_pool(db_path.to_str().expect("path UTF-8"))
+        .await
+        .expect("create pool");
+    db::run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+/// One user, category "Dairy", product "Milk" with a default variation, location "Store".
+/// Returns (`user_id`, `category_id`, `product_id`, `variation_id`, `location_id`).
+async fn seed(pool: &sqlx::SqlitePool) -> (Uuid, Uuid, Uuid, Uuid, Uuid) {
+    let now = 1_000_i64;
+    let user_id = Uuid::new_v4();
+    let user = User::new(
+        user_id,
+        "Alice".to_string(),
+        "alice@example.com".to_string(),
+        "hash".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid user");
+    db::user::insert(pool, &user).await.expect("insert user");
+
+    let cat_id = Uuid::new_v4();
+    let cat = Category::new(cat_id, None, "Dairy".to_string(), now, now, None).expect("valid");
+    db::category::insert(pool, &cat)
+        .await
+        .expect("insert category");
+
+    let product_id = Uuid::new_v4();
+    let product = Product::new(
+        product_id,
+        cat_id,
+        "Brand".to_string(),
+        "Milk".to_string(),
+        now,
+        now,
+        None,
+    )
+    .expect("valid");
+    db::product::insert(pool, &product)
+        .await
+        .expect("insert product");
+
+    let variation_id = Uuid::new_v4();
+    let variation =
+        ProductVariation::new(variation_id, product_id, "", "none", None, now, now, None)
+            .expect("valid variation");
+    db::product_variation::insert(pool, &variation)
+        .await
+        .expect("insert variation");
+
+    let location_id = Uuid::new_v4();
+    let location = Location::new(location_id, "Store".to_string(), None).expect("valid");
+    db::location::insert(pool, &location)
+        .await
+        .expect("insert location");
+
+    (user_id, cat_id, product_id, variation_id, location_id)
+}
+
+/// Set the deletion time of a row to `days` ago.
+async fn age_deletion(pool: &sqlx::SqlitePool, table: &str, id: Uuid, days: i64) {
+    let sql = format!("UPDATE {table} SET deleted_at = ? WHERE id = ?");
+    sqlx::query(&sql)
+        .bind(chrono::Utc::now().timestamp() - days * DAY)
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .expect("age deletion");
+}
+
+async fn count(pool: &sqlx::SqlitePool, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+        .fetch_one(pool)
+        .await
+        .expect("count")
+}
+
+#[tokio::test]
+async fn trash_list_page_returns_newest_deletion_first_with_deleting_user() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+    let (user_id, _, product_id, _, location_id) = seed(&pool).await;
+
+    db::location::soft_delete(&pool, location_id, Some(user_id))
+        .await
+        .expect("soft delete location");
+    age_deletion(&pool, "locations", location_id, 2).await;
+    db::product::soft_delete(&pool, product_id, None)
+        .await
+        .expect("soft delete product");
+
+    let page = db::trash::list_page(&pool, None, &PageRequest::default())
+        .await
+        .expect("list_page");
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.items[0].kind, TrashKind::Product);
+    assert_eq!(page.items[0].deleted_by, None);
+    assert_eq!(page.items[1].kind, TrashKind::Location);
+    assert_eq!(page.items[1].label, "Store");
+    assert_eq!(page.items[1].deleted_by, Some(user_id));
+    assert_eq!(page.items[1].deleted_by_name.as_deref(), Some("Alice"));
+
+    let first = db::trash::list_page(
+        &pool,
+        None,
+        &PageRequest {
+            limit: Some(1),
+            after: None,
+        },
+    )
+    .await
+    .expect("first page");
+    let cursor = first.next_cursor.expect("next cursor");
+    let second = db::trash::list_page(
+        &pool,
+        None,
+        &PageRequest {
+            limit: Some(1),
+            after: db::page::Cursor::decode(&cursor),
+        },
+    )
+    .await
+    .expect("second page");
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].id, location_id);
+    assert!(second.next_cursor.is_none());
+
+    let only_products =
+        db::trash::list_page(&pool, Some(TrashKind::Product), &PageRequest::default())
+            .await
+            .expect("filtered");
+    assert_eq!(only_products.items.len(), 1);
+    assert_eq!(only_products.items[0].id, product_id);
+}
+
+#[tokio::test]
+async fn restore_clears_deleted_by() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+    let (user_id, _, _, _, location_id) = seed(&pool).await;
+
+    db::location::soft_delete(&pool, location_id, Some(user_id))
+        .await
+        .expect("soft delete");
+    db::location::restore(&pool, location_id)
+        .await
+        .expect("restore");
+
+    let deleted_by: Option<String> =
+        sqlx::query_scalar("SELECT deleted_by FROM locations WHERE id = ?")
+            .bind(location_id.to_string())
+            .fetch_one(&pool)
+            .await
+            .expect("deleted_by");
+    assert!(deleted_by.is_none());
+}
+
+#[tokio::test]
+async fn purge_removes_old_rows_in_dependency_order() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+    let (user_id, cat_id, product_id, variation_id, location_id) = seed(&pool).await;
+
+    let purchase = Purchase::new(
+        Uuid::new_v4(),
+        user_id,
+        product_id,
+        variation_id,
+        location_id,
+        1,
+        Decimal::from(2),
+        1_000,
+        None,
+    )
+    .expect("valid purchase");
+    db::purchase::insert(&pool, &purchase)
+        .await
+        .expect("insert purchase");
+    let review = Review::new(
+        Uuid::new_v4(),
+        product_id,
+        user_id,
+        Decimal::from(4),
+        None,
+        1_000,
+        1_000,
+        None,
+    )
+    .expect("valid review");
+    db::review::insert(&pool, &review)
+        .await
+        .expect("insert review");
+
+    db::purchase::soft_delete(&pool, purchase.id(), None)
+        .await
+        .expect("delete purchase");
+    db::review::soft_delete(&pool, review.id(), None)
+        .await
+        .expect("delete review");
+    // Products and locations with purchases cannot be soft-deleted; age_deletion marks them
+    // (and the category) deleted directly.
+    for (table, id) in [
+        ("purchases", purchase.id()),
+        ("reviews", review.id()),
+        ("products", product_id),
+        ("categories", cat_id),
+        ("locations", location_id),
+    ] {
+        age_deletion(&pool, table, id, 100).await;
+    }
+    let before = chrono::Utc::now().timestamp() - 90 * DAY;
+
+    let dry = db::trash::purge(&pool, before, true)
+        .await
+        .expect("dry run");
+    assert_eq!(dry.purchases, 1);
+    assert_eq!(dry.reviews, 1);
+    assert_eq!(dry.variations, 1);
+    assert_eq!(dry.products, 1);
+    assert_eq!(dry.categories, 1);
+    assert_eq!(dry.locations, 1);
+    assert_eq!(count(&pool, "products").await, 1, "dry run keeps rows");
+    let hard_deletes = "audit_log WHERE action = 'hard_delete'";
+    assert_eq!(
+        count(&pool, hard_deletes).await,
+        0,
+        "dry run records nothing"
+    );
+
+    let report = db::trash::purge(&pool, before, false).await.expect("purge");
+    assert_eq!(report, dry);
+    let recorded: Vec<(String, String)> = sqlx::query_as(
+        "SELECT entity_type, entity_id FROM audit_log WHERE action = 'hard_delete' ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("audit entries");
+    assert_eq!(
+        recorded,
+        vec![
+            ("review".to_string(), review.id().to_string()),
+            ("purchase".to_string(), purchase.id().to_string()),
+            ("variation".to_string(), variation_id.to_string()),
+            ("product".to_string(), product_id.to_string()),
+            ("category".to_string(), cat_id.to_string()),
+            ("location".to_string(), location_id.to_string()),
+        ],
+        "every purged row is recorded"
+    );
+    for table in [
+        "purchases",
+        "reviews",
+        "product_variations",
+        "products",
+        "products_fts",
+        "categories",
+        "locations",
+    ] {
+        assert_eq!(count(&pool, table).await, 0, "{table} should be empty");
+    }
+}
+
+#[tokio::test]
+async fn purge_keeps_recent_deletions_and_rows_they_reference() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pool = test_pool(&dir).await;
+    let (user_id, cat_id, product_id, variation_id, location_id) = seed(&pool).await;
+
+    let purchase = Purchase::new(
+        Uuid::new_v4(),
+        user_id,
+        product_id,
+        variation_id,
+        location_id,
+        1,
+        Decimal::from(2),
+        1_000,
+        None,
+    )
+    .expect("valid purchase");
+    db::purchase::insert(&pool, &purchase)
+        .await
+        .expect("insert purchase");
+    db::purchase::soft_delete(&pool, purchase.id(), None)
+        .await
+        .expect("delete purchase");
+    // A product with purchases cannot be soft-deleted; age_deletion marks it and its category.
+    age_deletion(&pool, "products", product_id, 100).await;
+    age_deletion(&pool, "categories", cat_id, 100).await;
+
+    let before = chrono::Utc::now().timestamp() - 90 * DAY;
+    let report = db::trash::purge(&pool, before, false).await.expect("purge");
+    assert_eq!(
+        report.total(),
+        0,
+        "recent purchase still references the product"
+    );
+    assert_eq!(count(&pool, "products").await, 1);
+    assert_eq!(count(&pool, "categories").await, 1);
+}
@@ -23,6 +23,7 @@ import {
   listPurchases,
   listReviews,
   login,
+  loginTotp,
   updateCategory,
   updateLocation,
   updateProduct,
@@ -150,6 +151,26 @@ describe('api', () => {
     await expect(login('u@example.com', 'wrong')).rejects.toThrow('Invalid email or password');
   });
 
+  it('loginTotp sends the challenge and code to /api/v1/auth/login/totp', async () => {
+    const mockFetch = vi.mocked(fetch);
+    mockFetch.mockResolvedValueOnce(
+      new Response(JSON.stringify({ token: 'jwt-here', refresh_token: 'refresh' }), {
+        status: 200,
+        headers: { 'Content-Type': 'application/json' }
+      })
+    );
+
+    const result = await loginTotp('challenge-1', '123456');
+
+    expect(result).toEqual({ token: 'jwt-here', refresh_token: 'refresh' });
+    const [url, init] = mockFetch.mock.calls[0];
+    expect(String(url)).toContain('/api/v1/auth/login/totp');
+    expect(JSON.parse(init?.body as string)).toEqual({
+      challenge: 'challenge-1',
+      code: '123456'
+    });
+  });
+
   it('when response has X-New-Token, setToken is called with that value', async () => {
     const mockFetch = vi.mocked(fetch);
     mockFetch.mockResolvedValueOnce(
@@ -1,4 +1,4 @@
-import { getToken, setToken, clearToken } from '$lib/auth';
+import { getToken, setToken, clearToken, getRefreshToken, setRefreshToken } from '$lib/auth';
 import type {
   Category,
   Location,
@@ -44,7 +44,26 @@ export function isValidUuid(value: string): boolean {
   return UUID_RE.test(value);
 }
 
-/** Fetch with Bearer token and X-New-Token handling. On 401, clears token and redirects to login. */
+/** Exchange the stored refresh token for a new token pair. Returns the new JWT, or null when that fails. */
+async function refreshSession(): Promise<string | null> {
+  const refreshToken = getRefreshToken();
+  if (!refreshToken) return null;
+  const res = await fetch(ensureAbsolute('/api/v1/auth/refresh'), {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ refresh_token: refreshToken })
+  });
+  if (!res.ok) return null;
+  const data = (await res.json()) as LoginResponse;
+  setToken(data.token);
+  setRefreshToken(data.refresh_token);
+  return data.token;
+}
+
+/**
+ * Fetch with Bearer token and X-New-Token handling. On 401, renews the session with the refresh token and retries
+ * once; if that fails, clears token and redirects to login.
+ */
 export async function apiFetch(path: string, init: RequestInit = {}): Promise<Response> {
   const url = ensureAbsolute(path);
   const token = getToken();
@@ -55,13 +74,20 @@ export async function apiFetch(path: string, init: RequestInit = {}): Promise<Re
   if (!headers.has('Content-Type') && init.body != null && typeof init.body === 'string') {
     headers.set('Content-Type', 'application/json');
   }
-  const res = await fetch(url, { ...init, headers });
+  let res = await fetch(url, { ...init, headers });
   if (res.status === 401 && token && typeof window !== 'undefined') {
-    clearToken();
-    const { goto } = await import('$app/navigation');
-    const { resolve } = await import('$app/paths');
-    goto(`${resolve('/login')}?expired=1`);
-    return res;
+    const renewed = await refreshSession();
+    if (renewed) {
+      headers.set('Authorization', `Bearer ${renewed}`);
+      res = await fetch(url, { ...init, headers });
+    }
+    if (res.status === 401) {
+      clearToken();
+      const { goto } = await import('$app/navigation');
+      const { resolve } = await import('$app/paths');
+      goto(`${resolve('/login')}?expired=1`);
+      return res;
+    }
   }
   const newToken = res.headers.get('X-New-Token');
   if (newToken) {
@@ -146,15 +172,42 @@ export async function apiDelete(path: string): Promise<void> {
 
 export interface LoginResponse {
   token: string;
+  refresh_token: string;
+}
+
+/** Login answer for users with two-factor authentication: redeem the challenge with `loginTotp`. */
+export interface TwoFactorChallenge {
+  two_factor_required: true;
+  challenge: string;
+  expires_at: number;
+}
+
+export function isTwoFactorChallenge(
+  result: LoginResponse | TwoFactorChallenge
+): result is TwoFactorChallenge {
+  return 'two_factor_required' in result && result.two_factor_required === true;
+}
+
+export function login(email: string, password: string): Promise<LoginResponse | TwoFactorChallenge> {
+  return apiPost<LoginResponse | TwoFactorChallenge>('/api/v1/auth/login', { email, password });
+}
+
+/** Second login step: redeem the challenge with an authenticator or recovery code. */
+export function loginTotp(challenge: string, code: string): Promise<LoginResponse> {
+  return apiPost<LoginResponse>('/api/v1/auth/login/totp', { challenge, code });
 }
 
-export function login(email: string, password: string): Promise<LoginResponse> {
-  return apiPost<LoginResponse>('/api/v1/auth/login', { email, password });
+/** Revoke the current session on the server. */
+export async function logout(): Promise<void> {
+  await apiFetch('/api/v1/auth/logout', { method: 'POST' });
 }
 
 export interface MeResponse {
   user_id: string;
   name: string;
+  email: string;
+  role: 'admin' | 'member' | 'guest';
+  household: { id: string; name: string };
 }
 
 export function me(): Promise<MeResponse> {
@@ -1,6 +1,7 @@
 import { get as getStoreValue, writable } from 'svelte/store';
 
 const TOKEN_KEY = 'pocketratings_token';
+const REFRESH_TOKEN_KEY = 'pocketratings_refresh_token';
 
 /** Reactive token store. Updated by setToken/clearToken; sync from localStorage on client init. */
 export const token = writable<string | null>(null);
@@ -25,9 +26,22 @@ export function setToken(newToken: string): void {
   token.set(newToken);
 }
 
-/** Remove JWT (e.g. on logout). */
+/** Get stored refresh token (used to renew the JWT when it expires). */
+export function getRefreshToken(): string | null {
+  if (typeof window === 'undefined') return null;
+  return localStorage.getItem(REFRESH_TOKEN_KEY);
+}
+
+/** Store refresh token and persist to localStorage. */
+export function setRefreshToken(newToken: string): void {
+  if (typeof window === 'undefined') return;
+  localStorage.setItem(REFRESH_TOKEN_KEY, newToken);
+}
+
+/** Remove JWT and refresh token (e.g. on logout). */
 export function clearToken(): void {
   if (typeof window === 'undefined') return;
   localStorage.removeItem(TOKEN_KEY);
+  localStorage.removeItem(REFRESH_TOKEN_KEY);
   token.set(null);
 }
@@ -5,11 +5,17 @@ import LoginPage from '../../src/routes/login/+page.svelte';
 
 const mocks = vi.hoisted(() => ({
   goto: vi.fn(),
-  login: vi.fn()
+  login: vi.fn(),
+  loginTotp: vi.fn()
 }));
 
 vi.mock('$app/navigation', () => ({ goto: mocks.goto }));
-vi.mock('$lib/api', () => ({ login: mocks.login }));
+vi.mock('$lib/api', () => ({
+  isTwoFactorChallenge: (result: { two_factor_required?: boolean }) =>
+    result.two_factor_required === true,
+  login: mocks.login,
+  loginTotp: mocks.loginTotp
+}));
 
 describe('Login page', () => {
   beforeEach(() => {
@@ -44,4 +50,24 @@ describe('Login page', () => {
     const alert = await screen.findByRole('alert');
     expect(alert).toHaveTextContent('Invalid email or password');
   });
+
+  it('with two-factor authentication asks for a code and redeems the challenge', async () => {
+    mocks.login.mockResolvedValueOnce({
+      two_factor_required: true,
+      challenge: 'challenge-1',
+      expires_at: 1700000300
+    });
+    mocks.loginTotp.mockResolvedValueOnce({ token: 'jwt', refresh_token: 'refresh' });
+    render(LoginPage);
+    await userEvent.type(screen.getByLabelText(/email/i), 'u@example.com');
+    await userEvent.type(screen.getByLabelText(/password/i), 'secret');
+    await userEvent.click(screen.getByRole('button', { name: /sign in/i }));
+
+    expect(mocks.goto).not.toHaveBeenCalled();
+    await userEvent.type(await screen.findByLabelText(/authentication code/i), '123456');
+    await userEvent.click(screen.getByRole('button', { name: /verify/i }));
+
+    expect(mocks.loginTotp).toHaveBeenCalledWith('challenge-1', '123456');
+    expect(mocks.goto).toHaveBeenCalledWith('/');
+  });
 });